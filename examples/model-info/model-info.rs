@@ -1,6 +1,9 @@
 use std::{env, process};
 
-use ort::session::Session;
+use ort::{
+	model::{Model, RenderOptions},
+	session::Session
+};
 
 // Include common code for `ort` examples that allows using the various feature flags to enable different EPs and
 // backends.
@@ -12,11 +15,25 @@ fn main() -> ort::Result<()> {
 	common::init()?;
 
 	let Some(path) = env::args().nth(1) else {
-		eprintln!("usage: ./model-info <model>.onnx");
+		eprintln!("usage: ./model-info <model>.onnx [--dot | --mermaid]");
 		process::exit(0);
 	};
 
-	let session = Session::builder()?.commit_from_file(path)?;
+	let model = Model::from_file(&path)?;
+	// print just the graph so it can be piped into e.g. `dot -Tsvg`
+	match env::args().nth(2).as_deref() {
+		Some("--dot") => {
+			print!("{}", model.to_dot(&RenderOptions::new()));
+			return Ok(());
+		}
+		Some("--mermaid") => {
+			print!("{}", model.to_mermaid(&RenderOptions::new()));
+			return Ok(());
+		}
+		_ => {}
+	}
+
+	let session = Session::builder()?.commit_from_file(&path)?;
 
 	let meta = session.metadata()?;
 	if let Ok(x) = meta.name() {
@@ -38,5 +55,20 @@ fn main() -> ort::Result<()> {
 		println!("    {i} {}: {}", output.name, output.output_type);
 	}
 
+	println!("IR version {}, opsets:", model.ir_version);
+	for opset in &model.opset_imports {
+		println!("    {}: {}", if opset.domain.is_empty() { "ai.onnx" } else { &opset.domain }, opset.version);
+	}
+	println!("Operators:");
+	for ((domain, op_type), count) in model.op_types() {
+		let domain = if domain.is_empty() { "ai.onnx" } else { domain };
+		println!("    {domain}::{op_type} x{count}");
+	}
+	let custom_domains = model.custom_domains();
+	if !custom_domains.is_empty() {
+		println!("Custom operator domains: {}", custom_domains.into_iter().collect::<Vec<_>>().join(", "));
+	}
+	println!("Parameters: {}", model.parameter_count());
+
 	Ok(())
 }
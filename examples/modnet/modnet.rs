@@ -24,7 +24,7 @@ fn main() -> ort::Result<()> {
 	// Register EPs based on feature flags - this isn't crucial for usage and can be removed.
 	common::init()?;
 
-	let mut session =
+	let session =
 		Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/modnet_photographic_portrait_matting.onnx")?;
 
 	let original_img = image::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("photo.jpg")).unwrap();
@@ -30,7 +30,7 @@ fn main() -> ort::Result<()> {
 	common::init()?;
 
 	// Load our model
-	let mut session = Session::builder()?
+	let session = Session::builder()?
 		.with_optimization_level(GraphOptimizationLevel::Level1)?
 		.with_intra_threads(1)?
 		.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/all-MiniLM-L6-v2.onnx")?;
@@ -0,0 +1,145 @@
+//! Derive macros for [`ort`](https://docs.rs/ort)'s `ModelInputs` & `ModelOutputs` traits.
+//!
+//! These macros are re-exported by `ort` when its `derive` feature is enabled; you should not need to depend on this
+//! crate directly. See the documentation of `ort::session::typed` for more information.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Type, parse_macro_input};
+
+struct ModelField {
+	ident: syn::Ident,
+	ty: Type,
+	/// The name of the field, without the `r#` prefix of raw identifiers.
+	field: LitStr,
+	/// The name of the model input/output this field maps to.
+	name: LitStr
+}
+
+fn parse_fields(input: &DeriveInput, derive: &str) -> syn::Result<Vec<ModelField>> {
+	let fields = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => &fields.named,
+			_ => return Err(syn::Error::new(input.ident.span(), format!("`{derive}` can only be derived for structs with named fields")))
+		},
+		_ => return Err(syn::Error::new(input.ident.span(), format!("`{derive}` can only be derived for structs")))
+	};
+
+	let mut out = Vec::with_capacity(fields.len());
+	for field in fields {
+		let ident = field.ident.clone().expect("named fields always have an identifier");
+		let mut name = None;
+		for attr in field.attrs.iter().filter(|a| a.path().is_ident("ort")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("name") {
+					name = Some(meta.value()?.parse::<LitStr>()?);
+					Ok(())
+				} else {
+					Err(meta.error("unknown `ort` attribute; expected `name = \"...\"`"))
+				}
+			})?;
+		}
+
+		let field_name = ident.to_string();
+		let field_name = LitStr::new(field_name.strip_prefix("r#").unwrap_or(&field_name), ident.span());
+		out.push(ModelField {
+			name: name.unwrap_or_else(|| field_name.clone()),
+			field: field_name,
+			ty: field.ty.clone(),
+			ident
+		});
+	}
+	Ok(out)
+}
+
+fn field_signatures(fields: &[ModelField], signature_trait: &TokenStream2) -> TokenStream2 {
+	let entries = fields.iter().map(|ModelField { ty, field, name, .. }| {
+		quote! {
+			::ort::session::typed::FieldSignature {
+				field: #field,
+				name: #name,
+				signature: <#ty as #signature_trait>::signature()
+			}
+		}
+	});
+	quote! { ::ort::__private::alloc::vec![#(#entries),*] }
+}
+
+/// Derives `ort::session::ModelInputs` for a struct with named fields.
+///
+/// Each field maps to the model input of the same name, or the name given by `#[ort(name = "...")]`. Field types
+/// must implement `ort::session::typed::IntoModelInput`.
+#[proc_macro_derive(ModelInputs, attributes(ort))]
+pub fn derive_model_inputs(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let fields = match parse_fields(&input, "ModelInputs") {
+		Ok(fields) => fields,
+		Err(e) => return e.to_compile_error().into()
+	};
+
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let signature = field_signatures(&fields, &quote!(::ort::session::typed::IntoModelInput));
+	let lifetime = syn::Lifetime::new("'__ort_v", Span::call_site());
+	let values = fields.iter().map(|ModelField { ident, ty, field, name }| {
+		quote! {
+			(
+				::ort::__private::alloc::borrow::Cow::Borrowed(#name),
+				::ort::session::typed::__input_field::<#ty>(#field, #name, self.#ident)?
+			)
+		}
+	});
+
+	quote! {
+		impl #impl_generics ::ort::session::typed::ModelInputs for #ident #ty_generics #where_clause {
+			fn signature() -> ::ort::__private::alloc::vec::Vec<::ort::session::typed::FieldSignature> {
+				#signature
+			}
+
+			fn into_session_inputs<#lifetime>(self) -> ::ort::Result<::ort::session::SessionInputs<#lifetime, #lifetime>>
+			where
+				Self: #lifetime
+			{
+				::core::result::Result::Ok(::ort::session::SessionInputs::ValueMap(::ort::__private::alloc::vec![#(#values),*]))
+			}
+		}
+	}
+	.into()
+}
+
+/// Derives `ort::session::ModelOutputs` for a struct with named fields.
+///
+/// Each field maps to the model output of the same name, or the name given by `#[ort(name = "...")]`. Field types
+/// must implement `ort::session::typed::FromModelOutput`. The struct does not need to contain every output of the
+/// model.
+#[proc_macro_derive(ModelOutputs, attributes(ort))]
+pub fn derive_model_outputs(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let fields = match parse_fields(&input, "ModelOutputs") {
+		Ok(fields) => fields,
+		Err(e) => return e.to_compile_error().into()
+	};
+
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let signature = field_signatures(&fields, &quote!(::ort::session::typed::FromModelOutput));
+	let values = fields.iter().map(|ModelField { ident, ty, field, name }| {
+		quote! {
+			#ident: ::ort::session::typed::__output_field::<#ty>(&mut outputs, #field, #name)?
+		}
+	});
+
+	quote! {
+		impl #impl_generics ::ort::session::typed::ModelOutputs for #ident #ty_generics #where_clause {
+			fn signature() -> ::ort::__private::alloc::vec::Vec<::ort::session::typed::FieldSignature> {
+				#signature
+			}
+
+			fn from_session_outputs(mut outputs: ::ort::session::SessionOutputs<'_, '_>) -> ::ort::Result<Self> {
+				::core::result::Result::Ok(Self { #(#values),* })
+			}
+		}
+	}
+	.into()
+}
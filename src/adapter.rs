@@ -167,7 +167,7 @@ mod tests {
 	#[cfg(feature = "std")]
 	fn test_lora() -> crate::Result<()> {
 		let model = std::fs::read("tests/data/lora_model.onnx").expect("");
-		let mut session = Session::builder()?.commit_from_memory(&model)?;
+		let session = Session::builder()?.commit_from_memory(&model)?;
 		let lora = Adapter::from_file("tests/data/adapter.orl", None)?;
 
 		let mut run_options = RunOptions::new()?;
@@ -190,7 +190,7 @@ mod tests {
 	#[test]
 	fn test_lora_from_memory() -> crate::Result<()> {
 		let model = std::fs::read("tests/data/lora_model.onnx").expect("");
-		let mut session = Session::builder()?.commit_from_memory(&model)?;
+		let session = Session::builder()?.commit_from_memory(&model)?;
 
 		let lora_bytes = std::fs::read("tests/data/adapter.orl").expect("");
 		let lora = Adapter::from_memory(&lora_bytes, None)?;
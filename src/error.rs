@@ -146,7 +146,39 @@ pub enum ErrorCode {
 	ModelLoaded,
 	NotImplemented,
 	InvalidGraph,
-	ExecutionProviderFailure
+	ExecutionProviderFailure,
+	/// An input name passed to a session run does not correspond to any of the model's inputs.
+	UnknownInput,
+	/// A required model input was not provided to a session run.
+	MissingInput,
+	/// A value passed to a session run is of a different type (or tensor element type) than the model expects.
+	InputTypeMismatch,
+	/// A tensor passed to a session run has a different rank or dimensions than the model expects.
+	InputShapeMismatch,
+	/// A field of a [`ModelOutputs`] struct refers to an output the model does not have.
+	///
+	/// [`ModelOutputs`]: crate::session::ModelOutputs
+	UnknownOutput,
+	/// An output expected by a [`ModelOutputs`] struct was not returned by a session run.
+	///
+	/// [`ModelOutputs`]: crate::session::ModelOutputs
+	MissingOutput,
+	/// A model output is of a different type (or tensor element type) than a [`ModelOutputs`] struct expects.
+	///
+	/// [`ModelOutputs`]: crate::session::ModelOutputs
+	OutputTypeMismatch,
+	/// A model output has a different rank than a [`ModelOutputs`] struct expects.
+	///
+	/// [`ModelOutputs`]: crate::session::ModelOutputs
+	OutputShapeMismatch,
+	/// A run was terminated because it exceeded the timeout or deadline configured in its
+	/// [`RunOptions`](crate::session::RunOptions).
+	Timeout,
+	/// A model was rejected because it is not signed, it or its external data was modified, or it was not signed by a
+	/// trusted key. See [`SessionBuilder::with_signature_verification`].
+	///
+	/// [`SessionBuilder::with_signature_verification`]: crate::session::builder::SessionBuilder::with_signature_verification
+	SignatureVerificationFailed
 }
 
 impl From<ort_sys::OrtErrorCode> for ErrorCode {
@@ -184,7 +216,16 @@ impl From<ErrorCode> for ort_sys::OrtErrorCode {
 			ErrorCode::ModelLoaded => ort_sys::OrtErrorCode::ORT_MODEL_LOADED,
 			ErrorCode::NotImplemented => ort_sys::OrtErrorCode::ORT_NOT_IMPLEMENTED,
 			ErrorCode::InvalidGraph => ort_sys::OrtErrorCode::ORT_INVALID_GRAPH,
-			ErrorCode::ExecutionProviderFailure => ort_sys::OrtErrorCode::ORT_EP_FAIL
+			ErrorCode::ExecutionProviderFailure => ort_sys::OrtErrorCode::ORT_EP_FAIL,
+			ErrorCode::UnknownInput
+			| ErrorCode::MissingInput
+			| ErrorCode::InputTypeMismatch
+			| ErrorCode::InputShapeMismatch
+			| ErrorCode::UnknownOutput
+			| ErrorCode::MissingOutput
+			| ErrorCode::OutputTypeMismatch
+			| ErrorCode::OutputShapeMismatch => ort_sys::OrtErrorCode::ORT_INVALID_ARGUMENT,
+			ErrorCode::Timeout | ErrorCode::SignatureVerificationFailed => ort_sys::OrtErrorCode::ORT_FAIL
 		}
 	}
 }
@@ -14,6 +14,8 @@
 //! }
 //! ```
 
+#[cfg(feature = "model-cache")]
+use alloc::string::String;
 use alloc::{ffi::CString, string::ToString, sync::Arc, vec::Vec};
 use core::{
 	ffi::c_char,
@@ -161,12 +163,17 @@ pub enum ArenaExtendStrategy {
 #[non_exhaustive]
 pub struct ExecutionProviderDispatch {
 	pub(crate) inner: Arc<dyn ExecutionProvider>,
+	/// The EP's name & options, which become part of the optimized model cache key once it is registered.
+	#[cfg(feature = "model-cache")]
+	fingerprint: String,
 	error_on_failure: bool
 }
 
 impl ExecutionProviderDispatch {
-	pub(crate) fn new<E: ExecutionProvider + 'static>(ep: E) -> Self {
+	pub(crate) fn new<E: ExecutionProvider + Debug + 'static>(ep: E) -> Self {
 		ExecutionProviderDispatch {
+			#[cfg(feature = "model-cache")]
+			fingerprint: alloc::format!("{ep:?}"),
 			inner: Arc::new(ep) as _,
 			error_on_failure: false
 		}
@@ -359,6 +366,8 @@ pub(crate) fn apply_execution_providers(session_builder: &mut SessionBuilder, ep
 			Ok(false)
 		} else {
 			crate::info!(%source, "Successfully registered `{}`", ep.inner.as_str());
+			#[cfg(feature = "model-cache")]
+			session_builder.cache.record(alloc::format!("ep={}", ep.fingerprint));
 			Ok(true)
 		}
 	}
@@ -0,0 +1,599 @@
+//! Beam search decoding for decoder-only and encoder-decoder models.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use super::{
+	DecoderLayout, bind_cache,
+	cache::{KvCache, gather_axis},
+	int_tensor,
+	layout::InputTypes,
+	output_binding, run_step
+};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	session::Session,
+	tensor::TensorElementType,
+	value::{DynTensor, DynTensorValueType, DynValue, ValueType}
+};
+
+/// Options for [`BeamSearch`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeamSearchOptions {
+	num_beams: usize,
+	num_return_sequences: usize,
+	max_tokens: usize,
+	max_length: Option<usize>,
+	length_penalty: f32,
+	early_stopping: bool,
+	eos_token_ids: Vec<i64>,
+	pad_token_id: i64,
+	decoder_start_token_id: Option<i64>
+}
+
+impl Default for BeamSearchOptions {
+	fn default() -> Self {
+		Self {
+			num_beams: 4,
+			num_return_sequences: 1,
+			max_tokens: 256,
+			max_length: None,
+			length_penalty: 1.0,
+			early_stopping: false,
+			eos_token_ids: Vec::new(),
+			pad_token_id: 0,
+			decoder_start_token_id: None
+		}
+	}
+}
+
+impl BeamSearchOptions {
+	/// Sets the number of hypotheses kept for each input at every step. Defaults to 4.
+	pub fn with_num_beams(mut self, num_beams: usize) -> Self {
+		self.num_beams = num_beams;
+		self
+	}
+
+	/// Sets the number of finished hypotheses returned for each input, which must be at most the number of beams.
+	/// Defaults to 1.
+	pub fn with_num_return_sequences(mut self, num_return_sequences: usize) -> Self {
+		self.num_return_sequences = num_return_sequences;
+		self
+	}
+
+	/// Sets the maximum number of tokens to generate. Defaults to 256.
+	pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+		self.max_tokens = max_tokens;
+		self
+	}
+
+	/// Sets the maximum length of the decoder's sequence, including the (padded) prompt.
+	pub fn with_max_length(mut self, max_length: usize) -> Self {
+		self.max_length = Some(max_length);
+		self
+	}
+
+	/// Sets the exponent applied to the length of a hypothesis when scoring it: the score is the sum of the
+	/// log-probabilities of its tokens divided by `length ^ length_penalty`. Values above 0 favor longer sequences;
+	/// values below 0 favor shorter sequences. Defaults to 1.
+	pub fn with_length_penalty(mut self, length_penalty: f32) -> Self {
+		self.length_penalty = length_penalty;
+		self
+	}
+
+	/// If `true`, search for an input stops as soon as there are `num_beams` finished hypotheses. Otherwise (the
+	/// default), search stops once no running beam could score better than the finished hypotheses.
+	pub fn with_early_stopping(mut self, early_stopping: bool) -> Self {
+		self.early_stopping = early_stopping;
+		self
+	}
+
+	/// Adds a token which ends a hypothesis. The token is not included in [`Hypothesis::tokens`].
+	pub fn with_eos_token_id(mut self, token: i64) -> Self {
+		self.eos_token_ids.push(token);
+		self
+	}
+
+	/// Sets the token used to pad prompts of different lengths, which is masked out by the attention mask. Defaults to
+	/// 0.
+	pub fn with_pad_token_id(mut self, token: i64) -> Self {
+		self.pad_token_id = token;
+		self
+	}
+
+	/// Sets the token the decoder of an encoder-decoder model starts from. Required for encoder-decoder models.
+	pub fn with_decoder_start_token_id(mut self, token: i64) -> Self {
+		self.decoder_start_token_id = Some(token);
+		self
+	}
+}
+
+/// A finished hypothesis returned by [`BeamSearch`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hypothesis {
+	/// The generated tokens, excluding the prompt and EOS token.
+	pub tokens: Vec<i64>,
+	/// The length-penalized score used to rank hypotheses.
+	pub score: f32,
+	/// The sum of the log-probabilities of the generated tokens (including the EOS token, if any).
+	pub log_prob: f32
+}
+
+/// Beam search over a [`Session`], for decoder-only and encoder-decoder models with a KV cache.
+///
+/// Each input is expanded to `num_beams` rows, so the decoder runs on a batch of `inputs × num_beams` sequences. After
+/// every step, the `2 × num_beams` best continuations of each input are considered; the KV cache is reordered to follow
+/// the surviving beams.
+///
+/// For encoder-decoder models (e.g. translation or captioning), use [`BeamSearch::with_encoder`] to run a separate
+/// encoder session once before decoding, or pass precomputed encoder outputs to [`BeamSearch::search_encoded`]. The
+/// decoder must be a merged decoder (with a `use_cache_branch` input), or a decoder without a KV cache.
+///
+/// ```no_run
+/// # use ort::{generation::beam::{BeamSearch, BeamSearchOptions}, session::Session};
+/// # fn main() -> ort::Result<()> {
+/// let encoder = Session::builder()?.commit_from_file("encoder_model.onnx")?;
+/// let decoder = Session::builder()?.commit_from_file("decoder_model_merged.onnx")?;
+///
+/// let options = BeamSearchOptions::default()
+/// 	.with_num_beams(4)
+/// 	.with_num_return_sequences(2)
+/// 	.with_eos_token_id(0)
+/// 	.with_pad_token_id(58100)
+/// 	.with_decoder_start_token_id(58100);
+/// let search = BeamSearch::new(&decoder, options)?.with_encoder(&encoder)?;
+///
+/// // one list of hypotheses per input, best first
+/// for hypotheses in search.search(&[&[1010, 7, 2088, 0], &[3016, 0]])? {
+/// 	for hypothesis in hypotheses {
+/// 		println!("{:?} (score {})", hypothesis.tokens, hypothesis.score);
+/// 	}
+/// }
+/// # 	Ok(())
+/// # }
+/// ```
+pub struct BeamSearch<'s> {
+	decoder: &'s Session,
+	encoder: Option<&'s Session>,
+	layout: DecoderLayout,
+	types: InputTypes,
+	options: BeamSearchOptions
+}
+
+impl<'s> BeamSearch<'s> {
+	/// Creates a beam search driver for `decoder`, detecting its layout via [`DecoderLayout::detect`].
+	pub fn new(decoder: &'s Session, options: BeamSearchOptions) -> Result<Self> {
+		Self::with_layout(decoder, DecoderLayout::detect(decoder)?, options)
+	}
+
+	/// Creates a beam search driver for `decoder` with an explicit [`DecoderLayout`].
+	pub fn with_layout(decoder: &'s Session, layout: DecoderLayout, options: BeamSearchOptions) -> Result<Self> {
+		if options.num_beams == 0 || options.num_return_sequences == 0 || options.num_return_sequences > options.num_beams {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!(
+					"Invalid beam search options: need at least 1 beam and 1 return sequence, and at most as many return sequences as beams (got {} beams, {} return sequences)",
+					options.num_beams, options.num_return_sequences
+				)
+			));
+		}
+		if layout.encoder_hidden_states.is_some() && options.decoder_start_token_id.is_none() {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Encoder-decoder models require a decoder start token"));
+		}
+		Ok(Self {
+			decoder,
+			encoder: None,
+			types: InputTypes::resolve(&layout, &decoder.inputs)?,
+			layout,
+			options
+		})
+	}
+
+	/// Sets the encoder session of an encoder-decoder model, which is run on the inputs passed to
+	/// [`BeamSearch::search`] to produce the decoder's `encoder_hidden_states`.
+	///
+	/// The encoder must take `input_ids` (and optionally `attention_mask`) as input. Its `last_hidden_state` output (or
+	/// first output, if there is no such output) is passed to the decoder.
+	pub fn with_encoder(mut self, encoder: &'s Session) -> Result<Self> {
+		if self.layout.encoder_hidden_states.is_none() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				"Decoder has no `encoder_hidden_states` input; it is not part of an encoder-decoder model"
+			));
+		}
+		if !encoder.inputs.iter().any(|input| input.name == "input_ids") {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				"Encoder has no `input_ids` input; run it manually and use `BeamSearch::search_encoded` instead"
+			));
+		}
+		self.encoder = Some(encoder);
+		Ok(self)
+	}
+
+	/// Runs beam search on a batch of inputs, returning the best [`Hypothesis`]es for each input, best first.
+	///
+	/// For decoder-only models, the inputs are the prompts. For encoder-decoder models, the inputs are passed to the
+	/// encoder set with [`BeamSearch::with_encoder`].
+	pub fn search(&self, inputs: &[&[i64]]) -> Result<Vec<Vec<Hypothesis>>> {
+		if inputs.is_empty() {
+			return Ok(Vec::new());
+		}
+		if self.layout.encoder_hidden_states.is_none() {
+			return self.decode(inputs.iter().map(|input| input.to_vec()).collect(), None);
+		}
+		let encoder = self.encoder.ok_or_else(|| {
+			Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				"Decoder requires encoder outputs; set an encoder with `BeamSearch::with_encoder` or use `BeamSearch::search_encoded`"
+			)
+		})?;
+
+		let (ids, mask, shape) = pad(inputs, self.options.pad_token_id, Padding::Right);
+		let mut encoder_inputs = Vec::<(String, DynValue)>::new();
+		for input in &encoder.inputs {
+			let value = match input.name.as_str() {
+				"input_ids" => &ids,
+				"attention_mask" => &mask,
+				name => {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!("Unsupported encoder input `{name}`; use `BeamSearch::search_encoded`")
+					));
+				}
+			};
+			let ty = match &input.input_type {
+				ValueType::Tensor { ty, .. } => *ty,
+				_ => TensorElementType::Int64
+			};
+			encoder_inputs.push((input.name.clone(), int_tensor(ty, shape, value.iter().copied())?.into_dyn()));
+		}
+		let mut outputs = encoder.run(encoder_inputs)?;
+		let name = encoder
+			.outputs
+			.iter()
+			.find(|output| output.name == "last_hidden_state")
+			.or_else(|| encoder.outputs.first())
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Encoder has no outputs"))?
+			.name
+			.clone();
+		let hidden_states = outputs
+			.remove(&name)
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Encoder did not produce output `{name}`")))?
+			.downcast::<DynTensorValueType>()?;
+		let mask = int_tensor(self.types.encoder_attention_mask, shape, mask.into_iter())?;
+		self.search_encoded(&hidden_states, Some(&mask))
+	}
+
+	/// Runs beam search for an encoder-decoder model given the output of the encoder, of shape
+	/// `[batch_size, encoder_sequence_length, hidden_size]`, and optionally the encoder's attention mask of shape
+	/// `[batch_size, encoder_sequence_length]`.
+	pub fn search_encoded(&self, encoder_hidden_states: &DynTensor, encoder_attention_mask: Option<&DynTensor>) -> Result<Vec<Vec<Hypothesis>>> {
+		let start = self
+			.options
+			.decoder_start_token_id
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Encoder-decoder models require a decoder start token"))?;
+		let shape = match encoder_hidden_states.dtype() {
+			ValueType::Tensor { shape, .. } if shape.len() >= 2 => shape.clone(),
+			ty => {
+				return Err(Error::new_with_code(
+					ErrorCode::InvalidArgument,
+					format!("Expected encoder hidden states of shape [batch, sequence, ...], got {ty}")
+				));
+			}
+		};
+		let (batch_size, sequence_len) = (shape[0] as usize, shape[1] as usize);
+		let rows = expand_rows(batch_size, self.options.num_beams);
+		let hidden_states = gather_axis(encoder_hidden_states, 0, &rows)?;
+		let mask = match encoder_attention_mask {
+			Some(mask) => gather_axis(mask, 0, &rows)?,
+			None => int_tensor(self.types.encoder_attention_mask, [rows.len(), sequence_len], core::iter::repeat(1).take(rows.len() * sequence_len))?
+		};
+		self.decode(vec![vec![start]; batch_size], Some((hidden_states, mask)))
+	}
+
+	fn decode(&self, prompts: Vec<Vec<i64>>, encoder_outputs: Option<(DynTensor, DynTensor)>) -> Result<Vec<Vec<Hypothesis>>> {
+		let (layout, types, options) = (&self.layout, &self.types, &self.options);
+		let (batch_size, num_beams) = (prompts.len(), options.num_beams);
+		let num_rows = batch_size * num_beams;
+
+		let prompts: Vec<&[i64]> = prompts.iter().map(Vec::as_slice).collect();
+		let (ids, mask, [_, prompt_len]) = pad(&prompts, options.pad_token_id, Padding::Left);
+		let mut beams: Vec<Beam> = expand_rows(batch_size, num_beams)
+			.into_iter()
+			.enumerate()
+			.map(|(row, input)| Beam {
+				tokens: ids[input * prompt_len..(input + 1) * prompt_len].to_vec(),
+				mask: mask[input * prompt_len..(input + 1) * prompt_len].to_vec(),
+				// all beams of an input start out identical, so only expand the first on the first step
+				log_prob: if row % num_beams == 0 { 0.0 } else { f32::NEG_INFINITY }
+			})
+			.collect();
+
+		let mut binding = output_binding(self.decoder, layout)?;
+		if let (Some((hidden_states, mask)), Some(hidden_states_name)) = (&encoder_outputs, &layout.encoder_hidden_states) {
+			binding.bind_input(hidden_states_name, hidden_states)?;
+			if let Some(name) = &layout.encoder_attention_mask {
+				binding.bind_input(name, mask)?;
+			}
+		}
+		let mut cache = KvCache::empty(layout, num_rows)?;
+		let mut finished: Vec<Finished> = (0..batch_size).map(|_| Finished::new(num_beams)).collect();
+		let mut generated = 0;
+
+		while generated < options.max_tokens && options.max_length.map_or(true, |max| prompt_len + generated < max) {
+			let (past_len, total_len) = (cache.len, prompt_len + generated);
+			let new_len = total_len - past_len;
+			binding.bind_input(
+				&layout.input_ids,
+				&int_tensor(types.input_ids, [num_rows, new_len], beams.iter().flat_map(|beam| beam.tokens[past_len..].iter().copied()))?
+			)?;
+			if let Some(name) = &layout.attention_mask {
+				binding.bind_input(name, &int_tensor(types.attention_mask, [num_rows, total_len], beams.iter().flat_map(|beam| beam.mask.iter().copied()))?)?;
+			}
+			if let Some(name) = &layout.position_ids {
+				let positions = beams.iter().flat_map(|beam| positions(&beam.mask).skip(past_len));
+				binding.bind_input(name, &int_tensor(types.position_ids, [num_rows, new_len], positions)?)?;
+			}
+			bind_cache(&mut binding, layout, &cache)?;
+
+			let (logits, presents) = run_step(self.decoder, &binding, layout)?;
+			cache.update(layout, presents, total_len);
+			let (log_probs, vocab_size) = last_log_probs(&logits, num_rows)?;
+			generated += 1;
+
+			let mut next = Vec::with_capacity(num_rows);
+			for (input, finished) in finished.iter_mut().enumerate() {
+				let rows = input * num_beams..(input + 1) * num_beams;
+				if finished.done {
+					// keep the batch shape intact; these rows are ignored from now on
+					next.extend(rows.map(|row| (row, options.pad_token_id, f32::NEG_INFINITY)));
+					continue;
+				}
+
+				let mut candidates = Vec::with_capacity(num_beams * num_beams * 2);
+				for row in rows.clone().filter(|&row| beams[row].log_prob > f32::NEG_INFINITY) {
+					let row_log_probs = &log_probs[row * vocab_size..(row + 1) * vocab_size];
+					for token in top_k(row_log_probs, 2 * num_beams) {
+						candidates.push((beams[row].log_prob + row_log_probs[token], row, token as i64));
+					}
+				}
+				candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+				let start = next.len();
+				for (rank, &(log_prob, row, token)) in candidates.iter().enumerate() {
+					if options.eos_token_ids.contains(&token) {
+						// only EOS tokens among the best `num_beams` candidates end a hypothesis
+						if rank < num_beams {
+							finished.add(&beams[row].tokens[prompt_len..], log_prob, generated, options.length_penalty);
+						}
+					} else {
+						next.push((row, token, log_prob));
+						if next.len() - start == num_beams {
+							break;
+						}
+					}
+				}
+				// the vocabulary may be too small to fill every beam
+				next.resize(start + num_beams, (rows.start, options.pad_token_id, f32::NEG_INFINITY));
+
+				let best_running = next[start].2;
+				finished.done = finished.is_done(best_running, generated, options);
+			}
+			if finished.iter().all(|finished| finished.done) {
+				break;
+			}
+
+			let order: Vec<usize> = next.iter().map(|&(row, ..)| row).collect();
+			cache.reorder(layout, &order)?;
+			beams = next
+				.into_iter()
+				.map(|(row, token, log_prob)| {
+					let mut beam = beams[row].clone();
+					beam.tokens.push(token);
+					beam.mask.push(1);
+					beam.log_prob = log_prob;
+					beam
+				})
+				.collect();
+		}
+
+		Ok(finished
+			.into_iter()
+			.enumerate()
+			.map(|(input, mut finished)| {
+				if !finished.done {
+					for beam in &beams[input * num_beams..(input + 1) * num_beams] {
+						if beam.log_prob > f32::NEG_INFINITY {
+							finished.add(&beam.tokens[prompt_len..], beam.log_prob, generated, options.length_penalty);
+						}
+					}
+				}
+				finished.hypotheses.truncate(options.num_return_sequences);
+				finished.hypotheses
+			})
+			.collect())
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Beam {
+	tokens: Vec<i64>,
+	mask: Vec<i64>,
+	log_prob: f32
+}
+
+/// The best finished hypotheses for one input, sorted by descending score.
+struct Finished {
+	hypotheses: Vec<Hypothesis>,
+	num_beams: usize,
+	done: bool
+}
+
+impl Finished {
+	fn new(num_beams: usize) -> Self {
+		Self {
+			hypotheses: Vec::with_capacity(num_beams + 1),
+			num_beams,
+			done: false
+		}
+	}
+
+	fn add(&mut self, tokens: &[i64], log_prob: f32, len: usize, length_penalty: f32) {
+		let score = log_prob / (len as f32).powf(length_penalty);
+		if self.hypotheses.len() == self.num_beams && self.hypotheses.last().is_some_and(|worst| worst.score >= score) {
+			return;
+		}
+		let index = self.hypotheses.partition_point(|hypothesis| hypothesis.score >= score);
+		self.hypotheses.insert(
+			index,
+			Hypothesis {
+				tokens: tokens.to_vec(),
+				score,
+				log_prob
+			}
+		);
+		self.hypotheses.truncate(self.num_beams);
+	}
+
+	fn is_done(&self, best_running: f32, len: usize, options: &BeamSearchOptions) -> bool {
+		if self.hypotheses.len() < self.num_beams {
+			return false;
+		}
+		if options.early_stopping || best_running == f32::NEG_INFINITY {
+			return true;
+		}
+		// log-probabilities only decrease, so assuming `length_penalty > 0`, the best a running beam can do is to end now
+		let best_possible = best_running / (len as f32).powf(options.length_penalty);
+		self.hypotheses.last().is_some_and(|worst| worst.score >= best_possible)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Padding {
+	Left,
+	Right
+}
+
+/// Pads sequences to the same length, returning the flattened tokens, the attention mask, and the shape.
+fn pad(sequences: &[&[i64]], pad_token_id: i64, padding: Padding) -> (Vec<i64>, Vec<i64>, [usize; 2]) {
+	let len = sequences.iter().map(|sequence| sequence.len()).max().unwrap_or(0);
+	let mut ids = Vec::with_capacity(sequences.len() * len);
+	let mut mask = Vec::with_capacity(sequences.len() * len);
+	for sequence in sequences {
+		let padding_len = len - sequence.len();
+		if padding == Padding::Left {
+			ids.extend(core::iter::repeat(pad_token_id).take(padding_len));
+			mask.extend(core::iter::repeat(0).take(padding_len));
+		}
+		ids.extend_from_slice(sequence);
+		mask.extend(core::iter::repeat(1).take(sequence.len()));
+		if padding == Padding::Right {
+			ids.extend(core::iter::repeat(pad_token_id).take(padding_len));
+			mask.extend(core::iter::repeat(0).take(padding_len));
+		}
+	}
+	(ids, mask, [sequences.len(), len])
+}
+
+/// Returns the index of the input each row belongs to when every input is expanded to `num_beams` rows.
+fn expand_rows(batch_size: usize, num_beams: usize) -> Vec<usize> {
+	(0..batch_size).flat_map(|input| core::iter::repeat(input).take(num_beams)).collect()
+}
+
+/// Position IDs for a (possibly left-padded) sequence: padding gets position 0, and real tokens count up from 0.
+fn positions(mask: &[i64]) -> impl Iterator<Item = i64> + '_ {
+	mask.iter().scan(0, |position, &mask| {
+		let current = if mask == 0 { 0 } else { *position };
+		*position += mask;
+		Some(current)
+	})
+}
+
+/// Returns the indices of the `k` largest values, in no particular order.
+fn top_k(values: &[f32], k: usize) -> Vec<usize> {
+	let mut indices: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_nan()).collect();
+	let compare = |&a: &usize, &b: &usize| values[b].total_cmp(&values[a]).then(a.cmp(&b));
+	if k < indices.len() {
+		indices.select_nth_unstable_by(k, compare);
+		indices.truncate(k);
+	}
+	indices
+}
+
+/// Extracts the log-softmax of the last position's logits for each row from a `[rows, sequence, vocab]` tensor.
+fn last_log_probs(logits: &DynValue, rows: usize) -> Result<(Vec<f32>, usize)> {
+	let (ty, shape) = match logits.dtype() {
+		ValueType::Tensor { ty, shape, .. } if shape.len() == 3 && shape[0] as usize == rows => (*ty, shape),
+		ty => return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Expected logits of shape [{rows}, sequence, vocab], got {ty}")))
+	};
+	let (sequence_len, vocab_size) = (shape[1] as usize, shape[2] as usize);
+	let last = |row: usize| (row * sequence_len + sequence_len - 1) * vocab_size;
+	let mut log_probs = Vec::with_capacity(rows * vocab_size);
+	match ty {
+		TensorElementType::Float32 => {
+			let (_, data) = logits.try_extract_tensor::<f32>()?;
+			for row in 0..rows {
+				log_probs.extend_from_slice(&data[last(row)..last(row) + vocab_size]);
+			}
+		}
+		#[cfg(feature = "half")]
+		TensorElementType::Float16 => {
+			let (_, data) = logits.try_extract_tensor::<half::f16>()?;
+			for row in 0..rows {
+				log_probs.extend(data[last(row)..last(row) + vocab_size].iter().map(|x| x.to_f32()));
+			}
+		}
+		ty => return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Unsupported logits type {ty}; expected f32")))
+	}
+	for row in log_probs.chunks_mut(vocab_size.max(1)) {
+		let max = row.iter().copied().filter(|x| !x.is_nan()).fold(f32::NEG_INFINITY, f32::max);
+		let log_sum = row.iter().map(|&x| (x - max).exp()).sum::<f32>().ln() + max;
+		for x in row {
+			*x -= log_sum;
+		}
+	}
+	Ok((log_probs, vocab_size))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BeamSearchOptions, Finished, Padding, pad, positions, top_k};
+
+	#[test]
+	fn test_padding() {
+		let (ids, mask, shape) = pad(&[&[5, 6, 7], &[8]], 0, Padding::Left);
+		assert_eq!(shape, [2, 3]);
+		assert_eq!(ids, [5, 6, 7, 0, 0, 8]);
+		assert_eq!(mask, [1, 1, 1, 0, 0, 1]);
+		assert_eq!(positions(&mask[3..]).collect::<alloc::vec::Vec<_>>(), [0, 0, 0]);
+		assert_eq!(positions(&[0, 1, 1, 1]).collect::<alloc::vec::Vec<_>>(), [0, 0, 1, 2]);
+
+		let (ids, mask, _) = pad(&[&[5, 6, 7], &[8]], 1, Padding::Right);
+		assert_eq!(ids, [5, 6, 7, 8, 1, 1]);
+		assert_eq!(mask, [1, 1, 1, 1, 0, 0]);
+	}
+
+	#[test]
+	fn test_top_k() {
+		let mut top = top_k(&[0.1, 0.5, f32::NAN, 0.3, 0.5], 3);
+		top.sort_unstable();
+		assert_eq!(top, [1, 3, 4]);
+		assert_eq!(top_k(&[0.1, 0.5], 3).len(), 2);
+	}
+
+	#[test]
+	fn test_finished_hypotheses() {
+		let options = BeamSearchOptions::default().with_num_beams(2);
+		let mut finished = Finished::new(2);
+		finished.add(&[1, 2, 3], -3.0, 3, 1.0);
+		assert!(!finished.is_done(-0.5, 3, &options));
+		finished.add(&[1], -2.0, 1, 1.0);
+		finished.add(&[4, 5, 6, 7], -2.0, 4, 1.0);
+		// [4, 5, 6, 7] scores -0.5, [1, 2, 3] scores -1.0, and [1] scores -2.0 and is dropped
+		assert_eq!(finished.hypotheses.iter().map(|h| h.tokens.len()).collect::<alloc::vec::Vec<_>>(), [4, 3]);
+		// a running beam at -2.5 could still end with a score of -0.83, beating the worst finished hypothesis
+		assert!(!finished.is_done(-2.5, 3, &options));
+		assert!(finished.is_done(-2.5, 3, &options.clone().with_early_stopping(true)));
+		assert!(finished.is_done(-4.0, 4, &options));
+	}
+}
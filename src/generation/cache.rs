@@ -0,0 +1,141 @@
+use alloc::{format, vec::Vec};
+use core::ops::Range;
+
+use super::layout::DecoderLayout;
+use crate::{
+	error::{Error, ErrorCode, Result},
+	memory::Allocator,
+	tensor::TensorElementType,
+	value::{DynTensor, ValueType}
+};
+
+/// The past key/value tensors of a decoder model, in the order of [`DecoderLayout::cache`].
+#[derive(Debug)]
+pub(crate) struct KvCache {
+	pub(crate) values: Vec<DynTensor>,
+	/// The number of positions currently stored in the cache.
+	pub(crate) len: usize
+}
+
+impl KvCache {
+	pub(crate) fn empty(layout: &DecoderLayout, batch_size: usize) -> Result<Self> {
+		let allocator = Allocator::default();
+		let values = layout
+			.cache
+			.iter()
+			.map(|entry| DynTensor::new(&allocator, entry.element_type, entry.empty_shape(batch_size)?))
+			.collect::<Result<_>>()?;
+		Ok(Self { values, len: 0 })
+	}
+
+	/// Replaces the cache with the present key/values output by a step, after which the cache holds `len` positions.
+	pub(crate) fn update(&mut self, layout: &DecoderLayout, presents: Vec<DynTensor>, len: usize) {
+		let first_step = self.len == 0;
+		for ((value, present), entry) in self.values.iter_mut().zip(presents).zip(&layout.cache) {
+			// cross-attention caches only depend on the encoder output, so they're only computed on the first step; merged
+			// decoders may output dummy values for them afterwards
+			if first_step || !entry.cross_attention {
+				*value = present;
+			}
+		}
+		self.len = len;
+	}
+
+	/// Drops all but the first `len` positions from the cache.
+	pub(crate) fn truncate(&mut self, layout: &DecoderLayout, len: usize) -> Result<()> {
+		if len >= self.len {
+			return Ok(());
+		}
+		if len == 0 {
+			*self = Self::empty(layout, self.batch_size(layout))?;
+			return Ok(());
+		}
+		let indices: Vec<usize> = (0..len).collect();
+		for (value, entry) in self.values.iter_mut().zip(&layout.cache) {
+			if !entry.cross_attention {
+				*value = gather_axis(value, entry.sequence_axis, &indices)?;
+			}
+		}
+		self.len = len;
+		Ok(())
+	}
+
+	/// Rearranges the sequences in the batch, such that sequence `i` of the new cache is sequence `rows[i]` of the old
+	/// cache. Used to follow the surviving hypotheses in beam search.
+	#[cfg(feature = "std")]
+	pub(crate) fn reorder(&mut self, layout: &DecoderLayout, rows: &[usize]) -> Result<()> {
+		if rows.iter().enumerate().all(|(i, &row)| i == row) {
+			return Ok(());
+		}
+		for (value, entry) in self.values.iter_mut().zip(&layout.cache) {
+			// beams are only ever reordered within the same batch item, and every beam of an item shares the same
+			// cross-attention cache
+			if !entry.cross_attention {
+				*value = gather_axis(value, entry.batch_axis, rows)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn batch_size(&self, layout: &DecoderLayout) -> usize {
+		match (self.values.first().map(|value| value.dtype()), layout.cache.first()) {
+			(Some(ValueType::Tensor { shape, .. }), Some(entry)) => shape[entry.batch_axis] as usize,
+			_ => 1
+		}
+	}
+}
+
+/// Copies the slices of `tensor` at `indices` along `axis` into a new CPU tensor.
+pub(crate) fn gather_axis(tensor: &DynTensor, axis: usize, indices: &[usize]) -> Result<DynTensor> {
+	let (ty, shape) = match tensor.dtype() {
+		ValueType::Tensor { ty, shape, .. } => (*ty, shape),
+		_ => unreachable!("`DynTensor` is always a tensor")
+	};
+	if !tensor.memory_info().is_cpu_accessible() {
+		return Err(Error::new_with_code(ErrorCode::InvalidArgument, "KV cache tensors must be CPU-accessible"));
+	}
+	let element_size = match ty {
+		TensorElementType::String
+		| TensorElementType::Uint4
+		| TensorElementType::Int4
+		| TensorElementType::Float8E4M3FN
+		| TensorElementType::Float8E4M3FNUZ
+		| TensorElementType::Float8E5M2
+		| TensorElementType::Float8E5M2FNUZ
+		| TensorElementType::Undefined => {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cannot slice a tensor of {ty}")));
+		}
+		ty => ty.byte_size(1)
+	};
+
+	let dims: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+	let axis_len = dims[axis];
+	if let Some(index) = indices.iter().find(|&&i| i >= axis_len) {
+		return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Index {index} is out of bounds for axis {axis} of shape {shape}")));
+	}
+	let outer: usize = dims[..axis].iter().product();
+	let inner: usize = dims[axis + 1..].iter().product::<usize>() * element_size;
+
+	let mut new_shape = dims.clone();
+	new_shape[axis] = indices.len();
+	let mut out = DynTensor::new(&Allocator::default(), ty, new_shape)?;
+
+	let src_len = outer * axis_len * inner;
+	let dst_len = outer * indices.len() * inner;
+	if dst_len == 0 {
+		return Ok(out);
+	}
+	let src = unsafe { core::slice::from_raw_parts(tensor.data_ptr().cast::<u8>(), src_len) };
+	let dst = unsafe { core::slice::from_raw_parts_mut(out.data_ptr_mut().cast::<u8>(), dst_len) };
+	for o in 0..outer {
+		for (i, &index) in indices.iter().enumerate() {
+			let src_range = row(o * axis_len + index, inner);
+			dst[row(o * indices.len() + i, inner)].copy_from_slice(&src[src_range]);
+		}
+	}
+	Ok(out)
+}
+
+fn row(index: usize, len: usize) -> Range<usize> {
+	index * len..(index + 1) * len
+}
@@ -0,0 +1,314 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	session::{Input, Output, Session},
+	tensor::TensorElementType,
+	value::ValueType
+};
+
+/// A past key/value input of a decoder model, and the corresponding present output which becomes the input for the
+/// next step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+	/// The name of the input, e.g. `past_key_values.0.key`.
+	pub input: String,
+	/// The name of the output, e.g. `present.0.key`.
+	pub output: String,
+	pub element_type: TensorElementType,
+	/// The shape of the cache tensor as declared by the model, with `-1` for dynamic dimensions.
+	pub shape: Vec<i64>,
+	/// The axis along which sequences are batched.
+	pub batch_axis: usize,
+	/// The axis along which the cache grows with each step.
+	pub sequence_axis: usize,
+	/// Whether this is a cross-attention cache in an encoder-decoder model (e.g. `past_key_values.0.encoder.key`).
+	/// Cross-attention caches are computed from the encoder's output on the first step and reused as-is afterwards.
+	pub cross_attention: bool
+}
+
+impl CacheEntry {
+	/// Returns the shape of an empty cache tensor for the given batch size.
+	pub(crate) fn empty_shape(&self, batch_size: usize) -> Result<Vec<i64>> {
+		self.shape
+			.iter()
+			.enumerate()
+			.map(|(axis, &dim)| match axis {
+				_ if axis == self.batch_axis => Ok(batch_size as i64),
+				_ if axis == self.sequence_axis => Ok(0),
+				_ if dim >= 0 => Ok(dim),
+				_ => Err(Error::new_with_code(
+					ErrorCode::InvalidArgument,
+					format!("Cannot create an empty KV cache for `{}`: dimension {axis} of shape {:?} is dynamic", self.input, self.shape)
+				))
+			})
+			.collect()
+	}
+}
+
+/// Describes how to drive a decoder-only model: which inputs receive the token ids, attention mask & position ids,
+/// which output contains the logits, and how past key/values are threaded between steps.
+///
+/// Use [`DecoderLayout::detect`] to infer the layout from the model's inputs & outputs, which follows the naming
+/// conventions used by Hugging Face Optimum, ONNX Runtime GenAI, and older GPT-2 exports:
+/// - `input_ids` (required), `attention_mask`, `position_ids`, and `use_cache_branch` inputs;
+/// - `encoder_hidden_states` & `encoder_attention_mask` inputs for the decoder of an encoder-decoder model;
+/// - a `logits` output (or the first output which is not part of the cache);
+/// - `past_key_values.*` inputs paired with `present.*` outputs, or `past*` inputs paired with `present*` outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecoderLayout {
+	pub input_ids: String,
+	pub attention_mask: Option<String>,
+	pub position_ids: Option<String>,
+	/// Boolean input used by merged decoders to select between the no-cache and cached subgraphs.
+	pub use_cache_branch: Option<String>,
+	/// The output of the encoder, for encoder-decoder models.
+	pub encoder_hidden_states: Option<String>,
+	pub encoder_attention_mask: Option<String>,
+	pub logits: String,
+	pub cache: Vec<CacheEntry>
+}
+
+impl DecoderLayout {
+	/// Infers the layout of a decoder-only model from the session's inputs & outputs.
+	pub fn detect(session: &Session) -> Result<Self> {
+		Self::from_io(&session.inputs, &session.outputs)
+	}
+
+	pub(crate) fn from_io(inputs: &[Input], outputs: &[Output]) -> Result<Self> {
+		let find_input = |name: &str| inputs.iter().find(|input| input.name == name);
+
+		let input_ids = find_input("input_ids").ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Decoder model has no `input_ids` input"))?;
+		let attention_mask = find_input("attention_mask");
+		let position_ids = find_input("position_ids");
+		let use_cache_branch = find_input("use_cache_branch");
+		let encoder_hidden_states = find_input("encoder_hidden_states");
+		let encoder_attention_mask = find_input("encoder_attention_mask");
+
+		let mut cache = Vec::new();
+		for input in inputs.iter().filter(|input| input.name.starts_with("past")) {
+			let output = present_name_candidates(&input.name)
+				.into_iter()
+				.find_map(|candidate| outputs.iter().find(|output| output.name == candidate))
+				.ok_or_else(|| {
+					Error::new_with_code(ErrorCode::InvalidArgument, format!("Could not find the present output corresponding to cache input `{}`", input.name))
+				})?;
+			let ValueType::Tensor { ty, shape, dimension_symbols } = &input.input_type else {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cache input `{}` must be a tensor", input.name)));
+			};
+			let (batch_axis, sequence_axis) = cache_axes(shape, dimension_symbols);
+			cache.push(CacheEntry {
+				input: input.name.clone(),
+				output: output.name.clone(),
+				element_type: *ty,
+				shape: shape.to_vec(),
+				batch_axis,
+				sequence_axis,
+				cross_attention: input.name.contains("encoder") || input.name.contains("cross")
+			});
+		}
+
+		let logits = outputs
+			.iter()
+			.find(|output| output.name == "logits")
+			.or_else(|| outputs.iter().find(|output| !cache.iter().any(|entry| entry.output == output.name)))
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Decoder model has no logits output"))?;
+
+		let known = |name: &str| {
+			[Some(input_ids), attention_mask, position_ids, use_cache_branch, encoder_hidden_states, encoder_attention_mask]
+				.iter()
+				.flatten()
+				.any(|input| input.name == name)
+				|| cache.iter().any(|entry| entry.input == name)
+		};
+		if let Some(input) = inputs
+			.iter()
+			.find(|input| !known(&input.name) && !matches!(input.input_type, ValueType::Optional(_)))
+		{
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!("Decoder model has an unrecognized input `{}`; construct a `DecoderLayout` manually or use a different export", input.name)
+			));
+		}
+
+		Ok(Self {
+			input_ids: input_ids.name.clone(),
+			attention_mask: attention_mask.map(|input| input.name.clone()),
+			position_ids: position_ids.map(|input| input.name.clone()),
+			use_cache_branch: use_cache_branch.map(|input| input.name.clone()),
+			encoder_hidden_states: encoder_hidden_states.map(|input| input.name.clone()),
+			encoder_attention_mask: encoder_attention_mask.map(|input| input.name.clone()),
+			logits: logits.name.clone(),
+			cache
+		})
+	}
+}
+
+/// Element types of a decoder's integer inputs, which may be either `int64` or `int32` depending on the export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InputTypes {
+	pub(crate) input_ids: TensorElementType,
+	pub(crate) attention_mask: TensorElementType,
+	pub(crate) position_ids: TensorElementType,
+	pub(crate) encoder_attention_mask: TensorElementType
+}
+
+impl InputTypes {
+	pub(crate) fn resolve(layout: &DecoderLayout, inputs: &[Input]) -> Result<Self> {
+		let resolve = |name: Option<&String>| -> Result<TensorElementType> {
+			let Some(name) = name else {
+				return Ok(TensorElementType::Int64);
+			};
+			match inputs.iter().find(|input| &input.name == name).map(|input| &input.input_type) {
+				Some(ValueType::Tensor { ty, .. }) => Ok(*ty),
+				Some(ty) => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Expected model input `{name}` to be a tensor, but it is a {ty}"))),
+				None => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Model has no input named `{name}`")))
+			}
+		};
+		Ok(Self {
+			input_ids: resolve(Some(&layout.input_ids))?,
+			attention_mask: resolve(layout.attention_mask.as_ref())?,
+			position_ids: resolve(layout.position_ids.as_ref())?,
+			encoder_attention_mask: resolve(layout.encoder_attention_mask.as_ref())?
+		})
+	}
+}
+
+/// Returns possible names of the present output for a past input, e.g. `past_key_values.0.key` -> `present.0.key`,
+/// `past_0` -> `present_0`, `past_key.0` -> `present_key.0`.
+fn present_name_candidates(input: &str) -> Vec<String> {
+	let mut candidates = Vec::new();
+	if let Some(rest) = input.strip_prefix("past_key_values") {
+		candidates.push(format!("present{rest}"));
+		candidates.push(format!("present_key_values{rest}"));
+	}
+	if let Some(rest) = input.strip_prefix("past") {
+		candidates.push(format!("present{rest}"));
+	}
+	candidates
+}
+
+/// Determines the batch & sequence axes of a cache tensor from its symbolic dimension names, falling back to
+/// `[batch, num_heads, sequence, head_dim]` (or `[2, batch, num_heads, sequence, head_dim]` for rank 5).
+fn cache_axes(shape: &[i64], symbols: &[String]) -> (usize, usize) {
+	let symbol = |axis: usize| symbols.get(axis).map(|s| s.to_lowercase()).unwrap_or_default();
+	let rank = shape.len();
+	let sequence_axis = (0..rank)
+		.find(|&axis| {
+			let symbol = symbol(axis);
+			!symbol.contains("batch") && (symbol.contains("seq") || symbol.contains("past") || symbol.contains("length"))
+		})
+		.unwrap_or(rank.saturating_sub(2));
+	let batch_axis = (0..rank)
+		.find(|&axis| symbol(axis).contains("batch"))
+		.unwrap_or(if rank == 5 && shape[0] == 2 { 1 } else { 0 });
+	(batch_axis, sequence_axis)
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::String, vec::Vec};
+
+	use super::{DecoderLayout, InputTypes};
+	use crate::{
+		session::{Input, Output},
+		tensor::{SymbolicDimensions, TensorElementType},
+		value::ValueType
+	};
+
+	fn tensor(ty: TensorElementType, shape: &[i64], symbols: &[&str]) -> ValueType {
+		ValueType::Tensor {
+			ty,
+			shape: shape.into(),
+			dimension_symbols: SymbolicDimensions::new(symbols.iter().map(|s| String::from(*s)))
+		}
+	}
+
+	fn input(name: &str, input_type: ValueType) -> Input {
+		Input { name: name.into(), input_type }
+	}
+
+	fn output(name: &str, output_type: ValueType) -> Output {
+		Output { name: name.into(), output_type }
+	}
+
+	#[test]
+	fn test_detect_optimum() -> crate::Result<()> {
+		let kv = tensor(TensorElementType::Float32, &[-1, 12, -1, 64], &["batch_size", "", "past_sequence_length", ""]);
+		let mut inputs = vec![
+			input("input_ids", tensor(TensorElementType::Int64, &[-1, -1], &["batch_size", "sequence_length"])),
+			input("attention_mask", tensor(TensorElementType::Int64, &[-1, -1], &["batch_size", "total_sequence_length"])),
+		];
+		let mut outputs = vec![output("logits", tensor(TensorElementType::Float32, &[-1, -1, 50257], &["batch_size", "sequence_length", ""]))];
+		for layer in 0..2 {
+			for kind in ["key", "value"] {
+				inputs.push(input(&format!("past_key_values.{layer}.{kind}"), kv.clone()));
+				outputs.push(output(&format!("present.{layer}.{kind}"), kv.clone()));
+			}
+		}
+
+		let layout = DecoderLayout::from_io(&inputs, &outputs)?;
+		assert_eq!(layout.attention_mask.as_deref(), Some("attention_mask"));
+		assert_eq!(layout.position_ids, None);
+		assert_eq!(layout.logits, "logits");
+		assert_eq!(layout.cache.len(), 4);
+		assert_eq!(layout.cache[1].input, "past_key_values.0.value");
+		assert_eq!(layout.cache[1].output, "present.0.value");
+		assert_eq!((layout.cache[1].batch_axis, layout.cache[1].sequence_axis), (0, 2));
+		assert_eq!(layout.cache[1].empty_shape(1)?, [1, 12, 0, 64]);
+
+		inputs.push(input("token_type_ids", tensor(TensorElementType::Int64, &[-1, -1], &[])));
+		assert!(DecoderLayout::from_io(&inputs, &outputs).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_detect_legacy_gpt2() -> crate::Result<()> {
+		let kv = tensor(TensorElementType::Float16, &[2, -1, 12, -1, 64], &[]);
+		let inputs: Vec<Input> = [
+			input("input_ids", tensor(TensorElementType::Int32, &[-1, -1], &[])),
+			input("position_ids", tensor(TensorElementType::Int32, &[-1, -1], &[])),
+			input("past_0", kv.clone())
+		]
+		.into();
+		let outputs = [output("output_0", tensor(TensorElementType::Float16, &[-1, -1, 50257], &[])), output("present_0", kv)];
+
+		let layout = DecoderLayout::from_io(&inputs, &outputs)?;
+		assert_eq!(layout.logits, "output_0");
+		let types = InputTypes::resolve(&layout, &inputs)?;
+		assert_eq!((types.input_ids, types.attention_mask, types.position_ids), (TensorElementType::Int32, TensorElementType::Int64, TensorElementType::Int32));
+		assert_eq!(layout.cache[0].output, "present_0");
+		assert_eq!((layout.cache[0].batch_axis, layout.cache[0].sequence_axis), (1, 3));
+		assert_eq!(layout.cache[0].empty_shape(1)?, [2, 1, 12, 0, 64]);
+		Ok(())
+	}
+
+	#[test]
+	fn test_detect_encoder_decoder() -> crate::Result<()> {
+		let self_kv = tensor(TensorElementType::Float32, &[-1, 8, -1, 64], &["batch_size", "", "past_decoder_sequence_length", ""]);
+		let cross_kv = tensor(TensorElementType::Float32, &[-1, 8, -1, 64], &["batch_size", "", "encoder_sequence_length_out", ""]);
+		let ids = tensor(TensorElementType::Int64, &[-1, -1], &[]);
+		let inputs = [
+			input("encoder_attention_mask", ids.clone()),
+			input("input_ids", ids.clone()),
+			input("encoder_hidden_states", tensor(TensorElementType::Float32, &[-1, -1, 512], &[])),
+			input("past_key_values.0.decoder.key", self_kv.clone()),
+			input("past_key_values.0.encoder.key", cross_kv.clone()),
+			input("use_cache_branch", tensor(TensorElementType::Bool, &[1], &[]))
+		];
+		let outputs = [
+			output("logits", tensor(TensorElementType::Float32, &[-1, -1, 58101], &[])),
+			output("present.0.decoder.key", self_kv),
+			output("present.0.encoder.key", cross_kv)
+		];
+
+		let layout = DecoderLayout::from_io(&inputs, &outputs)?;
+		assert_eq!(layout.encoder_hidden_states.as_deref(), Some("encoder_hidden_states"));
+		assert_eq!(layout.encoder_attention_mask.as_deref(), Some("encoder_attention_mask"));
+		assert_eq!(layout.use_cache_branch.as_deref(), Some("use_cache_branch"));
+		assert_eq!(layout.cache.iter().map(|entry| entry.cross_attention).collect::<Vec<_>>(), [false, true]);
+		assert_eq!(layout.cache[1].output, "present.0.encoder.key");
+		assert_eq!(layout.cache[1].sequence_axis, 2);
+		Ok(())
+	}
+}
@@ -0,0 +1,448 @@
+//! Logits processors, which reshape the distribution of the next token before it is sampled.
+//!
+//! Processors are chained in a [`Pipeline`] ending in a [`Sampler`]; the pipeline itself is a [`Sampler`], so it can be
+//! passed to [`Generator::with_sampler`](super::Generator::with_sampler) or used on its own with
+//! [`Sampler::sample_tensor`].
+//!
+//! ```
+//! # use ort::generation::{Multinomial, Sampler, logits::{Pipeline, RepetitionPenalty, Temperature, TopK, TopP}};
+//! # fn main() -> ort::Result<()> {
+//! let mut sampler = Pipeline::new(Multinomial::seeded(42))
+//! 	.with_processor(RepetitionPenalty(1.2))
+//! 	.with_processor(Temperature(0.7))
+//! 	.with_processor(TopK(50))
+//! 	.with_processor(TopP(0.9));
+//!
+//! let mut logits = [0.5, 3.0, -1.0, 2.5];
+//! let token = sampler.sample(&[1, 3], &mut logits)?;
+//! # 	assert!(token == 1 || token == 3);
+//! # 	Ok(())
+//! # }
+//! ```
+//!
+//! Processors mask out tokens by setting their logits to `-inf`. Processors that operate on tokens (penalties, banned
+//! tokens, etc.) silently ignore token IDs outside of the vocabulary.
+
+use alloc::{boxed::Box, collections::BTreeMap, format, vec, vec::Vec};
+use core::cmp::Ordering;
+
+use super::sampling::{Greedy, Sampler, softmax};
+use crate::error::{Error, ErrorCode, Result};
+
+mod regex;
+
+pub use self::regex::RegexConstraint;
+
+/// Modifies the logits for the last position of the sequence in place.
+///
+/// `tokens` contains the full sequence so far (prompt & generated tokens). Like [`Sampler`], this trait is implemented
+/// for closures taking `(&[i64], &mut [f32])`.
+pub trait LogitsProcessor {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()>;
+}
+
+impl<F: FnMut(&[i64], &mut [f32]) -> Result<()>> LogitsProcessor for F {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		self(tokens, logits)
+	}
+}
+
+/// A chain of [`LogitsProcessor`]s, applied in the order they were added, followed by a [`Sampler`].
+pub struct Pipeline<S = Greedy> {
+	processors: Vec<Box<dyn LogitsProcessor>>,
+	sampler: S
+}
+
+impl Default for Pipeline<Greedy> {
+	fn default() -> Self {
+		Self::new(Greedy)
+	}
+}
+
+impl<S> Pipeline<S> {
+	/// Creates an empty pipeline which samples with `sampler`.
+	pub fn new(sampler: S) -> Self {
+		Self { processors: Vec::new(), sampler }
+	}
+
+	/// Appends a processor to the pipeline.
+	pub fn with_processor(mut self, processor: impl LogitsProcessor + 'static) -> Self {
+		self.processors.push(Box::new(processor));
+		self
+	}
+
+	/// Returns a mutable reference to the pipeline's sampler.
+	pub fn sampler_mut(&mut self) -> &mut S {
+		&mut self.sampler
+	}
+}
+
+impl<S> LogitsProcessor for Pipeline<S> {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		for processor in &mut self.processors {
+			processor.process(tokens, logits)?;
+		}
+		Ok(())
+	}
+}
+
+impl<S: Sampler> Sampler for Pipeline<S> {
+	fn sample(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<i64> {
+		self.process(tokens, logits)?;
+		self.sampler.sample(tokens, logits)
+	}
+}
+
+/// Divides logits by the temperature. Temperatures below 1 sharpen the distribution; temperatures above 1 flatten it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Temperature(pub f32);
+
+impl LogitsProcessor for Temperature {
+	fn process(&mut self, _: &[i64], logits: &mut [f32]) -> Result<()> {
+		if !(self.0 > 0.0 && self.0.is_finite()) {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Temperature must be positive, got {}", self.0)));
+		}
+		for logit in logits {
+			*logit /= self.0;
+		}
+		Ok(())
+	}
+}
+
+/// Keeps only the `k` tokens with the highest logits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopK(pub usize);
+
+impl LogitsProcessor for TopK {
+	fn process(&mut self, _: &[i64], logits: &mut [f32]) -> Result<()> {
+		if self.0 == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Top-k must keep at least 1 token"));
+		}
+		let mut order = candidates(logits);
+		let keep = self.0.min(order.len());
+		if keep < order.len() {
+			order.select_nth_unstable_by(keep - 1, |&a, &b| compare_descending(logits, a, b));
+		}
+		keep_only(logits, &order[..keep]);
+		Ok(())
+	}
+}
+
+/// Nucleus sampling: keeps the smallest set of most likely tokens whose cumulative probability is at least `p`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TopP(pub f32);
+
+impl LogitsProcessor for TopP {
+	fn process(&mut self, _: &[i64], logits: &mut [f32]) -> Result<()> {
+		if !(self.0 > 0.0 && self.0 <= 1.0) {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Top-p must be in (0, 1], got {}", self.0)));
+		}
+		let mut probabilities = logits.to_vec();
+		if !softmax(&mut probabilities) {
+			return Ok(());
+		}
+		let mut order = candidates(logits);
+		order.sort_unstable_by(|&a, &b| compare_descending(logits, a, b));
+		let mut cumulative = 0.0;
+		let keep = order
+			.iter()
+			.position(|&index| {
+				cumulative += probabilities[index];
+				cumulative >= self.0
+			})
+			.map_or(order.len(), |i| i + 1);
+		keep_only(logits, &order[..keep]);
+		Ok(())
+	}
+}
+
+/// Keeps only tokens whose probability is at least `p` times the probability of the most likely token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinP(pub f32);
+
+impl LogitsProcessor for MinP {
+	fn process(&mut self, _: &[i64], logits: &mut [f32]) -> Result<()> {
+		if !(0.0..=1.0).contains(&self.0) {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Min-p must be in [0, 1], got {}", self.0)));
+		}
+		let mut probabilities = logits.to_vec();
+		if !softmax(&mut probabilities) {
+			return Ok(());
+		}
+		let threshold = probabilities.iter().copied().fold(0.0, f32::max) * self.0;
+		for (logit, p) in logits.iter_mut().zip(probabilities) {
+			if p < threshold {
+				*logit = f32::NEG_INFINITY;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Penalizes tokens which already appear in the sequence, as described in the
+/// [CTRL paper](https://arxiv.org/abs/1909.05858): positive logits are divided by the penalty, negative logits are
+/// multiplied by it. Penalties above 1 discourage repetition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepetitionPenalty(pub f32);
+
+impl LogitsProcessor for RepetitionPenalty {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		if !(self.0 > 0.0 && self.0.is_finite()) {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Repetition penalty must be positive, got {}", self.0)));
+		}
+		for (index, _) in token_counts(tokens, logits.len()) {
+			let logit = &mut logits[index];
+			*logit = if *logit > 0.0 { *logit / self.0 } else { *logit * self.0 };
+		}
+		Ok(())
+	}
+}
+
+/// Subtracts a fixed penalty from the logits of every token which appears in the sequence, regardless of how often.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresencePenalty(pub f32);
+
+impl LogitsProcessor for PresencePenalty {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		for (index, _) in token_counts(tokens, logits.len()) {
+			logits[index] -= self.0;
+		}
+		Ok(())
+	}
+}
+
+/// Subtracts a penalty proportional to the number of times each token appears in the sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyPenalty(pub f32);
+
+impl LogitsProcessor for FrequencyPenalty {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		for (index, count) in token_counts(tokens, logits.len()) {
+			logits[index] -= self.0 * count as f32;
+		}
+		Ok(())
+	}
+}
+
+/// Prevents any n-gram of the given size from appearing twice in the sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoRepeatNGram(pub usize);
+
+impl LogitsProcessor for NoRepeatNGram {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		let n = self.0;
+		if n == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "N-gram size must be at least 1"));
+		}
+		if tokens.len() < n {
+			return Ok(());
+		}
+		let prefix = &tokens[tokens.len() - (n - 1)..];
+		for window in tokens.windows(n) {
+			if window[..n - 1] == *prefix {
+				mask(logits, window[n - 1]);
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Forces specific tokens to be generated at specific positions in the sequence.
+///
+/// Positions are indices into the full sequence (including the prompt), i.e. a token forced at position `p` is the one
+/// sampled when the sequence has length `p`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ForcedTokens {
+	tokens: BTreeMap<usize, i64>
+}
+
+impl ForcedTokens {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Forces `token` to be generated at `position`.
+	pub fn with_token(mut self, position: usize, token: i64) -> Self {
+		self.tokens.insert(position, token);
+		self
+	}
+}
+
+impl LogitsProcessor for ForcedTokens {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		let Some(&token) = self.tokens.get(&tokens.len()) else {
+			return Ok(());
+		};
+		let index = vocab_index(token, logits.len())
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Forced token {token} is outside of the vocabulary")))?;
+		logits.fill(f32::NEG_INFINITY);
+		logits[index] = 0.0;
+		Ok(())
+	}
+}
+
+/// Prevents the given tokens from ever being generated.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BannedTokens(pub Vec<i64>);
+
+impl LogitsProcessor for BannedTokens {
+	fn process(&mut self, _: &[i64], logits: &mut [f32]) -> Result<()> {
+		for &token in &self.0 {
+			mask(logits, token);
+		}
+		Ok(())
+	}
+}
+
+/// Restricts generation to the tokens allowed by a callback, which receives the sequence so far and a mask
+/// (initially all `true`) to clear for disallowed tokens.
+///
+/// This is the building block for grammar-constrained generation: drive your grammar's parser with the tokens
+/// generated so far and allow only the tokens it accepts next. For regular languages, see [`RegexConstraint`].
+pub struct TokenMask<F> {
+	mask: F,
+	allowed: Vec<bool>
+}
+
+impl<F: FnMut(&[i64], &mut [bool]) -> Result<()>> TokenMask<F> {
+	pub fn new(mask: F) -> Self {
+		Self { mask, allowed: Vec::new() }
+	}
+}
+
+impl<F: FnMut(&[i64], &mut [bool]) -> Result<()>> LogitsProcessor for TokenMask<F> {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		self.allowed.clear();
+		self.allowed.resize(logits.len(), true);
+		(self.mask)(tokens, &mut self.allowed)?;
+		for (logit, &allowed) in logits.iter_mut().zip(&self.allowed) {
+			if !allowed {
+				*logit = f32::NEG_INFINITY;
+			}
+		}
+		Ok(())
+	}
+}
+
+fn vocab_index(token: i64, vocab_size: usize) -> Option<usize> {
+	usize::try_from(token).ok().filter(|&index| index < vocab_size)
+}
+
+fn mask(logits: &mut [f32], token: i64) {
+	if let Some(index) = vocab_index(token, logits.len()) {
+		logits[index] = f32::NEG_INFINITY;
+	}
+}
+
+/// Returns each distinct in-vocabulary token in `tokens` with the number of times it occurs.
+fn token_counts(tokens: &[i64], vocab_size: usize) -> impl Iterator<Item = (usize, usize)> {
+	let mut sorted: Vec<usize> = tokens.iter().filter_map(|&token| vocab_index(token, vocab_size)).collect();
+	sorted.sort_unstable();
+	let mut counts = vec![];
+	for chunk in sorted.chunk_by(|a, b| a == b) {
+		counts.push((chunk[0], chunk.len()));
+	}
+	counts.into_iter()
+}
+
+/// Returns the indices of all logits which are neither masked nor `NaN`.
+fn candidates(logits: &[f32]) -> Vec<usize> {
+	(0..logits.len()).filter(|&i| logits[i] > f32::NEG_INFINITY).collect()
+}
+
+/// Masks all logits except those at `indices`.
+fn keep_only(logits: &mut [f32], indices: &[usize]) {
+	let kept: Vec<f32> = indices.iter().map(|&i| logits[i]).collect();
+	logits.fill(f32::NEG_INFINITY);
+	for (&i, logit) in indices.iter().zip(kept) {
+		logits[i] = logit;
+	}
+}
+
+/// Orders indices by descending logit, breaking ties by ascending index.
+fn compare_descending(logits: &[f32], a: usize, b: usize) -> Ordering {
+	logits[b].total_cmp(&logits[a]).then(a.cmp(&b))
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec::Vec;
+
+	use super::*;
+	use crate::generation::Multinomial;
+
+	const NEG_INF: f32 = f32::NEG_INFINITY;
+
+	fn process(mut processor: impl LogitsProcessor, tokens: &[i64], logits: &[f32]) -> Vec<f32> {
+		let mut logits = logits.to_vec();
+		processor.process(tokens, &mut logits).expect("processing failed");
+		logits
+	}
+
+	#[test]
+	fn test_truncation() {
+		let logits = [1.0, 3.0, NEG_INF, 3.0, 2.0, f32::NAN];
+		assert_eq!(process(TopK(2), &[], &logits), [NEG_INF, 3.0, NEG_INF, 3.0, NEG_INF, NEG_INF]);
+		assert_eq!(process(TopK(1), &[], &logits), [NEG_INF, 3.0, NEG_INF, NEG_INF, NEG_INF, NEG_INF]);
+		assert_eq!(process(TopK(10), &[], &[1.0, 2.0]), [1.0, 2.0]);
+		assert!(TopK(0).process(&[], &mut [1.0]).is_err());
+
+		// probabilities: ~0.64, ~0.24, ~0.09, ~0.03
+		let logits = [3.0, 2.0, 1.0, 0.0];
+		assert_eq!(process(TopP(0.5), &[], &logits), [3.0, NEG_INF, NEG_INF, NEG_INF]);
+		assert_eq!(process(TopP(0.8), &[], &logits), [3.0, 2.0, NEG_INF, NEG_INF]);
+		assert_eq!(process(TopP(1.0), &[], &logits), logits);
+		assert_eq!(process(MinP(0.3), &[], &logits), [3.0, 2.0, NEG_INF, NEG_INF]);
+		assert_eq!(process(MinP(0.0), &[], &logits), logits);
+
+		assert_eq!(process(Temperature(0.5), &[], &[1.0, -2.0]), [2.0, -4.0]);
+		assert!(Temperature(0.0).process(&[], &mut [1.0]).is_err());
+	}
+
+	#[test]
+	fn test_penalties() {
+		let logits = [2.0, -2.0, 1.0, 0.5];
+		let tokens = [0, 1, 1, 7, -1];
+		assert_eq!(process(RepetitionPenalty(2.0), &tokens, &logits), [1.0, -4.0, 1.0, 0.5]);
+		assert_eq!(process(PresencePenalty(0.5), &tokens, &logits), [1.5, -2.5, 1.0, 0.5]);
+		assert_eq!(process(FrequencyPenalty(0.5), &tokens, &logits), [1.5, -3.0, 1.0, 0.5]);
+	}
+
+	#[test]
+	fn test_token_constraints() {
+		let logits = [0.0; 5];
+		// the sequence ends in `1 2`, and `1 2 3` has been seen before
+		assert_eq!(process(NoRepeatNGram(3), &[1, 2, 3, 4, 1, 2], &logits), [0.0, 0.0, 0.0, NEG_INF, 0.0]);
+		assert_eq!(process(NoRepeatNGram(1), &[0, 4], &logits), [NEG_INF, 0.0, 0.0, 0.0, NEG_INF]);
+		assert_eq!(process(NoRepeatNGram(3), &[1, 2], &logits), logits);
+
+		assert_eq!(process(BannedTokens(vec![1, 3, 99]), &[], &logits), [0.0, NEG_INF, 0.0, NEG_INF, 0.0]);
+
+		let forced = ForcedTokens::new().with_token(2, 4);
+		assert_eq!(process(forced.clone(), &[0], &logits), logits);
+		assert_eq!(process(forced.clone(), &[0, 0], &logits), [NEG_INF, NEG_INF, NEG_INF, NEG_INF, 0.0]);
+		assert!(ForcedTokens::new().with_token(0, 5).process(&[], &mut [0.0; 5]).is_err());
+
+		let even = TokenMask::new(|_: &[i64], allowed: &mut [bool]| {
+			for (i, allowed) in allowed.iter_mut().enumerate() {
+				*allowed = i % 2 == 0;
+			}
+			Ok(())
+		});
+		assert_eq!(process(even, &[], &logits), [0.0, NEG_INF, 0.0, NEG_INF, 0.0]);
+	}
+
+	#[test]
+	fn test_pipeline() -> crate::Result<()> {
+		let mut pipeline = Pipeline::default()
+			.with_processor(BannedTokens(vec![2]))
+			.with_processor(RepetitionPenalty(10.0));
+		assert_eq!(pipeline.sample(&[1], &mut [0.0, 2.0, 3.0, 1.0])?, 3);
+
+		let mut pipeline = Pipeline::new(Multinomial::seeded(1)).with_processor(TopK(2));
+		for _ in 0..100 {
+			assert!(matches!(pipeline.sample(&[], &mut [0.0, 2.0, 3.0, 1.0])?, 1 | 2));
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,579 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{iter::Peekable, str::Chars};
+
+use super::LogitsProcessor;
+use crate::error::{Error, ErrorCode, Result};
+
+/// Constrains generated text to match a regular expression.
+///
+/// The generated text (excluding the prompt) is checked against the pattern after every token; a token is only allowed
+/// if the text including it is still a prefix of some match. EOS tokens are only allowed once the text is a complete
+/// match. The pattern is implicitly anchored at both ends.
+///
+/// The supported syntax is a subset of common regex syntax: literals, `.`, character classes (`[a-z]`, `[^0-9]`, `\d`,
+/// `\w`, `\s` and their negations), groups (`(...)`, `(?:...)`), alternation (`|`), and the quantifiers `*`, `+`,
+/// `?`, `{n}`, `{n,}` and `{n,m}`. Quantifiers can't be stacked (as in `a{2}{3}`); wrap the inner repetition in a group
+/// instead.
+///
+/// The constraint needs the text of every token in the vocabulary, indexed by token ID. Make sure these are the
+/// *decoded* strings (e.g. with GPT-2's `Ġ` replaced by a space). Tokens with no text (like special tokens) are never
+/// allowed, except for EOS tokens.
+///
+/// ```
+/// # use ort::generation::logits::{LogitsProcessor, RegexConstraint};
+/// # fn main() -> ort::Result<()> {
+/// let vocab = ["<eos>", "1", "23", "-", "a", "4-"];
+/// let mut constraint = RegexConstraint::new(r"\d+-\d+", vocab)?.with_eos_token_id(0);
+///
+/// let mut logits = [0.0; 6];
+/// // the first call marks the start of generation; here, the prompt is `[4]`
+/// constraint.process(&[4], &mut logits)?;
+/// assert_eq!(logits.map(f32::is_finite), [false, true, true, false, false, true]);
+/// # 	Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexConstraint {
+	program: Program,
+	vocab: Vec<String>,
+	eos_token_ids: Vec<i64>,
+	prompt_len: Option<usize>
+}
+
+impl RegexConstraint {
+	/// Compiles `pattern` for a vocabulary where `vocab[id]` is the text of token `id`.
+	///
+	/// Returns an error if the pattern is invalid, nests groups too deeply, or expands to too large of an automaton
+	/// (for example, because of nested counted repetitions like `((a{1000}){1000}){1000}`).
+	pub fn new(pattern: &str, vocab: impl IntoIterator<Item = impl Into<String>>) -> Result<Self> {
+		Ok(Self {
+			program: Program::compile(&Parser::parse(pattern)?)?,
+			vocab: vocab.into_iter().map(Into::into).collect(),
+			eos_token_ids: Vec::new(),
+			prompt_len: None
+		})
+	}
+
+	/// Allows `token` to end generation once the generated text fully matches the pattern.
+	pub fn with_eos_token_id(mut self, token: i64) -> Self {
+		self.eos_token_ids.push(token);
+		self
+	}
+
+	/// Forgets where the generated text starts, so the constraint can be reused for a new sequence. The next call to
+	/// [`LogitsProcessor::process`] marks the new start of generation.
+	pub fn reset(&mut self) {
+		self.prompt_len = None;
+	}
+}
+
+impl LogitsProcessor for RegexConstraint {
+	fn process(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<()> {
+		// if the sequence was truncated past the start of generation, treat it as entirely generated
+		let prompt_len = (*self.prompt_len.get_or_insert(tokens.len())).min(tokens.len());
+		let mut threads = Threads::new(self.program.insts.len());
+
+		let mut states = self.program.start(&mut threads);
+		let mut next = Vec::new();
+		for &token in &tokens[prompt_len..] {
+			if self.eos_token_ids.contains(&token) {
+				continue;
+			}
+			let text = usize::try_from(token)
+				.ok()
+				.and_then(|index| self.vocab.get(index))
+				.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Token {token} is outside of the constraint's vocabulary")))?;
+			for c in text.chars() {
+				self.program.step(&states, c, &mut next, &mut threads);
+				core::mem::swap(&mut states, &mut next);
+			}
+		}
+		let is_match = self.program.is_match(&states);
+
+		let mut current = Vec::new();
+		for (index, logit) in logits.iter_mut().enumerate() {
+			let allowed = if self.eos_token_ids.contains(&(index as i64)) {
+				is_match
+			} else {
+				match self.vocab.get(index).filter(|text| !text.is_empty()) {
+					Some(text) => {
+						current.clone_from(&states);
+						text.chars().all(|c| {
+							self.program.step(&current, c, &mut next, &mut threads);
+							core::mem::swap(&mut current, &mut next);
+							!current.is_empty()
+						})
+					}
+					None => false
+				}
+			};
+			if !allowed {
+				*logit = f32::NEG_INFINITY;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Class {
+	ranges: Vec<(char, char)>,
+	negated: bool
+}
+
+impl Class {
+	fn literal(c: char) -> Self {
+		Self { ranges: vec![(c, c)], negated: false }
+	}
+
+	fn matches(&self, c: char) -> bool {
+		self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+	Class(Class),
+	Concat(Vec<Node>),
+	Alternate(Vec<Node>),
+	Repeat { node: Box<Node>, min: u32, max: Option<u32> }
+}
+
+/// Upper bound on counted repetitions, which are expanded when compiling.
+const MAX_REPEAT: u32 = 1000;
+/// Upper bound on how deeply groups can be nested.
+const MAX_DEPTH: usize = 128;
+/// Upper bound on the number of instructions in a compiled pattern, since nested repetitions multiply.
+const MAX_PROGRAM_SIZE: usize = 100_000;
+
+struct Parser<'p> {
+	chars: Peekable<Chars<'p>>,
+	depth: usize
+}
+
+impl Parser<'_> {
+	fn parse(pattern: &str) -> Result<Node> {
+		// the pattern is always anchored
+		let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+		let pattern = pattern.strip_suffix('$').filter(|p| !p.ends_with('\\')).unwrap_or(pattern);
+		let mut parser = Parser {
+			chars: pattern.chars().peekable(),
+			depth: 0
+		};
+		let node = parser.alternate()?;
+		match parser.chars.next() {
+			None => Ok(node),
+			Some(c) => Err(regex_error(format!("unexpected `{c}`")))
+		}
+	}
+
+	fn alternate(&mut self) -> Result<Node> {
+		let mut alternatives = vec![self.concat()?];
+		while self.chars.next_if_eq(&'|').is_some() {
+			alternatives.push(self.concat()?);
+		}
+		Ok(if alternatives.len() == 1 { alternatives.remove(0) } else { Node::Alternate(alternatives) })
+	}
+
+	fn concat(&mut self) -> Result<Node> {
+		let mut nodes = Vec::new();
+		while let Some(&c) = self.chars.peek() {
+			if c == '|' || c == ')' {
+				break;
+			}
+			let mut node = self.atom()?;
+			if let Some((min, max)) = self.quantifier()? {
+				node = Node::Repeat { node: Box::new(node), min, max };
+				if matches!(self.chars.peek(), Some('*' | '+' | '?' | '{')) {
+					return Err(regex_error("quantifiers cannot be stacked; wrap the repeated expression in a group instead"));
+				}
+			}
+			nodes.push(node);
+		}
+		Ok(Node::Concat(nodes))
+	}
+
+	fn atom(&mut self) -> Result<Node> {
+		let c = self.chars.next().ok_or_else(|| regex_error("unexpected end of pattern"))?;
+		Ok(Node::Class(match c {
+			'(' => {
+				if self.chars.next_if_eq(&'?').is_some() && self.chars.next_if_eq(&':').is_none() {
+					return Err(regex_error("only non-capturing groups (`(?:...)`) are supported"));
+				}
+				if self.depth == MAX_DEPTH {
+					return Err(regex_error(format!("groups are nested more than {MAX_DEPTH} levels deep")));
+				}
+				self.depth += 1;
+				let node = self.alternate()?;
+				self.depth -= 1;
+				if self.chars.next_if_eq(&')').is_none() {
+					return Err(regex_error("unclosed group"));
+				}
+				return Ok(node);
+			}
+			'[' => self.class()?,
+			'.' => Class {
+				ranges: vec![('\n', '\n')],
+				negated: true
+			},
+			'\\' => self.escape()?,
+			'*' | '+' | '?' | '{' => return Err(regex_error(format!("`{c}` must follow an expression"))),
+			'^' | '$' => return Err(regex_error("anchors are only supported at the start & end of the pattern")),
+			c => Class::literal(c)
+		}))
+	}
+
+	fn escape(&mut self) -> Result<Class> {
+		let c = self.chars.next().ok_or_else(|| regex_error("unexpected end of pattern after `\\`"))?;
+		let (ranges, negated): (&[(char, char)], bool) = match c {
+			'd' => (DIGIT, false),
+			'D' => (DIGIT, true),
+			'w' => (WORD, false),
+			'W' => (WORD, true),
+			's' => (SPACE, false),
+			'S' => (SPACE, true),
+			'n' => return Ok(Class::literal('\n')),
+			'r' => return Ok(Class::literal('\r')),
+			't' => return Ok(Class::literal('\t')),
+			c if c.is_ascii_alphanumeric() => return Err(regex_error(format!("unsupported escape `\\{c}`"))),
+			c => return Ok(Class::literal(c))
+		};
+		Ok(Class { ranges: ranges.to_vec(), negated })
+	}
+
+	fn class(&mut self) -> Result<Class> {
+		let negated = self.chars.next_if_eq(&'^').is_some();
+		let mut ranges = Vec::new();
+		let mut first = true;
+		loop {
+			let c = self.chars.next().ok_or_else(|| regex_error("unclosed character class"))?;
+			let lo = match c {
+				']' if !first => break,
+				'\\' => {
+					let class = self.escape()?;
+					if class.negated {
+						return Err(regex_error("negated escapes are not supported inside character classes"));
+					}
+					if class.ranges.len() > 1 || class.ranges[0].0 != class.ranges[0].1 {
+						ranges.extend(class.ranges);
+						first = false;
+						continue;
+					}
+					class.ranges[0].0
+				}
+				c => c
+			};
+			first = false;
+			if self.chars.peek() == Some(&'-') {
+				let mut lookahead = self.chars.clone();
+				lookahead.next();
+				if lookahead.peek().is_some_and(|&c| c != ']') {
+					self.chars.next();
+					let hi = match self.chars.next() {
+						Some('\\') => {
+							let class = self.escape()?;
+							match class.ranges[..] {
+								[(c, d)] if c == d && !class.negated => c,
+								_ => return Err(regex_error("invalid range in character class"))
+							}
+						}
+						Some(c) => c,
+						None => return Err(regex_error("unclosed character class"))
+					};
+					if hi < lo {
+						return Err(regex_error(format!("invalid range `{lo}-{hi}` in character class")));
+					}
+					ranges.push((lo, hi));
+					continue;
+				}
+			}
+			ranges.push((lo, lo));
+		}
+		Ok(Class { ranges, negated })
+	}
+
+	fn quantifier(&mut self) -> Result<Option<(u32, Option<u32>)>> {
+		let quantifier = match self.chars.peek() {
+			Some('*') => (0, None),
+			Some('+') => (1, None),
+			Some('?') => (0, Some(1)),
+			Some('{') => {
+				self.chars.next();
+				let min = self.number()?.ok_or_else(|| regex_error("expected a number after `{`"))?;
+				let max = if self.chars.next_if_eq(&',').is_some() { self.number()? } else { Some(min) };
+				if self.chars.next_if_eq(&'}').is_none() {
+					return Err(regex_error("unclosed counted repetition"));
+				}
+				if max.is_some_and(|max| max < min) || min.max(max.unwrap_or(0)) > MAX_REPEAT {
+					return Err(regex_error(format!("invalid counted repetition; counts must be ordered and at most {MAX_REPEAT}")));
+				}
+				return Ok(Some((min, max)));
+			}
+			_ => return Ok(None)
+		};
+		self.chars.next();
+		Ok(Some(quantifier))
+	}
+
+	fn number(&mut self) -> Result<Option<u32>> {
+		let mut number: Option<u32> = None;
+		while let Some(digit) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+			self.chars.next();
+			number = Some(
+				number
+					.unwrap_or(0)
+					.checked_mul(10)
+					.and_then(|n| n.checked_add(digit))
+					.ok_or_else(|| regex_error("counted repetition is too large"))?
+			);
+		}
+		Ok(number)
+	}
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+fn regex_error(message: impl core::fmt::Display) -> Error {
+	Error::new_with_code(ErrorCode::InvalidArgument, format!("Invalid regex: {message}"))
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+	Class(Class),
+	Split(usize, usize),
+	Jump(usize),
+	Match
+}
+
+/// A Thompson NFA, simulated by tracking the set of all states the automaton could be in.
+#[derive(Debug, Clone)]
+struct Program {
+	insts: Vec<Inst>
+}
+
+impl Program {
+	fn compile(node: &Node) -> Result<Self> {
+		let mut program = Self { insts: Vec::new() };
+		// every `emit` call is counted, not just the instructions it adds, since repeating an empty group adds none
+		let mut budget = MAX_PROGRAM_SIZE;
+		program.emit(node, &mut budget)?;
+		program.insts.push(Inst::Match);
+		Ok(program)
+	}
+
+	fn emit(&mut self, node: &Node, budget: &mut usize) -> Result<()> {
+		if self.insts.len() >= MAX_PROGRAM_SIZE || *budget == 0 {
+			return Err(regex_error(format!("pattern compiles to more than {MAX_PROGRAM_SIZE} instructions")));
+		}
+		*budget -= 1;
+		match node {
+			Node::Class(class) => self.insts.push(Inst::Class(class.clone())),
+			Node::Concat(nodes) => {
+				for node in nodes {
+					self.emit(node, budget)?;
+				}
+			}
+			Node::Alternate(nodes) => {
+				let mut jumps = Vec::new();
+				for (i, node) in nodes.iter().enumerate() {
+					if i == nodes.len() - 1 {
+						self.emit(node, budget)?;
+						break;
+					}
+					let split = self.placeholder();
+					self.emit(node, budget)?;
+					jumps.push(self.placeholder());
+					self.insts[split] = Inst::Split(split + 1, self.insts.len());
+				}
+				let end = self.insts.len();
+				for jump in jumps {
+					self.insts[jump] = Inst::Jump(end);
+				}
+			}
+			Node::Repeat { node, min, max } => {
+				for _ in 0..*min {
+					self.emit(node, budget)?;
+				}
+				match max {
+					None => {
+						let split = self.placeholder();
+						self.emit(node, budget)?;
+						self.insts.push(Inst::Jump(split));
+						self.insts[split] = Inst::Split(split + 1, self.insts.len());
+					}
+					Some(max) => {
+						for _ in *min..*max {
+							let split = self.placeholder();
+							self.emit(node, budget)?;
+							self.insts[split] = Inst::Split(split + 1, self.insts.len());
+						}
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn placeholder(&mut self) -> usize {
+		self.insts.push(Inst::Match);
+		self.insts.len() - 1
+	}
+
+	fn start(&self, threads: &mut Threads) -> Vec<usize> {
+		let mut states = Vec::new();
+		self.add(&mut states, 0, threads);
+		threads.clear(&states);
+		states
+	}
+
+	/// Advances every state in `states` over `c`, storing the resulting states in `next`.
+	fn step(&self, states: &[usize], c: char, next: &mut Vec<usize>, threads: &mut Threads) {
+		next.clear();
+		for &pc in states {
+			if let Inst::Class(class) = &self.insts[pc] {
+				if class.matches(c) {
+					self.add(next, pc + 1, threads);
+				}
+			}
+		}
+		threads.clear(next);
+	}
+
+	fn is_match(&self, states: &[usize]) -> bool {
+		states.iter().any(|&pc| matches!(self.insts[pc], Inst::Match))
+	}
+
+	/// Adds `pc` and every state reachable from it without consuming a character.
+	fn add(&self, states: &mut Vec<usize>, pc: usize, threads: &mut Threads) {
+		// epsilon chains can be as long as the program, so they're followed with an explicit stack
+		threads.stack.push(pc);
+		while let Some(pc) = threads.stack.pop() {
+			if threads.visited[pc] {
+				continue;
+			}
+			threads.visited[pc] = true;
+			// epsilon transitions are marked as visited but not added to `states`, so they're tracked separately for
+			// clearing
+			match self.insts[pc] {
+				Inst::Jump(target) => {
+					threads.epsilon.push(pc);
+					threads.stack.push(target);
+				}
+				Inst::Split(a, b) => {
+					threads.epsilon.push(pc);
+					threads.stack.push(b);
+					threads.stack.push(a);
+				}
+				Inst::Class(_) | Inst::Match => states.push(pc)
+			}
+		}
+	}
+}
+
+/// Scratch space for [`Program`] simulation.
+struct Threads {
+	visited: Vec<bool>,
+	epsilon: Vec<usize>,
+	stack: Vec<usize>
+}
+
+impl Threads {
+	fn new(len: usize) -> Self {
+		Self {
+			visited: vec![false; len],
+			epsilon: Vec::new(),
+			stack: Vec::new()
+		}
+	}
+
+	fn clear(&mut self, states: &[usize]) {
+		for &pc in states.iter().chain(&self.epsilon) {
+			self.visited[pc] = false;
+		}
+		self.epsilon.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{format, vec::Vec};
+
+	use super::{Parser, Program, RegexConstraint, Threads};
+	use crate::generation::logits::LogitsProcessor;
+
+	fn full_match(pattern: &str, text: &str) -> Option<bool> {
+		let program = Program::compile(&Parser::parse(pattern).expect("invalid pattern")).expect("pattern too large");
+		let mut threads = Threads::new(program.insts.len());
+		let mut states = program.start(&mut threads);
+		let mut next = Vec::new();
+		for c in text.chars() {
+			program.step(&states, c, &mut next, &mut threads);
+			core::mem::swap(&mut states, &mut next);
+			if states.is_empty() {
+				return None;
+			}
+		}
+		Some(program.is_match(&states))
+	}
+
+	#[test]
+	fn test_regex() {
+		assert_eq!(full_match("abc", "abc"), Some(true));
+		assert_eq!(full_match("abc", "ab"), Some(false));
+		assert_eq!(full_match("abc", "abd"), None);
+		assert_eq!(full_match("^(yes|no)$", "no"), Some(true));
+		assert_eq!(full_match("(?:yes|no)", "n"), Some(false));
+		assert_eq!(full_match(r"\d{2,3}", "1234"), None);
+		assert_eq!(full_match(r"\d{2,3}", "123"), Some(true));
+		assert_eq!(full_match(r"\d{2,}", "12345"), Some(true));
+		assert_eq!(full_match("[a-c_-]+x?", "a-b_cx"), Some(true));
+		assert_eq!(full_match(r"[^\d]*", "abc1"), None);
+		assert_eq!(full_match(r"\w+\s\W", "hi !"), Some(true));
+		assert_eq!(full_match("(a*)*b", "aaab"), Some(true));
+		assert_eq!(full_match(".*", "anything at all"), Some(true));
+		assert_eq!(full_match(r"\.", "a"), None);
+		assert_eq!(full_match("", ""), Some(true));
+
+		for pattern in ["(", "a)", "[a", "*", "a{3,1}", "a{2000}", r"\q", "(?=a)", "[z-a]", "a^", "a**", "a{2}{3}"] {
+			assert!(Parser::parse(pattern).is_err(), "{pattern}");
+		}
+	}
+
+	#[test]
+	fn test_regex_limits() {
+		// a long chain of optional states
+		assert_eq!(full_match("((a?){150}){150}b", "ab"), Some(true));
+		// nested repetitions multiply
+		for pattern in ["((a{1000}){1000}){1000}", "((){1000}){1000}", "(a{1000}b{1000}){1000}"] {
+			assert!(RegexConstraint::new(pattern, [""]).is_err(), "{pattern}");
+		}
+
+		let nested = format!("{}a{}", "(".repeat(128), ")".repeat(128));
+		assert_eq!(full_match(&nested, "a"), Some(true));
+		let nested = format!("{}a{}", "(".repeat(129), ")".repeat(129));
+		assert!(Parser::parse(&nested).is_err());
+		assert!(Parser::parse(&"(".repeat(100_000)).is_err());
+	}
+
+	#[test]
+	fn test_regex_constraint() -> crate::Result<()> {
+		let vocab = ["<eos>", "tr", "ue", "f", "alse", "true", "x", ""];
+		let mut constraint = RegexConstraint::new("true|false", vocab)?.with_eos_token_id(0);
+		let allowed = |constraint: &mut RegexConstraint, tokens: &[i64]| -> crate::Result<Vec<usize>> {
+			let mut logits = [0.0; 9];
+			constraint.process(tokens, &mut logits)?;
+			Ok((0..logits.len()).filter(|&i| logits[i] == 0.0).collect())
+		};
+		// prompt is `[6, 6]`
+		assert_eq!(allowed(&mut constraint, &[6, 6])?, [1, 3, 5]);
+		assert_eq!(allowed(&mut constraint, &[6, 6, 1])?, [2]);
+		assert_eq!(allowed(&mut constraint, &[6, 6, 1, 2])?, [0]);
+
+		constraint.reset();
+		assert_eq!(allowed(&mut constraint, &[])?, [1, 3, 5]);
+		assert_eq!(allowed(&mut constraint, &[3])?, [4]);
+		Ok(())
+	}
+}
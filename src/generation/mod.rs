@@ -0,0 +1,370 @@
+//! Autoregressive text generation with language models.
+//!
+//! [`Generator`] drives a decoder-only model exported to ONNX with a KV cache (e.g. via Hugging Face Optimum or ONNX
+//! Runtime GenAI). The model's inputs & outputs are detected automatically (see [`DecoderLayout`]); only the new tokens
+//! are fed to the model at each step, with the past key/values kept bound to an [`IoBinding`] between steps.
+//!
+//! Tokens are picked by a [`Sampler`]; see the [`logits`] module for processors like temperature & top-k. For beam
+//! search, including encoder-decoder models, see [`beam`].
+//!
+//! ```no_run
+//! # use ort::{generation::{GenerationOptions, Generator}, session::Session};
+//! # fn main() -> ort::Result<()> {
+//! let session = Session::builder()?.commit_from_file("gpt2.onnx")?;
+//! let mut generator =
+//! 	Generator::new(&session, GenerationOptions::default().with_max_tokens(64).with_eos_token_id(50256))?;
+//!
+//! // token IDs from your tokenizer of choice
+//! generator.push_tokens(&[464, 3139, 318])?;
+//! for token in generator.generate() {
+//! 	let token = token?;
+//! 	// decode & print the token...
+//! }
+//! println!("stopped because of {:?}", generator.stop_reason());
+//! # 	Ok(())
+//! # }
+//! ```
+
+use alloc::{boxed::Box, format, vec, vec::Vec};
+use core::iter;
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	io_binding::IoBinding,
+	memory::MemoryInfo,
+	session::Session,
+	tensor::TensorElementType,
+	value::{DynTensor, DynTensorValueType, DynValue, Tensor}
+};
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod beam;
+mod cache;
+mod layout;
+pub mod logits;
+pub mod sampling;
+
+use self::{cache::KvCache, layout::InputTypes};
+pub use self::{
+	layout::{CacheEntry, DecoderLayout},
+	sampling::{Greedy, Multinomial, Rng, Sampler}
+};
+
+/// Stop conditions for [`Generator::generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationOptions {
+	max_tokens: usize,
+	max_length: Option<usize>,
+	eos_token_ids: Vec<i64>,
+	stop_sequences: Vec<Vec<i64>>
+}
+
+impl Default for GenerationOptions {
+	fn default() -> Self {
+		Self {
+			max_tokens: 256,
+			max_length: None,
+			eos_token_ids: Vec::new(),
+			stop_sequences: Vec::new()
+		}
+	}
+}
+
+impl GenerationOptions {
+	/// Sets the maximum number of tokens to generate per call to [`Generator::generate`]. Defaults to 256.
+	pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+		self.max_tokens = max_tokens;
+		self
+	}
+
+	/// Sets the maximum length of the entire sequence, including the prompt. This should usually be set to the
+	/// context length of the model.
+	pub fn with_max_length(mut self, max_length: usize) -> Self {
+		self.max_length = Some(max_length);
+		self
+	}
+
+	/// Stops generation when the given token is generated. The token is not yielded by [`Generator::generate`].
+	pub fn with_eos_token_id(mut self, token: i64) -> Self {
+		self.eos_token_ids.push(token);
+		self
+	}
+
+	/// Stops generation when any of the given tokens is generated. The token is not yielded by
+	/// [`Generator::generate`].
+	pub fn with_eos_token_ids(mut self, tokens: impl IntoIterator<Item = i64>) -> Self {
+		self.eos_token_ids.extend(tokens);
+		self
+	}
+
+	/// Stops generation after the given sequence of tokens is generated. Unlike EOS tokens, the tokens of the stop
+	/// sequence are yielded by [`Generator::generate`], since they are only known to form the stop sequence once the
+	/// last one is generated.
+	pub fn with_stop_sequence(mut self, sequence: impl Into<Vec<i64>>) -> Self {
+		let sequence = sequence.into();
+		if !sequence.is_empty() {
+			self.stop_sequences.push(sequence);
+		}
+		self
+	}
+}
+
+/// The reason [`Generator::generate`] stopped generating tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+	/// [`GenerationOptions::with_max_tokens`] tokens were generated.
+	MaxTokens,
+	/// The sequence reached [`GenerationOptions::with_max_length`] tokens.
+	MaxLength,
+	/// An EOS token was generated.
+	EndOfSequence(i64),
+	/// The stop sequence at the given index (in the order they were added to [`GenerationOptions`]) was generated.
+	StopSequence(usize),
+	/// An error occurred during generation.
+	Error
+}
+
+/// Drives autoregressive generation for a decoder-only model; see the [module-level documentation](self).
+pub struct Generator<'s> {
+	session: &'s Session,
+	layout: DecoderLayout,
+	options: GenerationOptions,
+	sampler: Box<dyn Sampler + 's>,
+	types: InputTypes,
+	binding: IoBinding,
+	cache: KvCache,
+	/// All tokens in the sequence. The first `cache.len` tokens have been processed by the model; the rest are fed to
+	/// the model in the next step.
+	tokens: Vec<i64>,
+	stop_reason: Option<StopReason>
+}
+
+impl<'s> Generator<'s> {
+	/// Creates a new generator for `session`, detecting the model's layout via [`DecoderLayout::detect`]. Tokens are
+	/// selected with [`Greedy`] decoding unless a different sampler is set with [`Generator::with_sampler`].
+	pub fn new(session: &'s Session, options: GenerationOptions) -> Result<Self> {
+		Self::with_layout(session, DecoderLayout::detect(session)?, options)
+	}
+
+	/// Creates a new generator for `session` with an explicit [`DecoderLayout`].
+	pub fn with_layout(session: &'s Session, layout: DecoderLayout, options: GenerationOptions) -> Result<Self> {
+		if layout.encoder_hidden_states.is_some() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				"`Generator` only supports decoder-only models; use `BeamSearch` to drive an encoder-decoder model"
+			));
+		}
+		Ok(Self {
+			session,
+			types: InputTypes::resolve(&layout, &session.inputs)?,
+			binding: output_binding(session, &layout)?,
+			cache: KvCache::empty(&layout, 1)?,
+			layout,
+			options,
+			sampler: Box::new(Greedy),
+			tokens: Vec::new(),
+			stop_reason: None
+		})
+	}
+
+	/// Sets the [`Sampler`] used to select tokens from the model's logits.
+	pub fn with_sampler(mut self, sampler: impl Sampler + 's) -> Self {
+		self.sampler = Box::new(sampler);
+		self
+	}
+
+	/// Returns the layout of the model being driven.
+	pub fn layout(&self) -> &DecoderLayout {
+		&self.layout
+	}
+
+	/// Returns all tokens in the sequence; both pushed and generated.
+	pub fn tokens(&self) -> &[i64] {
+		&self.tokens
+	}
+
+	/// Appends tokens to the sequence, e.g. the prompt, or a user's reply in a chat. The tokens are processed by the
+	/// model at the next step.
+	pub fn push_tokens(&mut self, tokens: &[i64]) -> Result<()> {
+		self.tokens.extend_from_slice(tokens);
+		Ok(())
+	}
+
+	/// Shortens the sequence to the first `len` tokens, trimming the KV cache accordingly.
+	pub fn truncate(&mut self, len: usize) -> Result<()> {
+		if len >= self.tokens.len() {
+			return Ok(());
+		}
+		self.tokens.truncate(len);
+		// keep at least one token pending so that the next step produces logits
+		self.cache.truncate(&self.layout, len.saturating_sub(1))?;
+		Ok(())
+	}
+
+	/// Clears the sequence & KV cache.
+	pub fn reset(&mut self) -> Result<()> {
+		self.tokens.clear();
+		self.cache = KvCache::empty(&self.layout, 1)?;
+		self.stop_reason = None;
+		Ok(())
+	}
+
+	/// Returns the reason the last call to [`Generator::generate`] stopped, or `None` if it hasn't stopped yet.
+	pub fn stop_reason(&self) -> Option<StopReason> {
+		self.stop_reason
+	}
+
+	/// Returns an iterator which generates tokens until a stop condition in [`GenerationOptions`] is met.
+	pub fn generate(&mut self) -> Generate<'_, 's> {
+		self.stop_reason = None;
+		Generate { generator: self, generated: 0 }
+	}
+
+	/// Runs the model on all pending tokens, then samples & appends the next token, which is returned.
+	///
+	/// This does not check any stop conditions; see [`Generator::generate`].
+	pub fn step(&mut self) -> Result<i64> {
+		let past_len = self.cache.len;
+		let new_tokens = &self.tokens[past_len..];
+		if new_tokens.is_empty() {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "No tokens to process; push a prompt with `Generator::push_tokens` first"));
+		}
+		let total_len = self.tokens.len();
+
+		let (layout, types) = (&self.layout, &self.types);
+		let binding = &mut self.binding;
+		binding.bind_input(&layout.input_ids, &int_tensor(types.input_ids, [1, new_tokens.len()], new_tokens.iter().copied())?)?;
+		if let Some(name) = &layout.attention_mask {
+			binding.bind_input(name, &int_tensor(types.attention_mask, [1, total_len], iter::repeat(1).take(total_len))?)?;
+		}
+		if let Some(name) = &layout.position_ids {
+			binding.bind_input(name, &int_tensor(types.position_ids, [1, new_tokens.len()], past_len as i64..total_len as i64)?)?;
+		}
+		bind_cache(binding, layout, &self.cache)?;
+
+		let (mut logits, presents) = run_step(self.session, &self.binding, layout)?;
+		self.cache.update(layout, presents, total_len);
+
+		let token = self.sampler.sample_tensor(&self.tokens, &mut logits)?;
+		self.tokens.push(token);
+		Ok(token)
+	}
+}
+
+/// Creates an [`IoBinding`] with the logits & present outputs of `layout` bound to CPU memory, so that the presents can
+/// be fed back as inputs to the next step.
+fn output_binding(session: &Session, layout: &DecoderLayout) -> Result<IoBinding> {
+	let mut binding = session.create_binding()?;
+	let memory_info = MemoryInfo::default();
+	binding.bind_output_to_device(&layout.logits, &memory_info)?;
+	for entry in &layout.cache {
+		binding.bind_output_to_device(&entry.output, &memory_info)?;
+	}
+	Ok(binding)
+}
+
+/// Binds the past key/values, and the `use_cache_branch` flag of merged decoders.
+fn bind_cache(binding: &mut IoBinding, layout: &DecoderLayout, cache: &KvCache) -> Result<()> {
+	if let Some(name) = &layout.use_cache_branch {
+		binding.bind_input(name, &Tensor::from_array(([1], vec![cache.len > 0]))?)?;
+	}
+	for (value, entry) in cache.values.iter().zip(&layout.cache) {
+		binding.bind_input(&entry.input, value)?;
+	}
+	Ok(())
+}
+
+/// Runs the model with the inputs bound to `binding`, returning the logits and the present key/values.
+fn run_step(session: &Session, binding: &IoBinding, layout: &DecoderLayout) -> Result<(DynValue, Vec<DynTensor>)> {
+	let mut outputs = session.run_binding(binding)?;
+	let mut take = |name: &str| {
+		outputs
+			.remove(name)
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Model did not produce output `{name}`")))
+	};
+	let logits = take(&layout.logits)?;
+	let presents = layout
+		.cache
+		.iter()
+		.map(|entry| take(&entry.output)?.downcast::<DynTensorValueType>())
+		.collect::<Result<Vec<DynTensor>>>()?;
+	Ok((logits, presents))
+}
+
+/// Creates an integer tensor of the given type (which should be `int64` or `int32`).
+fn int_tensor(ty: TensorElementType, shape: [usize; 2], values: impl Iterator<Item = i64>) -> Result<DynTensor> {
+	match ty {
+		TensorElementType::Int64 => Ok(Tensor::from_array((shape, values.collect::<Vec<i64>>()))?.upcast()),
+		TensorElementType::Int32 => Ok(Tensor::from_array((shape, values.map(|x| x as i32).collect::<Vec<i32>>()))?.upcast()),
+		ty => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Unsupported integer input type {ty}; expected int64 or int32")))
+	}
+}
+
+/// Returns the index of the first stop sequence which `tokens` ends with.
+fn find_stop_sequence(tokens: &[i64], stop_sequences: &[Vec<i64>]) -> Option<usize> {
+	stop_sequences.iter().position(|sequence| tokens.ends_with(sequence))
+}
+
+/// Iterator returned by [`Generator::generate`], yielding generated token IDs.
+///
+/// Once the iterator is exhausted, the reason can be retrieved with [`Generator::stop_reason`]. If an error occurs,
+/// it is yielded and the iterator stops.
+pub struct Generate<'g, 's> {
+	generator: &'g mut Generator<'s>,
+	generated: usize
+}
+
+impl Iterator for Generate<'_, '_> {
+	type Item = Result<i64>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let generator = &mut *self.generator;
+		if generator.stop_reason.is_some() {
+			return None;
+		}
+		if self.generated >= generator.options.max_tokens {
+			generator.stop_reason = Some(StopReason::MaxTokens);
+			return None;
+		}
+		if generator.options.max_length.is_some_and(|max| generator.tokens.len() >= max) {
+			generator.stop_reason = Some(StopReason::MaxLength);
+			return None;
+		}
+
+		let token = match generator.step() {
+			Ok(token) => token,
+			Err(e) => {
+				generator.stop_reason = Some(StopReason::Error);
+				return Some(Err(e));
+			}
+		};
+		self.generated += 1;
+
+		if generator.options.eos_token_ids.contains(&token) {
+			generator.stop_reason = Some(StopReason::EndOfSequence(token));
+			return None;
+		}
+		if let Some(index) = find_stop_sequence(&generator.tokens, &generator.options.stop_sequences) {
+			generator.stop_reason = Some(StopReason::StopSequence(index));
+		}
+		Some(Ok(token))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{GenerationOptions, find_stop_sequence};
+
+	#[test]
+	fn test_stop_sequences() {
+		let options = GenerationOptions::default()
+			.with_stop_sequence([13, 13])
+			.with_stop_sequence([])
+			.with_stop_sequence([50, 13]);
+		assert_eq!(options.stop_sequences.len(), 2);
+		assert_eq!(find_stop_sequence(&[1, 2, 13], &options.stop_sequences), None);
+		assert_eq!(find_stop_sequence(&[1, 50, 13], &options.stop_sequences), Some(1));
+		assert_eq!(find_stop_sequence(&[13, 13], &options.stop_sequences), Some(0));
+	}
+}
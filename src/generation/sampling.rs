@@ -0,0 +1,251 @@
+//! Selection of the next token from a model's logits.
+
+use alloc::format;
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::TensorElementType,
+	value::{DynValue, ValueType}
+};
+
+/// Selects the next token given the logits for the last position of the sequence.
+///
+/// `tokens` contains the full sequence so far (prompt & generated tokens). Samplers are free to modify `logits` in
+/// place.
+///
+/// This trait is implemented for closures, so a custom sampler can be as simple as:
+/// ```
+/// # use ort::generation::Sampler;
+/// fn check<S: Sampler>(_: S) {}
+/// check(|_tokens: &[i64], logits: &mut [f32]| Ok(logits.len() as i64 - 1));
+/// ```
+pub trait Sampler {
+	fn sample(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<i64>;
+
+	/// Samples from the last row of a logits tensor of shape `[..., vocab_size]`, as output by a language model.
+	///
+	/// `f32` logits are modified in place. `f16` logits (with the `half` feature) are converted to `f32` first.
+	fn sample_tensor(&mut self, tokens: &[i64], logits: &mut DynValue) -> Result<i64> {
+		let (ty, vocab_size) = match logits.dtype() {
+			ValueType::Tensor { ty, shape, .. } => (*ty, shape.last().copied().unwrap_or_default() as usize),
+			ty => return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Expected logits to be a tensor, got {ty}")))
+		};
+		match ty {
+			TensorElementType::Float32 => {
+				let (_, data) = logits.try_extract_tensor_mut::<f32>()?;
+				let start = data.len() - vocab_size;
+				self.sample(tokens, &mut data[start..])
+			}
+			#[cfg(feature = "half")]
+			TensorElementType::Float16 => {
+				let (_, data) = logits.try_extract_tensor::<half::f16>()?;
+				let mut logits: alloc::vec::Vec<f32> = data[data.len() - vocab_size..].iter().map(|x| x.to_f32()).collect();
+				self.sample(tokens, &mut logits)
+			}
+			ty => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Unsupported logits type {ty}; expected f32")))
+		}
+	}
+}
+
+impl<F: FnMut(&[i64], &mut [f32]) -> Result<i64>> Sampler for F {
+	fn sample(&mut self, tokens: &[i64], logits: &mut [f32]) -> Result<i64> {
+		self(tokens, logits)
+	}
+}
+
+/// Always selects the token with the highest logit. `NaN` logits are never selected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Greedy;
+
+impl Sampler for Greedy {
+	fn sample(&mut self, _: &[i64], logits: &mut [f32]) -> Result<i64> {
+		argmax(logits)
+			.map(|i| i as i64)
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Cannot sample from empty or all-NaN logits"))
+	}
+}
+
+/// A source of random numbers for [`Multinomial`] sampling.
+///
+/// Implement this for your RNG of choice (e.g. `rand::rngs::StdRng`) to use it instead of the built-in [`Rng`].
+pub trait RandomSource {
+	/// Returns the next uniformly distributed 64-bit integer.
+	fn next_u64(&mut self) -> u64;
+
+	/// Returns a uniformly distributed float in `[0, 1)`.
+	fn next_f32(&mut self) -> f32 {
+		(self.next_u64() >> 40) as f32 * (1.0 / (1u64 << 24) as f32)
+	}
+}
+
+/// A small, fast, seedable pseudo-random number generator (SplitMix64).
+///
+/// This is **not** cryptographically secure; it exists so that sampling is reproducible given a seed without pulling
+/// in an RNG crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+	state: u64
+}
+
+impl Rng {
+	/// Creates a new RNG from the given seed. The same seed always produces the same sequence of numbers.
+	pub fn new(seed: u64) -> Self {
+		Self { state: seed }
+	}
+}
+
+impl RandomSource for Rng {
+	fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+}
+
+/// Samples a token from the probability distribution given by the softmax of the logits.
+///
+/// Combine with [`logits`](super::logits) processors like [`Temperature`](super::logits::Temperature) and
+/// [`TopK`](super::logits::TopK) via a [`Pipeline`](super::logits::Pipeline) to control the distribution.
+#[derive(Debug, Clone)]
+pub struct Multinomial<R: RandomSource = Rng> {
+	rng: R
+}
+
+impl Multinomial<Rng> {
+	/// Creates a multinomial sampler using the built-in [`Rng`] with the given seed.
+	pub fn seeded(seed: u64) -> Self {
+		Self::new(Rng::new(seed))
+	}
+}
+
+impl<R: RandomSource> Multinomial<R> {
+	/// Creates a multinomial sampler drawing random numbers from `rng`.
+	pub fn new(rng: R) -> Self {
+		Self { rng }
+	}
+
+	/// Returns a mutable reference to the underlying RNG.
+	pub fn rng_mut(&mut self) -> &mut R {
+		&mut self.rng
+	}
+}
+
+impl<R: RandomSource> Sampler for Multinomial<R> {
+	fn sample(&mut self, _: &[i64], logits: &mut [f32]) -> Result<i64> {
+		if !softmax(logits) {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Cannot sample from logits where every token is masked"));
+		}
+		let threshold = self.rng.next_f32();
+		let mut cumulative = 0.0;
+		let mut last = 0;
+		for (i, &p) in logits.iter().enumerate() {
+			if p > 0.0 {
+				cumulative += p;
+				last = i;
+				if threshold < cumulative {
+					return Ok(i as i64);
+				}
+			}
+		}
+		// rounding error may leave `cumulative` slightly below 1
+		Ok(last as i64)
+	}
+}
+
+/// Returns the index of the largest non-NaN value, preferring the lowest index in case of ties.
+pub(crate) fn argmax(values: &[f32]) -> Option<usize> {
+	values
+		.iter()
+		.enumerate()
+		.filter(|(_, x)| !x.is_nan())
+		.fold(None, |best: Option<(usize, f32)>, (i, &x)| match best {
+			Some((_, max)) if max >= x => best,
+			_ => Some((i, x))
+		})
+		.map(|(i, _)| i)
+}
+
+/// Converts logits to probabilities in place. `NaN` & `-inf` logits get a probability of 0.
+///
+/// Returns `false` if every logit was masked, in which case `values` is left in an unspecified state.
+pub(crate) fn softmax(values: &mut [f32]) -> bool {
+	let max = values.iter().copied().filter(|x| !x.is_nan()).fold(f32::NEG_INFINITY, f32::max);
+	if max == f32::NEG_INFINITY {
+		return false;
+	}
+	let mut sum = 0.0;
+	for x in values.iter_mut() {
+		*x = if x.is_nan() { 0.0 } else { exp(*x - max) };
+		sum += *x;
+	}
+	for x in values.iter_mut() {
+		*x /= sum;
+	}
+	true
+}
+
+/// `e^x` for `x <= 0`, which is all softmax needs; `core` doesn't provide `f32::exp`.
+pub(crate) fn exp(x: f32) -> f32 {
+	use core::f32::consts::LOG2_E;
+	// ln(2) split into a high part with trailing zero bits (so `k * LN_2_HI` is exact) and the remainder
+	const LN_2_HI: f32 = 6.931_457_5e-1;
+	const LN_2_LO: f32 = 1.428_606_8e-6;
+	if x < -87.0 {
+		return 0.0;
+	}
+	// e^x = 2^k * e^r, with |r| <= ln(2) / 2; `as` truncates towards zero, so this rounds since `x <= 0`
+	let k = (x * LOG2_E - 0.5) as i32;
+	let r = (x - k as f32 * LN_2_HI) - k as f32 * LN_2_LO;
+	let p = 1.0 + r * (1.0 + r * (1.0 / 2.0 + r * (1.0 / 6.0 + r * (1.0 / 24.0 + r * (1.0 / 120.0 + r * (1.0 / 720.0))))));
+	p * f32::from_bits(((k + 127) as u32) << 23)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Greedy, Multinomial, RandomSource, Rng, Sampler, exp};
+
+	#[test]
+	fn test_greedy() -> crate::Result<()> {
+		assert_eq!(Greedy.sample(&[], &mut [0.5, 2.0, f32::NAN, 2.0, -1.0])?, 1);
+		assert_eq!(Greedy.sample(&[], &mut [f32::NEG_INFINITY, -3.0])?, 1);
+		assert!(Greedy.sample(&[], &mut [f32::NAN]).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_exp() {
+		for i in 0..=800 {
+			let x = i as f32 * -0.1;
+			assert!((exp(x) - x.exp()).abs() <= x.exp() * 1e-6, "{x}");
+		}
+		assert_eq!(exp(f32::NEG_INFINITY), 0.0);
+	}
+
+	#[test]
+	fn test_multinomial() -> crate::Result<()> {
+		let mut a = Rng::new(42);
+		let mut b = Rng::new(42);
+		assert!((0..16).all(|_| a.next_u64() == b.next_u64()));
+		assert!((0..1024).map(|_| a.next_f32()).all(|x| (0.0..1.0).contains(&x)));
+
+		let mut sampler = Multinomial::seeded(0);
+		let mut counts = [0; 3];
+		for _ in 0..1000 {
+			counts[sampler.sample(&[], &mut [0.0, f32::NEG_INFINITY, core::f32::consts::LN_2])? as usize] += 1;
+		}
+		assert_eq!(counts[1], 0);
+		assert!((600..730).contains(&counts[2]), "{counts:?}");
+
+		let samples = |seed| {
+			let mut sampler = Multinomial::seeded(seed);
+			(0..8)
+				.map(|_| sampler.sample(&[], &mut [0.0; 100]))
+				.collect::<crate::Result<alloc::vec::Vec<_>>>()
+		};
+		assert_eq!(samples(7)?, samples(7)?);
+		assert!(Multinomial::seeded(0).sample(&[], &mut [f32::NEG_INFINITY]).is_err());
+		Ok(())
+	}
+}
@@ -44,10 +44,10 @@ use crate::{
 /// # 	value::Tensor
 /// # };
 /// # fn main() -> ort::Result<()> {
-/// let mut text_encoder = Session::builder()?
+/// let text_encoder = Session::builder()?
 /// 	.with_execution_providers([CUDAExecutionProvider::default().build()])?
 /// 	.commit_from_file("text_encoder.onnx")?;
-/// let mut unet = Session::builder()?
+/// let unet = Session::builder()?
 /// 	.with_execution_providers([CUDAExecutionProvider::default().build()])?
 /// 	.commit_from_file("unet.onnx")?;
 ///
@@ -270,7 +270,7 @@ mod tests {
 	#[test]
 	#[cfg(all(feature = "ndarray", feature = "fetch-models"))]
 	fn test_mnist_input_bound() -> Result<()> {
-		let mut session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
+		let session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
 
 		let array = get_image();
 
@@ -288,7 +288,7 @@ mod tests {
 	#[test]
 	#[cfg(all(feature = "ndarray", feature = "fetch-models"))]
 	fn test_mnist_input_output_bound() -> Result<()> {
-		let mut session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
+		let session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
 
 		let array = get_image();
 
@@ -308,7 +308,7 @@ mod tests {
 	#[test]
 	#[cfg(all(feature = "ndarray", feature = "fetch-models"))]
 	fn test_send_iobinding() -> Result<()> {
-		let mut session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
+		let session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
 
 		let array = get_image();
 
@@ -333,7 +333,7 @@ mod tests {
 	#[test]
 	#[cfg(all(feature = "ndarray", feature = "fetch-models"))]
 	fn test_mnist_clear_bounds() -> Result<()> {
-		let mut session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
+		let session = Session::builder()?.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/mnist.onnx")?;
 
 		let array = get_image();
 
@@ -28,10 +28,12 @@ pub mod adapter;
 pub mod environment;
 pub mod error;
 pub mod execution_providers;
+pub mod generation;
 pub mod io_binding;
 pub mod logging;
 pub mod memory;
 pub mod metadata;
+pub mod model;
 pub mod operator;
 pub mod session;
 pub mod tensor;
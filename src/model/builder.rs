@@ -0,0 +1,250 @@
+use alloc::{string::String, vec::Vec};
+
+use super::{Dimension, Graph, Initializer, Model, Node, OperatorSetId, ValueInfo};
+use crate::{
+	error::Result,
+	tensor::TensorElementType,
+	value::{DefiniteTensorValueTypeMarker, Value}
+};
+
+/// Builds an ONNX [`Model`] in code.
+///
+/// This is handy for small helper graphs (preprocessing, casts, argmax heads) and test fixtures, which would otherwise
+/// need to be exported from Python & checked in as `.onnx` files.
+///
+/// ```no_run
+/// # use ort::{model::{Dimension, GraphBuilder, Node}, session::Session, tensor::TensorElementType};
+/// # fn main() -> ort::Result<()> {
+/// let model = GraphBuilder::new("argmax")
+/// 	.with_input("logits", TensorElementType::Float32, [Dimension::from("batch"), 10.into()])
+/// 	.with_output("label", TensorElementType::Int64, [Dimension::from("batch"), 1.into()])
+/// 	.with_node(
+/// 		Node::new("ArgMax")
+/// 			.with_inputs(["logits"])
+/// 			.with_outputs(["label"])
+/// 			.with_attribute("axis", 1_i64)
+/// 	)
+/// 	.build()?;
+///
+/// let session = Session::builder()?.commit_from_model(&model)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`GraphBuilder::build`] checks that every value a node consumes is defined before it is used, that every output is
+/// produced, and that initializer data matches its declared shape, so mistakes are reported with the offending
+/// node's name rather than as an opaque error from ONNX Runtime.
+#[derive(Debug, Clone)]
+pub struct GraphBuilder {
+	graph: Graph,
+	opset_imports: Vec<OperatorSetId>
+}
+
+impl GraphBuilder {
+	/// The version of the default ONNX operator set used unless overridden by [`GraphBuilder::with_opset`].
+	pub const DEFAULT_OPSET: i64 = 17;
+
+	/// Creates a new, empty graph with the given name.
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			graph: Graph {
+				name: name.into(),
+				..Default::default()
+			},
+			opset_imports: alloc::vec![OperatorSetId::new("", Self::DEFAULT_OPSET)]
+		}
+	}
+
+	/// Imports version `version` of the operator set `domain`. The default ONNX domain is `""`.
+	///
+	/// Domains used by nodes which are not explicitly imported are imported at version 1.
+	pub fn with_opset(mut self, domain: impl Into<String>, version: i64) -> Self {
+		let domain = domain.into();
+		match self.opset_imports.iter_mut().find(|o| o.domain == domain) {
+			Some(opset) => opset.version = version,
+			None => self.opset_imports.push(OperatorSetId::new(domain, version))
+		}
+		self
+	}
+
+	/// Declares a tensor input to the graph. Dimensions can be fixed sizes, symbolic names, or negative for dynamic
+	/// dimensions.
+	pub fn with_input<D: Into<Dimension>>(mut self, name: impl Into<String>, ty: TensorElementType, shape: impl IntoIterator<Item = D>) -> Self {
+		self.graph.inputs.push(ValueInfo::tensor(name, ty, shape));
+		self
+	}
+
+	/// Declares a tensor output of the graph. Dimensions can be fixed sizes, symbolic names, or negative for dynamic
+	/// dimensions.
+	pub fn with_output<D: Into<Dimension>>(mut self, name: impl Into<String>, ty: TensorElementType, shape: impl IntoIterator<Item = D>) -> Self {
+		self.graph.outputs.push(ValueInfo::tensor(name, ty, shape));
+		self
+	}
+
+	/// Appends a node to the graph. Nodes must be added in topological order, i.e. after the nodes producing their
+	/// inputs.
+	pub fn with_node(mut self, node: Node) -> Self {
+		self.graph.nodes.push(node);
+		self
+	}
+
+	/// Adds a constant initializer to the graph, copying the shape & data of `tensor`.
+	pub fn with_initializer<T: DefiniteTensorValueTypeMarker + ?Sized>(mut self, name: impl Into<String>, tensor: &Value<T>) -> Result<Self> {
+		self.graph.initializers.push(Initializer::from_tensor(name, tensor)?);
+		Ok(self)
+	}
+
+	/// Adds an already constructed [`Initializer`] to the graph.
+	pub fn with_raw_initializer(mut self, initializer: Initializer) -> Self {
+		self.graph.initializers.push(initializer);
+		self
+	}
+
+	/// Returns the graph without validating it, for use as a subgraph attribute of a control flow node (like an `If`
+	/// branch). Subgraphs are validated when the outer graph is [built](GraphBuilder::build).
+	pub fn into_graph(self) -> Graph {
+		self.graph
+	}
+
+	/// Validates the graph & wraps it in a [`Model`], which can be encoded with [`Model::to_bytes`] or committed
+	/// directly with [`SessionBuilder::commit_from_model`](crate::session::builder::SessionBuilder::commit_from_model).
+	pub fn build(mut self) -> Result<Model> {
+		self.graph.validate()?;
+
+		for node in self.graph.all_nodes() {
+			let domain = if node.domain == "ai.onnx" { "" } else { node.domain.as_str() };
+			if !self.opset_imports.iter().any(|o| o.domain == domain) {
+				self.opset_imports.push(OperatorSetId::new(domain, 1));
+			}
+		}
+
+		let opset = self
+			.opset_imports
+			.iter()
+			.find(|o| o.domain.is_empty())
+			.map_or(Self::DEFAULT_OPSET, |o| o.version);
+		Ok(Model {
+			ir_version: ir_version(opset),
+			opset_imports: self.opset_imports,
+			producer_name: String::from("ort"),
+			producer_version: String::from(env!("CARGO_PKG_VERSION")),
+			graph: self.graph,
+			..Default::default()
+		})
+	}
+}
+
+/// Returns the lowest IR version supporting the given version of the default ONNX operator set.
+pub(crate) fn ir_version(opset: i64) -> i64 {
+	match opset {
+		..=17 => 8,
+		18..=19 => 9,
+		_ => 10
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec};
+
+	use super::GraphBuilder;
+	use crate::{
+		model::{Dimension, Initializer, Model, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	fn relu() -> GraphBuilder {
+		GraphBuilder::new("relu")
+			.with_input("x", TensorElementType::Float32, [Dimension::from("n"), 4.into()])
+			.with_output("y", TensorElementType::Float32, [-1_i64, 4])
+	}
+
+	#[test]
+	fn test_build() -> crate::Result<()> {
+		let model = relu()
+			.with_opset("", 19)
+			.with_node(Node::new("Relu").with_inputs(["x"]).with_outputs(["r"]))
+			.with_node(Node::new("Custom").with_domain("my.ops").with_inputs(["r", "", "w"]).with_outputs(["y"]))
+			.with_raw_initializer(Initializer::new("w", TensorElementType::Float16, [2], TensorData::Raw(vec![0; 4])))
+			.build()?;
+		assert_eq!(model.ir_version, 9);
+		assert_eq!(model.opset_version(""), Some(19));
+		assert_eq!(model.opset_version("my.ops"), Some(1));
+		assert_eq!(model.graph.outputs[0].ty.as_ref().and_then(|ty| ty.shape()), Some(&[Dimension::Unknown, Dimension::Value(4)][..]));
+		assert_eq!(Model::from_bytes(&model.to_bytes())?, model);
+		Ok(())
+	}
+
+	#[test]
+	fn test_validation() {
+		let err = relu()
+			.with_node(Node::new("Relu").with_name("act").with_inputs(["z"]).with_outputs(["y"]))
+			.build()
+			.expect_err("undefined input");
+		assert!(err.to_string().contains("Node `act` (Relu) uses `z`"), "{err}");
+
+		let err = relu()
+			.with_node(Node::new("Relu").with_inputs(["x"]).with_outputs(["x"]))
+			.build()
+			.expect_err("redefined value");
+		assert!(err.to_string().contains("Node #0 (Relu) outputs `x`, which is already defined"), "{err}");
+
+		let err = relu().build().expect_err("missing output");
+		assert!(err.to_string().contains("Graph output `y` is not produced"), "{err}");
+
+		let err = relu()
+			.with_node(Node::new("Add").with_inputs(["x", "b"]).with_outputs(["y"]))
+			.with_raw_initializer(Initializer::new("b", TensorElementType::Float32, [4], TensorData::Raw(vec![0; 12])))
+			.build()
+			.expect_err("short initializer");
+		assert!(
+			err.to_string()
+				.contains("has 12 bytes of data, but its shape [4] of f32 requires 16 bytes"),
+			"{err}"
+		);
+
+		let err = relu()
+			.with_node(Node::new("Add").with_inputs(["x", "b"]).with_outputs(["y"]))
+			.with_raw_initializer(Initializer::new("b", TensorElementType::Int64, [2, 2], TensorData::Int64(vec![1, 2, 3])))
+			.build()
+			.expect_err("short initializer");
+		assert!(err.to_string().contains("has 3 values, but its shape [2, 2] requires 4"), "{err}");
+	}
+
+	#[test]
+	fn test_subgraph_scope() -> crate::Result<()> {
+		let branch = |output: &str| {
+			GraphBuilder::new("branch")
+				.with_output(output, TensorElementType::Float32, [4_i64])
+				.with_node(Node::new("Neg").with_inputs(["x"]).with_outputs([output]))
+				.into_graph()
+		};
+		let cond = GraphBuilder::new("if")
+			.with_input("x", TensorElementType::Float32, [4_i64])
+			.with_input("cond", TensorElementType::Bool, vec::Vec::<i64>::new())
+			.with_output("y", TensorElementType::Float32, [4_i64]);
+		cond.clone()
+			.with_node(
+				Node::new("If")
+					.with_inputs(["cond"])
+					.with_outputs(["y"])
+					.with_attribute("then_branch", branch("a"))
+					.with_attribute("else_branch", branch("b"))
+			)
+			.build()?;
+
+		// subgraphs may not shadow values from the outer scope
+		let err = cond
+			.with_node(
+				Node::new("If")
+					.with_inputs(["cond"])
+					.with_outputs(["y"])
+					.with_attribute("then_branch", branch("x"))
+					.with_attribute("else_branch", branch("b"))
+			)
+			.build()
+			.expect_err("subgraph shadows outer value");
+		assert!(err.to_string().contains("Node #0 (Neg) outputs `x`, which is already defined"), "{err}");
+		Ok(())
+	}
+}
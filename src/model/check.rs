@@ -0,0 +1,120 @@
+use alloc::{collections::BTreeSet, format, string::String};
+
+use super::{
+	Graph, Initializer, Model, TensorData,
+	tensor::{element_type, raw_size}
+};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::TensorElementType
+};
+
+impl Model {
+	/// Checks that the model's graph is well-formed; see [`Graph::validate`].
+	pub fn validate(&self) -> Result<()> {
+		self.graph.validate()
+	}
+}
+
+impl Graph {
+	/// Checks that the graph is well-formed, returning an [`ErrorCode::InvalidGraph`] error describing the first
+	/// problem found:
+	/// - every value a node consumes must be a graph input, initializer, or output of an earlier node (or, in a
+	///   subgraph, a value of an enclosing graph);
+	/// - every value must be defined only once, and every graph output must be defined;
+	/// - initializer data must match the initializer's shape & type.
+	pub fn validate(&self) -> Result<()> {
+		check_graph(self, &BTreeSet::new())
+	}
+}
+
+pub(crate) fn invalid_graph(message: String) -> Error {
+	Error::new_with_code(ErrorCode::InvalidGraph, message)
+}
+
+/// Checks that every value used in `graph` is defined before use, either in `graph` or in `outer` (the scope of the
+/// graphs containing it).
+pub(crate) fn check_graph<'g>(graph: &'g Graph, outer: &BTreeSet<&'g str>) -> Result<()> {
+	let mut defined = outer.clone();
+	for initializer in &graph.initializers {
+		check_initializer(initializer)?;
+		defined.insert(&initializer.name);
+	}
+	for input in &graph.inputs {
+		if input.ty.is_none() {
+			return Err(invalid_graph(format!("Graph input `{}` has no type", input.name)));
+		}
+		defined.insert(&input.name);
+	}
+
+	for (i, node) in graph.nodes.iter().enumerate() {
+		let describe = || {
+			if node.name.is_empty() {
+				format!("Node #{i} ({})", node.op_type)
+			} else {
+				format!("Node `{}` ({})", node.name, node.op_type)
+			}
+		};
+		if node.op_type.is_empty() {
+			return Err(invalid_graph(format!("{} has no operator type", describe())));
+		}
+		if let Some(input) = node.inputs.iter().find(|input| !input.is_empty() && !defined.contains(input.as_str())) {
+			return Err(invalid_graph(format!("{} uses `{input}`, which is not a graph input, initializer, or output of an earlier node", describe())));
+		}
+		for subgraph in node.attributes.iter().flat_map(|attr| attr.value.graphs()) {
+			check_graph(subgraph, &defined)?;
+		}
+		for output in node.outputs.iter().filter(|output| !output.is_empty()) {
+			if !defined.insert(output) {
+				return Err(invalid_graph(format!("{} outputs `{output}`, which is already defined", describe())));
+			}
+		}
+	}
+
+	for output in &graph.outputs {
+		if !defined.contains(output.name.as_str()) {
+			return Err(invalid_graph(format!("Graph output `{}` is not produced by any node", output.name)));
+		}
+	}
+	Ok(())
+}
+
+pub(crate) fn check_initializer(initializer: &Initializer) -> Result<()> {
+	let name = &initializer.name;
+	let dims = &initializer.dims;
+	if dims.iter().any(|&d| d < 0) {
+		return Err(invalid_graph(format!("Initializer `{name}` has a negative dimension in its shape {dims:?}")));
+	}
+	let ty = element_type(initializer.data_type)
+		.filter(|&ty| ty != TensorElementType::Undefined)
+		.ok_or_else(|| invalid_graph(format!("Initializer `{name}` has unknown data type {}", initializer.data_type)))?;
+
+	let elements = initializer.num_elements();
+	let (expected, actual) = match &initializer.data {
+		TensorData::Raw(bytes) => {
+			let expected = raw_size(ty, elements).ok_or_else(|| invalid_graph(format!("Initializer `{name}` of type {ty} can't be stored as raw data")))?;
+			if expected != bytes.len() {
+				return Err(invalid_graph(format!(
+					"Initializer `{name}` has {} bytes of data, but its shape {dims:?} of {ty} requires {expected} bytes",
+					bytes.len()
+				)));
+			}
+			return Ok(());
+		}
+		TensorData::External(_) => return Ok(()),
+		// 4-bit types pack two elements into each `int32`, and complex types store each element as two floats
+		TensorData::Int32(values) if matches!(ty, TensorElementType::Int4 | TensorElementType::Uint4) => (elements.div_ceil(2), values.len()),
+		TensorData::Float(values) if ty == TensorElementType::Complex64 => (elements * 2, values.len()),
+		TensorData::Double(values) if ty == TensorElementType::Complex128 => (elements * 2, values.len()),
+		TensorData::Float(values) => (elements, values.len()),
+		TensorData::Double(values) => (elements, values.len()),
+		TensorData::Int32(values) => (elements, values.len()),
+		TensorData::Int64(values) => (elements, values.len()),
+		TensorData::Uint64(values) => (elements, values.len()),
+		TensorData::String(values) => (elements, values.len())
+	};
+	if expected != actual {
+		return Err(invalid_graph(format!("Initializer `{name}` has {actual} values, but its shape {dims:?} requires {expected}")));
+	}
+	Ok(())
+}
@@ -0,0 +1,698 @@
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::{Component, Path, PathBuf};
+
+use super::{
+	Graph, Model, Node, TensorData,
+	check::{check_initializer, invalid_graph},
+	schema,
+	tensor::{element_type, raw_size}
+};
+use crate::error::Result;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+	/// The model is likely to work, but may not behave as intended.
+	Warning,
+	/// The model is invalid and will fail to load or run.
+	Error
+}
+
+/// The kind of problem a [`Diagnostic`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+	/// A node uses a value produced by a later node; ONNX requires nodes to be topologically sorted.
+	UnsortedNodes,
+	/// A value, node, or initializer name is used more than once.
+	DuplicateName,
+	/// A node uses a value which is never defined.
+	UndefinedValue,
+	/// A graph output is not produced by any node.
+	UndefinedOutput,
+	/// A graph input has no type.
+	MissingType,
+	/// A node has no operator type.
+	MissingOperatorType,
+	/// An initializer's data does not match its shape & type.
+	InvalidInitializer,
+	/// A node has an attribute which is not part of its operator's schema.
+	UnknownAttribute,
+	/// A node's attribute has a different type than its operator's schema requires.
+	AttributeType,
+	/// A node is missing an attribute its operator requires.
+	MissingAttribute,
+	/// A node uses an operator set domain which is not imported by the model.
+	MissingOpset,
+	/// The model's IR version or an operator set version is not supported by the targeted ONNX Runtime version.
+	UnsupportedVersion,
+	/// An initializer stored in an external file has an invalid or unresolvable reference.
+	ExternalData
+}
+
+impl DiagnosticKind {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::UnsortedNodes => "unsorted-nodes",
+			Self::DuplicateName => "duplicate-name",
+			Self::UndefinedValue => "undefined-value",
+			Self::UndefinedOutput => "undefined-output",
+			Self::MissingType => "missing-type",
+			Self::MissingOperatorType => "missing-operator-type",
+			Self::InvalidInitializer => "invalid-initializer",
+			Self::UnknownAttribute => "unknown-attribute",
+			Self::AttributeType => "attribute-type",
+			Self::MissingAttribute => "missing-attribute",
+			Self::MissingOpset => "missing-opset",
+			Self::UnsupportedVersion => "unsupported-version",
+			Self::ExternalData => "external-data"
+		}
+	}
+}
+
+/// A single problem found by the [`ModelChecker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub kind: DiagnosticKind,
+	/// The path of the graph containing the problem; the main graph is `""`, and subgraphs are identified by the node
+	/// & attribute containing them, e.g. ``Node `loop` (Loop).body``.
+	pub graph: String,
+	/// The node the problem concerns, formatted as ``Node `name` (OpType)``, or `Node #index (OpType)` for unnamed
+	/// nodes.
+	pub node: Option<String>,
+	pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.severity {
+			Severity::Warning => write!(f, "warning[{}]: ", self.kind.as_str())?,
+			Severity::Error => write!(f, "error[{}]: ", self.kind.as_str())?
+		}
+		if !self.graph.is_empty() {
+			write!(f, "in {}: ", self.graph)?;
+		}
+		if let Some(node) = &self.node {
+			write!(f, "{node}: ")?;
+		}
+		f.write_str(&self.message)
+	}
+}
+
+/// The result of [checking](ModelChecker::check) a model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckReport {
+	/// All problems found, in the order they were encountered.
+	pub diagnostics: Vec<Diagnostic>
+}
+
+impl CheckReport {
+	/// Returns `true` if any [errors](Severity::Error) were found.
+	pub fn has_errors(&self) -> bool {
+		self.errors().next().is_some()
+	}
+
+	/// Returns all diagnostics of [`Severity::Error`].
+	pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> + '_ {
+		self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+	}
+
+	/// Returns all diagnostics of [`Severity::Warning`].
+	pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> + '_ {
+		self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+	}
+
+	/// Converts the report into an [`ErrorCode::InvalidGraph`](crate::error::ErrorCode::InvalidGraph) error listing
+	/// every error found, or `Ok(())` if there were only warnings.
+	pub fn into_result(self) -> Result<()> {
+		if !self.has_errors() {
+			return Ok(());
+		}
+		let errors = self.errors().map(Diagnostic::to_string).collect::<Vec<_>>();
+		Err(invalid_graph(format!("Model failed checks:\n{}", errors.join("\n"))))
+	}
+}
+
+impl fmt::Display for CheckReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for diagnostic in &self.diagnostics {
+			writeln!(f, "{diagnostic}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Checks a [`Model`] for problems before it is loaded, like Python's `onnx.checker.check_model`.
+///
+/// In addition to the structural checks of [`Model::validate`], the checker verifies that:
+/// - nodes are topologically sorted, and names are unique;
+/// - the attributes of operators in the default ONNX domain match the operator's schema at the model's operator set
+///   version;
+/// - every domain used by a node is imported, and the IR & operator set versions are supported by the targeted version
+///   of ONNX Runtime;
+/// - references to external data files are well-formed &, when a directory is given with
+///   [`ModelChecker::with_external_data_dir`], point to files which exist & are large enough.
+///
+/// Rather than stopping at the first problem like [`Model::validate`], the checker reports every problem found as a
+/// structured [`Diagnostic`].
+///
+/// ```
+/// # use ort::model::{Model, ModelChecker};
+/// # fn main() -> ort::Result<()> {
+/// let model = Model::from_file("tests/data/upsample.onnx")?;
+/// let report = ModelChecker::new().with_external_data_dir("tests/data").check(&model);
+/// for diagnostic in &report.diagnostics {
+/// 	println!("{diagnostic}");
+/// }
+/// report.into_result()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The checker can also be run automatically when a session is committed with
+/// [`SessionBuilder::with_model_checker`](crate::session::builder::SessionBuilder::with_model_checker).
+#[derive(Debug, Clone, Default)]
+pub struct ModelChecker {
+	runtime_version: Option<u32>,
+	#[cfg(feature = "std")]
+	external_data_dir: Option<PathBuf>,
+	/// Names & sizes of external data files provided to the session in memory, which take precedence over files in
+	/// `external_data_dir`.
+	#[cfg(feature = "std")]
+	in_memory_files: Vec<(String, u64)>
+}
+
+impl ModelChecker {
+	/// Creates a checker targeting the version of ONNX Runtime `ort` was built for ([`crate::MINOR_VERSION`]).
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Checks version compatibility against ONNX Runtime `1.{minor}` instead of [`crate::MINOR_VERSION`].
+	pub fn with_runtime_version(mut self, minor: u32) -> Self {
+		self.runtime_version = Some(minor);
+		self
+	}
+
+	/// Resolves external data files relative to `dir`, which should be the directory containing the model file.
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn with_external_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+		self.external_data_dir = Some(dir.into());
+		self
+	}
+
+	/// Resolves external data relative to `dir` unless a directory was set explicitly.
+	#[cfg(feature = "std")]
+	pub(crate) fn for_model_dir(mut self, dir: &Path) -> Self {
+		if self.external_data_dir.is_none() {
+			self.external_data_dir = Some(dir.to_path_buf());
+		}
+		self
+	}
+
+	/// Checks references to the external data file `location` against a file of `len` bytes provided in memory instead
+	/// of one on disk.
+	#[cfg(any(feature = "signing", feature = "compression"))]
+	pub(crate) fn with_in_memory_file(mut self, location: &str, len: usize) -> Self {
+		self.in_memory_files.push((location.to_string(), len as u64));
+		self
+	}
+
+	/// Targets the version of the loaded ONNX Runtime binary, as reported by [`crate::info`], unless a version was
+	/// set explicitly.
+	pub(crate) fn for_loaded_runtime(mut self) -> Self {
+		if self.runtime_version.is_none() {
+			// e.g. `ORT Build Info: git-branch=rel-1.20.1, ...`
+			self.runtime_version = crate::info()
+				.split_once("rel-1.")
+				.and_then(|(_, version)| version.split(|c: char| !c.is_ascii_digit()).next())
+				.and_then(|minor| minor.parse().ok());
+		}
+		self
+	}
+
+	/// Checks `model`, returning all problems found.
+	pub fn check(&self, model: &Model) -> CheckReport {
+		let mut checker = Checker {
+			options: self,
+			model,
+			diagnostics: Vec::new()
+		};
+		checker.check_versions();
+		checker.check_graph(&model.graph, String::new(), &BTreeSet::new());
+		CheckReport { diagnostics: checker.diagnostics }
+	}
+}
+
+struct Checker<'c> {
+	options: &'c ModelChecker,
+	model: &'c Model,
+	diagnostics: Vec<Diagnostic>
+}
+
+impl<'c> Checker<'c> {
+	fn report(&mut self, severity: Severity, kind: DiagnosticKind, graph: &str, node: Option<String>, message: String) {
+		self.diagnostics.push(Diagnostic {
+			severity,
+			kind,
+			graph: graph.to_string(),
+			node,
+			message
+		});
+	}
+
+	fn check_versions(&mut self) {
+		let minor = self.options.runtime_version.unwrap_or(crate::MINOR_VERSION);
+		let (max_ir, max_opset) = schema::runtime_limits(minor);
+		let ir_version = self.model.ir_version;
+		if ir_version <= 0 {
+			self.report(Severity::Error, DiagnosticKind::UnsupportedVersion, "", None, String::from("Model has no IR version"));
+		} else if ir_version > max_ir {
+			self.report(
+				Severity::Error,
+				DiagnosticKind::UnsupportedVersion,
+				"",
+				None,
+				format!("Model has IR version {ir_version}, but ONNX Runtime 1.{minor} supports up to IR version {max_ir}")
+			);
+		}
+		match self.model.opset_version("") {
+			Some(opset) if opset > max_opset => self.report(
+				Severity::Error,
+				DiagnosticKind::UnsupportedVersion,
+				"",
+				None,
+				format!("Model imports version {opset} of the default operator set, but ONNX Runtime 1.{minor} supports up to version {max_opset}")
+			),
+			Some(_) => {}
+			None => self.report(Severity::Warning, DiagnosticKind::MissingOpset, "", None, String::from("Model does not import the default operator set"))
+		}
+
+		let mut imported = BTreeSet::new();
+		for opset in &self.model.opset_imports {
+			if !imported.insert(opset.domain.as_str()) {
+				self.report(
+					Severity::Error,
+					DiagnosticKind::DuplicateName,
+					"",
+					None,
+					format!("Operator set domain `{}` is imported more than once", opset.domain)
+				);
+			}
+		}
+	}
+
+	fn check_graph<'g>(&mut self, graph: &'g Graph, path: String, outer: &BTreeSet<&'g str>) {
+		let mut defined = outer.clone();
+		let define = |this: &mut Self, defined: &mut BTreeSet<&'g str>, name: &'g str, node: Option<String>, what: &str| {
+			if !defined.insert(name) {
+				this.report(Severity::Error, DiagnosticKind::DuplicateName, &path, node, format!("{what} `{name}` is already defined"));
+			}
+		};
+
+		for initializer in &graph.initializers {
+			define(self, &mut defined, &initializer.name, None, "Initializer");
+			if let Err(e) = check_initializer(initializer) {
+				self.report(Severity::Error, DiagnosticKind::InvalidInitializer, &path, None, e.message().to_string());
+			}
+			if let TensorData::External(entries) = &initializer.data {
+				self.check_external_data(&path, &initializer.name, entries, raw_size_of(initializer));
+			}
+		}
+		for input in &graph.inputs {
+			if input.ty.is_none() {
+				self.report(Severity::Error, DiagnosticKind::MissingType, &path, None, format!("Graph input `{}` has no type", input.name));
+			}
+			// older models list initializers as graph inputs too
+			if graph.initializer(&input.name).is_none() {
+				define(self, &mut defined, &input.name, None, "Graph input");
+			}
+		}
+
+		// values produced later in the graph, to tell unsorted nodes apart from undefined values
+		let mut produced_at: BTreeMap<&str, usize> = BTreeMap::new();
+		for (i, node) in graph.nodes.iter().enumerate() {
+			for output in node.outputs.iter().filter(|output| !output.is_empty()) {
+				produced_at.entry(output.as_str()).or_insert(i);
+			}
+		}
+
+		let mut node_names = BTreeSet::new();
+		for (i, node) in graph.nodes.iter().enumerate() {
+			let label = if node.name.is_empty() {
+				format!("Node #{i} ({})", node.op_type)
+			} else {
+				format!("Node `{}` ({})", node.name, node.op_type)
+			};
+			if !node.name.is_empty() && !node_names.insert(node.name.as_str()) {
+				self.report(
+					Severity::Warning,
+					DiagnosticKind::DuplicateName,
+					&path,
+					Some(label.clone()),
+					format!("Node name `{}` is used by more than one node", node.name)
+				);
+			}
+			if node.op_type.is_empty() {
+				self.report(Severity::Error, DiagnosticKind::MissingOperatorType, &path, Some(label.clone()), String::from("Node has no operator type"));
+			}
+
+			for input in node.inputs.iter().filter(|input| !input.is_empty()) {
+				if defined.contains(input.as_str()) {
+					continue;
+				}
+				match produced_at.get(input.as_str()) {
+					Some(&j) if j > i => self.report(
+						Severity::Error,
+						DiagnosticKind::UnsortedNodes,
+						&path,
+						Some(label.clone()),
+						format!("Input `{input}` is produced by node #{j}, which comes after this node")
+					),
+					_ => self.report(
+						Severity::Error,
+						DiagnosticKind::UndefinedValue,
+						&path,
+						Some(label.clone()),
+						format!("Input `{input}` is not a graph input, initializer, or output of another node")
+					)
+				}
+			}
+
+			self.check_node(node, &path, &label);
+
+			for attribute in &node.attributes {
+				let graphs = attribute.value.graphs();
+				for (j, subgraph) in graphs.iter().enumerate() {
+					let mut subpath = if path.is_empty() { label.clone() } else { format!("{path} / {label}") };
+					subpath.push('.');
+					subpath.push_str(&attribute.name);
+					if graphs.len() > 1 {
+						subpath.push_str(&format!("[{j}]"));
+					}
+					self.check_graph(subgraph, subpath, &defined);
+				}
+			}
+
+			for output in node.outputs.iter().filter(|output| !output.is_empty()) {
+				define(self, &mut defined, output, Some(label.clone()), "Output");
+			}
+		}
+
+		for output in &graph.outputs {
+			if !defined.contains(output.name.as_str()) {
+				self.report(
+					Severity::Error,
+					DiagnosticKind::UndefinedOutput,
+					&path,
+					None,
+					format!("Graph output `{}` is not produced by any node", output.name)
+				);
+			}
+		}
+	}
+
+	fn check_node(&mut self, node: &Node, path: &str, label: &str) {
+		let domain = if node.domain == "ai.onnx" { "" } else { node.domain.as_str() };
+		let is_local_function = self.model.functions.iter().any(|f| f.domain == domain && f.name == node.op_type);
+		let Some(opset) = self.model.opset_version(domain) else {
+			// ONNX Runtime implicitly imports the latest version of domains it knows (including those of registered
+			// custom operators), so this is only a warning
+			if !is_local_function {
+				self.report(
+					Severity::Warning,
+					DiagnosticKind::MissingOpset,
+					path,
+					Some(label.to_string()),
+					format!("Operator set domain `{}` is not imported by the model", node.domain)
+				);
+			}
+			return;
+		};
+		// only operators of the default domain have schemas
+		if !domain.is_empty() || is_local_function {
+			return;
+		}
+		let Some(schema) = schema::attributes(&node.op_type, opset) else {
+			return;
+		};
+
+		let mut seen = BTreeSet::new();
+		for expected in schema {
+			let Some(attribute) = node.attributes.iter().find(|a| a.name == expected.name) else {
+				if expected.required {
+					self.report(
+						Severity::Error,
+						DiagnosticKind::MissingAttribute,
+						path,
+						Some(label.to_string()),
+						format!("Required attribute `{}` is missing", expected.name)
+					);
+				}
+				continue;
+			};
+			seen.insert(expected.name);
+			// attributes referencing a function's attribute are typed by the caller
+			let actual = attribute.value.type_code();
+			if attribute.ref_attr_name.is_empty() && actual != expected.type_code {
+				self.report(
+					Severity::Error,
+					DiagnosticKind::AttributeType,
+					path,
+					Some(label.to_string()),
+					format!(
+						"Attribute `{}` should be of type {}, but is of type {}",
+						attribute.name,
+						schema::type_name(expected.type_code),
+						schema::type_name(actual)
+					)
+				);
+			}
+		}
+		for attribute in node.attributes.iter().filter(|a| !seen.contains(a.name.as_str())) {
+			self.report(
+				Severity::Error,
+				DiagnosticKind::UnknownAttribute,
+				path,
+				Some(label.to_string()),
+				format!("Attribute `{}` is not part of the schema of {} at operator set version {opset}", attribute.name, node.op_type)
+			);
+		}
+	}
+
+	fn check_external_data(&mut self, path: &str, name: &str, entries: &[(String, String)], expected_size: Option<u64>) {
+		let entry = |key: &str| entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+		let mut error = |message: String| {
+			self.diagnostics.push(Diagnostic {
+				severity: Severity::Error,
+				kind: DiagnosticKind::ExternalData,
+				graph: path.to_string(),
+				node: None,
+				message: format!("Initializer `{name}` {message}")
+			});
+		};
+
+		let Some(location) = entry("location") else {
+			error(String::from("is stored externally, but has no `location`"));
+			return;
+		};
+		if location.starts_with(['/', '\\']) || location.contains(':') || location.split(['/', '\\']).any(|part| part == "..") {
+			error(format!("references external data at `{location}`, which is not a path relative to the model's directory"));
+			return;
+		}
+		let mut parse = |key: &str| match entry(key).map(str::parse::<u64>) {
+			Some(Ok(value)) => Some(value),
+			Some(Err(_)) => {
+				error(format!("has an invalid external data `{key}`"));
+				None
+			}
+			None => None
+		};
+		let offset = parse("offset").unwrap_or(0);
+		let length = parse("length");
+		if let (Some(length), Some(expected)) = (length, expected_size) {
+			if length != expected {
+				error(format!("has {length} bytes of external data, but its shape requires {expected} bytes"));
+			}
+		}
+
+		#[cfg(feature = "std")]
+		if let Some((_, len)) = self.options.in_memory_files.iter().find(|(file, _)| file == location) {
+			let required = offset + length.or(expected_size).unwrap_or(0);
+			if *len < required {
+				error(format!("references {required} bytes of `{location}`, but the file provided in memory only has {len} bytes"));
+			}
+		} else if let Some(dir) = &self.options.external_data_dir {
+			let file = dir.join(
+				Path::new(location)
+					.components()
+					.filter(|c| matches!(c, Component::Normal(_)))
+					.collect::<PathBuf>()
+			);
+			let required = offset + length.or(expected_size).unwrap_or(0);
+			match std::fs::metadata(&file) {
+				Ok(metadata) if metadata.len() < required => {
+					error(format!("references {required} bytes of `{location}`, but the file only has {} bytes", metadata.len()));
+				}
+				Ok(_) => {}
+				Err(_) => error(format!("references external data at `{location}`, which does not exist in `{}`", dir.display()))
+			}
+		}
+		#[cfg(not(feature = "std"))]
+		let _ = offset;
+	}
+}
+
+fn raw_size_of(initializer: &super::Initializer) -> Option<u64> {
+	element_type(initializer.data_type)
+		.and_then(|ty| raw_size(ty, initializer.num_elements()))
+		.map(|size| size as u64)
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec, vec::Vec};
+
+	use super::{DiagnosticKind, ModelChecker, Severity};
+	use crate::{
+		model::{Dimension, GraphBuilder, Initializer, Model, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	fn model(nodes: Vec<Node>) -> Model {
+		let mut model = GraphBuilder::new("test")
+			.with_input("x", TensorElementType::Float32, [Dimension::from("n"), 4.into()])
+			.with_output("y", TensorElementType::Float32, [Dimension::from("n"), 4.into()])
+			.with_node(Node::new("Identity").with_inputs(["x"]).with_outputs(["y"]))
+			.build()
+			.expect("valid model");
+		model.graph.nodes = nodes;
+		model
+	}
+
+	fn kinds(model: &Model) -> Vec<(Severity, DiagnosticKind)> {
+		ModelChecker::new()
+			.check(model)
+			.diagnostics
+			.iter()
+			.map(|d| (d.severity, d.kind))
+			.collect()
+	}
+
+	#[test]
+	fn test_structure() {
+		let model = model(vec![
+			Node::new("Relu").with_name("b").with_inputs(["a"]).with_outputs(["y"]),
+			Node::new("Relu").with_name("a").with_inputs(["x"]).with_outputs(["a"]),
+			Node::new("Relu").with_name("a").with_inputs(["z"]).with_outputs(["a"]),
+		]);
+		let report = ModelChecker::new().check(&model);
+		assert_eq!(
+			report.diagnostics.iter().map(|d| (d.severity, d.kind)).collect::<Vec<_>>(),
+			[
+				(Severity::Error, DiagnosticKind::UnsortedNodes),
+				(Severity::Warning, DiagnosticKind::DuplicateName),
+				(Severity::Error, DiagnosticKind::UndefinedValue),
+				(Severity::Error, DiagnosticKind::DuplicateName)
+			]
+		);
+		assert_eq!(report.diagnostics[0].to_string(), "error[unsorted-nodes]: Node `b` (Relu): Input `a` is produced by node #1, which comes after this node");
+		let err = report.into_result().expect_err("model has errors");
+		assert!(
+			err.to_string()
+				.contains("error[undefined-value]: Node `a` (Relu): Input `z` is not a graph input"),
+			"{err}"
+		);
+	}
+
+	#[test]
+	fn test_attributes() {
+		let model = model(vec![
+			Node::new("Concat").with_inputs(["x"]).with_outputs(["c"]),
+			Node::new("Softmax")
+				.with_inputs(["c"])
+				.with_outputs(["s"])
+				.with_attribute("axis", 1.0_f32),
+			Node::new("Squeeze")
+				.with_inputs(["s"])
+				.with_outputs(["y"])
+				.with_attribute("axes", [0_i64]),
+		]);
+		assert_eq!(
+			kinds(&model),
+			[
+				(Severity::Error, DiagnosticKind::MissingAttribute),
+				(Severity::Error, DiagnosticKind::AttributeType),
+				(Severity::Error, DiagnosticKind::UnknownAttribute)
+			]
+		);
+
+		// `axes` was an attribute of `Squeeze` before opset 13
+		let mut model = model;
+		model.set_opset_version("", 11);
+		assert_eq!(kinds(&model), [(Severity::Error, DiagnosticKind::MissingAttribute), (Severity::Error, DiagnosticKind::AttributeType)]);
+	}
+
+	#[test]
+	fn test_versions() {
+		let mut model = model(vec![Node::new("Custom").with_domain("my.ops").with_inputs(["x"]).with_outputs(["y"])]);
+		model.ir_version = 11;
+		model.set_opset_version("", 30);
+		let report = ModelChecker::new().with_runtime_version(17).check(&model);
+		let messages = report.diagnostics.iter().map(|d| d.message.as_str()).collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			[
+				"Model has IR version 11, but ONNX Runtime 1.17 supports up to IR version 9",
+				"Model imports version 30 of the default operator set, but ONNX Runtime 1.17 supports up to version 20",
+				"Operator set domain `my.ops` is not imported by the model"
+			]
+		);
+	}
+
+	#[test]
+	fn test_external_data() {
+		let mut model = model(vec![Node::new("Add").with_inputs(["x", "w"]).with_outputs(["y"])]);
+		let external = |location: &str, length: &str| {
+			TensorData::External(vec![("location".to_string(), location.to_string()), ("length".to_string(), length.to_string())])
+		};
+		model
+			.graph
+			.initializers
+			.push(Initializer::new("w", TensorElementType::Float32, [4], external("weights.bin", "16")));
+		assert!(ModelChecker::new().check(&model).diagnostics.is_empty());
+
+		model.graph.initializers[0].data = external("../weights.bin", "12");
+		let report = ModelChecker::new().check(&model);
+		assert_eq!(report.diagnostics.len(), 1);
+		assert!(report.diagnostics[0].message.contains("not a path relative to the model's directory"));
+
+		model.graph.initializers[0].data = external("weights.bin", "12");
+		let report = ModelChecker::new().check(&model);
+		assert_eq!(report.diagnostics[0].message, "Initializer `w` has 12 bytes of external data, but its shape requires 16 bytes");
+
+		#[cfg(feature = "std")]
+		{
+			model.graph.initializers[0].data = external("upsample.onnx", "16");
+			assert!(
+				ModelChecker::new()
+					.with_external_data_dir("tests/data")
+					.check(&model)
+					.diagnostics
+					.is_empty()
+			);
+			model.graph.initializers[0].data = external("missing.bin", "16");
+			let report = ModelChecker::new().with_external_data_dir("tests/data").check(&model);
+			assert!(report.diagnostics[0].message.contains("which does not exist in `tests/data`"), "{report}");
+		}
+	}
+}
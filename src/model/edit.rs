@@ -0,0 +1,479 @@
+//! Model surgery: editing the graph of a parsed [`Model`] while keeping it valid.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+
+use super::{Graph, Model, Node, OperatorSetId, TypeInfo, ValueInfo, check::invalid_graph, graph::Dimension};
+use crate::error::{Error, ErrorCode, Result};
+
+fn not_found(message: String) -> Error {
+	Error::new_with_code(ErrorCode::InvalidArgument, message)
+}
+
+impl Model {
+	/// Renames a graph input, updating every node that consumes it.
+	///
+	/// Returns an error if the graph has no input named `from`, or if `to` is already used by another value.
+	pub fn rename_input(&mut self, from: &str, to: impl Into<String>) -> Result<()> {
+		if !self.graph.inputs.iter().any(|input| input.name == from) {
+			return Err(not_found(format!("Graph has no input named `{from}`")));
+		}
+		self.rename_value(from, to.into())
+	}
+
+	/// Renames a graph output, updating the node that produces it.
+	///
+	/// Returns an error if the graph has no output named `from`, or if `to` is already used by another value.
+	pub fn rename_output(&mut self, from: &str, to: impl Into<String>) -> Result<()> {
+		if !self.graph.outputs.iter().any(|output| output.name == from) {
+			return Err(not_found(format!("Graph has no output named `{from}`")));
+		}
+		self.rename_value(from, to.into())
+	}
+
+	fn rename_value(&mut self, from: &str, to: String) -> Result<()> {
+		if from == to {
+			return Ok(());
+		}
+		let mut names = BTreeSet::new();
+		value_names(&self.graph, &mut names);
+		if names.contains(to.as_str()) {
+			return Err(invalid_graph(format!("Cannot rename `{from}` to `{to}`: a value named `{to}` already exists")));
+		}
+		rename_in_graph(&mut self.graph, from, &to);
+		Ok(())
+	}
+
+	/// Promotes an intermediate value (or an input or initializer) to a graph output.
+	///
+	/// The output's type is taken from the graph's `value_info` if present; otherwise, ONNX Runtime will infer it when
+	/// the session is created. Does nothing if `name` is already an output.
+	pub fn add_output(&mut self, name: &str) -> Result<()> {
+		if self.graph.outputs.iter().any(|output| output.name == name) {
+			return Ok(());
+		}
+		let defined = self.graph.inputs.iter().any(|input| input.name == name)
+			|| self.graph.initializers.iter().any(|initializer| initializer.name == name)
+			|| self.graph.nodes.iter().any(|node| node.outputs.iter().any(|output| output == name));
+		if !defined {
+			return Err(not_found(format!("Graph has no value named `{name}`")));
+		}
+		let output = self.graph.find_value_info(name).unwrap_or_else(|| ValueInfo {
+			name: name.to_string(),
+			..Default::default()
+		});
+		self.graph.outputs.push(output);
+		Ok(())
+	}
+
+	/// Removes a graph output. The nodes producing it are kept; use [`Model::prune`] to remove nodes which no longer
+	/// contribute to any output.
+	pub fn remove_output(&mut self, name: &str) -> Result<()> {
+		let index = self
+			.graph
+			.outputs
+			.iter()
+			.position(|output| output.name == name)
+			.ok_or_else(|| not_found(format!("Graph has no output named `{name}`")))?;
+		self.graph.outputs.remove(index);
+		Ok(())
+	}
+
+	/// Removes the node with the given name from the main graph, returning it.
+	///
+	/// Returns an error, leaving the model unchanged, if the node's outputs are still used by other nodes or are
+	/// graph outputs.
+	pub fn remove_node(&mut self, name: &str) -> Result<Node> {
+		let index = self.node_index(name)?;
+		let node = self.graph.nodes.remove(index);
+		if let Err(e) = self.graph.validate() {
+			self.graph.nodes.insert(index, node);
+			return Err(invalid_graph(format!("Cannot remove node `{name}`: {}", e.message())));
+		}
+		Ok(node)
+	}
+
+	/// Replaces the node with the given name in the main graph with `node`, returning the old node.
+	///
+	/// Returns an error, leaving the model unchanged, if the graph would be invalid after the replacement, e.g. if the
+	/// new node doesn't produce an output of the old node which is still in use.
+	pub fn replace_node(&mut self, name: &str, node: Node) -> Result<Node> {
+		let index = self.node_index(name)?;
+		let old = core::mem::replace(&mut self.graph.nodes[index], node);
+		if let Err(e) = self.graph.validate() {
+			self.graph.nodes[index] = old;
+			return Err(invalid_graph(format!("Cannot replace node `{name}`: {}", e.message())));
+		}
+		Ok(old)
+	}
+
+	fn node_index(&self, name: &str) -> Result<usize> {
+		self.graph
+			.nodes
+			.iter()
+			.position(|node| node.name == name)
+			.ok_or_else(|| not_found(format!("Graph has no node named `{name}`")))
+	}
+
+	/// Extracts the part of the model that computes `outputs` from `inputs` as a new model.
+	///
+	/// `inputs` & `outputs` can name any values in the main graph. Only the nodes & initializers required to compute
+	/// `outputs` are kept. Returns an error if computing `outputs` requires a value other than `inputs` which isn't
+	/// an initializer or computed from `inputs`, or if the type of an input can't be determined (try running the model
+	/// through ONNX shape inference first).
+	///
+	/// ```no_run
+	/// # use ort::model::Model;
+	/// # fn main() -> ort::Result<()> {
+	/// let model = Model::from_file("resnet50.onnx")?;
+	/// // cut off the classification head, keeping only the feature extractor
+	/// let backbone = model.extract(&["data"], &["flatten_473"])?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn extract(&self, inputs: &[&str], outputs: &[&str]) -> Result<Model> {
+		let graph = &self.graph;
+		let boundary: BTreeSet<&str> = inputs.iter().copied().collect();
+		let (live, used) = live_nodes(graph, outputs.iter().copied(), &boundary)?;
+
+		let mut extracted = Graph {
+			name: graph.name.clone(),
+			doc_string: graph.doc_string.clone(),
+			nodes: graph
+				.nodes
+				.iter()
+				.enumerate()
+				.filter(|(i, _)| live.contains(i))
+				.map(|(_, node)| node.clone())
+				.collect(),
+			initializers: graph
+				.initializers
+				.iter()
+				.filter(|initializer| used.contains(initializer.name.as_str()) && !boundary.contains(initializer.name.as_str()))
+				.cloned()
+				.collect(),
+			..Default::default()
+		};
+		for &input in inputs {
+			let value_info = graph
+				.find_value_info(input)
+				.filter(|value_info| value_info.ty.is_some())
+				.ok_or_else(|| invalid_graph(format!("Cannot extract subgraph: the type of input `{input}` is unknown")))?;
+			extracted.inputs.push(value_info);
+		}
+		for &output in outputs {
+			extracted.outputs.push(graph.find_value_info(output).unwrap_or_else(|| ValueInfo {
+				name: output.to_string(),
+				..Default::default()
+			}));
+		}
+		extracted.value_info = graph
+			.value_info
+			.iter()
+			.filter(|value_info| used.contains(value_info.name.as_str()) && !boundary.contains(value_info.name.as_str()))
+			.cloned()
+			.collect();
+		extracted.validate()?;
+
+		Ok(Model {
+			ir_version: self.ir_version,
+			opset_imports: self.opset_imports.clone(),
+			producer_name: self.producer_name.clone(),
+			producer_version: self.producer_version.clone(),
+			domain: self.domain.clone(),
+			model_version: self.model_version,
+			doc_string: self.doc_string.clone(),
+			graph: extracted,
+			metadata_props: self.metadata_props.clone(),
+			functions: self.functions.clone(),
+			unknown_fields: Vec::new()
+		})
+	}
+
+	/// Removes nodes which don't contribute to any graph output, and initializers & `value_info` entries which are no
+	/// longer used. Graph inputs are never removed.
+	pub fn prune(&mut self) -> Result<()> {
+		let boundary: BTreeSet<&str> = self.graph.inputs.iter().map(|input| input.name.as_str()).collect();
+		let (live, used) = live_nodes(&self.graph, self.graph.outputs.iter().map(|output| output.name.as_str()), &boundary)?;
+		let used: BTreeSet<String> = used.into_iter().map(String::from).collect();
+
+		let mut index = 0;
+		self.graph.nodes.retain(|_| {
+			index += 1;
+			live.contains(&(index - 1))
+		});
+		self.graph
+			.initializers
+			.retain(|initializer| used.contains(&initializer.name) || self.graph.inputs.iter().any(|input| input.name == initializer.name));
+		self.graph.value_info.retain(|value_info| used.contains(&value_info.name));
+		Ok(())
+	}
+
+	/// Sets the imported version of an operator set domain, adding the import if it isn't present. The default ONNX
+	/// domain can be specified as either `""` or `"ai.onnx"`.
+	///
+	/// This only changes the import; use [`Model::convert_opset`] to convert nodes between versions of the default
+	/// operator set.
+	pub fn set_opset_version(&mut self, domain: &str, version: i64) {
+		let domain = if domain == "ai.onnx" { "" } else { domain };
+		match self
+			.opset_imports
+			.iter_mut()
+			.find(|opset| opset.domain == domain || (domain.is_empty() && opset.domain == "ai.onnx"))
+		{
+			Some(opset) => opset.version = version,
+			None => self.opset_imports.push(OperatorSetId::new(domain, version))
+		}
+	}
+}
+
+impl Graph {
+	/// Returns the name & type of a value from the graph's inputs, outputs, `value_info`, or initializers.
+	pub fn find_value_info(&self, name: &str) -> Option<ValueInfo> {
+		if let Some(value_info) = self
+			.inputs
+			.iter()
+			.chain(&self.outputs)
+			.chain(&self.value_info)
+			.find(|value_info| value_info.name == name && value_info.ty.is_some())
+		{
+			return Some(value_info.clone());
+		}
+		self.initializer(name).map(|initializer| ValueInfo {
+			name: name.to_string(),
+			ty: Some(TypeInfo::Tensor {
+				elem_type: initializer.data_type,
+				shape: Some(initializer.dims.iter().map(|&d| Dimension::Value(d)).collect())
+			}),
+			..Default::default()
+		})
+	}
+}
+
+/// Walks backwards from `outputs`, returning the indices of the nodes required to compute them, and the names of all
+/// values used along the way. The walk stops at values in `boundary`.
+fn live_nodes<'g>(graph: &'g Graph, outputs: impl Iterator<Item = &'g str>, boundary: &BTreeSet<&str>) -> Result<(BTreeSet<usize>, BTreeSet<&'g str>)> {
+	let producers: BTreeMap<&str, usize> = graph
+		.nodes
+		.iter()
+		.enumerate()
+		.flat_map(|(i, node)| node.outputs.iter().map(move |output| (output.as_str(), i)))
+		.collect();
+	let mut live = BTreeSet::new();
+	let mut used = BTreeSet::new();
+	let mut stack: Vec<&str> = outputs.collect();
+	while let Some(value) = stack.pop() {
+		if !used.insert(value) || boundary.contains(value) {
+			continue;
+		}
+		if let Some(&index) = producers.get(value) {
+			if live.insert(index) {
+				stack.extend(consumed_values(&graph.nodes[index]));
+			}
+		} else if graph.initializer(value).is_none() {
+			return Err(invalid_graph(if graph.inputs.iter().any(|input| input.name == value) {
+				format!("Cannot extract subgraph: it depends on graph input `{value}`, which is not one of the extracted inputs")
+			} else {
+				format!("Cannot extract subgraph: `{value}` is not produced by any node")
+			}));
+		}
+	}
+	Ok((live, used))
+}
+
+/// Returns the values consumed by a node, including those used implicitly by its subgraphs.
+pub(crate) fn consumed_values(node: &Node) -> BTreeSet<&str> {
+	let mut values: BTreeSet<&str> = node.inputs.iter().filter(|input| !input.is_empty()).map(String::as_str).collect();
+	for subgraph in node.attributes.iter().flat_map(|attr| attr.value.graphs()) {
+		outer_values(subgraph, &mut values);
+	}
+	values
+}
+
+/// Collects the values used by a subgraph which are defined in an enclosing graph.
+fn outer_values<'g>(graph: &'g Graph, out: &mut BTreeSet<&'g str>) {
+	let mut defined: BTreeSet<&str> = graph
+		.inputs
+		.iter()
+		.map(|input| input.name.as_str())
+		.chain(graph.initializers.iter().map(|initializer| initializer.name.as_str()))
+		.collect();
+	for node in &graph.nodes {
+		out.extend(consumed_values(node).into_iter().filter(|value| !defined.contains(value)));
+		defined.extend(node.outputs.iter().map(String::as_str));
+	}
+	out.extend(
+		graph
+			.outputs
+			.iter()
+			.map(|output| output.name.as_str())
+			.filter(|output| !defined.contains(output))
+	);
+}
+
+/// Collects the names of all values defined anywhere in `graph`, including in subgraphs.
+fn value_names<'g>(graph: &'g Graph, out: &mut BTreeSet<&'g str>) {
+	out.extend(graph.inputs.iter().map(|input| input.name.as_str()));
+	out.extend(graph.initializers.iter().map(|initializer| initializer.name.as_str()));
+	for node in &graph.nodes {
+		out.extend(node.outputs.iter().map(String::as_str));
+		for subgraph in node.attributes.iter().flat_map(|attr| attr.value.graphs()) {
+			value_names(subgraph, out);
+		}
+	}
+}
+
+/// The names of all values & nodes in a graph, used to generate new names which don't collide with existing ones.
+pub(crate) struct Names(BTreeSet<String>);
+
+impl Names {
+	pub(crate) fn new(graph: &Graph) -> Self {
+		let mut values = BTreeSet::new();
+		value_names(graph, &mut values);
+		let mut names: BTreeSet<String> = values.into_iter().map(String::from).collect();
+		names.extend(graph.all_nodes().map(|node| node.name.clone()));
+		Self(names)
+	}
+
+	/// Reserves & returns `name`, or `name` with a numeric suffix if it's already taken.
+	pub(crate) fn fresh(&mut self, name: String) -> String {
+		let mut candidate = name.clone();
+		let mut i = 1;
+		while self.0.contains(&candidate) {
+			candidate = format!("{name}_{i}");
+			i += 1;
+		}
+		self.0.insert(candidate.clone());
+		candidate
+	}
+}
+
+pub(crate) fn rename_in_graph(graph: &mut Graph, from: &str, to: &str) {
+	let rename = |name: &mut String| {
+		if name == from {
+			*name = to.to_string();
+		}
+	};
+	for value_info in graph.inputs.iter_mut().chain(&mut graph.outputs).chain(&mut graph.value_info) {
+		rename(&mut value_info.name);
+	}
+	for initializer in &mut graph.initializers {
+		rename(&mut initializer.name);
+	}
+	for node in &mut graph.nodes {
+		node.inputs.iter_mut().chain(&mut node.outputs).for_each(rename);
+		for attribute in &mut node.attributes {
+			for subgraph in attribute.value.graphs_mut() {
+				rename_in_graph(subgraph, from, to);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec};
+
+	use crate::{
+		model::{GraphBuilder, Initializer, Model, Node, TensorData, ValueInfo},
+		tensor::TensorElementType
+	};
+
+	/// `input.1` -> Relu -> Mul(w) -> Softmax -> `output`
+	fn model() -> Model {
+		GraphBuilder::new("classifier")
+			.with_input("input.1", TensorElementType::Float32, [1_i64, 4])
+			.with_output("output", TensorElementType::Float32, [1_i64, 4])
+			.with_raw_initializer(Initializer::new("w", TensorElementType::Float32, [4], TensorData::Float(vec![1.0; 4])))
+			.with_node(Node::new("Relu").with_name("relu").with_inputs(["input.1"]).with_outputs(["features"]))
+			.with_node(Node::new("Mul").with_name("head").with_inputs(["features", "w"]).with_outputs(["logits"]))
+			.with_node(Node::new("Softmax").with_name("softmax").with_inputs(["logits"]).with_outputs(["output"]))
+			.build()
+			.expect("valid model")
+	}
+
+	#[test]
+	fn test_rename() -> crate::Result<()> {
+		let mut model = model();
+		model.rename_input("input.1", "pixels")?;
+		model.rename_output("output", "probabilities")?;
+		assert_eq!(model.graph.inputs[0].name, "pixels");
+		assert_eq!(model.graph.nodes[0].inputs, ["pixels"]);
+		assert_eq!(model.graph.nodes[2].outputs, ["probabilities"]);
+		model.validate()?;
+
+		assert!(model.rename_input("pixels", "w").is_err());
+		assert!(model.rename_input("input.1", "x").is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_extract() -> crate::Result<()> {
+		let mut model = model();
+		let err = model.extract(&["features"], &["output"]).expect_err("untyped input");
+		assert!(err.to_string().contains("the type of input `features` is unknown"), "{err}");
+
+		model
+			.graph
+			.value_info
+			.push(ValueInfo::tensor("features", TensorElementType::Float32, [1_i64, 4]));
+		model.add_output("features")?;
+		assert_eq!(model.graph.outputs[1], model.graph.value_info[0]);
+		model.validate()?;
+
+		let head = model.extract(&["features"], &["output"])?;
+		assert_eq!(head.graph.nodes.iter().map(|n| n.name.as_str()).collect::<vec::Vec<_>>(), ["head", "softmax"]);
+		assert_eq!(head.graph.initializers.len(), 1);
+		assert_eq!(head.graph.inputs[0].name, "features");
+		assert!(head.graph.value_info.is_empty());
+
+		let backbone = model.extract(&["input.1"], &["features"])?;
+		assert_eq!(backbone.graph.nodes.len(), 1);
+		assert!(backbone.graph.initializers.is_empty());
+
+		let err = model.extract(&[], &["output"]).expect_err("missing input");
+		assert!(err.to_string().contains("depends on graph input `input.1`"), "{err}");
+		Ok(())
+	}
+
+	#[test]
+	fn test_remove_nodes() -> crate::Result<()> {
+		let mut model = model();
+		let err = model.remove_node("head").expect_err("dangling `logits`");
+		assert!(
+			err.to_string()
+				.contains("Cannot remove node `head`: Node `softmax` (Softmax) uses `logits`"),
+			"{err}"
+		);
+		assert_eq!(model.graph.nodes.len(), 3);
+
+		model.replace_node(
+			"softmax",
+			Node::new("Identity")
+				.with_name("identity")
+				.with_inputs(["logits"])
+				.with_outputs(["output"])
+		)?;
+		assert_eq!(model.graph.nodes[2].op_type, "Identity");
+		assert!(
+			model
+				.replace_node("identity", Node::new("Identity").with_inputs(["missing"]).with_outputs(["output"]))
+				.is_err()
+		);
+
+		model.add_output("features")?;
+		model.remove_output("output")?;
+		model.remove_node("identity")?;
+		model.prune()?;
+		assert_eq!(model.graph.nodes.len(), 1);
+		assert!(model.graph.initializers.is_empty());
+
+		model.set_opset_version("ai.onnx", 18);
+		assert_eq!(model.opset_version(""), Some(18));
+		Ok(())
+	}
+}
@@ -0,0 +1,523 @@
+//! Conversion of models from `float32` to `float16` or `bfloat16`.
+//!
+//! [`Float16Converter`] halves the size of a model's floating point weights, and lets execution providers with fast
+//! half-precision kernels (like CUDA, TensorRT, and DirectML) use them. Operators which are numerically sensitive or
+//! poorly supported in half precision can be kept in `float32` with a blocklist; `Cast` nodes are inserted wherever a
+//! value crosses between the two precisions.
+//!
+//! Conversion can be checked with [`verify`], which runs both models on the CPU and compares their outputs. (ONNX
+//! Runtime's CPU execution provider implements few operators in `float16`, but automatically runs the rest in
+//! `float32`, so results are close to those of a GPU.)
+//!
+//! ```no_run
+//! # use ort::{model::{Model, float16::{DEFAULT_BLOCKED_OP_TYPES, Float16Converter, HalfType, verify}}, session::debug::Tolerance, value::Tensor};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = Model::from_file("bert.onnx")?;
+//! let converted = Float16Converter::new(HalfType::Float16)
+//! 	.with_blocked_op_types(DEFAULT_BLOCKED_OP_TYPES.iter().chain(&["Softmax", "LayerNormalization"]))
+//! 	.with_keep_io_types(true)
+//! 	.convert(&model)?;
+//!
+//! let input_ids = Tensor::from_array(([1, 8], vec![101_i64, 7592, 1010, 2088, 999, 102, 0, 0]))?;
+//! if let Some(divergence) = verify(&model, &converted, ort::inputs!["input_ids" => input_ids], Tolerance::new(1e-2, 1e-2))? {
+//! 	println!("{divergence}");
+//! }
+//! std::fs::write("bert.fp16.onnx", converted.to_bytes())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc::{
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use core::mem;
+
+use super::{
+	Attribute, AttributeValue, Initializer, Model, Node, TensorData, TypeInfo,
+	check::invalid_graph,
+	edit::{Names, consumed_values, rename_in_graph},
+	infer::{FLOAT, element_types}
+};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	session::{
+		Session, SessionInputValue,
+		debug::{Divergence, Tolerance, compare_values}
+	},
+	tensor::TensorElementType,
+	value::{Tensor, ValueType}
+};
+
+/// Operator types kept in `float32` by default, because they're numerically sensitive or lack half-precision
+/// implementations in ONNX Runtime.
+pub const DEFAULT_BLOCKED_OP_TYPES: &[&str] = &[
+	"Resize",
+	"Upsample",
+	"Range",
+	"CumSum",
+	"Min",
+	"Max",
+	"TopK",
+	"NonMaxSuppression",
+	"RoiAlign",
+	"RandomNormal",
+	"RandomNormalLike",
+	"RandomUniform",
+	"RandomUniformLike"
+];
+
+/// The half-precision type to convert to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HalfType {
+	/// IEEE 754 half precision. Values outside of `±65504` are clamped.
+	Float16,
+	/// Brain floating point, which has the same range as `float32` but less precision. Requires version 13 or newer of
+	/// the default operator set.
+	Bfloat16
+}
+
+impl HalfType {
+	fn element_type(self) -> TensorElementType {
+		match self {
+			Self::Float16 => TensorElementType::Float16,
+			Self::Bfloat16 => TensorElementType::Bfloat16
+		}
+	}
+
+	fn data_type(self) -> i32 {
+		super::tensor::data_type(self.element_type())
+	}
+
+	fn suffix(self) -> &'static str {
+		match self {
+			Self::Float16 => "fp16",
+			Self::Bfloat16 => "bf16"
+		}
+	}
+
+	/// Converts `values` to little-endian half-precision bytes.
+	fn convert(self, values: &[f32]) -> Vec<u8> {
+		match self {
+			Self::Float16 => values
+				.iter()
+				.flat_map(|&value| {
+					let value = if value.is_finite() { value.clamp(-65504.0, 65504.0) } else { value };
+					half::f16::from_f32(value).to_le_bytes()
+				})
+				.collect(),
+			Self::Bfloat16 => values.iter().flat_map(|&value| half::bf16::from_f32(value).to_le_bytes()).collect()
+		}
+	}
+}
+
+/// Converts the `float32` initializers, constants, and values of a model to `float16` or `bfloat16`.
+///
+/// Nodes with an operator type in the blocklist, nodes in a domain other than the default ONNX domain or
+/// `com.microsoft`, and nodes with subgraphs (like `If` & `Loop`) are kept in `float32`. By default, the model's
+/// `float32` inputs & outputs are converted too; use [`Float16Converter::with_keep_io_types`] to keep them in `float32`
+/// so the converted model is a drop-in replacement.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct Float16Converter {
+	ty: HalfType,
+	keep_io_types: bool,
+	blocked_op_types: Vec<String>,
+	blocked_nodes: Vec<String>
+}
+
+impl Float16Converter {
+	/// Creates a new converter to the given type, blocking [`DEFAULT_BLOCKED_OP_TYPES`].
+	pub fn new(ty: HalfType) -> Self {
+		Self {
+			ty,
+			keep_io_types: false,
+			blocked_op_types: DEFAULT_BLOCKED_OP_TYPES.iter().map(|op_type| op_type.to_string()).collect(),
+			blocked_nodes: Vec::new()
+		}
+	}
+
+	/// Keep the model's `float32` inputs & outputs in `float32`, converting them at the boundaries of the graph.
+	pub fn with_keep_io_types(mut self, enable: bool) -> Self {
+		self.keep_io_types = enable;
+		self
+	}
+
+	/// Sets the operator types to keep in `float32`, replacing [`DEFAULT_BLOCKED_OP_TYPES`].
+	pub fn with_blocked_op_types<S: AsRef<str>>(mut self, op_types: impl IntoIterator<Item = S>) -> Self {
+		self.blocked_op_types = op_types.into_iter().map(|op_type| op_type.as_ref().to_string()).collect();
+		self
+	}
+
+	/// Keeps the nodes with the given names in `float32`.
+	pub fn with_blocked_nodes<S: AsRef<str>>(mut self, nodes: impl IntoIterator<Item = S>) -> Self {
+		self.blocked_nodes = nodes.into_iter().map(|node| node.as_ref().to_string()).collect();
+		self
+	}
+
+	fn is_blocked(&self, node: &Node) -> bool {
+		!matches!(node.domain.as_str(), "" | "ai.onnx" | "com.microsoft")
+			|| self.blocked_op_types.contains(&node.op_type)
+			|| self.blocked_nodes.contains(&node.name)
+			|| node.attributes.iter().any(|attribute| !attribute.value.graphs().is_empty())
+	}
+
+	/// Returns a converted copy of `model`.
+	pub fn convert(&self, model: &Model) -> Result<Model> {
+		if self.ty == HalfType::Bfloat16 && model.opset_version("").unwrap_or(0) < 13 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Conversion to bfloat16 requires version 13 or newer of the default operator set"));
+		}
+
+		let mut model = model.clone();
+		let graph = &mut model.graph;
+		let types = element_types(graph);
+		let is_float = |name: &str| types.get(name) == Some(&FLOAT);
+		let mut casts = Casts {
+			names: Names::new(graph),
+			casts: BTreeMap::new()
+		};
+		// `float32` values which are now stored in half precision
+		let mut half: BTreeSet<String> = BTreeSet::new();
+
+		// initializers are converted if only unblocked nodes use them
+		let mut blocked_uses = BTreeSet::new();
+		let mut unblocked_uses = BTreeSet::new();
+		for node in &graph.nodes {
+			let uses = if self.is_blocked(node) { &mut blocked_uses } else { &mut unblocked_uses };
+			uses.extend(consumed_values(node).into_iter().map(String::from));
+		}
+		let io: BTreeSet<&str> = graph.inputs.iter().chain(&graph.outputs).map(|value| value.name.as_str()).collect();
+		for initializer in &mut graph.initializers {
+			if !unblocked_uses.contains(&initializer.name) || blocked_uses.contains(&initializer.name) || io.contains(initializer.name.as_str()) {
+				continue;
+			}
+			if let Some(values) = initializer.float_values() {
+				initializer.data_type = self.ty.data_type();
+				initializer.data = TensorData::Raw(self.ty.convert(&values));
+				half.insert(initializer.name.clone());
+			}
+		}
+		if !self.keep_io_types {
+			for input in &mut graph.inputs {
+				if let Some(TypeInfo::Tensor { elem_type, .. }) = &mut input.ty {
+					if *elem_type == FLOAT {
+						*elem_type = self.ty.data_type();
+						half.insert(input.name.clone());
+					}
+				}
+			}
+		}
+
+		for mut node in mem::take(&mut graph.nodes) {
+			if self.is_blocked(&node) {
+				for input in &mut node.inputs {
+					if half.contains(input) {
+						*input = casts.cast(input, FLOAT, &mut graph.nodes);
+					}
+				}
+				let implicit: Vec<String> = consumed_values(&node)
+					.into_iter()
+					.filter(|value| half.contains(*value) && !node.inputs.iter().any(|input| input == value))
+					.map(String::from)
+					.collect();
+				for value in implicit {
+					let cast = casts.cast(&value, FLOAT, &mut graph.nodes);
+					for attribute in &mut node.attributes {
+						for subgraph in attribute.value.graphs_mut() {
+							rename_in_graph(subgraph, &value, &cast);
+						}
+					}
+				}
+			} else {
+				for input in &mut node.inputs {
+					if is_float(input) && !half.contains(input) {
+						*input = casts.cast(input, self.ty.data_type(), &mut graph.nodes);
+					}
+				}
+				self.convert_attributes(&mut node);
+				half.extend(node.outputs.iter().filter(|output| is_float(output)).cloned());
+			}
+			graph.nodes.push(node);
+		}
+
+		for i in 0..graph.outputs.len() {
+			let name = graph.outputs[i].name.clone();
+			if !is_float(&name) || graph.inputs.iter().any(|input| input.name == name) {
+				continue;
+			}
+			let to_half = !self.keep_io_types;
+			if half.contains(&name) != to_half {
+				// rename the value inside the graph, and cast it to the output's name
+				let suffix = if to_half { "fp32" } else { self.ty.suffix() };
+				let renamed = casts.names.fresh(format!("{name}_{suffix}"));
+				let outputs = mem::take(&mut graph.outputs);
+				rename_in_graph(graph, &name, &renamed);
+				graph.outputs = outputs;
+				if half.remove(&name) {
+					half.insert(renamed.clone());
+				}
+				let cast = Node::new("Cast")
+					.with_name(casts.names.fresh(format!("{name}_Cast")))
+					.with_attribute("to", i64::from(if to_half { self.ty.data_type() } else { FLOAT }))
+					.with_inputs([renamed])
+					.with_outputs([name.as_str()]);
+				graph.nodes.push(cast);
+			}
+			if let Some(TypeInfo::Tensor { elem_type, .. }) = &mut graph.outputs[i].ty {
+				*elem_type = if to_half { self.ty.data_type() } else { FLOAT };
+			}
+		}
+		for value_info in &mut graph.value_info {
+			if let Some(TypeInfo::Tensor { elem_type, .. }) = &mut value_info.ty {
+				if *elem_type == FLOAT && half.contains(&value_info.name) {
+					*elem_type = self.ty.data_type();
+				}
+			}
+		}
+
+		graph.validate()?;
+		Ok(model)
+	}
+
+	/// Converts the `float32` type & tensor attributes of an unblocked node.
+	fn convert_attributes(&self, node: &mut Node) {
+		let ty = self.ty;
+		let half_tensor = |values: &[f32], dims: Vec<i64>| Initializer::new("", ty.element_type(), dims, TensorData::Raw(ty.convert(values)));
+		for attribute in &mut node.attributes {
+			match (&mut attribute.value, attribute.name.as_str()) {
+				(AttributeValue::Int(data_type), "to" | "dtype") if *data_type == i64::from(FLOAT) => *data_type = i64::from(ty.data_type()),
+				(AttributeValue::Tensor(tensor), _) => {
+					if let Some(values) = tensor.float_values() {
+						let name = mem::take(&mut tensor.name);
+						*tensor = Initializer {
+							name,
+							..half_tensor(&values, mem::take(&mut tensor.dims))
+						};
+					}
+				}
+				(AttributeValue::Float(value), "value_float") if node.op_type == "Constant" => {
+					*attribute = Attribute::new("value", AttributeValue::Tensor(half_tensor(&[*value], Vec::new())));
+				}
+				(AttributeValue::Floats(values), "value_floats") if node.op_type == "Constant" => {
+					let dims = alloc::vec![values.len() as i64];
+					*attribute = Attribute::new("value", AttributeValue::Tensor(half_tensor(values, dims)));
+				}
+				_ => {}
+			}
+		}
+		// without a `value`, `ConstantOfShape` produces `float32` zeros
+		if node.op_type == "ConstantOfShape" && node.attribute("value").is_none() {
+			node.attributes
+				.push(Attribute::new("value", AttributeValue::Tensor(half_tensor(&[0.0], alloc::vec![1]))));
+		}
+	}
+}
+
+/// Inserts `Cast` nodes, reusing them for values which are cast to the same type more than once.
+struct Casts {
+	names: Names,
+	casts: BTreeMap<(String, i32), String>
+}
+
+impl Casts {
+	/// Returns the name of `value` cast to `data_type`, inserting a `Cast` node into `nodes` if it doesn't exist yet.
+	fn cast(&mut self, value: &str, data_type: i32, nodes: &mut Vec<Node>) -> String {
+		let key = (value.to_string(), data_type);
+		if let Some(cast) = self.casts.get(&key) {
+			return cast.clone();
+		}
+		let suffix = match data_type {
+			FLOAT => "fp32",
+			_ if data_type == HalfType::Bfloat16.data_type() => "bf16",
+			_ => "fp16"
+		};
+		let output = self.names.fresh(format!("{value}_{suffix}"));
+		nodes.push(
+			Node::new("Cast")
+				.with_name(self.names.fresh(format!("{value}_Cast_{suffix}")))
+				.with_attribute("to", i64::from(data_type))
+				.with_inputs([value])
+				.with_outputs([output.as_str()])
+		);
+		self.casts.insert(key, output.clone());
+		output
+	}
+}
+
+/// Runs `original` & `converted` on the CPU with the same inputs, and returns the first output (in the order of
+/// `original`'s outputs) whose values differ by more than `tolerance`.
+///
+/// `inputs` are given by name, as created by [`inputs!`](crate::inputs). `float32` inputs are converted to half
+/// precision for `converted` if its inputs were converted. Outputs are compared
+/// as `f64`s regardless of their element type.
+pub fn verify(original: &Model, converted: &Model, inputs: Vec<(Cow<'_, str>, SessionInputValue<'_>)>, tolerance: Tolerance) -> Result<Option<Divergence>> {
+	let expected_session = Session::builder()?.commit_from_model(original)?;
+	let actual_session = Session::builder()?.commit_from_model(converted)?;
+
+	let mut original_inputs = Vec::with_capacity(inputs.len());
+	let mut converted_inputs = Vec::with_capacity(inputs.len());
+	for (name, value) in &inputs {
+		original_inputs.push((name.clone(), SessionInputValue::from(&**value)));
+		let input_type = converted
+			.graph
+			.inputs
+			.iter()
+			.find(|input| input.name == *name)
+			.and_then(|input| input.ty.as_ref())
+			.and_then(TypeInfo::element_type);
+		let converted_value = match (input_type, value.dtype()) {
+			(Some(TensorElementType::Float16), ValueType::Tensor { ty: TensorElementType::Float32, .. }) => {
+				let (shape, data) = value.try_extract_tensor::<f32>()?;
+				let data: Vec<half::f16> = data.iter().map(|&x| half::f16::from_f32(x)).collect();
+				SessionInputValue::from(Tensor::from_array((shape.clone(), data))?)
+			}
+			(Some(TensorElementType::Bfloat16), ValueType::Tensor { ty: TensorElementType::Float32, .. }) => {
+				let (shape, data) = value.try_extract_tensor::<f32>()?;
+				let data: Vec<half::bf16> = data.iter().map(|&x| half::bf16::from_f32(x)).collect();
+				SessionInputValue::from(Tensor::from_array((shape.clone(), data))?)
+			}
+			_ => SessionInputValue::from(&**value)
+		};
+		converted_inputs.push((name.clone(), converted_value));
+	}
+
+	let expected = expected_session.run(original_inputs)?;
+	let actual = actual_session.run(converted_inputs)?;
+	for output in &expected_session.outputs {
+		let actual = actual
+			.get(&output.name)
+			.ok_or_else(|| invalid_graph(format!("Converted model has no output named `{}`", output.name)))?;
+		if let Some(mismatch) = compare_values(&expected[output.name.as_str()], actual, tolerance)? {
+			let producer = original
+				.graph
+				.nodes
+				.iter()
+				.enumerate()
+				.find(|(_, node)| node.outputs.contains(&output.name));
+			return Ok(Some(Divergence {
+				name: output.name.clone(),
+				node: producer.map(|(_, node)| node.name.clone()).unwrap_or_default(),
+				node_index: producer.map_or(0, |(index, _)| index),
+				op_type: producer.map(|(_, node)| node.op_type.clone()).unwrap_or_default(),
+				mismatch
+			}));
+		}
+	}
+	Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec, vec::Vec};
+
+	use super::{Float16Converter, HalfType};
+	use crate::{
+		model::{AttributeValue, GraphBuilder, Initializer, Model, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	/// `x` -> MatMul(w) -> Softmax -> Add(b) -> `y`
+	fn model() -> Model {
+		GraphBuilder::new("classifier")
+			.with_opset("", 13)
+			.with_input("x", TensorElementType::Float32, [1_i64, 2])
+			.with_output("y", TensorElementType::Float32, [1_i64, 2])
+			.with_raw_initializer(Initializer::new("w", TensorElementType::Float32, [2, 2], TensorData::Float(vec![1.0, 0.5, -2.0, 1e6])))
+			.with_raw_initializer(Initializer::new("b", TensorElementType::Float32, [2], TensorData::Float(vec![0.25, -0.25])))
+			.with_node(Node::new("MatMul").with_inputs(["x", "w"]).with_outputs(["logits"]))
+			.with_node(
+				Node::new("Softmax")
+					.with_name("softmax")
+					.with_inputs(["logits"])
+					.with_outputs(["probabilities"])
+			)
+			.with_node(Node::new("Add").with_inputs(["probabilities", "b"]).with_outputs(["y"]))
+			.build()
+			.expect("valid model")
+	}
+
+	fn op_types(model: &Model) -> Vec<&str> {
+		model.graph.nodes.iter().map(|node| node.op_type.as_str()).collect()
+	}
+
+	#[test]
+	fn test_convert() -> crate::Result<()> {
+		let converted = Float16Converter::new(HalfType::Float16)
+			.with_blocked_op_types(["Softmax"])
+			.convert(&model())?;
+		assert_eq!(op_types(&converted), ["MatMul", "Cast", "Softmax", "Cast", "Add"]);
+		assert_eq!(converted.graph.nodes[1].outputs, ["logits_fp32"]);
+		assert_eq!(converted.graph.nodes[1].attribute("to").and_then(AttributeValue::as_int), Some(1));
+		assert_eq!(converted.graph.nodes[3].attribute("to").and_then(AttributeValue::as_int), Some(10));
+		assert_eq!(converted.graph.nodes[4].inputs, ["probabilities_fp16", "b"]);
+
+		let w = converted.graph.initializer("w").expect("w exists");
+		assert_eq!(w.element_type(), Some(TensorElementType::Float16));
+		// 1e6 is clamped to the largest finite f16
+		assert_eq!(w.data, TensorData::Raw(vec![0x00, 0x3c, 0x00, 0x38, 0x00, 0xc0, 0xff, 0x7b]));
+		assert_eq!(converted.graph.inputs[0].ty.as_ref().and_then(|ty| ty.element_type()), Some(TensorElementType::Float16));
+		assert_eq!(converted.graph.outputs[0].ty.as_ref().and_then(|ty| ty.element_type()), Some(TensorElementType::Float16));
+
+		let by_name = Float16Converter::new(HalfType::Float16)
+			.with_blocked_nodes(["softmax"])
+			.convert(&model())?;
+		assert_eq!(op_types(&by_name), op_types(&converted));
+		Ok(())
+	}
+
+	#[test]
+	fn test_keep_io_types() -> crate::Result<()> {
+		let converted = Float16Converter::new(HalfType::Bfloat16)
+			.with_blocked_op_types(["Softmax"])
+			.with_keep_io_types(true)
+			.convert(&model())?;
+		assert_eq!(op_types(&converted), ["Cast", "MatMul", "Cast", "Softmax", "Cast", "Add", "Cast"]);
+		assert_eq!(converted.graph.nodes[0].outputs, ["x_bf16"]);
+		assert_eq!(converted.graph.nodes[5].outputs, ["y_bf16"]);
+		assert_eq!(converted.graph.nodes[6].inputs, ["y_bf16"]);
+		assert_eq!(converted.graph.nodes[6].outputs, ["y"]);
+		assert_eq!(converted.graph.initializer("b").and_then(Initializer::element_type), Some(TensorElementType::Bfloat16));
+		assert_eq!(converted.graph.inputs[0].ty.as_ref().and_then(|ty| ty.element_type()), Some(TensorElementType::Float32));
+		assert_eq!(converted.graph.outputs[0].ty.as_ref().and_then(|ty| ty.element_type()), Some(TensorElementType::Float32));
+
+		let mut old = model();
+		old.set_opset_version("", 12);
+		let err = Float16Converter::new(HalfType::Bfloat16)
+			.convert(&old)
+			.expect_err("bfloat16 needs opset 13");
+		assert!(err.to_string().contains("requires version 13 or newer"), "{err}");
+		Ok(())
+	}
+
+	#[test]
+	fn test_constants() -> crate::Result<()> {
+		let model = GraphBuilder::new("constants")
+			.with_input("shape", TensorElementType::Int64, [1_i64])
+			.with_output("y", TensorElementType::Float32, [2_i64])
+			.with_node(
+				Node::new("Constant")
+					.with_attribute("value_floats", vec![1.0_f32, 2.0])
+					.with_outputs(["c"])
+			)
+			.with_node(Node::new("ConstantOfShape").with_inputs(["shape"]).with_outputs(["zeros"]))
+			.with_node(Node::new("Add").with_inputs(["c", "zeros"]).with_outputs(["sum"]))
+			.with_node(Node::new("Cast").with_attribute("to", 1_i64).with_inputs(["sum"]).with_outputs(["y"]))
+			.build()?;
+		let converted = Float16Converter::new(HalfType::Float16).convert(&model)?;
+		assert_eq!(op_types(&converted), ["Constant", "ConstantOfShape", "Add", "Cast"]);
+		let Some(AttributeValue::Tensor(c)) = converted.graph.nodes[0].attribute("value") else {
+			panic!("expected a tensor constant");
+		};
+		assert_eq!((c.element_type(), c.dims.as_slice()), (Some(TensorElementType::Float16), &[2][..]));
+		let Some(AttributeValue::Tensor(zero)) = converted.graph.nodes[1].attribute("value") else {
+			panic!("expected a tensor value");
+		};
+		assert_eq!(zero.element_type(), Some(TensorElementType::Float16));
+		assert_eq!(converted.graph.nodes[3].attribute("to").and_then(AttributeValue::as_int), Some(10));
+		Ok(())
+	}
+}
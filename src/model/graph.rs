@@ -0,0 +1,796 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::{fmt, slice};
+
+use super::{
+	proto::{Writer, for_each_field, invalid},
+	tensor::{Initializer, element_type}
+};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::TensorElementType
+};
+
+/// Upper bound on how deeply messages can be nested when decoding a model, matching the recursion limit of the protobuf
+/// library used by ONNX. Subgraphs & container types nest arbitrarily, so this keeps crafted models from overflowing
+/// the stack.
+pub(crate) const MAX_DEPTH: usize = 100;
+
+fn check_depth(depth: usize) -> Result<()> {
+	if depth > MAX_DEPTH {
+		return Err(Error::new_with_code(
+			ErrorCode::InvalidGraph,
+			format!("Failed to parse ONNX model: messages are nested more than {MAX_DEPTH} levels deep")
+		));
+	}
+	Ok(())
+}
+
+/// A computation graph (ONNX `GraphProto`), either the main graph of a [`Model`](super::Model) or a subgraph attribute
+/// of a control flow node like `If` or `Loop`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+	pub name: String,
+	/// The nodes of the graph, in topological order.
+	pub nodes: Vec<Node>,
+	/// Constant tensors, like model weights. Initializers may share a name with a graph input, in which case the
+	/// initializer acts as the input's default value.
+	pub initializers: Vec<Initializer>,
+	pub inputs: Vec<ValueInfo>,
+	pub outputs: Vec<ValueInfo>,
+	/// Type information for intermediate values, if the model has been shape-inferred.
+	pub value_info: Vec<ValueInfo>,
+	pub doc_string: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+impl Graph {
+	/// Returns an iterator over every node in this graph and, recursively, in the subgraphs of its control flow
+	/// nodes. Nodes are visited depth-first, so a node's subgraphs are visited before the next node.
+	pub fn all_nodes(&self) -> Nodes<'_> {
+		Nodes {
+			stack: alloc::vec![self.nodes.iter()]
+		}
+	}
+
+	/// Returns an iterator over every graph nested inside this graph's nodes, recursively. Does not include `self`.
+	pub fn subgraphs(&self) -> impl Iterator<Item = &Graph> {
+		self.all_nodes()
+			.flat_map(|node| node.attributes.iter().flat_map(|attr| attr.value.graphs()))
+	}
+
+	/// Returns the initializer with the given name, if present.
+	pub fn initializer(&self, name: &str) -> Option<&Initializer> {
+		self.initializers.iter().find(|i| i.name == name)
+	}
+
+	/// Decodes a graph nested `depth` messages deep.
+	pub(crate) fn decode(buf: &[u8], depth: usize) -> Result<Self> {
+		check_depth(depth)?;
+		let mut graph = Self::default();
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => graph.nodes.push(Node::decode(field.as_bytes()?, depth + 1)?),
+				2 => graph.name = field.as_string()?,
+				5 => graph.initializers.push(Initializer::decode(field.as_bytes()?)?),
+				10 => graph.doc_string = field.as_string()?,
+				11 => graph.inputs.push(ValueInfo::decode(field.as_bytes()?, depth + 1)?),
+				12 => graph.outputs.push(ValueInfo::decode(field.as_bytes()?, depth + 1)?),
+				13 => graph.value_info.push(ValueInfo::decode(field.as_bytes()?, depth + 1)?),
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		graph.unknown_fields = unknown;
+		Ok(graph)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		for node in &self.nodes {
+			w.message(1, |w| node.encode(w));
+		}
+		w.string(2, &self.name);
+		for initializer in &self.initializers {
+			w.message(5, |w| initializer.encode(w));
+		}
+		w.string(10, &self.doc_string);
+		for input in &self.inputs {
+			w.message(11, |w| input.encode(w));
+		}
+		for output in &self.outputs {
+			w.message(12, |w| output.encode(w));
+		}
+		for value_info in &self.value_info {
+			w.message(13, |w| value_info.encode(w));
+		}
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// Iterator over the nodes of a graph & its subgraphs; see [`Graph::all_nodes`].
+#[derive(Debug, Clone)]
+pub struct Nodes<'g> {
+	stack: Vec<slice::Iter<'g, Node>>
+}
+
+impl<'g> Iterator for Nodes<'g> {
+	type Item = &'g Node;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let iter = self.stack.last_mut()?;
+			match iter.next() {
+				Some(node) => {
+					let subgraphs: Vec<&'g Graph> = node.attributes.iter().flat_map(|attr| attr.value.graphs()).collect();
+					self.stack.extend(subgraphs.into_iter().rev().map(|graph| graph.nodes.iter()));
+					return Some(node);
+				}
+				None => {
+					self.stack.pop();
+				}
+			}
+		}
+	}
+}
+
+/// An operator invocation in a graph (ONNX `NodeProto`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Node {
+	pub name: String,
+	pub op_type: String,
+	/// The operator set domain of this node's operator. The default ONNX domain is represented by an empty string.
+	pub domain: String,
+	/// Names of the node's input values. An empty name denotes an omitted optional input.
+	pub inputs: Vec<String>,
+	/// Names of the node's output values. An empty name denotes an omitted optional output.
+	pub outputs: Vec<String>,
+	pub attributes: Vec<Attribute>,
+	pub doc_string: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+impl Node {
+	/// Creates a new node invoking the operator `op_type` from the default ONNX domain.
+	///
+	/// ```
+	/// # use ort::model::Node;
+	/// let node = Node::new("Transpose")
+	/// 	.with_inputs(["x"])
+	/// 	.with_outputs(["y"])
+	/// 	.with_attribute("perm", [0_i64, 2, 1]);
+	/// ```
+	pub fn new(op_type: impl Into<String>) -> Self {
+		Self {
+			op_type: op_type.into(),
+			..Default::default()
+		}
+	}
+
+	/// Sets the name of this node.
+	pub fn with_name(mut self, name: impl Into<String>) -> Self {
+		self.name = name.into();
+		self
+	}
+
+	/// Sets the operator set domain of this node's operator.
+	pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+		self.domain = domain.into();
+		self
+	}
+
+	/// Sets the names of this node's inputs. Use an empty string to skip an optional input.
+	pub fn with_inputs<S: Into<String>>(mut self, inputs: impl IntoIterator<Item = S>) -> Self {
+		self.inputs = inputs.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Sets the names of this node's outputs. Use an empty string to skip an optional output.
+	pub fn with_outputs<S: Into<String>>(mut self, outputs: impl IntoIterator<Item = S>) -> Self {
+		self.outputs = outputs.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Adds an attribute to this node, replacing any existing attribute with the same name.
+	pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<AttributeValue>) -> Self {
+		let attribute = Attribute::new(name, value.into());
+		match self.attributes.iter_mut().find(|a| a.name == attribute.name) {
+			Some(existing) => *existing = attribute,
+			None => self.attributes.push(attribute)
+		}
+		self
+	}
+
+	/// Returns the value of the attribute with the given name, if present.
+	pub fn attribute(&self, name: &str) -> Option<&AttributeValue> {
+		self.attributes.iter().find(|a| a.name == name).map(|a| &a.value)
+	}
+
+	pub(crate) fn decode(buf: &[u8], depth: usize) -> Result<Self> {
+		check_depth(depth)?;
+		let mut node = Self::default();
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => node.inputs.push(field.as_string()?),
+				2 => node.outputs.push(field.as_string()?),
+				3 => node.name = field.as_string()?,
+				4 => node.op_type = field.as_string()?,
+				5 => node.attributes.push(Attribute::decode(field.as_bytes()?, depth + 1)?),
+				6 => node.doc_string = field.as_string()?,
+				7 => node.domain = field.as_string()?,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		node.unknown_fields = unknown;
+		Ok(node)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		// inputs & outputs are written even if empty, since position matters
+		for input in &self.inputs {
+			w.bytes(1, input.as_bytes());
+		}
+		for output in &self.outputs {
+			w.bytes(2, output.as_bytes());
+		}
+		w.string(3, &self.name);
+		w.string(4, &self.op_type);
+		for attribute in &self.attributes {
+			w.message(5, |w| attribute.encode(w));
+		}
+		w.string(6, &self.doc_string);
+		w.string(7, &self.domain);
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// A named attribute of a [`Node`] (ONNX `AttributeProto`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+	pub name: String,
+	pub value: AttributeValue,
+	pub doc_string: String,
+	/// Inside a function body, the name of the function attribute this attribute refers to.
+	pub ref_attr_name: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+/// The value of an [`Attribute`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+	Float(f32),
+	Int(i64),
+	/// A string attribute. ONNX strings are arbitrary bytes, though they are almost always UTF-8.
+	String(Vec<u8>),
+	Tensor(Initializer),
+	Graph(Graph),
+	Floats(Vec<f32>),
+	Ints(Vec<i64>),
+	Strings(Vec<Vec<u8>>),
+	Tensors(Vec<Initializer>),
+	Graphs(Vec<Graph>),
+	/// An attribute type not otherwise supported, like sparse tensors or type protos, identified by its
+	/// `AttributeProto.AttributeType`. The value is kept in [`Attribute::unknown_fields`].
+	Other(i32)
+}
+
+impl AttributeValue {
+	/// Returns the value as a string, if it is a UTF-8 string attribute.
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Self::String(s) => core::str::from_utf8(s).ok(),
+			_ => None
+		}
+	}
+
+	/// Returns the value as an integer, if it is an integer attribute.
+	pub fn as_int(&self) -> Option<i64> {
+		match self {
+			Self::Int(i) => Some(*i),
+			_ => None
+		}
+	}
+
+	/// Returns the value as a float, if it is a float attribute.
+	pub fn as_float(&self) -> Option<f32> {
+		match self {
+			Self::Float(f) => Some(*f),
+			_ => None
+		}
+	}
+
+	/// Returns any subgraphs contained in this attribute.
+	pub fn graphs(&self) -> &[Graph] {
+		match self {
+			Self::Graph(graph) => slice::from_ref(graph),
+			Self::Graphs(graphs) => graphs,
+			_ => &[]
+		}
+	}
+
+	/// Returns mutable references to any subgraphs contained in this attribute.
+	pub fn graphs_mut(&mut self) -> &mut [Graph] {
+		match self {
+			Self::Graph(graph) => slice::from_mut(graph),
+			Self::Graphs(graphs) => graphs,
+			_ => &mut []
+		}
+	}
+
+	pub(crate) fn type_code(&self) -> i32 {
+		match self {
+			Self::Float(_) => 1,
+			Self::Int(_) => 2,
+			Self::String(_) => 3,
+			Self::Tensor(_) => 4,
+			Self::Graph(_) => 5,
+			Self::Floats(_) => 6,
+			Self::Ints(_) => 7,
+			Self::Strings(_) => 8,
+			Self::Tensors(_) => 9,
+			Self::Graphs(_) => 10,
+			Self::Other(code) => *code
+		}
+	}
+}
+
+macro_rules! impl_attribute_from {
+	($($ty:ty => |$v:ident| $value:expr),+) => {
+		$(impl From<$ty> for AttributeValue {
+			fn from($v: $ty) -> Self {
+				$value
+			}
+		})+
+	};
+}
+
+impl_attribute_from! {
+	f32 => |v| Self::Float(v),
+	i64 => |v| Self::Int(v),
+	&str => |v| Self::String(v.as_bytes().to_vec()),
+	String => |v| Self::String(v.into_bytes()),
+	Vec<f32> => |v| Self::Floats(v),
+	&[f32] => |v| Self::Floats(v.to_vec()),
+	Vec<i64> => |v| Self::Ints(v),
+	&[i64] => |v| Self::Ints(v.to_vec()),
+	Vec<String> => |v| Self::Strings(v.into_iter().map(String::into_bytes).collect()),
+	Initializer => |v| Self::Tensor(v),
+	Graph => |v| Self::Graph(v)
+}
+
+impl<const N: usize> From<[f32; N]> for AttributeValue {
+	fn from(value: [f32; N]) -> Self {
+		Self::Floats(value.to_vec())
+	}
+}
+
+impl<const N: usize> From<[i64; N]> for AttributeValue {
+	fn from(value: [i64; N]) -> Self {
+		Self::Ints(value.to_vec())
+	}
+}
+
+impl Attribute {
+	/// Creates a new attribute with the given name & value.
+	pub fn new(name: impl Into<String>, value: AttributeValue) -> Self {
+		Self {
+			name: name.into(),
+			value,
+			doc_string: String::new(),
+			ref_attr_name: String::new(),
+			unknown_fields: Vec::new()
+		}
+	}
+
+	pub(crate) fn decode(buf: &[u8], depth: usize) -> Result<Self> {
+		check_depth(depth)?;
+		let mut name = String::new();
+		let mut doc_string = String::new();
+		let mut ref_attr_name = String::new();
+		let mut ty = 0;
+		let (mut f, mut i, mut s, mut t, mut g) = (None, None, None, None, None);
+		let (mut floats, mut ints, mut strings, mut tensors, mut graphs) = (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => name = field.as_string()?,
+				2 => f = Some(field.as_f32()?),
+				3 => i = Some(field.as_i64()?),
+				4 => s = Some(field.as_bytes()?.to_vec()),
+				5 => t = Some(Initializer::decode(field.as_bytes()?)?),
+				6 => g = Some(Graph::decode(field.as_bytes()?, depth + 1)?),
+				7 => field.push_f32s(&mut floats)?,
+				8 => field.push_varints(&mut ints, |v| v as i64)?,
+				9 => strings.push(field.as_bytes()?.to_vec()),
+				10 => tensors.push(Initializer::decode(field.as_bytes()?)?),
+				11 => graphs.push(Graph::decode(field.as_bytes()?, depth + 1)?),
+				13 => doc_string = field.as_string()?,
+				20 => ty = field.as_i32()?,
+				21 => ref_attr_name = field.as_string()?,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		// models from before IR version 2 may not specify the attribute type, so infer it from the field that is set
+		if ty == 0 {
+			ty = if f.is_some() {
+				1
+			} else if i.is_some() {
+				2
+			} else if s.is_some() {
+				3
+			} else if t.is_some() {
+				4
+			} else if g.is_some() {
+				5
+			} else if !floats.is_empty() {
+				6
+			} else if !strings.is_empty() {
+				8
+			} else if !tensors.is_empty() {
+				9
+			} else if !graphs.is_empty() {
+				10
+			} else {
+				7
+			};
+		}
+		let missing = || invalid(alloc::format!("attribute `{name}` is missing its value"));
+		let value = match ty {
+			1 => AttributeValue::Float(f.unwrap_or_default()),
+			2 => AttributeValue::Int(i.unwrap_or_default()),
+			3 => AttributeValue::String(s.unwrap_or_default()),
+			4 => AttributeValue::Tensor(t.ok_or_else(missing)?),
+			5 => AttributeValue::Graph(g.ok_or_else(missing)?),
+			6 => AttributeValue::Floats(floats),
+			7 => AttributeValue::Ints(ints),
+			8 => AttributeValue::Strings(strings),
+			9 => AttributeValue::Tensors(tensors),
+			10 => AttributeValue::Graphs(graphs),
+			ty => AttributeValue::Other(ty)
+		};
+		Ok(Self {
+			name,
+			value,
+			doc_string,
+			ref_attr_name,
+			unknown_fields: unknown
+		})
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		w.string(1, &self.name);
+		match &self.value {
+			AttributeValue::Float(f) => w.f32(2, *f),
+			AttributeValue::Int(i) => w.i64(3, *i),
+			AttributeValue::String(s) => w.bytes(4, s),
+			AttributeValue::Tensor(t) => w.message(5, |w| t.encode(w)),
+			AttributeValue::Graph(g) => w.message(6, |w| g.encode(w)),
+			AttributeValue::Floats(floats) => {
+				for &f in floats {
+					w.f32(7, f);
+				}
+			}
+			AttributeValue::Ints(ints) => {
+				for &i in ints {
+					w.i64(8, i);
+				}
+			}
+			AttributeValue::Strings(strings) => {
+				for s in strings {
+					w.bytes(9, s);
+				}
+			}
+			AttributeValue::Tensors(tensors) => {
+				for t in tensors {
+					w.message(10, |w| t.encode(w));
+				}
+			}
+			AttributeValue::Graphs(graphs) => {
+				for g in graphs {
+					w.message(11, |w| g.encode(w));
+				}
+			}
+			AttributeValue::Other(_) => {}
+		}
+		w.string(13, &self.doc_string);
+		w.i32(20, self.value.type_code());
+		w.string(21, &self.ref_attr_name);
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// The name & type of a graph input, output, or intermediate value (ONNX `ValueInfoProto`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueInfo {
+	pub name: String,
+	/// The type of the value, or `None` if not specified (which is only valid for intermediate values).
+	pub ty: Option<TypeInfo>,
+	pub doc_string: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+impl ValueInfo {
+	/// Creates a new tensor value with the given element type & shape.
+	///
+	/// Dimensions can be fixed sizes, symbolic names, or negative for dynamic dimensions, e.g.
+	/// `[Dimension::from("batch"), 3.into(), (-1).into()]`.
+	pub fn tensor<D: Into<Dimension>>(name: impl Into<String>, element_type: TensorElementType, shape: impl IntoIterator<Item = D>) -> Self {
+		Self {
+			name: name.into(),
+			ty: Some(TypeInfo::Tensor {
+				elem_type: super::tensor::data_type(element_type),
+				shape: Some(shape.into_iter().map(Into::into).collect())
+			}),
+			..Default::default()
+		}
+	}
+
+	pub(crate) fn decode(buf: &[u8], depth: usize) -> Result<Self> {
+		check_depth(depth)?;
+		let mut value_info = Self::default();
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => value_info.name = field.as_string()?,
+				2 => value_info.ty = Some(TypeInfo::decode(field.as_bytes()?, depth + 1)?),
+				3 => value_info.doc_string = field.as_string()?,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		value_info.unknown_fields = unknown;
+		Ok(value_info)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		w.string(1, &self.name);
+		if let Some(ty) = &self.ty {
+			w.message(2, |w| ty.encode(w));
+		}
+		w.string(3, &self.doc_string);
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// The type of a value (ONNX `TypeProto`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeInfo {
+	/// A dense tensor. `shape` is `None` if the rank is unknown.
+	Tensor {
+		elem_type: i32,
+		shape: Option<Vec<Dimension>>
+	},
+	SparseTensor {
+		elem_type: i32,
+		shape: Option<Vec<Dimension>>
+	},
+	Sequence(Box<TypeInfo>),
+	Map {
+		key_type: i32,
+		value_type: Box<TypeInfo>
+	},
+	Optional(Box<TypeInfo>),
+	/// A type not otherwise supported (like opaque types), as the raw encoding of its `TypeProto`.
+	Other(Vec<u8>)
+}
+
+impl TypeInfo {
+	/// Returns the element type of a tensor or sparse tensor type.
+	pub fn element_type(&self) -> Option<TensorElementType> {
+		match self {
+			Self::Tensor { elem_type, .. } | Self::SparseTensor { elem_type, .. } => element_type(*elem_type),
+			_ => None
+		}
+	}
+
+	/// Returns the shape of a tensor or sparse tensor type, if known.
+	pub fn shape(&self) -> Option<&[Dimension]> {
+		match self {
+			Self::Tensor { shape, .. } | Self::SparseTensor { shape, .. } => shape.as_deref(),
+			_ => None
+		}
+	}
+
+	pub(crate) fn decode(buf: &[u8], depth: usize) -> Result<Self> {
+		check_depth(depth)?;
+		let mut ty = None;
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => {
+					let (elem_type, shape) = decode_tensor_type(field.as_bytes()?)?;
+					ty = Some(TypeInfo::Tensor { elem_type, shape });
+				}
+				4 => ty = Some(TypeInfo::Sequence(Box::new(decode_element_type(field.as_bytes()?, depth + 1)?))),
+				5 => {
+					let mut key_type = 0;
+					let mut value_type = None;
+					for_each_field(field.as_bytes()?, &mut Vec::new(), |number, field| {
+						match number {
+							1 => key_type = field.as_i32()?,
+							2 => value_type = Some(TypeInfo::decode(field.as_bytes()?, depth + 2)?),
+							_ => return Ok(false)
+						}
+						Ok(true)
+					})?;
+					let value_type = Box::new(value_type.ok_or_else(|| invalid("map type is missing its value type"))?);
+					ty = Some(TypeInfo::Map { key_type, value_type });
+				}
+				8 => {
+					let (elem_type, shape) = decode_tensor_type(field.as_bytes()?)?;
+					ty = Some(TypeInfo::SparseTensor { elem_type, shape });
+				}
+				9 => ty = Some(TypeInfo::Optional(Box::new(decode_element_type(field.as_bytes()?, depth + 1)?))),
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		Ok(ty.unwrap_or_else(|| TypeInfo::Other(buf.to_vec())))
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		match self {
+			Self::Tensor { elem_type, shape } => w.message(1, |w| encode_tensor_type(w, *elem_type, shape.as_deref())),
+			Self::SparseTensor { elem_type, shape } => w.message(8, |w| encode_tensor_type(w, *elem_type, shape.as_deref())),
+			Self::Sequence(elem) => w.message(4, |w| w.message(1, |w| elem.encode(w))),
+			Self::Map { key_type, value_type } => w.message(5, |w| {
+				w.i32(1, *key_type);
+				w.message(2, |w| value_type.encode(w));
+			}),
+			Self::Optional(elem) => w.message(9, |w| w.message(1, |w| elem.encode(w))),
+			Self::Other(raw) => w.raw(raw)
+		}
+	}
+}
+
+/// Formats types like [`ValueType`](crate::value::ValueType), e.g. `Tensor<f32>(batch, 3, 224, 224)`.
+impl fmt::Display for TypeInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn write_tensor(f: &mut fmt::Formatter<'_>, elem_type: i32, shape: Option<&[Dimension]>) -> fmt::Result {
+			match element_type(elem_type) {
+				Some(ty) => write!(f, "<{ty}>")?,
+				None => f.write_str("<undefined>")?
+			}
+			if let Some(shape) = shape {
+				f.write_str("(")?;
+				for (i, dimension) in shape.iter().enumerate() {
+					if i != 0 {
+						f.write_str(", ")?;
+					}
+					dimension.fmt(f)?;
+				}
+				f.write_str(")")?;
+			}
+			Ok(())
+		}
+
+		match self {
+			Self::Tensor { elem_type, shape } => {
+				f.write_str("Tensor")?;
+				write_tensor(f, *elem_type, shape.as_deref())
+			}
+			Self::SparseTensor { elem_type, shape } => {
+				f.write_str("SparseTensor")?;
+				write_tensor(f, *elem_type, shape.as_deref())
+			}
+			Self::Sequence(elem) => write!(f, "Sequence<{elem}>"),
+			Self::Map { key_type, value_type } => match element_type(*key_type) {
+				Some(key) => write!(f, "Map<{key}, {value_type}>"),
+				None => write!(f, "Map<undefined, {value_type}>")
+			},
+			Self::Optional(elem) => write!(f, "Option<{elem}>"),
+			Self::Other(_) => f.write_str("Opaque")
+		}
+	}
+}
+
+/// A single dimension of a tensor shape.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Dimension {
+	/// A fixed size.
+	Value(i64),
+	/// A named symbolic dimension, like `batch_size`.
+	Param(String),
+	/// A dynamic dimension with no name.
+	Unknown
+}
+
+/// Negative values are converted to [`Dimension::Unknown`].
+impl From<i64> for Dimension {
+	fn from(value: i64) -> Self {
+		if value < 0 { Self::Unknown } else { Self::Value(value) }
+	}
+}
+
+impl From<&str> for Dimension {
+	fn from(param: &str) -> Self {
+		Self::Param(param.into())
+	}
+}
+
+impl From<String> for Dimension {
+	fn from(param: String) -> Self {
+		Self::Param(param)
+	}
+}
+
+/// Dynamic dimensions with no name are formatted as `dyn`.
+impl fmt::Display for Dimension {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Value(value) => value.fmt(f),
+			Self::Param(param) => f.write_str(param),
+			Self::Unknown => f.write_str("dyn")
+		}
+	}
+}
+
+fn decode_element_type(buf: &[u8], depth: usize) -> Result<TypeInfo> {
+	let mut elem = None;
+	for_each_field(buf, &mut Vec::new(), |number, field| {
+		if number != 1 {
+			return Ok(false);
+		}
+		elem = Some(TypeInfo::decode(field.as_bytes()?, depth + 1)?);
+		Ok(true)
+	})?;
+	elem.ok_or_else(|| invalid("container type is missing its element type"))
+}
+
+fn decode_tensor_type(buf: &[u8]) -> Result<(i32, Option<Vec<Dimension>>)> {
+	let mut elem_type = 0;
+	let mut shape = None;
+	for_each_field(buf, &mut Vec::new(), |number, field| {
+		match number {
+			1 => elem_type = field.as_i32()?,
+			2 => {
+				let mut dims = Vec::new();
+				for_each_field(field.as_bytes()?, &mut Vec::new(), |number, field| {
+					if number != 1 {
+						return Ok(false);
+					}
+					dims.push(decode_dimension(field.as_bytes()?)?);
+					Ok(true)
+				})?;
+				shape = Some(dims);
+			}
+			_ => return Ok(false)
+		}
+		Ok(true)
+	})?;
+	Ok((elem_type, shape))
+}
+
+fn decode_dimension(buf: &[u8]) -> Result<Dimension> {
+	let mut dim = Dimension::Unknown;
+	for_each_field(buf, &mut Vec::new(), |number, field| {
+		match number {
+			1 => dim = Dimension::Value(field.as_i64()?),
+			2 => dim = Dimension::Param(field.as_string()?),
+			_ => return Ok(false)
+		}
+		Ok(true)
+	})?;
+	Ok(dim)
+}
+
+fn encode_tensor_type(w: &mut Writer, elem_type: i32, shape: Option<&[Dimension]>) {
+	w.i32(1, elem_type);
+	if let Some(shape) = shape {
+		w.message(2, |w| {
+			for dim in shape {
+				w.message(1, |w| match dim {
+					Dimension::Value(value) => w.i64(1, *value),
+					Dimension::Param(param) => w.string(2, param),
+					Dimension::Unknown => {}
+				});
+			}
+		});
+	}
+}
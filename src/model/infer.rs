@@ -0,0 +1,126 @@
+//! Element type inference for values which aren't annotated in a graph's `value_info`.
+//!
+//! This only tracks element types, not shapes, and only knows the handful of rules needed to tell floating point
+//! values from indices & masks. Anything it can't work out is left unknown.
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+
+use super::{AttributeValue, Graph, Node, TypeInfo};
+
+pub(crate) const FLOAT: i32 = 1;
+pub(crate) const UINT8: i32 = 2;
+pub(crate) const INT32: i32 = 6;
+pub(crate) const INT64: i32 = 7;
+pub(crate) const BOOL: i32 = 9;
+pub(crate) const DOUBLE: i32 = 11;
+
+/// Returns the element type (ONNX `TensorProto.DataType`) of every tensor in `graph` whose type is declared or can be
+/// inferred from the nodes producing it.
+pub(crate) fn element_types(graph: &Graph) -> BTreeMap<String, i32> {
+	let mut types = BTreeMap::new();
+	for initializer in &graph.initializers {
+		types.insert(initializer.name.clone(), initializer.data_type);
+	}
+	for value_info in graph.inputs.iter().chain(&graph.outputs).chain(&graph.value_info) {
+		if let Some(TypeInfo::Tensor { elem_type, .. }) = &value_info.ty {
+			types.insert(value_info.name.clone(), *elem_type);
+		}
+	}
+	for node in &graph.nodes {
+		for (output, ty) in node.outputs.iter().zip(infer_node(node, &types)) {
+			if let (false, Some(ty)) = (output.is_empty(), ty) {
+				types.entry(output.clone()).or_insert(ty);
+			}
+		}
+	}
+	types
+}
+
+fn infer_node(node: &Node, types: &BTreeMap<String, i32>) -> Vec<Option<i32>> {
+	let input = |i: usize| node.inputs.get(i).and_then(|input| types.get(input)).copied();
+	let int = |name: &str| node.attribute(name).and_then(AttributeValue::as_int).map(|v| v as i32);
+	if !node.domain.is_empty() && node.domain != "ai.onnx" {
+		return match (node.domain.as_str(), node.op_type.as_str()) {
+			("com.microsoft", "DynamicQuantizeMatMul" | "MatMulIntegerToFloat" | "FusedMatMul" | "FusedConv" | "FusedGemm") => vec![input(0).or(Some(FLOAT))],
+			_ => Vec::new()
+		};
+	}
+	match node.op_type.as_str() {
+		"Cast" => vec![int("to")],
+		"CastLike" => vec![input(1)],
+		"Shape" | "Size" | "NonZero" | "ArgMax" | "ArgMin" | "NonMaxSuppression" => vec![Some(INT64)],
+		"Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual" | "Not" | "And" | "Or" | "Xor" | "IsNaN" | "IsInf" => vec![Some(BOOL)],
+		"Constant" => vec![match node.attributes.first().map(|attribute| &attribute.value) {
+			Some(AttributeValue::Tensor(tensor)) => Some(tensor.data_type),
+			Some(AttributeValue::Float(_) | AttributeValue::Floats(_)) => Some(FLOAT),
+			Some(AttributeValue::Int(_) | AttributeValue::Ints(_)) => Some(INT64),
+			_ => None
+		}],
+		"ConstantOfShape" => vec![match node.attribute("value") {
+			Some(AttributeValue::Tensor(tensor)) => Some(tensor.data_type),
+			_ => Some(FLOAT)
+		}],
+		"RandomNormal" | "RandomUniform" => vec![int("dtype").or(Some(FLOAT))],
+		"RandomNormalLike" | "RandomUniformLike" | "EyeLike" => vec![int("dtype").or(input(0))],
+		"Multinomial" => vec![int("dtype").or(Some(INT32))],
+		"QuantizeLinear" => vec![input(2).or(Some(UINT8))],
+		"DequantizeLinear" => vec![input(1)],
+		"DynamicQuantizeLinear" => vec![Some(UINT8), Some(FLOAT), Some(UINT8)],
+		"MatMulInteger" | "ConvInteger" => vec![Some(INT32)],
+		"Where" => vec![input(1)],
+		"TopK" => vec![input(0), Some(INT64)],
+		"MaxPool" => vec![input(0), Some(INT64)],
+		"Dropout" => vec![input(0), Some(BOOL)],
+		"Unique" => vec![input(0), Some(INT64), Some(INT64), Some(INT64)],
+		"If" => match node.attribute("then_branch") {
+			Some(AttributeValue::Graph(branch)) => branch
+				.outputs
+				.iter()
+				.map(|output| match &output.ty {
+					Some(TypeInfo::Tensor { elem_type, .. }) => Some(*elem_type),
+					_ => None
+				})
+				.collect(),
+			_ => Vec::new()
+		},
+		"Loop" | "Scan" | "SequenceAt" | "SplitToSequence" | "SequenceConstruct" | "SequenceEmpty" | "ConcatFromSequence" => Vec::new(),
+		"Split" => vec![input(0); node.outputs.len()],
+		// most operators produce values of the same type as their first input
+		_ => vec![input(0)]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BOOL, FLOAT, INT64, element_types};
+	use crate::{
+		model::{GraphBuilder, Node},
+		tensor::TensorElementType
+	};
+
+	#[test]
+	fn test_element_types() {
+		let graph = GraphBuilder::new("types")
+			.with_input("x", TensorElementType::Float32, [4_i64])
+			.with_output("y", TensorElementType::Float32, [4_i64])
+			.with_node(Node::new("Relu").with_inputs(["x"]).with_outputs(["relu"]))
+			.with_node(Node::new("Shape").with_inputs(["relu"]).with_outputs(["shape"]))
+			.with_node(
+				Node::new("Cast")
+					.with_attribute("to", 1_i64)
+					.with_inputs(["shape"])
+					.with_outputs(["shape_float"])
+			)
+			.with_node(Node::new("Greater").with_inputs(["relu", "shape_float"]).with_outputs(["mask"]))
+			.with_node(Node::new("Where").with_inputs(["mask", "relu", "x"]).with_outputs(["y"]))
+			.with_node(Node::new("Custom").with_domain("my.domain").with_inputs(["y"]).with_outputs(["z"]))
+			.into_graph();
+		let types = element_types(&graph);
+		assert_eq!(types["relu"], FLOAT);
+		assert_eq!(types["shape"], INT64);
+		assert_eq!(types["shape_float"], FLOAT);
+		assert_eq!(types["mask"], BOOL);
+		assert_eq!(types["y"], FLOAT);
+		assert!(!types.contains_key("z"));
+	}
+}
@@ -0,0 +1,475 @@
+//! Inspection of ONNX models without creating a [`Session`](crate::session::Session).
+//!
+//! [`Model`] parses the ONNX `ModelProto` format in pure Rust, exposing the graph's nodes, initializers, and type
+//! information, along with model-level metadata like the IR version and operator set imports. This is useful for
+//! tooling that needs to know about a model before (or instead of) loading it into ONNX Runtime, like listing the
+//! operators a model uses, finding nodes that need a custom [`OperatorDomain`](crate::operator::OperatorDomain), or
+//! estimating the number of parameters.
+//!
+//! ```
+//! # use ort::model::Model;
+//! # fn main() -> ort::Result<()> {
+//! let model = Model::from_file("tests/data/custom_op_test.onnx")?;
+//! println!("IR version {}, opset {:?}", model.ir_version, model.opset_version(""));
+//! for ((domain, op_type), count) in model.op_types() {
+//! 	println!("{domain}::{op_type} x{count}");
+//! }
+//! for domain in model.custom_domains() {
+//! 	println!("requires operator domain `{domain}`");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Models can also be modified and re-encoded with [`Model::to_bytes`]. Fields `ort` doesn't parse are preserved
+//! as-is. To create a model from scratch, use [`GraphBuilder`].
+//!
+//! For code review or to locate profiling hot spots, a model's graph can be rendered as Graphviz DOT or a Mermaid
+//! flowchart with [`Model::to_dot`] & [`Model::to_mermaid`].
+//!
+//! Models can be converted to another version of the default operator set with [`Model::convert_opset`]. They can
+//! also be quantized to INT8 with the tools in the [`quantize`] module, or converted to half precision with the
+//! [`float16`] module.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	string::String,
+	vec::Vec
+};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+mod builder;
+mod check;
+mod checker;
+mod edit;
+#[cfg(feature = "half")]
+#[cfg_attr(docsrs, doc(cfg(feature = "half")))]
+pub mod float16;
+mod graph;
+mod infer;
+mod opset;
+mod proto;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod quantize;
+mod render;
+mod schema;
+mod tensor;
+
+pub use self::{
+	builder::GraphBuilder,
+	checker::{CheckReport, Diagnostic, DiagnosticKind, ModelChecker, Severity},
+	graph::{Attribute, AttributeValue, Dimension, Graph, Node, Nodes, TypeInfo, ValueInfo},
+	render::RenderOptions,
+	tensor::{Initializer, TensorData}
+};
+use self::{
+	proto::{Writer, for_each_field},
+	tensor::{decode_string_pair, encode_string_pair}
+};
+use crate::error::Result;
+#[cfg(feature = "std")]
+use crate::error::{Error, ErrorCode};
+
+/// Operator set domains implemented by ONNX Runtime itself. Nodes in any other domain need a custom operator.
+const BUILTIN_DOMAINS: &[&str] = &[
+	"",
+	"ai.onnx",
+	"ai.onnx.ml",
+	"ai.onnx.training",
+	"ai.onnx.preview.training",
+	"com.microsoft",
+	"com.microsoft.nchwc",
+	"com.microsoft.dml",
+	"com.microsoft.mlfeaturizers",
+	"com.ms.internal.nhwc"
+];
+
+/// An operator set import (ONNX `OperatorSetIdProto`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct OperatorSetId {
+	/// The operator set domain. The default ONNX domain is represented by an empty string (or `ai.onnx`).
+	pub domain: String,
+	pub version: i64
+}
+
+impl OperatorSetId {
+	pub fn new(domain: impl Into<String>, version: i64) -> Self {
+		Self { domain: domain.into(), version }
+	}
+
+	pub(crate) fn decode(buf: &[u8]) -> Result<Self> {
+		let mut opset = Self::default();
+		for_each_field(buf, &mut Vec::new(), |number, field| {
+			match number {
+				1 => opset.domain = field.as_string()?,
+				2 => opset.version = field.as_i64()?,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		Ok(opset)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		w.string(1, &self.domain);
+		w.i64(2, self.version);
+	}
+}
+
+/// A model-local function (ONNX `FunctionProto`), which nodes can invoke by its domain & name like any other operator.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Function {
+	pub name: String,
+	pub domain: String,
+	pub inputs: Vec<String>,
+	pub outputs: Vec<String>,
+	/// Names of the attributes this function accepts.
+	pub attributes: Vec<String>,
+	pub nodes: Vec<Node>,
+	pub opset_imports: Vec<OperatorSetId>,
+	pub doc_string: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+impl Function {
+	pub(crate) fn decode(buf: &[u8]) -> Result<Self> {
+		let mut function = Self::default();
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => function.name = field.as_string()?,
+				4 => function.inputs.push(field.as_string()?),
+				5 => function.outputs.push(field.as_string()?),
+				6 => function.attributes.push(field.as_string()?),
+				7 => function.nodes.push(Node::decode(field.as_bytes()?, 2)?),
+				8 => function.doc_string = field.as_string()?,
+				9 => function.opset_imports.push(OperatorSetId::decode(field.as_bytes()?)?),
+				10 => function.domain = field.as_string()?,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		function.unknown_fields = unknown;
+		Ok(function)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		w.string(1, &self.name);
+		for input in &self.inputs {
+			w.bytes(4, input.as_bytes());
+		}
+		for output in &self.outputs {
+			w.bytes(5, output.as_bytes());
+		}
+		for attribute in &self.attributes {
+			w.bytes(6, attribute.as_bytes());
+		}
+		for node in &self.nodes {
+			w.message(7, |w| node.encode(w));
+		}
+		w.string(8, &self.doc_string);
+		for opset in &self.opset_imports {
+			w.message(9, |w| opset.encode(w));
+		}
+		w.string(10, &self.domain);
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// An ONNX model (`ModelProto`).
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Model {
+	/// The version of the ONNX IR this model was saved with.
+	pub ir_version: i64,
+	pub opset_imports: Vec<OperatorSetId>,
+	pub producer_name: String,
+	pub producer_version: String,
+	pub domain: String,
+	pub model_version: i64,
+	pub doc_string: String,
+	pub graph: Graph,
+	/// Custom metadata key-value pairs, as returned by
+	/// [`ModelMetadata::custom`](crate::metadata::ModelMetadata::custom).
+	pub metadata_props: Vec<(String, String)>,
+	pub functions: Vec<Function>,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+impl Model {
+	/// Parses a model from the bytes of an `.onnx` file.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+		let mut model = Self::default();
+		let mut unknown = Vec::new();
+		for_each_field(bytes, &mut unknown, |number, field| {
+			match number {
+				1 => model.ir_version = field.as_i64()?,
+				2 => model.producer_name = field.as_string()?,
+				3 => model.producer_version = field.as_string()?,
+				4 => model.domain = field.as_string()?,
+				5 => model.model_version = field.as_i64()?,
+				6 => model.doc_string = field.as_string()?,
+				7 => model.graph = Graph::decode(field.as_bytes()?, 1)?,
+				8 => model.opset_imports.push(OperatorSetId::decode(field.as_bytes()?)?),
+				14 => model.metadata_props.push(decode_string_pair(field.as_bytes()?)?),
+				25 => model.functions.push(Function::decode(field.as_bytes()?)?),
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		model.unknown_fields = unknown;
+		Ok(model)
+	}
+
+	/// Reads & parses an `.onnx` file.
+	///
+	/// Tensor data stored in external files is not loaded; such initializers will have [`TensorData::External`]
+	/// data.
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let bytes =
+			std::fs::read(path).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, alloc::format!("Failed to read model `{}`: {e}", path.display())))?;
+		Self::from_bytes(&bytes)
+	}
+
+	/// Encodes the model to the `.onnx` format, suitable for
+	/// [`SessionBuilder::commit_from_memory`](crate::session::builder::SessionBuilder::commit_from_memory).
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut w = Writer::new();
+		w.i64(1, self.ir_version);
+		w.string(2, &self.producer_name);
+		w.string(3, &self.producer_version);
+		w.string(4, &self.domain);
+		if self.model_version != 0 {
+			w.i64(5, self.model_version);
+		}
+		w.string(6, &self.doc_string);
+		w.message(7, |w| self.graph.encode(w));
+		for opset in &self.opset_imports {
+			w.message(8, |w| opset.encode(w));
+		}
+		for (key, value) in &self.metadata_props {
+			w.message(14, |w| encode_string_pair(w, key, value));
+		}
+		for function in &self.functions {
+			w.message(25, |w| function.encode(w));
+		}
+		w.raw(&self.unknown_fields);
+		w.into_bytes()
+	}
+
+	/// Returns the imported version of the given operator set domain, if imported. The default ONNX domain can be
+	/// specified as either `""` or `"ai.onnx"`.
+	pub fn opset_version(&self, domain: &str) -> Option<i64> {
+		let domain = normalize_domain(domain);
+		self.opset_imports
+			.iter()
+			.find(|o| normalize_domain(&o.domain) == domain)
+			.map(|o| o.version)
+	}
+
+	/// Returns the number of times each operator is used in the model (including in subgraphs), keyed by
+	/// `(domain, op_type)`.
+	pub fn op_types(&self) -> BTreeMap<(&str, &str), usize> {
+		let mut counts = BTreeMap::new();
+		for node in self.graph.all_nodes() {
+			*counts.entry((node.domain.as_str(), node.op_type.as_str())).or_default() += 1;
+		}
+		counts
+	}
+
+	/// Returns all nodes (including in subgraphs) whose operators are not implemented by ONNX Runtime or a
+	/// model-local [`Function`], and thus require a custom [`OperatorDomain`](crate::operator::OperatorDomain).
+	pub fn custom_domain_nodes(&self) -> Vec<&Node> {
+		self.graph.all_nodes().filter(|node| self.is_custom_domain(&node.domain)).collect()
+	}
+
+	/// Returns the set of operator domains used by [custom nodes](Model::custom_domain_nodes).
+	pub fn custom_domains(&self) -> BTreeSet<&str> {
+		self.custom_domain_nodes().into_iter().map(|node| node.domain.as_str()).collect()
+	}
+
+	/// Returns the total number of elements in all initializers, including those in subgraphs.
+	pub fn parameter_count(&self) -> usize {
+		core::iter::once(&self.graph)
+			.chain(self.graph.subgraphs())
+			.flat_map(|graph| &graph.initializers)
+			.map(Initializer::num_elements)
+			.sum()
+	}
+
+	fn is_custom_domain(&self, domain: &str) -> bool {
+		!BUILTIN_DOMAINS.contains(&domain) && !self.functions.iter().any(|f| f.domain == domain)
+	}
+}
+
+fn normalize_domain(domain: &str) -> &str {
+	if domain == "ai.onnx" { "" } else { domain }
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::String, vec};
+
+	use super::{
+		Attribute, AttributeValue, Dimension, Graph, Initializer, Model, Node, OperatorSetId, TensorData, TypeInfo, ValueInfo, graph::MAX_DEPTH, proto::Writer
+	};
+	use crate::{error::ErrorCode, tensor::TensorElementType};
+
+	fn model() -> Model {
+		let branch = Graph {
+			name: "then".into(),
+			nodes: vec![Node {
+				op_type: "Relu".into(),
+				inputs: vec!["x".into()],
+				outputs: vec!["y".into()],
+				..Default::default()
+			}],
+			initializers: vec![Initializer::new("b", TensorElementType::Float32, [3], TensorData::Float(vec![1.0, 2.0, 3.0]))],
+			..Default::default()
+		};
+		Model {
+			ir_version: 9,
+			opset_imports: vec![OperatorSetId::new("", 17), OperatorSetId::new("my.domain", 1)],
+			producer_name: "ort".into(),
+			graph: Graph {
+				name: "main".into(),
+				nodes: vec![
+					Node {
+						op_type: "If".into(),
+						inputs: vec!["cond".into()],
+						outputs: vec!["y".into()],
+						attributes: vec![
+							Attribute::new("then_branch", AttributeValue::Graph(branch.clone())),
+							Attribute::new("else_branch", AttributeValue::Graph(branch)),
+						],
+						..Default::default()
+					},
+					Node {
+						op_type: "Custom".into(),
+						domain: "my.domain".into(),
+						inputs: vec!["y".into(), String::new(), "w".into()],
+						outputs: vec!["z".into()],
+						attributes: vec![
+							Attribute::new("alpha", AttributeValue::Float(0.5)),
+							Attribute::new("axes", AttributeValue::Ints(vec![-1, 2])),
+							Attribute::new("mode", AttributeValue::String(b"fast".to_vec())),
+						],
+						..Default::default()
+					},
+				],
+				initializers: vec![
+					Initializer::new("w", TensorElementType::Int64, [2, 2], TensorData::Int64(vec![1, -2, 3, -4])),
+					Initializer::new("raw", TensorElementType::Float16, [4], TensorData::Raw(vec![0; 8])),
+				],
+				inputs: vec![ValueInfo::tensor("cond", TensorElementType::Bool, vec::Vec::<Dimension>::new())],
+				outputs: vec![ValueInfo::tensor("z", TensorElementType::Float32, [Dimension::from("batch"), Dimension::Unknown, Dimension::from(3)])],
+				..Default::default()
+			},
+			metadata_props: vec![("key".into(), "value".into())],
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_roundtrip() -> crate::Result<()> {
+		let model = model();
+		let bytes = model.to_bytes();
+		let decoded = Model::from_bytes(&bytes)?;
+		assert_eq!(decoded, model);
+		assert_eq!(decoded.to_bytes(), bytes);
+
+		let TypeInfo::Tensor { shape: Some(shape), .. } = decoded.graph.outputs[0].ty.as_ref().expect("output has a type") else {
+			panic!("expected tensor type");
+		};
+		assert_eq!(shape, &[Dimension::Param("batch".into()), Dimension::Unknown, Dimension::Value(3)]);
+		assert_eq!(decoded.graph.initializer("raw").and_then(Initializer::element_type), Some(TensorElementType::Float16));
+		Ok(())
+	}
+
+	#[test]
+	fn test_unknown_fields() -> crate::Result<()> {
+		let mut bytes = model().to_bytes();
+		// field 20 (`training_info`) as an opaque message
+		bytes.extend_from_slice(&[0xa2, 0x01, 0x02, 0x08, 0x01]);
+		let model = Model::from_bytes(&bytes)?;
+		assert_eq!(model.unknown_fields, [0xa2, 0x01, 0x02, 0x08, 0x01]);
+		assert_eq!(model.to_bytes(), bytes);
+
+		assert!(Model::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_nesting_limit() -> crate::Result<()> {
+		// a model whose graph nests `levels` `If` nodes, each taking the next as its `then_branch`
+		let nested_model = |levels: usize| {
+			let mut graph = vec::Vec::new();
+			for _ in 0..levels {
+				let mut w = Writer::new();
+				w.message(1, |w| {
+					w.message(5, |w| {
+						w.string(1, "then_branch");
+						w.bytes(6, &graph);
+					});
+					w.string(4, "If");
+				});
+				graph = w.into_bytes();
+			}
+			let mut w = Writer::new();
+			w.bytes(7, &graph);
+			w.into_bytes()
+		};
+
+		// graphs are 3 messages apart (graph -> node -> attribute -> graph), starting at depth 1
+		let model = Model::from_bytes(&nested_model((MAX_DEPTH - 1) / 3))?;
+		assert_eq!(model.graph.all_nodes().count(), (MAX_DEPTH - 1) / 3);
+		let err = Model::from_bytes(&nested_model((MAX_DEPTH - 1) / 3 + 1)).expect_err("model is nested too deeply");
+		assert_eq!(err.code(), ErrorCode::InvalidGraph);
+		assert!(Model::from_bytes(&nested_model(10_000)).is_err());
+
+		// container types nest too
+		let mut ty = vec::Vec::new();
+		for _ in 0..10_000 {
+			let mut w = Writer::new();
+			w.message(4, |w| w.bytes(1, &ty));
+			ty = w.into_bytes();
+		}
+		let mut w = Writer::new();
+		w.message(7, |w| {
+			w.message(11, |w| {
+				w.string(1, "x");
+				w.bytes(2, &ty);
+			});
+		});
+		let err = Model::from_bytes(&w.into_bytes()).expect_err("type is nested too deeply");
+		assert_eq!(err.code(), ErrorCode::InvalidGraph);
+		Ok(())
+	}
+
+	#[test]
+	fn test_inspection() {
+		let model = model();
+		assert_eq!(model.opset_version("ai.onnx"), Some(17));
+		assert_eq!(model.opset_version("my.domain"), Some(1));
+		assert_eq!(model.opset_version("com.microsoft"), None);
+
+		assert_eq!(model.graph.all_nodes().map(|n| n.op_type.as_str()).collect::<vec::Vec<_>>(), ["If", "Relu", "Relu", "Custom"]);
+		let op_types = model.op_types();
+		assert_eq!(op_types[&("", "Relu")], 2);
+		assert_eq!(op_types[&("my.domain", "Custom")], 1);
+		assert_eq!(model.custom_domains().into_iter().collect::<vec::Vec<_>>(), ["my.domain"]);
+		assert_eq!(model.parameter_count(), 4 + 4 + 3 + 3);
+
+		let custom = &model.custom_domain_nodes()[0];
+		assert_eq!(custom.attribute("mode").and_then(AttributeValue::as_str), Some("fast"));
+		assert_eq!(custom.attribute("alpha").and_then(AttributeValue::as_float), Some(0.5));
+	}
+}
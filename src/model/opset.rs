@@ -0,0 +1,904 @@
+//! Conversion of models between versions of the default ONNX operator set.
+
+use alloc::{
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::{String, ToString},
+	vec,
+	vec::Vec
+};
+use core::mem;
+
+use super::{
+	Attribute, AttributeValue, Graph, Initializer, Model, Node, TensorData, builder,
+	edit::{Names, consumed_values},
+	infer::{DOUBLE, FLOAT, element_types},
+	schema,
+	tensor::element_type
+};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::TensorElementType
+};
+
+/// The range of default operator set versions [`Model::convert_opset`] can convert between.
+const SUPPORTED_OPSETS: core::ops::RangeInclusive<i64> = 7..=22;
+
+impl Model {
+	/// Converts the model to version `version` of the default ONNX operator set, returning the converted model.
+	///
+	/// Nodes whose operator's inputs or attributes changed between the two versions are rewritten; for example, the
+	/// `axes` attribute of `Squeeze`, `Unsqueeze` & `ReduceSum` and the `split` attribute of `Split` become inputs in
+	/// opset 13, `Clip`'s `min`/`max` and `Pad`'s `pads`/`value` become inputs in opset 11, and `Upsample` is replaced
+	/// by `Resize` in opset 10. Downgrading turns such inputs back into attributes, which requires them to be
+	/// initializers or `Constant`s. Nodes in subgraphs are converted too; nodes in other domains and model-local
+	/// functions are left as-is.
+	///
+	/// Versions 7 through 22 are supported. Returns an error listing every node which can't be converted, e.g. because
+	/// its operator was introduced after `version`, or because it uses a feature the target version doesn't have (like
+	/// `Resize` with `half_pixel` coordinates in opset 10).
+	///
+	/// ```no_run
+	/// # use ort::{model::Model, session::Session};
+	/// # fn main() -> ort::Result<()> {
+	/// let model = Model::from_file("yolov3-10.onnx")?;
+	/// let session = Session::builder()?.commit_from_memory(&model.convert_opset(17)?.to_bytes())?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn convert_opset(&self, version: i64) -> Result<Model> {
+		let from = self
+			.opset_version("")
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Model does not import the default operator set"))?;
+		if !SUPPORTED_OPSETS.contains(&from) || !SUPPORTED_OPSETS.contains(&version) {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!(
+					"Cannot convert model from opset {from} to {version}: only versions {} through {} are supported",
+					SUPPORTED_OPSETS.start(),
+					SUPPORTED_OPSETS.end()
+				)
+			));
+		}
+
+		let mut model = self.clone();
+		if from == version {
+			return Ok(model);
+		}
+		let mut converter = Converter {
+			from,
+			to: version,
+			names: Names::new(&self.graph),
+			errors: Vec::new()
+		};
+		converter.convert_graph(&mut model.graph, None);
+		if !converter.errors.is_empty() {
+			return Err(Error::new_with_code(
+				ErrorCode::NotImplemented,
+				format!("Cannot convert model from opset {from} to {version}:\n{}", converter.errors.join("\n"))
+			));
+		}
+
+		model.set_opset_version("", version);
+		if version > from {
+			model.ir_version = model.ir_version.max(builder::ir_version(version));
+		}
+		Ok(model)
+	}
+}
+
+/// Tensors with known values which are visible in a graph, i.e. initializers & the outputs of `Constant` nodes in the
+/// graph or any enclosing graph.
+struct Constants<'g> {
+	values: BTreeMap<&'g str, Cow<'g, Initializer>>,
+	outer: Option<&'g Constants<'g>>
+}
+
+impl Constants<'_> {
+	fn get(&self, name: &str) -> Option<&Initializer> {
+		match self.values.get(name) {
+			Some(value) => Some(value),
+			None => self.outer?.get(name)
+		}
+	}
+}
+
+/// State for converting the nodes of one graph.
+struct Rewrite<'g> {
+	constants: Constants<'g>,
+	types: BTreeMap<String, i32>,
+	ranks: BTreeMap<String, usize>,
+	/// Nodes to insert before & after the node being converted.
+	before: Vec<Node>,
+	after: Vec<Node>,
+	initializers: Vec<Initializer>,
+	/// Constants which were turned into attributes, and may no longer be used.
+	detached: BTreeSet<String>
+}
+
+struct Converter {
+	from: i64,
+	to: i64,
+	names: Names,
+	errors: Vec<String>
+}
+
+impl Converter {
+	/// Whether the conversion crosses `version` upwards, i.e. the model is below it and the target is at or above it.
+	fn upgrades(&self, version: i64) -> bool {
+		self.from < version && version <= self.to
+	}
+
+	fn downgrades(&self, version: i64) -> bool {
+		self.to < version && version <= self.from
+	}
+
+	fn convert_graph(&mut self, graph: &mut Graph, outer: Option<&Constants<'_>>) {
+		let types = element_types(graph);
+		let ranks = graph
+			.inputs
+			.iter()
+			.chain(&graph.outputs)
+			.chain(&graph.value_info)
+			.filter_map(|value_info| Some((value_info.name.clone(), value_info.ty.as_ref()?.shape()?.len())))
+			.chain(
+				graph
+					.initializers
+					.iter()
+					.map(|initializer| (initializer.name.clone(), initializer.dims.len()))
+			)
+			.collect();
+		let mut nodes = mem::take(&mut graph.nodes);
+
+		let graph_inputs: BTreeSet<&str> = graph.inputs.iter().map(|input| input.name.as_str()).collect();
+		let mut values: BTreeMap<&str, Cow<Initializer>> = graph
+			.initializers
+			.iter()
+			.filter(|initializer| !graph_inputs.contains(initializer.name.as_str()))
+			.map(|initializer| (initializer.name.as_str(), Cow::Borrowed(initializer)))
+			.collect();
+		let constant_nodes: Vec<Initializer> = nodes.iter().filter_map(constant_value).collect();
+		values.extend(constant_nodes.iter().map(|constant| (constant.name.as_str(), Cow::Borrowed(constant))));
+		let mut rewrite = Rewrite {
+			constants: Constants { values, outer },
+			types,
+			ranks,
+			before: Vec::new(),
+			after: Vec::new(),
+			initializers: Vec::new(),
+			detached: BTreeSet::new()
+		};
+
+		let mut converted = Vec::with_capacity(nodes.len());
+		for mut node in nodes.drain(..) {
+			for attribute in &mut node.attributes {
+				for subgraph in attribute.value.graphs_mut() {
+					self.convert_graph(subgraph, Some(&rewrite.constants));
+				}
+			}
+			if node.domain.is_empty() || node.domain == "ai.onnx" {
+				if let Err(reason) = self.convert_node(&mut node, &mut rewrite) {
+					self.errors.push(format!("- {}: {reason}", describe(&node)));
+				}
+			}
+			converted.append(&mut rewrite.before);
+			converted.push(node);
+			converted.append(&mut rewrite.after);
+		}
+
+		let Rewrite { initializers, detached, .. } = rewrite;
+		graph.nodes = converted;
+		graph.initializers.extend(initializers);
+		if !detached.is_empty() {
+			// remove constants which were only used by the attributes they were turned into
+			let used: BTreeSet<String> = graph
+				.nodes
+				.iter()
+				.flat_map(consumed_values)
+				.chain(graph.outputs.iter().map(|output| output.name.as_str()))
+				.map(String::from)
+				.collect();
+			let unused = |name: &String| detached.contains(name) && !used.contains(name);
+			graph.initializers.retain(|initializer| !unused(&initializer.name));
+			graph
+				.nodes
+				.retain(|node| !(node.op_type == "Constant" && node.outputs.len() == 1 && unused(&node.outputs[0])));
+		}
+	}
+
+	fn convert_node(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		match node.op_type.as_str() {
+			"Squeeze" | "Unsqueeze" => self.ints_attribute_to_input(node, rewrite, 13, "axes", 1),
+			"ReduceSum" => self.reduce(node, rewrite, 13),
+			"ReduceL1" | "ReduceL2" | "ReduceLogSum" | "ReduceLogSumExp" | "ReduceMax" | "ReduceMean" | "ReduceMin" | "ReduceProd" | "ReduceSumSquare" => {
+				self.reduce(node, rewrite, 18)
+			}
+			"Split" => self.split(node, rewrite),
+			"Slice" => self.slice(node, rewrite),
+			"TopK" => self.top_k(node, rewrite),
+			"Clip" => self.clip(node, rewrite),
+			"Pad" => self.pad(node, rewrite),
+			"Upsample" | "Resize" => self.resize(node, rewrite),
+			"Softmax" | "LogSoftmax" | "Hardmax" => self.softmax(node, rewrite),
+			"Dropout" => self.dropout(node, rewrite),
+			"BatchNormalization" => self.batch_normalization(node),
+			"Scatter" | "ScatterElements" => self.scatter(node),
+			"Gemm" => self.gemm(node, rewrite),
+			op_type => match schema::since_version(op_type) {
+				// the schema table only lists older operators from the version `ort` checks them at
+				Some(since) if since >= 10 && since > self.to => Err(format!("`{op_type}` was introduced in opset {since}")),
+				_ => Ok(())
+			}
+		}
+	}
+
+	/// Converts an `ints` attribute to/from a constant input, which it became in version `version`.
+	fn ints_attribute_to_input(&mut self, node: &mut Node, rewrite: &mut Rewrite, version: i64, attribute: &str, index: usize) -> Result<(), String> {
+		if self.upgrades(version) {
+			if let Some(value) = take_attribute(node, attribute) {
+				let AttributeValue::Ints(values) = value else {
+					return Err(format!("attribute `{attribute}` should be a list of ints"));
+				};
+				let name = self.ints_constant(rewrite, node, attribute, values);
+				set_input(node, index, name);
+			}
+		} else if self.downgrades(version) {
+			if let Some(values) = take_ints(node, rewrite, index)? {
+				set_attribute(node, attribute, values);
+			}
+		}
+		Ok(())
+	}
+
+	fn reduce(&mut self, node: &mut Node, rewrite: &mut Rewrite, version: i64) -> Result<(), String> {
+		let noop_with_empty_axes = if self.downgrades(version) { take_attribute(node, "noop_with_empty_axes") } else { None };
+		self.ints_attribute_to_input(node, rewrite, version, "axes", 1)?;
+		if noop_with_empty_axes.and_then(|value| value.as_int()) == Some(1)
+			&& !matches!(node.attribute("axes"), Some(AttributeValue::Ints(axes)) if !axes.is_empty())
+		{
+			return Err(format!("`noop_with_empty_axes` without axes can't be expressed before opset {version}"));
+		}
+		Ok(())
+	}
+
+	fn split(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.downgrades(18) {
+			take_attribute(node, "num_outputs");
+		}
+		self.ints_attribute_to_input(node, rewrite, 13, "split", 1)?;
+		if self.upgrades(18) && input(node, 1).is_none() && node.attribute("num_outputs").is_none() {
+			set_attribute(node, "num_outputs", node.outputs.len() as i64);
+		}
+		Ok(())
+	}
+
+	fn slice(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.upgrades(10) {
+			for (index, attribute) in ["starts", "ends", "axes"].into_iter().enumerate() {
+				if attribute != "axes" && node.attribute(attribute).is_none() {
+					return Err(format!("missing required attribute `{attribute}`"));
+				}
+				self.ints_attribute_to_input(node, rewrite, 10, attribute, index + 1)?;
+			}
+		} else if self.downgrades(10) {
+			if take_ints(node, rewrite, 4)?.is_some_and(|steps| steps.iter().any(|&step| step != 1)) {
+				return Err("steps other than 1 can't be expressed before opset 10".to_string());
+			}
+			for (index, attribute) in ["starts", "ends", "axes"].into_iter().enumerate() {
+				if attribute != "axes" && input(node, index + 1).is_none() {
+					return Err(format!("missing required input `{attribute}`"));
+				}
+				self.ints_attribute_to_input(node, rewrite, 10, attribute, index + 1)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn top_k(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.downgrades(11) {
+			if take_attribute(node, "largest").and_then(|value| value.as_int()) == Some(0) {
+				return Err("selecting the smallest elements can't be expressed before opset 11".to_string());
+			}
+			// older versions always sort, which is also valid for `sorted=0`
+			take_attribute(node, "sorted");
+		}
+		if self.upgrades(10) {
+			let k = take_attribute(node, "k")
+				.and_then(|value| value.as_int())
+				.ok_or_else(|| "missing required attribute `k`".to_string())?;
+			let name = self.ints_constant(rewrite, node, "k", vec![k]);
+			set_input(node, 1, name);
+		} else if self.downgrades(10) {
+			match take_ints(node, rewrite, 1)?.as_deref() {
+				Some(&[k]) => set_attribute(node, "k", k),
+				_ => return Err("`K` should be a tensor with one element".to_string())
+			}
+		}
+		Ok(())
+	}
+
+	fn clip(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		let ty = input(node, 0).and_then(|x| rewrite.types.get(x)).copied();
+		if self.upgrades(11) {
+			for (index, attribute) in [(1, "min"), (2, "max")] {
+				if let Some(value) = take_attribute(node, attribute) {
+					let value = value.as_float().ok_or_else(|| format!("attribute `{attribute}` should be a float"))?;
+					let name = self.float_constant(rewrite, node, attribute, ty.unwrap_or(FLOAT), value)?;
+					set_input(node, index, name);
+				}
+			}
+		} else if self.downgrades(11) {
+			if let Some(ty) = ty.filter(|ty| !is_float(*ty)) {
+				return Err(format!("clipping {} values can't be expressed before opset 12", type_name(ty)));
+			}
+			for (index, attribute) in [(2, "max"), (1, "min")] {
+				if let Some(value) = take_scalar_float(node, rewrite, index)? {
+					set_attribute(node, attribute, value);
+				}
+			}
+		} else if self.downgrades(12) {
+			if let Some(ty) = ty.filter(|ty| !is_float(*ty)) {
+				return Err(format!("clipping {} values can't be expressed before opset 12", type_name(ty)));
+			}
+		}
+		Ok(())
+	}
+
+	fn pad(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.downgrades(19) && node.attribute("mode").and_then(AttributeValue::as_str) == Some("wrap") {
+			return Err("`wrap` mode can't be expressed before opset 19".to_string());
+		}
+		if self.downgrades(18) && input(node, 3).is_some() {
+			return Err("the `axes` input can't be expressed before opset 18".to_string());
+		}
+		let ty = input(node, 0).and_then(|x| rewrite.types.get(x)).copied();
+		if self.upgrades(11) {
+			if node.attribute("pads").is_none() {
+				return Err("missing required attribute `pads`".to_string());
+			}
+			self.ints_attribute_to_input(node, rewrite, 11, "pads", 1)?;
+			if let Some(value) = take_attribute(node, "value") {
+				let value = value.as_float().ok_or_else(|| "attribute `value` should be a float".to_string())?;
+				let name = self.float_constant(rewrite, node, "constant_value", ty.unwrap_or(FLOAT), value)?;
+				set_input(node, 2, name);
+			}
+		} else if self.downgrades(11) {
+			if let Some(ty) = ty.filter(|ty| !is_float(*ty)) {
+				return Err(format!("padding {} values can't be expressed before opset 11", type_name(ty)));
+			}
+			if let Some(value) = take_scalar_float(node, rewrite, 2)? {
+				set_attribute(node, "value", value);
+			}
+			if input(node, 1).is_none() {
+				return Err("missing required input `pads`".to_string());
+			}
+			self.ints_attribute_to_input(node, rewrite, 11, "pads", 1)?;
+		}
+		Ok(())
+	}
+
+	fn resize(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if node.op_type == "Upsample" {
+			if self.upgrades(9) {
+				let scales = take_attribute(node, "scales");
+				let Some(AttributeValue::Floats(scales)) = scales else {
+					return Err("attribute `scales` should be a list of floats".to_string());
+				};
+				let name = self.add_constant(rewrite, node, "scales", TensorElementType::Float32, vec![scales.len() as i64], TensorData::Float(scales));
+				set_input(node, 1, name);
+			} else if self.downgrades(9) {
+				let scales = take_floats(node, rewrite, 1)?.ok_or_else(|| "missing required input `scales`".to_string())?;
+				set_attribute(node, "scales", scales);
+			}
+			if !self.upgrades(10) {
+				return Ok(());
+			}
+			// `Resize-10` is `Upsample-9` under a new name
+			node.op_type = "Resize".to_string();
+		} else if self.from < 10 {
+			return Ok(());
+		}
+
+		let string = |node: &Node, name: &str, default: &'static str| node.attribute(name).and_then(AttributeValue::as_str).unwrap_or(default).to_string();
+		if self.downgrades(19) && string(node, "coordinate_transformation_mode", "half_pixel") == "half_pixel_symmetric" {
+			return Err("`half_pixel_symmetric` coordinates can't be expressed before opset 19".to_string());
+		}
+		if self.downgrades(18) {
+			if take_attribute(node, "antialias")
+				.and_then(|value| value.as_int())
+				.is_some_and(|antialias| antialias != 0)
+			{
+				return Err("antialiasing can't be expressed before opset 18".to_string());
+			}
+			if take_attribute(node, "axes").is_some() {
+				return Err("the `axes` attribute can't be expressed before opset 18".to_string());
+			}
+			if take_attribute(node, "keep_aspect_ratio_policy").is_some_and(|policy| policy.as_str() != Some("stretch")) {
+				return Err("keeping the aspect ratio can't be expressed before opset 18".to_string());
+			}
+		}
+		if self.downgrades(13) {
+			// `roi` & `scales` are required before opset 13, though they may be empty
+			for (index, name) in [(1, "roi"), (2, "scales")] {
+				if input(node, index).is_none() {
+					let name = self.add_constant(rewrite, node, name, TensorElementType::Float32, vec![0], TensorData::Float(Vec::new()));
+					set_input(node, index, name);
+				}
+			}
+		}
+		if self.downgrades(11) {
+			let coordinates = string(node, "coordinate_transformation_mode", "half_pixel");
+			if coordinates != "asymmetric" {
+				return Err(format!("`{coordinates}` coordinates can't be expressed before opset 11; only `asymmetric` can"));
+			}
+			let mode = string(node, "mode", "nearest");
+			if mode == "cubic" {
+				return Err("`cubic` mode can't be expressed before opset 11".to_string());
+			}
+			let nearest_mode = string(node, "nearest_mode", "round_prefer_floor");
+			if mode == "nearest" && nearest_mode != "floor" {
+				return Err(format!("`{nearest_mode}` rounding can't be expressed before opset 11; only `floor` can"));
+			}
+			if node
+				.attribute("exclude_outside")
+				.and_then(AttributeValue::as_int)
+				.is_some_and(|exclude| exclude != 0)
+			{
+				return Err("`exclude_outside` can't be expressed before opset 11".to_string());
+			}
+			if input(node, 3).is_some() {
+				return Err("the `sizes` input can't be expressed before opset 11".to_string());
+			}
+			for attribute in ["coordinate_transformation_mode", "nearest_mode", "exclude_outside", "cubic_coeff_a", "extrapolation_value"] {
+				take_attribute(node, attribute);
+			}
+			let scales = input(node, 2).ok_or_else(|| "missing required input `scales`".to_string())?.to_string();
+			let roi = mem::take(&mut node.inputs[1]);
+			rewrite.detached.insert(roi);
+			node.inputs = vec![node.inputs[0].clone(), scales];
+		} else if self.upgrades(11) {
+			if node.inputs.len() != 2 {
+				return Err("expected 2 inputs".to_string());
+			}
+			let roi = self.add_constant(rewrite, node, "roi", TensorElementType::Float32, vec![0], TensorData::Float(Vec::new()));
+			node.inputs.insert(1, roi);
+			// `Resize-10` maps output pixels to input pixels like `asymmetric`, and for upsampling, rounds down
+			set_attribute(node, "coordinate_transformation_mode", "asymmetric");
+			if string(node, "mode", "nearest") == "nearest" {
+				set_attribute(node, "nearest_mode", "floor");
+			}
+		}
+		if self.downgrades(10) {
+			node.op_type = "Upsample".to_string();
+			if self.downgrades(9) {
+				let scales = take_floats(node, rewrite, 1)?.ok_or_else(|| "missing required input `scales`".to_string())?;
+				set_attribute(node, "scales", scales);
+			}
+		}
+		Ok(())
+	}
+
+	fn softmax(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		let rank = input(node, 0).and_then(|x| rewrite.ranks.get(x)).map(|&rank| rank as i64);
+		if self.upgrades(13) {
+			// before opset 13, the input is coerced to 2D at `axis`, and the operator is applied over the second
+			// dimension; from 13 on, it's applied over `axis` alone
+			let axis = node.attribute("axis").and_then(AttributeValue::as_int).unwrap_or(1);
+			let last = rank.is_some_and(|rank| axis == rank - 1 || axis == -1);
+			set_attribute(node, "axis", if last { axis } else { -1 });
+			if !last {
+				let x = input(node, 0).ok_or_else(|| "missing required input `input`".to_string())?.to_string();
+				let base = base_name(node).to_string();
+				let shape = self.names.fresh(format!("{base}_shape"));
+				let flattened = self.names.fresh(format!("{base}_flattened"));
+				rewrite.before.push(
+					Node::new("Shape")
+						.with_name(self.names.fresh(format!("{base}_Shape")))
+						.with_inputs([x.as_str()])
+						.with_outputs([shape.as_str()])
+				);
+				rewrite.before.push(
+					Node::new("Flatten")
+						.with_name(self.names.fresh(format!("{base}_Flatten")))
+						.with_attribute("axis", axis)
+						.with_inputs([x])
+						.with_outputs([flattened.as_str()])
+				);
+				let output = mem::replace(&mut node.outputs[0], self.names.fresh(format!("{base}_2d")));
+				rewrite.after.push(
+					Node::new("Reshape")
+						.with_name(self.names.fresh(format!("{base}_Reshape")))
+						.with_inputs([node.outputs[0].as_str(), shape.as_str()])
+						.with_outputs([output])
+				);
+				node.inputs[0] = flattened;
+			}
+		} else if self.downgrades(13) {
+			let axis = node.attribute("axis").and_then(AttributeValue::as_int).unwrap_or(-1);
+			if !(axis == -1 || rank.is_some_and(|rank| axis == rank - 1)) {
+				return Err(format!("applying `{}` over an axis other than the last can't be expressed before opset 13", node.op_type));
+			}
+			// negative axes are only accepted from opset 11
+			let axis = match (axis, rank) {
+				(-1, Some(rank)) if self.to < 11 => rank - 1,
+				(-1, None) if self.to < 11 => return Err(format!("the rank of the input must be known to convert to opset {}", self.to)),
+				_ => axis
+			};
+			set_attribute(node, "axis", axis);
+		}
+		Ok(())
+	}
+
+	fn dropout(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.upgrades(12) {
+			if let Some(ratio) = take_attribute(node, "ratio") {
+				let ratio = ratio.as_float().ok_or_else(|| "attribute `ratio` should be a float".to_string())?;
+				let name = self.float_constant(rewrite, node, "ratio", FLOAT, ratio)?;
+				set_input(node, 1, name);
+			}
+		} else if self.downgrades(12) {
+			if take_ints(node, rewrite, 2)?.is_some_and(|training_mode| training_mode.iter().any(|&v| v != 0)) {
+				return Err("training mode can't be expressed before opset 12".to_string());
+			}
+			if let Some(ratio) = take_scalar_float(node, rewrite, 1)? {
+				set_attribute(node, "ratio", ratio);
+			}
+		}
+		Ok(())
+	}
+
+	fn batch_normalization(&mut self, node: &mut Node) -> Result<(), String> {
+		if self.upgrades(9)
+			&& take_attribute(node, "spatial")
+				.and_then(|value| value.as_int())
+				.is_some_and(|spatial| spatial != 1)
+		{
+			return Err("non-spatial normalization can't be expressed from opset 9".to_string());
+		}
+		if self.downgrades(14)
+			&& take_attribute(node, "training_mode")
+				.and_then(|value| value.as_int())
+				.is_some_and(|training| training != 0)
+		{
+			return Err("training mode can't be expressed before opset 14".to_string());
+		}
+		Ok(())
+	}
+
+	fn scatter(&mut self, node: &mut Node) -> Result<(), String> {
+		if node.op_type == "Scatter" {
+			if self.upgrades(11) {
+				node.op_type = "ScatterElements".to_string();
+			}
+			return Ok(());
+		}
+		if self.downgrades(16) && take_attribute(node, "reduction").is_some_and(|reduction| reduction.as_str() != Some("none")) {
+			return Err("reductions can't be expressed before opset 16".to_string());
+		}
+		if self.downgrades(11) {
+			if self.to < 9 {
+				return Err("`ScatterElements` was introduced in opset 9 (as `Scatter`)".to_string());
+			}
+			node.op_type = "Scatter".to_string();
+		}
+		Ok(())
+	}
+
+	fn gemm(&mut self, node: &mut Node, rewrite: &mut Rewrite) -> Result<(), String> {
+		if self.downgrades(11) && input(node, 2).is_none() {
+			let ty = input(node, 0).and_then(|a| rewrite.types.get(a)).copied();
+			let name = self.float_constant(rewrite, node, "C", ty.unwrap_or(FLOAT), 0.0)?;
+			set_input(node, 2, name);
+		}
+		Ok(())
+	}
+
+	fn add_constant(&mut self, rewrite: &mut Rewrite, node: &Node, suffix: &str, ty: TensorElementType, dims: Vec<i64>, data: TensorData) -> String {
+		let name = self.names.fresh(format!("{}_{suffix}", base_name(node)));
+		rewrite.initializers.push(Initializer::new(name.clone(), ty, dims, data));
+		name
+	}
+
+	fn ints_constant(&mut self, rewrite: &mut Rewrite, node: &Node, suffix: &str, values: Vec<i64>) -> String {
+		self.add_constant(rewrite, node, suffix, TensorElementType::Int64, vec![values.len() as i64], TensorData::Int64(values))
+	}
+
+	/// Adds a scalar of element type `ty` (which must be `float` or `double`).
+	fn float_constant(&mut self, rewrite: &mut Rewrite, node: &Node, suffix: &str, ty: i32, value: f32) -> Result<String, String> {
+		let (ty, data) = match ty {
+			FLOAT => (TensorElementType::Float32, TensorData::Float(vec![value])),
+			DOUBLE => (TensorElementType::Float64, TensorData::Double(vec![f64::from(value)])),
+			_ => return Err(format!("`{suffix}` can't be converted to an input for {} values", type_name(ty)))
+		};
+		Ok(self.add_constant(rewrite, node, suffix, ty, Vec::new(), data))
+	}
+}
+
+/// Returns the value of a `Constant` node as a tensor named after its output.
+fn constant_value(node: &Node) -> Option<Initializer> {
+	if node.op_type != "Constant" || !(node.domain.is_empty() || node.domain == "ai.onnx") || node.outputs.len() != 1 {
+		return None;
+	}
+	let name = node.outputs[0].clone();
+	Some(match &node.attributes.first()?.value {
+		AttributeValue::Tensor(tensor) => Initializer { name, ..tensor.clone() },
+		AttributeValue::Int(value) => Initializer::new(name, TensorElementType::Int64, [], TensorData::Int64(vec![*value])),
+		AttributeValue::Ints(values) => Initializer::new(name, TensorElementType::Int64, [values.len() as i64], TensorData::Int64(values.clone())),
+		AttributeValue::Float(value) => Initializer::new(name, TensorElementType::Float32, [], TensorData::Float(vec![*value])),
+		AttributeValue::Floats(values) => Initializer::new(name, TensorElementType::Float32, [values.len() as i64], TensorData::Float(values.clone())),
+		_ => return None
+	})
+}
+
+fn describe(node: &Node) -> String {
+	if node.name.is_empty() {
+		format!("`{}` node producing `{}`", node.op_type, node.outputs.first().map_or("", String::as_str))
+	} else {
+		format!("`{}` node `{}`", node.op_type, node.name)
+	}
+}
+
+/// The name new values & nodes derived from `node` are named after.
+fn base_name(node: &Node) -> &str {
+	if node.name.is_empty() { node.outputs.first().map_or("", String::as_str) } else { &node.name }
+}
+
+fn type_name(ty: i32) -> String {
+	element_type(ty).map_or_else(|| format!("type {ty}"), |ty| ty.to_string())
+}
+
+fn is_float(ty: i32) -> bool {
+	matches!(element_type(ty), Some(TensorElementType::Float32 | TensorElementType::Float64 | TensorElementType::Float16))
+}
+
+fn input(node: &Node, index: usize) -> Option<&str> {
+	node.inputs.get(index).map(String::as_str).filter(|input| !input.is_empty())
+}
+
+fn set_input(node: &mut Node, index: usize, name: String) {
+	if node.inputs.len() <= index {
+		node.inputs.resize(index + 1, String::new());
+	}
+	node.inputs[index] = name;
+}
+
+/// Removes an optional input, returning its name if it was present.
+fn take_input(node: &mut Node, index: usize) -> Option<String> {
+	let name = node.inputs.get_mut(index).map(mem::take).filter(|name| !name.is_empty());
+	while node.inputs.last().is_some_and(String::is_empty) {
+		node.inputs.pop();
+	}
+	name
+}
+
+fn take_attribute(node: &mut Node, name: &str) -> Option<AttributeValue> {
+	let index = node.attributes.iter().position(|attribute| attribute.name == name)?;
+	Some(node.attributes.remove(index).value)
+}
+
+fn set_attribute(node: &mut Node, name: &str, value: impl Into<AttributeValue>) {
+	take_attribute(node, name);
+	node.attributes.push(Attribute::new(name, value.into()));
+}
+
+/// Removes an optional input which is to become an attribute, returning its value.
+fn take_constant<'r>(node: &mut Node, rewrite: &'r mut Rewrite, index: usize) -> Result<Option<&'r Initializer>, String> {
+	let Some(name) = take_input(node, index) else {
+		return Ok(None);
+	};
+	let constant = rewrite
+		.constants
+		.get(&name)
+		.ok_or_else(|| format!("input `{name}` must be an initializer or `Constant` to be converted to an attribute"))?;
+	rewrite.detached.insert(name);
+	Ok(Some(constant))
+}
+
+fn take_ints(node: &mut Node, rewrite: &mut Rewrite, index: usize) -> Result<Option<Vec<i64>>, String> {
+	take_constant(node, rewrite, index)?
+		.map(|constant| {
+			constant
+				.int_values()
+				.ok_or_else(|| format!("input `{}` should be an integer tensor", constant.name))
+		})
+		.transpose()
+}
+
+fn take_floats(node: &mut Node, rewrite: &mut Rewrite, index: usize) -> Result<Option<Vec<f32>>, String> {
+	take_constant(node, rewrite, index)?
+		.map(|constant| {
+			constant
+				.float_values()
+				.or_else(|| double_values(constant))
+				.ok_or_else(|| format!("input `{}` should be a float tensor", constant.name))
+		})
+		.transpose()
+}
+
+fn take_scalar_float(node: &mut Node, rewrite: &mut Rewrite, index: usize) -> Result<Option<f32>, String> {
+	match take_floats(node, rewrite, index)?.as_deref() {
+		None => Ok(None),
+		Some(&[value]) => Ok(Some(value)),
+		Some(_) => Err(format!("input {index} should be a scalar"))
+	}
+}
+
+fn double_values(initializer: &Initializer) -> Option<Vec<f32>> {
+	if initializer.data_type != DOUBLE {
+		return None;
+	}
+	let values: Vec<f32> = match &initializer.data {
+		TensorData::Raw(bytes) => bytes
+			.chunks_exact(8)
+			.map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+			.collect(),
+		TensorData::Double(values) => values.iter().map(|&v| v as f32).collect(),
+		_ => return None
+	};
+	(values.len() == initializer.num_elements()).then_some(values)
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec, vec::Vec};
+
+	use crate::{
+		model::{AttributeValue, GraphBuilder, Initializer, Model, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	fn ints(model: &Model, name: &str) -> Vec<i64> {
+		model
+			.graph
+			.initializer(name)
+			.and_then(Initializer::int_values)
+			.expect("initializer should exist")
+	}
+
+	fn node<'m>(model: &'m Model, op_type: &str) -> &'m Node {
+		model.graph.nodes.iter().find(|node| node.op_type == op_type).expect("node should exist")
+	}
+
+	#[test]
+	fn test_upgrade() -> crate::Result<()> {
+		let model = GraphBuilder::new("upgrade")
+			.with_opset("", 10)
+			.with_input("x", TensorElementType::Float32, [2_i64, 3, 4])
+			.with_output("y", TensorElementType::Float32, [2_i64, 3, 4])
+			.with_node(Node::new("Clip").with_attribute("min", 0.0_f32).with_inputs(["x"]).with_outputs(["clip"]))
+			.with_node(Node::new("Softmax").with_name("softmax").with_inputs(["clip"]).with_outputs(["softmax"]))
+			.with_node(
+				Node::new("ReduceSum")
+					.with_attribute("axes", vec![1_i64])
+					.with_inputs(["softmax"])
+					.with_outputs(["sum"])
+			)
+			.with_node(
+				Node::new("Unsqueeze")
+					.with_attribute("axes", vec![1_i64])
+					.with_inputs(["sum"])
+					.with_outputs(["unsqueezed"])
+			)
+			.with_node(
+				Node::new("Split")
+					.with_attribute("axis", 0_i64)
+					.with_attribute("split", vec![1_i64, 1])
+					.with_inputs(["x"])
+					.with_outputs(["a", "b"])
+			)
+			.with_node(Node::new("Mean").with_inputs(["a", "b"]).with_outputs(["mean"]))
+			.with_node(Node::new("Mul").with_inputs(["mean", "unsqueezed"]).with_outputs(["y"]))
+			.build()?;
+
+		let converted = model.convert_opset(18)?;
+		assert_eq!(converted.opset_version(""), Some(18));
+		converted.validate()?;
+
+		let clip = node(&converted, "Clip");
+		assert!(clip.attributes.is_empty());
+		assert_eq!(clip.inputs.len(), 2);
+		assert_eq!(converted.graph.initializer(&clip.inputs[1]).map(|min| &min.data), Some(&TensorData::Float(vec![0.0])));
+
+		// `Softmax` over the middle axis of a 3D tensor needs to be done in 2D
+		let op_types: Vec<&str> = converted.graph.nodes.iter().map(|node| node.op_type.as_str()).take(5).collect();
+		assert_eq!(op_types, ["Clip", "Shape", "Flatten", "Softmax", "Reshape"]);
+		assert_eq!(node(&converted, "Flatten").attribute("axis"), Some(&AttributeValue::Int(1)));
+		assert_eq!(node(&converted, "Softmax").attribute("axis"), Some(&AttributeValue::Int(-1)));
+		assert_eq!(node(&converted, "Reshape").outputs, ["softmax"]);
+
+		for op_type in ["ReduceSum", "Unsqueeze"] {
+			let node = node(&converted, op_type);
+			assert!(node.attributes.is_empty());
+			assert_eq!(ints(&converted, &node.inputs[1]), [1]);
+		}
+		let split = node(&converted, "Split");
+		assert_eq!(ints(&converted, &split.inputs[1]), [1, 1]);
+		assert_eq!(split.attribute("num_outputs"), None);
+		Ok(())
+	}
+
+	#[test]
+	fn test_downgrade() -> crate::Result<()> {
+		let model = GraphBuilder::new("downgrade")
+			.with_opset("", 13)
+			.with_input("x", TensorElementType::Float32, [2_i64, 3])
+			.with_output("y", TensorElementType::Float32, [1_i64, 2, 3])
+			.with_raw_initializer(Initializer::new("axes", TensorElementType::Int64, [1], TensorData::Int64(vec![0])))
+			.with_node(Node::new("Constant").with_attribute("value_float", 6.0_f32).with_outputs(["six"]))
+			.with_node(Node::new("Softmax").with_inputs(["x"]).with_outputs(["softmax"]))
+			.with_node(Node::new("Clip").with_inputs(["softmax", "", "six"]).with_outputs(["clip"]))
+			.with_node(Node::new("Unsqueeze").with_inputs(["clip", "axes"]).with_outputs(["y"]))
+			.build()?;
+
+		let converted = model.convert_opset(9)?;
+		converted.validate()?;
+		let clip = node(&converted, "Clip");
+		assert_eq!(clip.inputs, ["softmax"]);
+		assert_eq!(clip.attribute("max"), Some(&AttributeValue::Float(6.0)));
+		assert_eq!(node(&converted, "Softmax").attribute("axis"), Some(&AttributeValue::Int(1)));
+		assert_eq!(node(&converted, "Unsqueeze").attribute("axes"), Some(&AttributeValue::Ints(vec![0])));
+		// the constants are no longer used
+		assert!(converted.graph.initializers.is_empty());
+		assert!(converted.graph.nodes.iter().all(|node| node.op_type != "Constant"));
+		Ok(())
+	}
+
+	#[test]
+	fn test_resize() -> crate::Result<()> {
+		let model = GraphBuilder::new("upsample")
+			.with_opset("", 9)
+			.with_input("x", TensorElementType::Float32, [1_i64, 1, 2, 2])
+			.with_output("y", TensorElementType::Float32, [1_i64, 1, 4, 4])
+			.with_raw_initializer(Initializer::new("scales", TensorElementType::Float32, [4], TensorData::Float(vec![1.0, 1.0, 2.0, 2.0])))
+			.with_node(Node::new("Upsample").with_inputs(["x", "scales"]).with_outputs(["y"]))
+			.build()?;
+
+		let upgraded = model.convert_opset(13)?;
+		let resize = node(&upgraded, "Resize");
+		assert_eq!(resize.inputs.len(), 3);
+		assert_eq!(resize.inputs[2], "scales");
+		assert_eq!(resize.attribute("coordinate_transformation_mode").and_then(AttributeValue::as_str), Some("asymmetric"));
+		assert_eq!(resize.attribute("nearest_mode").and_then(AttributeValue::as_str), Some("floor"));
+
+		let downgraded = upgraded.convert_opset(7)?;
+		let upsample = node(&downgraded, "Upsample");
+		assert_eq!(upsample.inputs, ["x"]);
+		assert_eq!(upsample.attribute("scales"), Some(&AttributeValue::Floats(vec![1.0, 1.0, 2.0, 2.0])));
+		assert!(upsample.attribute("coordinate_transformation_mode").is_none());
+		assert!(downgraded.graph.initializers.is_empty());
+		Ok(())
+	}
+
+	#[test]
+	fn test_errors() -> crate::Result<()> {
+		let model = GraphBuilder::new("errors")
+			.with_opset("", 17)
+			.with_input("x", TensorElementType::Float32, [1_i64, 1, 2, 2])
+			.with_input("axes", TensorElementType::Int64, [1_i64])
+			.with_output("y", TensorElementType::Float32, [1_i64, 1, 4, 4])
+			.with_raw_initializer(Initializer::new("scales", TensorElementType::Float32, [4], TensorData::Float(vec![1.0, 1.0, 2.0, 2.0])))
+			.with_node(
+				Node::new("Resize")
+					.with_name("resize")
+					.with_inputs(["x", "", "scales"])
+					.with_outputs(["resized"])
+			)
+			.with_node(
+				Node::new("LayerNormalization")
+					.with_inputs(["resized", "scales"])
+					.with_outputs(["normalized"])
+			)
+			.with_node(Node::new("ReduceSum").with_inputs(["normalized", "axes"]).with_outputs(["y"]))
+			.build()?;
+
+		let message = model.convert_opset(10).expect_err("conversion should fail").to_string();
+		assert!(message.contains("`Resize` node `resize`: `half_pixel` coordinates"), "{message}");
+		assert!(message.contains("`LayerNormalization` node producing `normalized`: `LayerNormalization` was introduced in opset 17"), "{message}");
+		assert!(message.contains("input `axes` must be an initializer or `Constant`"), "{message}");
+		assert!(model.convert_opset(18).is_ok());
+		let message = model.convert_opset(6).expect_err("opset 6 should be unsupported").to_string();
+		assert!(message.contains("only versions 7 through 22"), "{message}");
+		Ok(())
+	}
+}
@@ -0,0 +1,330 @@
+//! Minimal protobuf wire format reader & writer, sufficient for the messages in `onnx.proto`.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::error::{Error, ErrorCode, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WireType {
+	Varint,
+	Fixed64,
+	Len,
+	Fixed32
+}
+
+/// A single field read from a message.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Field<'b> {
+	Varint(u64),
+	Fixed64(u64),
+	Len(&'b [u8]),
+	Fixed32(u32)
+}
+
+impl<'b> Field<'b> {
+	pub(crate) fn wire_type(&self) -> WireType {
+		match self {
+			Field::Varint(_) => WireType::Varint,
+			Field::Fixed64(_) => WireType::Fixed64,
+			Field::Len(_) => WireType::Len,
+			Field::Fixed32(_) => WireType::Fixed32
+		}
+	}
+
+	pub(crate) fn as_u64(&self) -> Result<u64> {
+		match self {
+			Field::Varint(v) => Ok(*v),
+			_ => Err(invalid(format!("expected varint, got {:?}", self.wire_type())))
+		}
+	}
+
+	pub(crate) fn as_i64(&self) -> Result<i64> {
+		self.as_u64().map(|v| v as i64)
+	}
+
+	/// `int32` & enum fields are encoded as sign-extended 64-bit varints.
+	pub(crate) fn as_i32(&self) -> Result<i32> {
+		self.as_u64().map(|v| v as i64 as i32)
+	}
+
+	pub(crate) fn as_f32(&self) -> Result<f32> {
+		match self {
+			Field::Fixed32(v) => Ok(f32::from_bits(*v)),
+			_ => Err(invalid(format!("expected fixed32, got {:?}", self.wire_type())))
+		}
+	}
+
+	pub(crate) fn as_bytes(&self) -> Result<&'b [u8]> {
+		match self {
+			Field::Len(bytes) => Ok(bytes),
+			_ => Err(invalid(format!("expected length-delimited field, got {:?}", self.wire_type())))
+		}
+	}
+
+	pub(crate) fn as_string(&self) -> Result<String> {
+		String::from_utf8(self.as_bytes()?.to_vec()).map_err(|_| invalid("string field is not valid UTF-8"))
+	}
+
+	/// Reads a repeated varint field, which may be packed or not.
+	pub(crate) fn push_varints<T>(&self, out: &mut Vec<T>, convert: impl Fn(u64) -> T) -> Result<()> {
+		match self {
+			Field::Varint(v) => out.push(convert(*v)),
+			Field::Len(bytes) => {
+				let mut reader = Reader::new(bytes);
+				while !reader.is_empty() {
+					out.push(convert(reader.varint()?));
+				}
+			}
+			_ => return Err(invalid(format!("expected varint, got {:?}", self.wire_type())))
+		}
+		Ok(())
+	}
+
+	/// Reads a repeated `float` field, which may be packed or not.
+	pub(crate) fn push_f32s(&self, out: &mut Vec<f32>) -> Result<()> {
+		match self {
+			Field::Fixed32(v) => out.push(f32::from_bits(*v)),
+			Field::Len(bytes) => {
+				if bytes.len() % 4 != 0 {
+					return Err(invalid("packed float field has a length which is not a multiple of 4"));
+				}
+				out.extend(bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+			}
+			_ => return Err(invalid(format!("expected fixed32, got {:?}", self.wire_type())))
+		}
+		Ok(())
+	}
+
+	/// Reads a repeated `double` field, which may be packed or not.
+	pub(crate) fn push_f64s(&self, out: &mut Vec<f64>) -> Result<()> {
+		match self {
+			Field::Fixed64(v) => out.push(f64::from_bits(*v)),
+			Field::Len(bytes) => {
+				if bytes.len() % 8 != 0 {
+					return Err(invalid("packed double field has a length which is not a multiple of 8"));
+				}
+				out.extend(
+					bytes
+						.chunks_exact(8)
+						.map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+				);
+			}
+			_ => return Err(invalid(format!("expected fixed64, got {:?}", self.wire_type())))
+		}
+		Ok(())
+	}
+}
+
+pub(crate) struct Reader<'b> {
+	buf: &'b [u8],
+	pos: usize
+}
+
+impl<'b> Reader<'b> {
+	pub(crate) fn new(buf: &'b [u8]) -> Self {
+		Self { buf, pos: 0 }
+	}
+
+	pub(crate) fn is_empty(&self) -> bool {
+		self.pos >= self.buf.len()
+	}
+
+	fn varint(&mut self) -> Result<u64> {
+		let mut value = 0u64;
+		for shift in (0..64).step_by(7) {
+			let byte = *self.buf.get(self.pos).ok_or_else(|| invalid("unexpected end of message in varint"))?;
+			self.pos += 1;
+			value |= u64::from(byte & 0x7f) << shift;
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+		}
+		Err(invalid("varint is too long"))
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'b [u8]> {
+		let end = self
+			.pos
+			.checked_add(len)
+			.filter(|&end| end <= self.buf.len())
+			.ok_or_else(|| invalid("unexpected end of message"))?;
+		let bytes = &self.buf[self.pos..end];
+		self.pos = end;
+		Ok(bytes)
+	}
+
+	/// Reads the next field, returning its number, value, and raw encoding (including the tag).
+	pub(crate) fn field(&mut self) -> Result<(u32, Field<'b>, &'b [u8])> {
+		let start = self.pos;
+		let tag = self.varint()?;
+		let number = u32::try_from(tag >> 3).map_err(|_| invalid("field number is too large"))?;
+		let field = match tag & 7 {
+			0 => Field::Varint(self.varint()?),
+			1 => {
+				let bytes = self.take(8)?;
+				Field::Fixed64(u64::from_le_bytes(bytes.try_into().expect("took 8 bytes")))
+			}
+			2 => {
+				let len = usize::try_from(self.varint()?).map_err(|_| invalid("length is too large"))?;
+				Field::Len(self.take(len)?)
+			}
+			5 => {
+				let bytes = self.take(4)?;
+				Field::Fixed32(u32::from_le_bytes(bytes.try_into().expect("took 4 bytes")))
+			}
+			wire_type => return Err(invalid(format!("unsupported wire type {wire_type}")))
+		};
+		Ok((number, field, &self.buf[start..self.pos]))
+	}
+}
+
+/// Iterates over the fields of a message, calling `f` with each field's number & value. `f` returns `false` for
+/// unrecognized fields, whose raw encoding is appended to `unknown` so it survives re-encoding.
+pub(crate) fn for_each_field<'b>(buf: &'b [u8], unknown: &mut Vec<u8>, mut f: impl FnMut(u32, Field<'b>) -> Result<bool>) -> Result<()> {
+	let mut reader = Reader::new(buf);
+	while !reader.is_empty() {
+		let (number, field, raw) = reader.field()?;
+		if !f(number, field)? {
+			unknown.extend_from_slice(raw);
+		}
+	}
+	Ok(())
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Writer {
+	buf: Vec<u8>
+}
+
+impl Writer {
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	pub(crate) fn into_bytes(self) -> Vec<u8> {
+		self.buf
+	}
+
+	fn varint(&mut self, mut value: u64) {
+		while value >= 0x80 {
+			self.buf.push((value as u8) | 0x80);
+			value >>= 7;
+		}
+		self.buf.push(value as u8);
+	}
+
+	fn tag(&mut self, number: u32, wire_type: WireType) {
+		let wire_type = match wire_type {
+			WireType::Varint => 0,
+			WireType::Fixed64 => 1,
+			WireType::Len => 2,
+			WireType::Fixed32 => 5
+		};
+		self.varint((u64::from(number) << 3) | wire_type);
+	}
+
+	pub(crate) fn u64(&mut self, number: u32, value: u64) {
+		self.tag(number, WireType::Varint);
+		self.varint(value);
+	}
+
+	pub(crate) fn i64(&mut self, number: u32, value: i64) {
+		self.u64(number, value as u64);
+	}
+
+	pub(crate) fn i32(&mut self, number: u32, value: i32) {
+		self.u64(number, value as i64 as u64);
+	}
+
+	pub(crate) fn f32(&mut self, number: u32, value: f32) {
+		self.tag(number, WireType::Fixed32);
+		self.buf.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub(crate) fn bytes(&mut self, number: u32, value: &[u8]) {
+		self.tag(number, WireType::Len);
+		self.varint(value.len() as u64);
+		self.buf.extend_from_slice(value);
+	}
+
+	/// Writes a string field, omitting it if empty.
+	pub(crate) fn string(&mut self, number: u32, value: &str) {
+		if !value.is_empty() {
+			self.bytes(number, value.as_bytes());
+		}
+	}
+
+	pub(crate) fn message(&mut self, number: u32, encode: impl FnOnce(&mut Writer)) {
+		let mut inner = Writer::new();
+		encode(&mut inner);
+		self.bytes(number, &inner.buf);
+	}
+
+	pub(crate) fn packed_varints(&mut self, number: u32, values: impl IntoIterator<Item = u64>) {
+		let mut inner = Writer::new();
+		for value in values {
+			inner.varint(value);
+		}
+		if !inner.buf.is_empty() {
+			self.bytes(number, &inner.buf);
+		}
+	}
+
+	pub(crate) fn packed_fixed(&mut self, number: u32, bytes: impl IntoIterator<Item = u8>) {
+		let bytes: Vec<u8> = bytes.into_iter().collect();
+		if !bytes.is_empty() {
+			self.bytes(number, &bytes);
+		}
+	}
+
+	/// Appends fields preserved from decoding verbatim.
+	pub(crate) fn raw(&mut self, raw: &[u8]) {
+		self.buf.extend_from_slice(raw);
+	}
+}
+
+pub(crate) fn invalid(message: impl core::fmt::Display) -> Error {
+	Error::new_with_code(ErrorCode::InvalidProtobuf, format!("Failed to parse ONNX model: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec::Vec;
+
+	use super::{Field, Reader, Writer};
+
+	#[test]
+	fn test_wire_format() -> crate::Result<()> {
+		let mut writer = Writer::new();
+		writer.i64(1, -2);
+		writer.i32(2, -1);
+		writer.f32(3, 1.5);
+		writer.string(4, "hi");
+		writer.string(5, "");
+		writer.packed_varints(6, [1, 300]);
+		writer.message(7, |w| w.u64(1, 150));
+		let bytes = writer.into_bytes();
+
+		let mut reader = Reader::new(&bytes);
+		let mut fields = Vec::new();
+		while !reader.is_empty() {
+			let (number, field, _) = reader.field()?;
+			fields.push((number, field));
+		}
+		assert_eq!(fields.iter().map(|(number, _)| *number).collect::<Vec<_>>(), [1, 2, 3, 4, 6, 7]);
+		assert_eq!(fields[0].1.as_i64()?, -2);
+		assert_eq!(fields[1].1.as_i32()?, -1);
+		assert_eq!(fields[2].1.as_f32()?, 1.5);
+		assert_eq!(fields[3].1.as_string()?, "hi");
+		let mut packed = Vec::new();
+		fields[4].1.push_varints(&mut packed, |v| v)?;
+		fields[4].1.push_varints(&mut packed, |v| v)?;
+		Field::Varint(7).push_varints(&mut packed, |v| v)?;
+		assert_eq!(packed, [1, 300, 1, 300, 7]);
+		assert_eq!(fields[5].1.as_bytes()?, [0x08, 0x96, 0x01]);
+
+		assert!(Reader::new(&[0x0a, 0x05, 0x00]).field().is_err());
+		assert!(Reader::new(&[0xff; 11]).field().is_err());
+		Ok(())
+	}
+}
@@ -0,0 +1,1138 @@
+//! INT8 quantization of ONNX models.
+//!
+//! Quantization stores a model's weights (and optionally its activations) as 8-bit integers, making the model ~4x
+//! smaller and letting ONNX Runtime use integer kernels, which are much faster on CPUs. Two approaches are supported,
+//! mirroring Python's `onnxruntime.quantization`:
+//!
+//! - **Dynamic quantization** ([`DynamicQuantizer`]) quantizes the weights of `MatMul` & `Gemm` nodes ahead of time.
+//!   Their other input is quantized at run time by `DynamicQuantizeLinear`, and the product is computed with
+//!   `MatMulInteger`. No calibration data is required; this works well for transformers & RNNs.
+//! - **Static quantization** ([`StaticQuantizer`]) runs the model over representative inputs to measure the range of
+//!   each activation, then inserts `QuantizeLinear`/`DequantizeLinear` pairs around the inputs & outputs of quantized
+//!   nodes (the *QDQ format*). ONNX Runtime fuses each `DequantizeLinear -> op -> QuantizeLinear` pattern into an
+//!   integer kernel when the session is created, unless disabled via [`SessionBuilder::with_quant_qdq`]. This works
+//!   well for CNNs.
+//!
+//! ```no_run
+//! # use ort::{model::{Model, quantize::{CalibrationMethod, StaticQuantizer}}, session::Session, value::Tensor};
+//! # fn main() -> ort::Result<()> {
+//! let model = Model::from_file("resnet50.onnx")?;
+//! let images = (0..32)
+//! 	.map(|_| Tensor::from_array(([1, 3, 224, 224], vec![0.5_f32; 3 * 224 * 224])))
+//! 	.collect::<ort::Result<Vec<_>>>()?;
+//!
+//! let quantizer = StaticQuantizer::new()
+//! 	.with_calibration_method(CalibrationMethod::Percentile(99.999))
+//! 	.with_per_channel(true);
+//! let calibration =
+//! 	quantizer.calibrate(&model, Session::builder()?, images.iter().map(|image| ort::inputs![image]))?;
+//! let quantized = quantizer.quantize(&model, &calibration)?;
+//!
+//! let session = Session::builder()?.with_quant_qdq(true)?.commit_from_model(&quantized)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only nodes of the main graph are quantized; nodes in subgraphs (e.g. the body of a `Loop`) are left as-is.
+//!
+//! [`SessionBuilder::with_quant_qdq`]: crate::session::builder::SessionBuilder::with_quant_qdq
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::String,
+	vec,
+	vec::Vec
+};
+use core::mem;
+
+use super::{AttributeValue, Graph, Initializer, Model, Node, TensorData, edit::Names, tensor::data_type};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	session::{SessionInputs, builder::SessionBuilder},
+	tensor::TensorElementType,
+	value::ValueType
+};
+
+/// The number of bins in the histograms used by [`CalibrationMethod::Percentile`] & [`CalibrationMethod::Entropy`].
+const HISTOGRAM_BINS: usize = 2048;
+/// The number of quantization levels [`CalibrationMethod::Entropy`] evaluates thresholds with.
+const ENTROPY_LEVELS: usize = 128;
+
+/// The integer type quantized values are stored as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantizationType {
+	/// Signed 8-bit integers. Values are quantized symmetrically, i.e. with a zero point of 0.
+	Int8,
+	/// Unsigned 8-bit integers. Values are quantized asymmetrically, mapping the range of values to `0..=255`.
+	Uint8
+}
+
+impl QuantizationType {
+	fn element_type(self) -> TensorElementType {
+		match self {
+			Self::Int8 => TensorElementType::Int8,
+			Self::Uint8 => TensorElementType::Uint8
+		}
+	}
+
+	/// Returns the scale & zero point used to quantize values in `min..=max`.
+	fn params(self, min: f32, max: f32) -> (f32, i32) {
+		let nonzero = |scale: f32| if scale > 0.0 && scale.is_finite() { scale } else { 1.0 };
+		match self {
+			Self::Int8 => (nonzero(min.abs().max(max.abs()) / 127.0), 0),
+			Self::Uint8 => {
+				let (min, max) = (min.min(0.0), max.max(0.0));
+				let scale = nonzero((max - min) / 255.0);
+				(scale, ((-min / scale).round_ties_even() as i32).clamp(0, 255))
+			}
+		}
+	}
+
+	/// Quantizes a single value, returning its byte representation.
+	fn quantize(self, value: f32, scale: f32, zero_point: i32) -> u8 {
+		let (min, max) = match self {
+			Self::Int8 => (-127, 127),
+			Self::Uint8 => (0, 255)
+		};
+		((value / scale).round_ties_even() as i32).saturating_add(zero_point).clamp(min, max) as u8
+	}
+}
+
+/// How [`StaticQuantizer::calibrate`] chooses the range of each activation from the values observed during
+/// calibration.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CalibrationMethod {
+	/// Uses the minimum & maximum observed values. This is simple, but sensitive to outliers.
+	#[default]
+	MinMax,
+	/// Clips the range to the given percentile of absolute values, e.g. `Percentile(99.999)`, ignoring rare outliers.
+	Percentile(f32),
+	/// Clips the range to the threshold that minimizes the KL divergence between the distribution of observed values
+	/// and its quantized approximation.
+	Entropy
+}
+
+/// The range of values of a tensor, as measured by calibration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TensorRange {
+	pub min: f32,
+	pub max: f32
+}
+
+impl TensorRange {
+	pub fn new(min: f32, max: f32) -> Self {
+		Self { min, max }
+	}
+}
+
+/// The ranges of a model's activations, produced by [`StaticQuantizer::calibrate`].
+///
+/// Tables can also be filled in by hand, e.g. to reuse ranges computed by another tool.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CalibrationTable {
+	ranges: BTreeMap<String, TensorRange>
+}
+
+impl CalibrationTable {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the range of the tensor with the given name.
+	pub fn insert(&mut self, name: impl Into<String>, range: TensorRange) {
+		self.ranges.insert(name.into(), range);
+	}
+
+	/// Returns the range of the tensor with the given name, if it was calibrated.
+	pub fn get(&self, name: &str) -> Option<TensorRange> {
+		self.ranges.get(name).copied()
+	}
+
+	/// Returns an iterator over the names & ranges of all calibrated tensors.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, TensorRange)> {
+		self.ranges.iter().map(|(name, range)| (name.as_str(), *range))
+	}
+
+	pub fn len(&self) -> usize {
+		self.ranges.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ranges.is_empty()
+	}
+}
+
+/// Quantizes the weights of `MatMul` & `Gemm` nodes ahead of time, and their activations at run time.
+///
+/// Each selected node whose second input (`B`) is a `float32` initializer is replaced by
+/// `DynamicQuantizeLinear -> MatMulInteger -> Cast -> Mul`. `Gemm` nodes with `transA` set are skipped. The model must
+/// import version 11 or newer of the default operator set.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct DynamicQuantizer {
+	weight_type: QuantizationType,
+	per_channel: bool,
+	op_types: Vec<String>,
+	excluded_nodes: Vec<String>
+}
+
+impl Default for DynamicQuantizer {
+	fn default() -> Self {
+		Self {
+			weight_type: QuantizationType::Int8,
+			per_channel: false,
+			op_types: vec![String::from("MatMul"), String::from("Gemm")],
+			excluded_nodes: Vec::new()
+		}
+	}
+}
+
+impl DynamicQuantizer {
+	/// Creates a new dynamic quantizer, quantizing the weights of all `MatMul` & `Gemm` nodes to
+	/// [`QuantizationType::Int8`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the type weights are quantized to. Defaults to [`QuantizationType::Int8`].
+	pub fn with_weight_type(mut self, ty: QuantizationType) -> Self {
+		self.weight_type = ty;
+		self
+	}
+
+	/// Quantize 2D weights per output column instead of with a single scale for the whole tensor. This usually improves
+	/// accuracy at no cost to performance.
+	pub fn with_per_channel(mut self, enable: bool) -> Self {
+		self.per_channel = enable;
+		self
+	}
+
+	/// Sets the operator types to quantize. Only `MatMul` & `Gemm` are supported; this can be used to quantize only one
+	/// of the two.
+	pub fn with_op_types<S: Into<String>>(mut self, op_types: impl IntoIterator<Item = S>) -> Self {
+		self.op_types = op_types.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Excludes nodes with the given names from quantization, e.g. to keep a sensitive output layer in full precision.
+	pub fn with_excluded_nodes<S: Into<String>>(mut self, nodes: impl IntoIterator<Item = S>) -> Self {
+		self.excluded_nodes = nodes.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Returns a quantized copy of `model`.
+	pub fn quantize(&self, model: &Model) -> Result<Model> {
+		require_opset(model, 11, "Dynamic quantization")?;
+		let mut model = model.clone();
+		let graph = &mut model.graph;
+		let mut rewriter = Rewriter::new(graph);
+		// activation -> (quantized, scale, zero point)
+		let mut activations: BTreeMap<String, [String; 3]> = BTreeMap::new();
+
+		let nodes = mem::take(&mut graph.nodes);
+		for node in nodes {
+			if selects(&self.op_types, &self.excluded_nodes, &node) {
+				if let Some(rewritten) = self.rewrite(&node, graph, &mut rewriter, &mut activations) {
+					graph.nodes.extend(rewritten);
+					continue;
+				}
+			}
+			graph.nodes.push(node);
+		}
+
+		rewriter.finish(graph);
+		graph.validate()?;
+		Ok(model)
+	}
+
+	/// Returns the nodes replacing a `MatMul` or `Gemm` node, or `None` if it can't be quantized.
+	fn rewrite(&self, node: &Node, graph: &Graph, rewriter: &mut Rewriter, activations: &mut BTreeMap<String, [String; 3]>) -> Option<Vec<Node>> {
+		let is_gemm = node.op_type == "Gemm";
+		let int = |name: &str| node.attribute(name).and_then(AttributeValue::as_int).unwrap_or(0);
+		let float = |name: &str| node.attribute(name).and_then(AttributeValue::as_float).unwrap_or(1.0);
+		if (is_gemm && int("transA") != 0) || node.outputs.len() != 1 {
+			return None;
+		}
+		let (Some(a), Some(b)) = (node.inputs.first(), node.inputs.get(1)) else {
+			return None;
+		};
+		let weight = graph.initializer(b)?;
+		if weight.dims.len() < 2 || (is_gemm && weight.dims.len() != 2) {
+			return None;
+		}
+		let axis = (self.per_channel && weight.dims.len() == 2).then_some(1);
+		let weight = rewriter.quantize_weight(weight, self.weight_type, axis, is_gemm && int("transB") != 0)?;
+
+		let mut nodes = Vec::new();
+		let [a_quantized, a_scale, a_zero_point] = match activations.get(a) {
+			Some(quantized) => quantized.clone(),
+			None => {
+				let quantized = [format!("{a}_quantized"), format!("{a}_scale"), format!("{a}_zero_point")].map(|name| rewriter.fresh(name));
+				nodes.push(
+					Node::new("DynamicQuantizeLinear")
+						.with_name(rewriter.fresh(format!("{a}_DynamicQuantizeLinear")))
+						.with_inputs([a.as_str()])
+						.with_outputs(quantized.clone())
+				);
+				activations.insert(a.clone(), quantized.clone());
+				quantized
+			}
+		};
+
+		let base = if node.name.is_empty() { node.outputs[0].clone() } else { node.name.clone() };
+		let output = node.outputs[0].clone();
+		let product = rewriter.fresh(format!("{base}_output_quantized"));
+		let product_float = rewriter.fresh(format!("{base}_output_float"));
+		let mut scale = rewriter.fresh(format!("{base}_scale"));
+		nodes.push(
+			Node::new("MatMulInteger")
+				.with_name(rewriter.fresh(format!("{base}_quant")))
+				.with_inputs([a_quantized, weight.name, a_zero_point, weight.zero_point])
+				.with_outputs([product.as_str()])
+		);
+		nodes.push(
+			Node::new("Cast")
+				.with_name(rewriter.fresh(format!("{base}_output_cast")))
+				.with_attribute("to", i64::from(data_type(TensorElementType::Float32)))
+				.with_inputs([product])
+				.with_outputs([product_float.as_str()])
+		);
+		nodes.push(
+			Node::new("Mul")
+				.with_name(rewriter.fresh(format!("{base}_scale_mul")))
+				.with_inputs([a_scale, weight.scale])
+				.with_outputs([scale.as_str()])
+		);
+		if is_gemm && float("alpha") != 1.0 {
+			let alpha = rewriter.scalar(format!("{base}_alpha"), float("alpha"));
+			let scaled = rewriter.fresh(format!("{base}_scale_alpha"));
+			nodes.push(
+				Node::new("Mul")
+					.with_name(rewriter.fresh(format!("{base}_alpha_mul")))
+					.with_inputs([scale, alpha])
+					.with_outputs([scaled.as_str()])
+			);
+			scale = scaled;
+		}
+
+		let bias = node.inputs.get(2).filter(|c| is_gemm && !c.is_empty());
+		let result = if bias.is_some() { rewriter.fresh(format!("{base}_output_unbiased")) } else { output.clone() };
+		nodes.push(
+			Node::new("Mul")
+				.with_name(rewriter.fresh(format!("{base}_output_scale_mul")))
+				.with_inputs([product_float, scale])
+				.with_outputs([result.as_str()])
+		);
+		if let Some(bias) = bias {
+			let mut bias = bias.clone();
+			if float("beta") != 1.0 {
+				let beta = rewriter.scalar(format!("{base}_beta"), float("beta"));
+				let scaled = rewriter.fresh(format!("{base}_bias_beta"));
+				nodes.push(
+					Node::new("Mul")
+						.with_name(rewriter.fresh(format!("{base}_beta_mul")))
+						.with_inputs([bias, beta])
+						.with_outputs([scaled.as_str()])
+				);
+				bias = scaled;
+			}
+			nodes.push(
+				Node::new("Add")
+					.with_name(rewriter.fresh(format!("{base}_bias_add")))
+					.with_inputs([result, bias])
+					.with_outputs([output])
+			);
+		}
+		Some(nodes)
+	}
+}
+
+/// Quantizes a model to the QDQ format using activation ranges measured by a calibration pass.
+///
+/// By default, `Conv`, `MatMul`, and `Gemm` nodes are quantized. Their `float32` weights are stored as quantized
+/// initializers followed by `DequantizeLinear`, their biases are stored as `int32` quantized with the product of the
+/// input & weight scales, and their calibrated inputs & outputs get `QuantizeLinear -> DequantizeLinear` pairs. For
+/// other operator types, only calibrated inputs & outputs are quantized. The model must import version 10 or newer of
+/// the default operator set, or 13 or newer for [per-channel quantization](StaticQuantizer::with_per_channel).
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct StaticQuantizer {
+	method: CalibrationMethod,
+	activation_type: QuantizationType,
+	weight_type: QuantizationType,
+	per_channel: bool,
+	op_types: Vec<String>,
+	excluded_nodes: Vec<String>
+}
+
+impl Default for StaticQuantizer {
+	fn default() -> Self {
+		Self {
+			method: CalibrationMethod::MinMax,
+			activation_type: QuantizationType::Uint8,
+			weight_type: QuantizationType::Int8,
+			per_channel: false,
+			op_types: vec![String::from("Conv"), String::from("MatMul"), String::from("Gemm")],
+			excluded_nodes: Vec::new()
+		}
+	}
+}
+
+impl StaticQuantizer {
+	/// Creates a new static quantizer, quantizing `Conv`, `MatMul`, and `Gemm` nodes with [`QuantizationType::Uint8`]
+	/// activations & [`QuantizationType::Int8`] weights, calibrated with [`CalibrationMethod::MinMax`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the method used to choose activation ranges. Defaults to [`CalibrationMethod::MinMax`].
+	pub fn with_calibration_method(mut self, method: CalibrationMethod) -> Self {
+		self.method = method;
+		self
+	}
+
+	/// Sets the type activations are quantized to. Defaults to [`QuantizationType::Uint8`].
+	pub fn with_activation_type(mut self, ty: QuantizationType) -> Self {
+		self.activation_type = ty;
+		self
+	}
+
+	/// Sets the type weights are quantized to. Defaults to [`QuantizationType::Int8`].
+	pub fn with_weight_type(mut self, ty: QuantizationType) -> Self {
+		self.weight_type = ty;
+		self
+	}
+
+	/// Quantize weights per output channel instead of with a single scale for the whole tensor. This usually improves
+	/// accuracy, especially for convolutions.
+	pub fn with_per_channel(mut self, enable: bool) -> Self {
+		self.per_channel = enable;
+		self
+	}
+
+	/// Sets the operator types to quantize.
+	pub fn with_op_types<S: Into<String>>(mut self, op_types: impl IntoIterator<Item = S>) -> Self {
+		self.op_types = op_types.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Excludes nodes with the given names from quantization, e.g. to keep a sensitive output layer in full precision.
+	pub fn with_excluded_nodes<S: Into<String>>(mut self, nodes: impl IntoIterator<Item = S>) -> Self {
+		self.excluded_nodes = nodes.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Measures the ranges of the activations this quantizer would quantize by running `model` over each set of
+	/// inputs in `data`.
+	///
+	/// The session is created from `builder` with the activations added as extra outputs, so `builder` can be
+	/// configured with the same options (e.g. execution providers) as the session the model will eventually run in.
+	/// Only `float32` activations are calibrated. Returns an error if `data` is empty.
+	pub fn calibrate<'i, 'v: 'i, const N: usize>(
+		&self,
+		model: &Model,
+		builder: SessionBuilder,
+		data: impl IntoIterator<Item = impl Into<SessionInputs<'i, 'v, N>>>
+	) -> Result<CalibrationTable> {
+		let graph = &model.graph;
+		let mut activations = BTreeSet::new();
+		for node in graph.nodes.iter().filter(|node| selects(&self.op_types, &self.excluded_nodes, node)) {
+			activations.extend(
+				node.inputs
+					.iter()
+					.chain(&node.outputs)
+					.filter(|name| !name.is_empty() && graph.initializer(name).is_none())
+			);
+		}
+
+		let mut calibration_model = model.clone();
+		for &name in &activations {
+			calibration_model.add_output(name)?;
+		}
+		let session = builder.commit_from_model(&calibration_model)?;
+		let mut collectors: Vec<(&str, Collector)> = session
+			.outputs
+			.iter()
+			.filter(|output| activations.contains(&output.name) && matches!(output.output_type, ValueType::Tensor { ty: TensorElementType::Float32, .. }))
+			.map(|output| (output.name.as_str(), Collector::new(self.method)))
+			.collect();
+
+		let mut batches = 0;
+		for inputs in data {
+			let outputs = session.run(inputs)?;
+			for (name, collector) in &mut collectors {
+				let (_, values) = outputs[*name].try_extract_tensor::<f32>()?;
+				collector.collect(values);
+			}
+			batches += 1;
+		}
+		if batches == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Calibration requires at least one set of inputs"));
+		}
+
+		let mut table = CalibrationTable::new();
+		for (name, collector) in collectors {
+			table.insert(name, collector.range(self.method));
+		}
+		Ok(table)
+	}
+
+	/// Returns a copy of `model` quantized to the QDQ format, using the activation ranges in `calibration`.
+	///
+	/// Activations without a range in `calibration` are left in full precision.
+	pub fn quantize(&self, model: &Model, calibration: &CalibrationTable) -> Result<Model> {
+		require_opset(model, if self.per_channel { 13 } else { 10 }, "Static quantization")?;
+		let mut model = model.clone();
+		let graph = &mut model.graph;
+		let mut rewriter = Rewriter::new(graph);
+		let nodes = mem::take(&mut graph.nodes);
+
+		// activation -> (dequantized name, scale, QuantizeLinear & DequantizeLinear nodes)
+		let mut activations: BTreeMap<String, (String, f32, Vec<Node>)> = BTreeMap::new();
+		for node in nodes.iter().filter(|node| selects(&self.op_types, &self.excluded_nodes, node)) {
+			for name in node.inputs.iter().chain(&node.outputs) {
+				if name.is_empty() || graph.initializer(name).is_some() || activations.contains_key(name) {
+					continue;
+				}
+				let Some(range) = calibration.get(name) else {
+					continue;
+				};
+				let (scale, zero_point) = self.activation_type.params(range.min, range.max);
+				let scale_name = rewriter.scalar(format!("{name}_scale"), scale);
+				let zero_point_name = rewriter.fresh(format!("{name}_zero_point"));
+				rewriter.initializers.push(Initializer::new(
+					zero_point_name.as_str(),
+					self.activation_type.element_type(),
+					[],
+					TensorData::Raw(vec![zero_point as u8])
+				));
+				let quantized = rewriter.fresh(format!("{name}_quantized"));
+				let dequantized = rewriter.fresh(format!("{name}_dequantized"));
+				let qdq = vec![
+					Node::new("QuantizeLinear")
+						.with_name(rewriter.fresh(format!("{name}_QuantizeLinear")))
+						.with_inputs([name.as_str(), &scale_name, &zero_point_name])
+						.with_outputs([quantized.as_str()]),
+					Node::new("DequantizeLinear")
+						.with_name(rewriter.fresh(format!("{name}_DequantizeLinear")))
+						.with_inputs([quantized.as_str(), &scale_name, &zero_point_name])
+						.with_outputs([dequantized.as_str()]),
+				];
+				activations.insert(name.clone(), (dequantized, scale, qdq));
+			}
+		}
+
+		for input in &graph.inputs {
+			if let Some((_, _, qdq)) = activations.get_mut(&input.name) {
+				graph.nodes.append(qdq);
+			}
+		}
+		// weight -> dequantized name
+		let mut weights: BTreeMap<String, String> = BTreeMap::new();
+		for mut node in nodes {
+			if selects(&self.op_types, &self.excluded_nodes, &node) {
+				self.quantize_weights(&mut node, &graph.initializers, &mut graph.nodes, &mut rewriter, &activations, &mut weights);
+			}
+			for input in &mut node.inputs {
+				if let Some((dequantized, ..)) = activations.get(input) {
+					*input = dequantized.clone();
+				}
+			}
+			let outputs = node.outputs.clone();
+			graph.nodes.push(node);
+			for output in &outputs {
+				if let Some((_, _, qdq)) = activations.get_mut(output) {
+					graph.nodes.append(qdq);
+				}
+			}
+		}
+
+		rewriter.finish(graph);
+		graph.validate()?;
+		Ok(model)
+	}
+
+	/// Replaces the weight & bias inputs of a `Conv`, `MatMul`, or `Gemm` node with dequantized initializers, appending
+	/// the `DequantizeLinear` nodes to `nodes`.
+	fn quantize_weights(
+		&self,
+		node: &mut Node,
+		initializers: &[Initializer],
+		nodes: &mut Vec<Node>,
+		rewriter: &mut Rewriter,
+		activations: &BTreeMap<String, (String, f32, Vec<Node>)>,
+		weights: &mut BTreeMap<String, String>
+	) {
+		let transposed = node.attribute("transB").and_then(AttributeValue::as_int).unwrap_or(0) != 0;
+		let input_scale = node.inputs.first().and_then(|input| activations.get(input)).map(|(_, scale, _)| *scale);
+		let mut weight_scales: Option<Vec<f32>> = None;
+		for index in 0..node.inputs.len() {
+			let Some(initializer) = initializers.iter().find(|initializer| initializer.name == node.inputs[index]) else {
+				continue;
+			};
+			let rank = initializer.dims.len();
+			let axis = match (node.op_type.as_str(), index) {
+				("Conv", 1) => Some(0),
+				("MatMul", 1) if rank == 2 => Some(1),
+				("Gemm", 1) => Some(if transposed { 0 } else { 1 }),
+				("MatMul" | "Gemm", 0 | 1) => None,
+				("Conv" | "Gemm", 2) => {
+					if let (Some(input_scale), Some(weight_scales)) = (input_scale, &weight_scales) {
+						if let Some(dequantized) = quantize_bias(initializer, input_scale, weight_scales, nodes, rewriter) {
+							node.inputs[index] = dequantized;
+						}
+					}
+					continue;
+				}
+				_ => continue
+			};
+			let axis = axis.filter(|_| self.per_channel);
+			let Some(weight) = rewriter.quantize_weight(initializer, self.weight_type, axis, false) else {
+				continue;
+			};
+			weight_scales = Some(weight.scales.clone());
+			let dequantized = match weights.get(&weight.name) {
+				Some(dequantized) => dequantized.clone(),
+				None => {
+					let dequantized = rewriter.fresh(format!("{}_dequantized", initializer.name));
+					let mut dequantize = Node::new("DequantizeLinear")
+						.with_name(rewriter.fresh(format!("{}_DequantizeLinear", initializer.name)))
+						.with_inputs([weight.name.as_str(), &weight.scale, &weight.zero_point])
+						.with_outputs([dequantized.as_str()]);
+					if let Some(axis) = axis {
+						dequantize = dequantize.with_attribute("axis", axis as i64);
+					}
+					nodes.push(dequantize);
+					weights.insert(weight.name, dequantized.clone());
+					dequantized
+				}
+			};
+			node.inputs[index] = dequantized;
+		}
+	}
+}
+
+/// Quantizes a 1D bias to `int32` with a scale of `input_scale * weight_scale` per channel, returning the name of the
+/// dequantized bias.
+fn quantize_bias(initializer: &Initializer, input_scale: f32, weight_scales: &[f32], nodes: &mut Vec<Node>, rewriter: &mut Rewriter) -> Option<String> {
+	let values = initializer.float_values()?;
+	if initializer.dims.len() != 1 || (weight_scales.len() != 1 && weight_scales.len() != values.len()) {
+		return None;
+	}
+	let scales: Vec<f32> = weight_scales.iter().map(|&scale| scale * input_scale).collect();
+	let data = values
+		.iter()
+		.enumerate()
+		.flat_map(|(i, &value)| ((value / scales[i % scales.len()]).round_ties_even() as i32).to_le_bytes())
+		.collect();
+
+	let name = &initializer.name;
+	let quantized = rewriter.fresh(format!("{name}_quantized"));
+	let scale = rewriter.fresh(format!("{name}_scale"));
+	let dequantized = rewriter.fresh(format!("{name}_dequantized"));
+	rewriter
+		.initializers
+		.push(Initializer::new(quantized.as_str(), TensorElementType::Int32, initializer.dims.clone(), TensorData::Raw(data)));
+	let per_channel = scales.len() > 1;
+	rewriter.initializers.push(Initializer::new(
+		scale.as_str(),
+		TensorElementType::Float32,
+		if per_channel { vec![scales.len() as i64] } else { Vec::new() },
+		TensorData::Float(scales)
+	));
+	rewriter.quantized.insert(name.clone());
+
+	let mut dequantize = Node::new("DequantizeLinear")
+		.with_name(rewriter.fresh(format!("{name}_DequantizeLinear")))
+		.with_inputs([quantized, scale])
+		.with_outputs([dequantized.as_str()]);
+	if per_channel {
+		dequantize = dequantize.with_attribute("axis", 0_i64);
+	}
+	nodes.push(dequantize);
+	Some(dequantized)
+}
+
+fn selects(op_types: &[String], excluded_nodes: &[String], node: &Node) -> bool {
+	node.domain.is_empty() && op_types.contains(&node.op_type) && !excluded_nodes.contains(&node.name)
+}
+
+fn require_opset(model: &Model, version: i64, what: &str) -> Result<()> {
+	match model.opset_version("") {
+		Some(opset) if opset >= version => Ok(()),
+		opset => Err(Error::new_with_code(
+			ErrorCode::InvalidArgument,
+			format!(
+				"{what} requires version {version} or newer of the default operator set, but the model imports {}",
+				opset.map_or_else(|| String::from("none"), |opset| format!("version {opset}"))
+			)
+		))
+	}
+}
+
+/// A quantized weight: the names of its quantized initializer, scale, and zero point, and the scale of each channel.
+#[derive(Debug, Clone)]
+struct QuantizedWeight {
+	name: String,
+	scale: String,
+	zero_point: String,
+	scales: Vec<f32>
+}
+
+/// Tracks the initializers & names added while quantizing a graph.
+struct Rewriter {
+	names: Names,
+	initializers: Vec<Initializer>,
+	/// Names of the original initializers which were quantized, which can be removed if no longer used.
+	quantized: BTreeSet<String>,
+	weights: BTreeMap<(String, Option<usize>, bool), QuantizedWeight>
+}
+
+impl Rewriter {
+	fn new(graph: &Graph) -> Self {
+		Self {
+			names: Names::new(graph),
+			initializers: Vec::new(),
+			quantized: BTreeSet::new(),
+			weights: BTreeMap::new()
+		}
+	}
+
+	fn fresh(&mut self, name: String) -> String {
+		self.names.fresh(name)
+	}
+
+	/// Adds a scalar `float32` initializer, returning its name.
+	fn scalar(&mut self, name: String, value: f32) -> String {
+		let name = self.fresh(name);
+		self.initializers
+			.push(Initializer::new(name.as_str(), TensorElementType::Float32, [], TensorData::Float(vec![value])));
+		name
+	}
+
+	/// Quantizes a `float32` initializer per-tensor, or per-channel along `axis`. 2D weights can be transposed first
+	/// (in which case `axis` refers to the transposed shape). Returns `None` if the initializer isn't `float32`.
+	fn quantize_weight(&mut self, initializer: &Initializer, ty: QuantizationType, axis: Option<usize>, transpose: bool) -> Option<QuantizedWeight> {
+		let key = (initializer.name.clone(), axis, transpose);
+		if let Some(weight) = self.weights.get(&key) {
+			return Some(weight.clone());
+		}
+
+		let mut values = initializer.float_values()?;
+		let mut dims = initializer.dims.clone();
+		if transpose {
+			let (rows, columns) = (dims[0] as usize, dims[1] as usize);
+			values = (0..rows * columns).map(|i| values[(i % rows) * columns + i / rows]).collect();
+			dims.swap(0, 1);
+		}
+		let (data, scales, zero_points) = quantize_values(ty, &values, &dims, axis);
+
+		let source = &initializer.name;
+		let name = self.fresh(if transpose { format!("{source}_transposed_quantized") } else { format!("{source}_quantized") });
+		let scale = self.fresh(format!("{source}_scale"));
+		let zero_point = self.fresh(format!("{source}_zero_point"));
+		let channel_dims = if axis.is_some() { vec![scales.len() as i64] } else { Vec::new() };
+		self.initializers
+			.push(Initializer::new(name.as_str(), ty.element_type(), dims, TensorData::Raw(data)));
+		self.initializers
+			.push(Initializer::new(scale.as_str(), TensorElementType::Float32, channel_dims.clone(), TensorData::Float(scales.clone())));
+		self.initializers.push(Initializer::new(
+			zero_point.as_str(),
+			ty.element_type(),
+			channel_dims,
+			TensorData::Raw(zero_points.into_iter().map(|zero_point| zero_point as u8).collect())
+		));
+		self.quantized.insert(source.clone());
+
+		let weight = QuantizedWeight { name, scale, zero_point, scales };
+		self.weights.insert(key, weight.clone());
+		Some(weight)
+	}
+
+	/// Adds the new initializers to `graph`, removing original initializers which are no longer used.
+	fn finish(self, graph: &mut Graph) {
+		let used: BTreeSet<&str> = graph
+			.all_nodes()
+			.flat_map(|node| &node.inputs)
+			.chain(graph.outputs.iter().map(|output| &output.name))
+			.map(String::as_str)
+			.collect();
+		let unused: BTreeSet<String> = self.quantized.into_iter().filter(|name| !used.contains(name.as_str())).collect();
+		graph.initializers.retain(|initializer| !unused.contains(&initializer.name));
+		graph.initializers.extend(self.initializers);
+	}
+}
+
+/// Quantizes `values` (of shape `dims`) per-tensor, or per-channel along `axis`. Returns the quantized bytes and the
+/// scale & zero point of each channel.
+fn quantize_values(ty: QuantizationType, values: &[f32], dims: &[i64], axis: Option<usize>) -> (Vec<u8>, Vec<f32>, Vec<i32>) {
+	let (channels, stride) = match axis {
+		Some(axis) => (dims[axis].max(1) as usize, dims[axis + 1..].iter().product::<i64>().max(1) as usize),
+		None => (1, 1)
+	};
+	let channel = |i: usize| (i / stride) % channels;
+	let mut ranges = vec![(0.0_f32, 0.0_f32); channels];
+	for (i, &value) in values.iter().enumerate().filter(|(_, value)| value.is_finite()) {
+		let range = &mut ranges[channel(i)];
+		*range = (range.0.min(value), range.1.max(value));
+	}
+	let (scales, zero_points): (Vec<f32>, Vec<i32>) = ranges.into_iter().map(|(min, max)| ty.params(min, max)).unzip();
+	let data = values
+		.iter()
+		.enumerate()
+		.map(|(i, &value)| ty.quantize(value, scales[channel(i)], zero_points[channel(i)]))
+		.collect();
+	(data, scales, zero_points)
+}
+
+/// Accumulates the values of one activation during calibration.
+struct Collector {
+	min: f32,
+	max: f32,
+	/// Histogram of absolute values, for percentile & entropy calibration.
+	histogram: Option<Histogram>
+}
+
+impl Collector {
+	fn new(method: CalibrationMethod) -> Self {
+		Self {
+			min: f32::INFINITY,
+			max: f32::NEG_INFINITY,
+			histogram: (method != CalibrationMethod::MinMax).then(|| Histogram {
+				bins: vec![0; HISTOGRAM_BINS],
+				limit: 0.0
+			})
+		}
+	}
+
+	fn collect(&mut self, values: &[f32]) {
+		for &value in values.iter().filter(|value| value.is_finite()) {
+			self.min = self.min.min(value);
+			self.max = self.max.max(value);
+		}
+		if let Some(histogram) = &mut self.histogram {
+			histogram.collect(values);
+		}
+	}
+
+	fn range(&self, method: CalibrationMethod) -> TensorRange {
+		if self.min > self.max {
+			return TensorRange::new(0.0, 0.0);
+		}
+		let threshold = match (method, &self.histogram) {
+			(CalibrationMethod::Percentile(percentile), Some(histogram)) => histogram.percentile(percentile),
+			(CalibrationMethod::Entropy, Some(histogram)) => histogram.entropy_threshold(),
+			_ => return TensorRange::new(self.min, self.max)
+		};
+		TensorRange::new(self.min.max(-threshold), self.max.min(threshold))
+	}
+}
+
+/// A histogram of absolute values over `0..=limit`, which grows as larger values are observed.
+struct Histogram {
+	bins: Vec<u64>,
+	limit: f32
+}
+
+impl Histogram {
+	fn width(&self) -> f32 {
+		self.limit / HISTOGRAM_BINS as f32
+	}
+
+	fn collect(&mut self, values: &[f32]) {
+		let values = values.iter().filter(|value| value.is_finite()).map(|value| value.abs());
+		let limit = values.clone().fold(0.0_f32, f32::max);
+		if limit > self.limit {
+			// redistribute the existing counts by the center of their bin
+			let mut bins = vec![0; HISTOGRAM_BINS];
+			for (i, &count) in self.bins.iter().enumerate().filter(|(_, count)| **count > 0) {
+				let center = (i as f32 + 0.5) * self.width();
+				bins[((center / limit * HISTOGRAM_BINS as f32) as usize).min(HISTOGRAM_BINS - 1)] += count;
+			}
+			self.bins = bins;
+			self.limit = limit;
+		}
+		for value in values {
+			let bin = if self.limit > 0.0 { (value / self.width()) as usize } else { 0 };
+			self.bins[bin.min(HISTOGRAM_BINS - 1)] += 1;
+		}
+	}
+
+	fn percentile(&self, percentile: f32) -> f32 {
+		let total: u64 = self.bins.iter().sum();
+		let target = (total as f64 * (f64::from(percentile) / 100.0).clamp(0.0, 1.0)).ceil() as u64;
+		let mut cumulative = 0;
+		for (i, &count) in self.bins.iter().enumerate() {
+			cumulative += count;
+			if cumulative >= target {
+				return (i + 1) as f32 * self.width();
+			}
+		}
+		self.limit
+	}
+
+	fn entropy_threshold(&self) -> f32 {
+		let mut best = (f64::INFINITY, self.limit);
+		for end in (ENTROPY_LEVELS..=HISTOGRAM_BINS).step_by(ENTROPY_LEVELS / 16) {
+			// the reference distribution clips outliers into the last bin
+			let mut reference: Vec<f64> = self.bins[..end].iter().map(|&count| count as f64).collect();
+			reference[end - 1] += self.bins[end..].iter().sum::<u64>() as f64;
+
+			// the candidate merges the bins into `ENTROPY_LEVELS` levels, then spreads each level's count evenly
+			// back over the bins that are non-empty in the reference (including the last bin, if outliers were
+			// clipped into it)
+			let nonempty: Vec<bool> = reference.iter().map(|&count| count != 0.0).collect();
+			let mut candidate = vec![0.0; end];
+			for level in 0..ENTROPY_LEVELS {
+				let (start, stop) = (level * end / ENTROPY_LEVELS, (level + 1) * end / ENTROPY_LEVELS);
+				let norm = nonempty[start..stop].iter().filter(|&&nonempty| nonempty).count();
+				if norm == 0 {
+					continue;
+				}
+				let count = self.bins[start..stop].iter().sum::<u64>() as f64 / norm as f64;
+				for i in (start..stop).filter(|&i| nonempty[i]) {
+					candidate[i] = count;
+				}
+			}
+
+			let divergence = if smooth_distribution(&mut reference) && smooth_distribution(&mut candidate) {
+				kl_divergence(&reference, &candidate)
+			} else {
+				f64::INFINITY
+			};
+			if divergence < best.0 {
+				best = (divergence, end as f32 * self.width());
+			}
+		}
+		best.1
+	}
+}
+
+/// Moves a small amount of mass from the non-empty bins of an (unnormalized) distribution onto its empty bins, so that
+/// the KL divergence against it stays finite. This is the same smoothing onnxruntime's calibration tool applies.
+/// Returns `false` if the distribution is empty or too sparse to smooth.
+fn smooth_distribution(dist: &mut [f64]) -> bool {
+	const EPSILON: f64 = 1e-4;
+	let empty = dist.iter().filter(|&&count| count == 0.0).count();
+	if empty == dist.len() {
+		return false;
+	}
+	let taken = EPSILON * empty as f64 / (dist.len() - empty) as f64;
+	for count in dist.iter_mut() {
+		if *count == 0.0 {
+			*count = EPSILON;
+		} else if *count > taken {
+			*count -= taken;
+		} else {
+			return false;
+		}
+	}
+	true
+}
+
+/// Computes the KL divergence between two (unnormalized) distributions.
+fn kl_divergence(p: &[f64], q: &[f64]) -> f64 {
+	let (p_total, q_total) = (p.iter().sum::<f64>(), q.iter().sum::<f64>());
+	if p_total == 0.0 {
+		return 0.0;
+	} else if q_total == 0.0 {
+		return f64::INFINITY;
+	}
+	p.iter()
+		.zip(q)
+		.filter(|(p, _)| **p > 0.0)
+		.map(|(p, q)| {
+			let (p, q) = (p / p_total, (q / q_total).max(1e-12));
+			p * (p / q).ln()
+		})
+		.sum()
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec, vec::Vec};
+
+	use super::{CalibrationMethod, CalibrationTable, Collector, DynamicQuantizer, HISTOGRAM_BINS, Histogram, QuantizationType, StaticQuantizer, TensorRange};
+	use crate::{
+		model::{GraphBuilder, Initializer, Model, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	/// `x` -> MatMul(w) -> Gemm(w2, b, transB) -> `y`
+	fn model(opset: i64) -> Model {
+		GraphBuilder::new("mlp")
+			.with_opset("", opset)
+			.with_input("x", TensorElementType::Float32, [1_i64, 4])
+			.with_output("y", TensorElementType::Float32, [1_i64, 2])
+			.with_raw_initializer(Initializer::new(
+				"w",
+				TensorElementType::Float32,
+				[4, 3],
+				TensorData::Float(vec![1.0, -2.0, 0.5, 0.0, 1.0, 0.0, -1.0, 0.25, 2.0, 0.5, 0.5, -0.5])
+			))
+			.with_raw_initializer(Initializer::new("w2", TensorElementType::Float32, [2, 3], TensorData::Float(vec![1.0, 0.0, -1.0, 0.5, 0.5, 0.5])))
+			.with_raw_initializer(Initializer::new("b", TensorElementType::Float32, [2], TensorData::Float(vec![0.1, -0.1])))
+			.with_node(Node::new("MatMul").with_name("fc1").with_inputs(["x", "w"]).with_outputs(["hidden"]))
+			.with_node(
+				Node::new("Gemm")
+					.with_name("fc2")
+					.with_attribute("transB", 1_i64)
+					.with_inputs(["hidden", "w2", "b"])
+					.with_outputs(["y"])
+			)
+			.build()
+			.expect("valid model")
+	}
+
+	fn op_types(model: &Model) -> Vec<&str> {
+		model.graph.nodes.iter().map(|node| node.op_type.as_str()).collect()
+	}
+
+	fn raw<'m>(model: &'m Model, name: &str) -> &'m [u8] {
+		match &model.graph.initializer(name).expect("initializer exists").data {
+			TensorData::Raw(bytes) => bytes,
+			data => panic!("expected raw data, got {data:?}")
+		}
+	}
+
+	#[test]
+	fn test_dynamic() -> crate::Result<()> {
+		let err = DynamicQuantizer::new().quantize(&model(10)).expect_err("opset too old");
+		assert!(err.to_string().contains("requires version 11 or newer"), "{err}");
+
+		let quantized = DynamicQuantizer::new().quantize(&model(13))?;
+		assert_eq!(
+			op_types(&quantized),
+			[
+				"DynamicQuantizeLinear",
+				"MatMulInteger",
+				"Cast",
+				"Mul",
+				"Mul",
+				"DynamicQuantizeLinear",
+				"MatMulInteger",
+				"Cast",
+				"Mul",
+				"Mul",
+				"Add"
+			]
+		);
+		assert_eq!(quantized.graph.nodes[10].outputs, ["y"]);
+		assert!(quantized.graph.initializer("w").is_none());
+		assert!(quantized.graph.initializer("b").is_some());
+
+		// per-tensor symmetric: scale = 2 / 127
+		assert_eq!(raw(&quantized, "w_quantized")[..3], [64, (-127_i8) as u8, 32]);
+		assert_eq!(raw(&quantized, "w_zero_point"), [0]);
+		// `w2` is transposed to [3, 2]
+		let w2 = quantized.graph.initializer("w2_transposed_quantized").expect("w2 is quantized");
+		assert_eq!(w2.dims, [3, 2]);
+		assert_eq!(raw(&quantized, "w2_transposed_quantized"), [127, 64, 0, 64, (-127_i8) as u8, 64]);
+
+		let per_channel = DynamicQuantizer::new()
+			.with_per_channel(true)
+			.with_weight_type(QuantizationType::Uint8)
+			.with_excluded_nodes(["fc2"])
+			.quantize(&model(13))?;
+		assert_eq!(op_types(&per_channel), ["DynamicQuantizeLinear", "MatMulInteger", "Cast", "Mul", "Mul", "Gemm"]);
+		let scale = per_channel.graph.initializer("w_scale").expect("w is quantized");
+		assert_eq!(scale.dims, [3]);
+		assert_eq!(raw(&per_channel, "w_zero_point"), [127, 170, 51]);
+		Ok(())
+	}
+
+	#[test]
+	fn test_static() -> crate::Result<()> {
+		let mut calibration = CalibrationTable::new();
+		calibration.insert("x", TensorRange::new(-1.0, 3.0));
+		calibration.insert("hidden", TensorRange::new(0.0, 4.0));
+		calibration.insert("y", TensorRange::new(-2.0, 2.0));
+
+		let err = StaticQuantizer::new()
+			.with_per_channel(true)
+			.quantize(&model(11), &calibration)
+			.expect_err("opset too old");
+		assert!(err.to_string().contains("requires version 13 or newer"), "{err}");
+
+		let quantized = StaticQuantizer::new().with_per_channel(true).quantize(&model(13), &calibration)?;
+		assert_eq!(
+			op_types(&quantized),
+			[
+				"QuantizeLinear",
+				"DequantizeLinear",
+				"DequantizeLinear",
+				"MatMul",
+				"QuantizeLinear",
+				"DequantizeLinear",
+				"DequantizeLinear",
+				"DequantizeLinear",
+				"Gemm",
+				"QuantizeLinear",
+				"DequantizeLinear"
+			]
+		);
+		let matmul = &quantized.graph.nodes[3];
+		assert_eq!(matmul.inputs, ["x_dequantized", "w_dequantized"]);
+		let gemm = &quantized.graph.nodes[8];
+		assert_eq!(gemm.inputs, ["hidden_dequantized", "w2_dequantized", "b_dequantized"]);
+		// graph outputs stay in full precision
+		assert_eq!(gemm.outputs, ["y"]);
+		assert_eq!(quantized.graph.outputs[0].name, "y");
+
+		assert_eq!(raw(&quantized, "x_zero_point"), [64]);
+		assert_eq!(raw(&quantized, "hidden_zero_point"), [0]);
+		// `w2` is per-channel along axis 0 since `transB` is set
+		assert_eq!(quantized.graph.nodes[6].attribute("axis").and_then(|a| a.as_int()), Some(0));
+		assert_eq!(quantized.graph.initializer("w2_scale").map(|scale| scale.dims.clone()), Some(vec![2]));
+		// bias scale = hidden scale (4 / 255) * w2 scale (1 / 127 & 0.5 / 127)
+		let bias = raw(&quantized, "b_quantized");
+		let bias: Vec<i32> = bias.chunks_exact(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+		assert_eq!(bias, [810, -1619]);
+		assert!(quantized.graph.initializer("w").is_none());
+
+		let partial = StaticQuantizer::new()
+			.with_op_types(["MatMul"])
+			.quantize(&model(13), &CalibrationTable::new())?;
+		assert_eq!(op_types(&partial), ["DequantizeLinear", "MatMul", "Gemm"]);
+		Ok(())
+	}
+
+	#[test]
+	fn test_calibration() {
+		// exponentially distributed values with a mean of 0.1, plus two outliers
+		let values: Vec<f32> = (0..10_000)
+			.map(|i| -(1.0 - (i as f32 + 0.5) / 10_000.0).ln() * 0.1)
+			.chain([-0.5, 100.0])
+			.collect();
+		let range = |method| {
+			let mut collector = Collector::new(method);
+			collector.collect(&values[..5_000]);
+			collector.collect(&values[5_000..]);
+			collector.range(method)
+		};
+
+		assert_eq!(range(CalibrationMethod::MinMax), TensorRange::new(-0.5, 100.0));
+		let percentile = range(CalibrationMethod::Percentile(99.0));
+		assert_eq!(percentile.min, -percentile.max);
+		assert!((0.4..=0.55).contains(&percentile.max), "{percentile:?}");
+		let entropy = range(CalibrationMethod::Entropy);
+		assert!((1.0..10.0).contains(&entropy.max), "{entropy:?}");
+		assert_eq!(entropy.min, -0.5);
+	}
+
+	#[test]
+	fn test_entropy_clips_outliers() {
+		// values only fall into every other bin up to 512, plus ~0.7% outliers at the limit
+		let mut bins = vec![0; HISTOGRAM_BINS];
+		for i in (0..512).step_by(2) {
+			bins[i] = 100 + (i as u64 * 37) % 900;
+		}
+		bins[HISTOGRAM_BINS - 1] = 1_000;
+		let histogram = Histogram { bins, limit: HISTOGRAM_BINS as f32 };
+		// the last bin of every candidate threshold below the limit is empty, but still holds the clipped outliers
+		assert_eq!(histogram.entropy_threshold(), 512.0);
+	}
+}
@@ -0,0 +1,463 @@
+use alloc::{
+	collections::BTreeMap,
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use core::fmt::Write;
+
+use super::{AttributeValue, Graph, Model, Node};
+use crate::session::profiling::ProfileReport;
+
+/// Options for rendering a [`Model`] with [`Model::to_dot`] or [`Model::to_mermaid`].
+///
+/// ```no_run
+/// # use ort::{model::{Model, RenderOptions}, session::{Session, profiling::Profile}};
+/// # fn main() -> ort::Result<()> {
+/// let mut session = Session::builder()?.with_profiling("profile")?.commit_from_file("model.onnx")?;
+/// // ... run the session a few times ...
+/// let report = Profile::from_file(session.end_profiling()?)?.report();
+///
+/// let model = Model::from_file("model.onnx")?;
+/// std::fs::write("model.dot", model.to_dot(&RenderOptions::new().with_profile(&report))).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+	subgraphs: bool,
+	annotations: BTreeMap<String, Vec<String>>,
+	highlights: BTreeMap<String, String>
+}
+
+impl Default for RenderOptions {
+	fn default() -> Self {
+		Self {
+			subgraphs: true,
+			annotations: BTreeMap::new(),
+			highlights: BTreeMap::new()
+		}
+	}
+}
+
+impl RenderOptions {
+	/// Creates the default options, which draw subgraphs & don't annotate any nodes.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Controls whether the subgraphs of control flow nodes like `If`, `Loop`, and `Scan` are drawn as clusters.
+	/// Enabled by default.
+	pub fn with_subgraphs(mut self, enable: bool) -> Self {
+		self.subgraphs = enable;
+		self
+	}
+
+	/// Adds a line of text to the label of the node named `node`.
+	pub fn with_annotation(mut self, node: impl Into<String>, text: impl Into<String>) -> Self {
+		self.annotations.entry(node.into()).or_default().push(text.into());
+		self
+	}
+
+	/// Fills the node named `node` with `color`, which can be any color understood by both Graphviz & CSS, like
+	/// `"#ff7f50"` or `"orange"`.
+	pub fn with_highlight(mut self, node: impl Into<String>, color: impl Into<String>) -> Self {
+		self.highlights.insert(node.into(), color.into());
+		self
+	}
+
+	/// Annotates each node with its share of run time from a [`ProfileReport`] & highlights hot spots. Nodes taking at
+	/// least 10% of the total run time are filled red, and nodes taking at least 2% are filled orange.
+	pub fn with_profile(mut self, report: &ProfileReport) -> Self {
+		for node in &report.nodes {
+			self = self.with_annotation(&node.name, format!("{:.1}% ({:.2?})", node.share * 100.0, node.total));
+			if node.share >= 0.1 {
+				self = self.with_highlight(&node.name, "#f4a6a6");
+			} else if node.share >= 0.02 {
+				self = self.with_highlight(&node.name, "#fcd59c");
+			}
+		}
+		self
+	}
+}
+
+impl Model {
+	/// Renders the model's graph in the [Graphviz](https://graphviz.org/) DOT language, e.g. to be converted to an
+	/// image with `dot -Tsvg`.
+	///
+	/// Nodes are labelled with their operator type & name, and edges with the type & shape of the value they carry if
+	/// it is known. Initializers are listed in the label of the nodes using them rather than drawn separately.
+	pub fn to_dot(&self, options: &RenderOptions) -> String {
+		let scene = Scene::new(&self.graph, options);
+		let mut out = String::new();
+		let _ = writeln!(out, "digraph \"{}\" {{", escape_dot(&self.graph.name));
+		out.push_str("\tnode [fontname=\"Helvetica\", fontsize=10];\n\tedge [fontname=\"Helvetica\", fontsize=9];\n");
+		write_dot_items(&mut out, &scene.items, 1);
+		for edge in &scene.edges {
+			let _ = write!(out, "\t{} -> {}", edge.from, edge.to);
+			match (&edge.label, edge.dashed) {
+				(Some(label), _) => {
+					let _ = writeln!(out, " [label=\"{}\"];", escape_dot(label));
+				}
+				(None, true) => out.push_str(" [style=dashed];\n"),
+				(None, false) => out.push_str(";\n")
+			}
+		}
+		out.push_str("}\n");
+		out
+	}
+
+	/// Renders the model's graph as a [Mermaid](https://mermaid.js.org/) flowchart, which can be embedded in Markdown
+	/// on GitHub & most other code review tools.
+	///
+	/// See [`Model::to_dot`] for how the graph is drawn.
+	pub fn to_mermaid(&self, options: &RenderOptions) -> String {
+		let scene = Scene::new(&self.graph, options);
+		let mut out = String::from("flowchart TB\n");
+		let mut styles = String::new();
+		write_mermaid_items(&mut out, &mut styles, &scene.items, 1);
+		for edge in &scene.edges {
+			let arrow = if edge.dashed { "-.->" } else { "-->" };
+			match &edge.label {
+				Some(label) => {
+					let _ = writeln!(out, "\t{} {arrow}|\"{}\"| {}", edge.from, escape_mermaid(label), edge.to);
+				}
+				None => {
+					let _ = writeln!(out, "\t{} {arrow} {}", edge.from, edge.to);
+				}
+			}
+		}
+		out.push_str(&styles);
+		out
+	}
+}
+
+/// A renderer-independent layout of a graph.
+#[derive(Default)]
+struct Scene {
+	items: Vec<Item>,
+	edges: Vec<Edge>,
+	next_id: usize
+}
+
+enum Item {
+	Vertex {
+		id: String,
+		lines: Vec<String>,
+		is_value: bool,
+		fill: Option<String>
+	},
+	Cluster {
+		id: String,
+		title: String,
+		items: Vec<Item>
+	}
+}
+
+struct Edge {
+	from: String,
+	to: String,
+	label: Option<String>,
+	dashed: bool
+}
+
+/// Where a value referenced by a node comes from.
+enum Source {
+	/// The output of a vertex (a node or a graph input), with the value's type if known.
+	Vertex(String, Option<String>),
+	/// An initializer, which is collapsed into the label of nodes using it.
+	Initializer(String)
+}
+
+impl Scene {
+	fn new(graph: &Graph, options: &RenderOptions) -> Self {
+		let mut scene = Scene::default();
+		let mut scopes = Vec::new();
+		let (items, _) = scene.add_graph(graph, &mut scopes, options);
+		scene.items = items;
+		scene
+	}
+
+	fn id(&mut self, prefix: char) -> String {
+		self.next_id += 1;
+		format!("{prefix}{}", self.next_id - 1)
+	}
+
+	/// Lays out `graph`, returning its items & the vertices of its outputs.
+	fn add_graph<'g>(&mut self, graph: &'g Graph, scopes: &mut Vec<BTreeMap<&'g str, Source>>, options: &RenderOptions) -> (Vec<Item>, Vec<String>) {
+		let mut types: BTreeMap<&str, String> = BTreeMap::new();
+		for info in graph.value_info.iter().chain(&graph.inputs).chain(&graph.outputs) {
+			if let Some(ty) = &info.ty {
+				types.insert(&info.name, ty.to_string());
+			}
+		}
+
+		let mut scope = BTreeMap::new();
+		for initializer in &graph.initializers {
+			let ty = match initializer.element_type() {
+				Some(ty) => format!("Tensor<{ty}>({})", initializer.dims.iter().map(i64::to_string).collect::<Vec<_>>().join(", ")),
+				None => String::from("Tensor<undefined>")
+			};
+			scope.insert(initializer.name.as_str(), Source::Initializer(ty));
+		}
+		scopes.push(scope);
+
+		let mut items = Vec::new();
+		for input in &graph.inputs {
+			if graph.initializer(&input.name).is_some() {
+				continue;
+			}
+			let id = self.id('n');
+			items.push(Item::Vertex {
+				id: id.clone(),
+				lines: alloc::vec![input.name.clone()],
+				is_value: true,
+				fill: None
+			});
+			let ty = types.get(input.name.as_str()).cloned();
+			scopes
+				.last_mut()
+				.expect("scope was just pushed")
+				.insert(&input.name, Source::Vertex(id, ty));
+		}
+
+		for node in &graph.nodes {
+			let id = self.id('n');
+			let mut lines = alloc::vec![node.op_type.clone()];
+			if !node.name.is_empty() && node.name != node.op_type {
+				lines.push(node.name.clone());
+			}
+			for input in node.inputs.iter().filter(|input| !input.is_empty()) {
+				match scopes.iter().rev().find_map(|scope| scope.get(input.as_str())) {
+					Some(Source::Vertex(from, ty)) => self.edges.push(Edge {
+						from: from.clone(),
+						to: id.clone(),
+						label: ty.clone(),
+						dashed: false
+					}),
+					Some(Source::Initializer(ty)) => lines.push(format!("{input}: {ty}")),
+					None => {}
+				}
+			}
+			if let Some(annotations) = options.annotations.get(&node.name) {
+				lines.extend(annotations.iter().cloned());
+			}
+			items.push(Item::Vertex {
+				id: id.clone(),
+				lines,
+				is_value: false,
+				fill: options.highlights.get(&node.name).cloned()
+			});
+
+			if options.subgraphs {
+				self.add_subgraphs(node, &id, &mut items, scopes, options);
+			}
+
+			for output in node.outputs.iter().filter(|output| !output.is_empty()) {
+				let ty = types.get(output.as_str()).cloned();
+				scopes
+					.last_mut()
+					.expect("scope was pushed")
+					.insert(output, Source::Vertex(id.clone(), ty));
+			}
+		}
+
+		let mut outputs = Vec::new();
+		for output in &graph.outputs {
+			let id = self.id('n');
+			items.push(Item::Vertex {
+				id: id.clone(),
+				lines: alloc::vec![output.name.clone()],
+				is_value: true,
+				fill: None
+			});
+			if let Some(Source::Vertex(from, ty)) = scopes.iter().rev().find_map(|scope| scope.get(output.name.as_str())) {
+				self.edges.push(Edge {
+					from: from.clone(),
+					to: id.clone(),
+					label: types.get(output.name.as_str()).or(ty.as_ref()).cloned(),
+					dashed: false
+				});
+			}
+			outputs.push(id);
+		}
+
+		scopes.pop();
+		(items, outputs)
+	}
+
+	/// Lays out the subgraphs of a control flow node as clusters, connected to the node by dashed edges from their
+	/// outputs.
+	fn add_subgraphs<'g>(
+		&mut self,
+		node: &'g Node,
+		node_id: &str,
+		items: &mut Vec<Item>,
+		scopes: &mut Vec<BTreeMap<&'g str, Source>>,
+		options: &RenderOptions
+	) {
+		let label = if node.name.is_empty() {
+			node.op_type.clone()
+		} else {
+			format!("{} {}", node.op_type, node.name)
+		};
+		for attribute in &node.attributes {
+			let graphs = attribute.value.graphs();
+			for (i, graph) in graphs.iter().enumerate() {
+				let id = self.id('c');
+				let title = match &attribute.value {
+					AttributeValue::Graphs(_) => format!("{label}: {}[{i}]", attribute.name),
+					_ => format!("{label}: {}", attribute.name)
+				};
+				let (cluster, outputs) = self.add_graph(graph, scopes, options);
+				items.push(Item::Cluster { id, title, items: cluster });
+				for output in outputs {
+					self.edges.push(Edge {
+						from: output,
+						to: node_id.to_string(),
+						label: None,
+						dashed: true
+					});
+				}
+			}
+		}
+	}
+}
+
+fn write_dot_items(out: &mut String, items: &[Item], depth: usize) {
+	let indent = "\t".repeat(depth);
+	for item in items {
+		match item {
+			Item::Vertex { id, lines, is_value, fill } => {
+				let label = lines.iter().map(|line| escape_dot(line)).collect::<Vec<_>>().join("\\n");
+				let shape = if *is_value { "shape=ellipse" } else { "shape=box" };
+				match fill {
+					Some(fill) => {
+						let _ = writeln!(out, "{indent}{id} [label=\"{label}\", {shape}, style=filled, fillcolor=\"{}\"];", escape_dot(fill));
+					}
+					None => {
+						let _ = writeln!(out, "{indent}{id} [label=\"{label}\", {shape}];");
+					}
+				}
+			}
+			Item::Cluster { id, title, items } => {
+				let _ = writeln!(out, "{indent}subgraph cluster_{id} {{");
+				let _ = writeln!(out, "{indent}\tlabel=\"{}\";\n{indent}\tstyle=dashed;", escape_dot(title));
+				write_dot_items(out, items, depth + 1);
+				let _ = writeln!(out, "{indent}}}");
+			}
+		}
+	}
+}
+
+fn write_mermaid_items(out: &mut String, styles: &mut String, items: &[Item], depth: usize) {
+	let indent = "\t".repeat(depth);
+	for item in items {
+		match item {
+			Item::Vertex { id, lines, is_value, fill } => {
+				let label = lines.iter().map(|line| escape_mermaid(line)).collect::<Vec<_>>().join("<br/>");
+				if *is_value {
+					let _ = writeln!(out, "{indent}{id}([\"{label}\"])");
+				} else {
+					let _ = writeln!(out, "{indent}{id}[\"{label}\"]");
+				}
+				if let Some(fill) = fill {
+					let _ = writeln!(styles, "\tstyle {id} fill:{fill}");
+				}
+			}
+			Item::Cluster { id, title, items } => {
+				let _ = writeln!(out, "{indent}subgraph {id} [\"{}\"]", escape_mermaid(title));
+				write_mermaid_items(out, styles, items, depth + 1);
+				let _ = writeln!(out, "{indent}end");
+			}
+		}
+	}
+}
+
+fn escape_dot(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escapes characters which would end a quoted Mermaid label or be interpreted as HTML.
+fn escape_mermaid(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'#' | '"' | '<' | '>' | '&' => {
+				let _ = write!(escaped, "#{};", c as u32);
+			}
+			'\n' => escaped.push_str("<br/>"),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec;
+
+	use super::RenderOptions;
+	use crate::{
+		model::{Dimension, GraphBuilder, Initializer, Node, TensorData},
+		session::profiling::Profile,
+		tensor::TensorElementType
+	};
+
+	fn model() -> crate::Result<crate::model::Model> {
+		let branch = |op: &str, output: &str| {
+			GraphBuilder::new("branch")
+				.with_output(output, TensorElementType::Float32, [Dimension::from("n")])
+				.with_node(Node::new(op).with_inputs(["h"]).with_outputs([output]))
+				.into_graph()
+		};
+		GraphBuilder::new("demo \"graph\"")
+			.with_input("x", TensorElementType::Float32, [Dimension::from("n")])
+			.with_input("cond", TensorElementType::Bool, vec::Vec::<i64>::new())
+			.with_output("y", TensorElementType::Float32, [Dimension::from("n")])
+			.with_raw_initializer(Initializer::new("b", TensorElementType::Float32, [1], TensorData::Float(vec![1.0])))
+			.with_node(Node::new("Add").with_name("add").with_inputs(["x", "b"]).with_outputs(["h"]))
+			.with_node(
+				Node::new("If")
+					.with_name("branch")
+					.with_inputs(["cond"])
+					.with_outputs(["y"])
+					.with_attribute("then_branch", branch("Relu", "a"))
+					.with_attribute("else_branch", branch("Neg", "c"))
+			)
+			.build()
+	}
+
+	#[test]
+	fn test_dot() -> crate::Result<()> {
+		let dot = model()?.to_dot(&RenderOptions::new().with_highlight("add", "orange"));
+		assert!(dot.starts_with("digraph \"demo \\\"graph\\\"\" {\n"), "{dot}");
+		// initializers are collapsed into their consumers
+		assert!(dot.contains("n2 [label=\"Add\\nadd\\nb: Tensor<f32>(1)\", shape=box, style=filled, fillcolor=\"orange\"];"), "{dot}");
+		assert!(dot.contains("\tn0 -> n2 [label=\"Tensor<f32>(n)\"];"), "{dot}");
+		assert!(dot.contains("\tsubgraph cluster_c4 {\n\t\tlabel=\"If branch: then_branch\";"), "{dot}");
+		// values from the outer scope are connected to the nodes using them in subgraphs
+		assert!(dot.contains("\tn2 -> n5;"), "{dot}");
+		assert!(dot.contains("\tn6 -> n3 [style=dashed];"), "{dot}");
+		assert!(!model()?.to_dot(&RenderOptions::new().with_subgraphs(false)).contains("cluster"));
+		Ok(())
+	}
+
+	#[test]
+	fn test_mermaid() -> crate::Result<()> {
+		let profile = Profile::parse(
+			r#"[
+{"cat" : "Session","pid" :1,"tid" :1,"dur" :100,"ts" :0,"ph" : "X","name" :"model_run","args" : {}},
+{"cat" : "Node","pid" :1,"tid" :1,"dur" :50,"ts" :1,"ph" : "X","name" :"add_kernel_time","args" : {"op_name" : "Add","provider" : "CPUExecutionProvider"}}
+]"#
+		)?;
+		let mermaid = model()?.to_mermaid(&RenderOptions::new().with_profile(&profile.report()));
+		assert!(mermaid.starts_with("flowchart TB\n\tn0([\"x\"])\n"), "{mermaid}");
+		assert!(mermaid.contains("\tn2[\"Add<br/>add<br/>b: Tensor#60;f32#62;(1)<br/>50.0% (50.00µs)\"]"), "{mermaid}");
+		assert!(mermaid.contains("\tsubgraph c7 [\"If branch: else_branch\"]\n\t\tn8[\"Neg\"]\n\t\tn9([\"c\"])\n\tend"), "{mermaid}");
+		assert!(mermaid.contains("\tn0 -->|\"Tensor#60;f32#62;(n)\"| n2"), "{mermaid}");
+		assert!(mermaid.contains("\tn9 -.-> n3"), "{mermaid}");
+		assert!(mermaid.ends_with("\tstyle n2 fill:#f4a6a6\n"), "{mermaid}");
+		Ok(())
+	}
+}
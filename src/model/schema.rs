@@ -0,0 +1,241 @@
+//! Attribute schemas for operators of the default ONNX domain, used by the [`ModelChecker`](super::ModelChecker).
+
+/// `(op_type, since_version, attributes)`. Each entry applies from its operator set version until the next entry for
+/// the same operator. Attributes are given as space-separated `name:type` pairs, where a `!` after the name marks the
+/// attribute as required, and the type is one of `f`, `i`, `s`, `t`, `g`, `st` (sparse tensor), `fs`, `is`, `ss`,
+/// `ts`, or `gs`.
+///
+/// Operators are only checked at versions covered by this table; older versions (which often had legacy attributes
+/// like `broadcast` and `consumed_inputs`) and operators missing from the table are not checked.
+#[rustfmt::skip]
+const SCHEMAS: &[(&str, i64, &str)] = &[
+	("Abs", 6, ""), ("Ceil", 6, ""), ("Exp", 6, ""), ("Floor", 6, ""), ("Log", 6, ""), ("Neg", 6, ""),
+	("Reciprocal", 6, ""), ("Relu", 6, ""), ("Sigmoid", 6, ""), ("Sqrt", 6, ""), ("Tanh", 6, ""),
+	("Sin", 7, ""), ("Cos", 7, ""), ("Tan", 7, ""), ("Asin", 7, ""), ("Acos", 7, ""), ("Atan", 7, ""),
+	("Sinh", 9, ""), ("Cosh", 9, ""), ("Asinh", 9, ""), ("Acosh", 9, ""), ("Atanh", 9, ""), ("Erf", 9, ""),
+	("Sign", 9, ""), ("IsNaN", 9, ""), ("Not", 1, ""), ("Round", 11, ""), ("Softplus", 1, ""), ("Softsign", 1, ""),
+	("Identity", 1, ""), ("Size", 1, ""), ("Det", 11, ""), ("Mish", 18, ""), ("HardSwish", 14, ""), ("PRelu", 7, ""),
+	("IsInf", 10, "detect_negative:i detect_positive:i"),
+	("Add", 7, ""), ("Sub", 7, ""), ("Mul", 7, ""), ("Div", 7, ""), ("Pow", 7, ""),
+	("And", 7, ""), ("Or", 7, ""), ("Xor", 7, ""), ("Equal", 7, ""), ("Greater", 7, ""), ("Less", 7, ""),
+	("GreaterOrEqual", 12, ""), ("LessOrEqual", 12, ""),
+	("BitwiseAnd", 18, ""), ("BitwiseOr", 18, ""), ("BitwiseXor", 18, ""), ("BitwiseNot", 18, ""),
+	("Mod", 10, "fmod:i"), ("BitShift", 11, "direction!:s"),
+	("Sum", 6, ""), ("Max", 6, ""), ("Min", 6, ""), ("Mean", 6, ""),
+	("Elu", 6, "alpha:f"), ("LeakyRelu", 6, "alpha:f"), ("Selu", 6, "alpha:f gamma:f"),
+	("HardSigmoid", 6, "alpha:f beta:f"), ("ThresholdedRelu", 10, "alpha:f"), ("Celu", 12, "alpha:f"),
+	("Shrink", 9, "bias:f lambd:f"), ("Gelu", 20, "approximate:s"),
+	("Softmax", 1, "axis:i"), ("LogSoftmax", 1, "axis:i"), ("Hardmax", 1, "axis:i"),
+	("Clip", 6, "max:f min:f"), ("Clip", 11, ""),
+	("MatMul", 1, ""), ("MatMulInteger", 10, ""), ("QLinearMatMul", 10, ""),
+	("Gemm", 7, "alpha:f beta:f transA:i transB:i"),
+	("Einsum", 12, "equation!:s"),
+	("Conv", 1, "auto_pad:s dilations:is group:i kernel_shape:is pads:is strides:is"),
+	("ConvInteger", 10, "auto_pad:s dilations:is group:i kernel_shape:is pads:is strides:is"),
+	("QLinearConv", 10, "auto_pad:s dilations:is group:i kernel_shape:is pads:is strides:is"),
+	("ConvTranspose", 1, "auto_pad:s dilations:is group:i kernel_shape:is output_padding:is output_shape:is pads:is strides:is"),
+	("MaxPool", 8, "auto_pad:s kernel_shape!:is pads:is storage_order:i strides:is"),
+	("MaxPool", 10, "auto_pad:s ceil_mode:i dilations:is kernel_shape!:is pads:is storage_order:i strides:is"),
+	("AveragePool", 7, "auto_pad:s count_include_pad:i kernel_shape!:is pads:is strides:is"),
+	("AveragePool", 10, "auto_pad:s ceil_mode:i count_include_pad:i kernel_shape!:is pads:is strides:is"),
+	("AveragePool", 19, "auto_pad:s ceil_mode:i count_include_pad:i dilations:is kernel_shape!:is pads:is strides:is"),
+	("LpPool", 2, "auto_pad:s kernel_shape!:is p:i pads:is strides:is"),
+	("LpPool", 18, "auto_pad:s ceil_mode:i dilations:is kernel_shape!:is p:i pads:is strides:is"),
+	("MaxUnpool", 9, "kernel_shape!:is pads:is strides:is"),
+	("MaxRoiPool", 1, "pooled_shape!:is spatial_scale:f"),
+	("GlobalAveragePool", 1, ""), ("GlobalMaxPool", 1, ""), ("GlobalLpPool", 2, "p:i"),
+	("BatchNormalization", 9, "epsilon:f momentum:f"),
+	("BatchNormalization", 14, "epsilon:f momentum:f training_mode:i"),
+	("InstanceNormalization", 6, "epsilon:f"),
+	("LayerNormalization", 17, "axis:i epsilon:f stash_type:i"),
+	("GroupNormalization", 18, "epsilon:f num_groups!:i"),
+	("GroupNormalization", 21, "epsilon:f num_groups!:i stash_type:i"),
+	("LRN", 1, "alpha:f beta:f bias:f size!:i"),
+	("LpNormalization", 1, "axis:i p:i"),
+	("MeanVarianceNormalization", 9, "axes:is"),
+	("Dropout", 7, "ratio:f"), ("Dropout", 12, "seed:i"),
+	("Flatten", 1, "axis:i"),
+	("Reshape", 5, ""), ("Reshape", 14, "allowzero:i"),
+	("Transpose", 1, "perm:is"),
+	("Squeeze", 1, "axes:is"), ("Squeeze", 13, ""),
+	("Unsqueeze", 1, "axes!:is"), ("Unsqueeze", 13, ""),
+	("Concat", 4, "axis!:i"),
+	("Split", 2, "axis:i split:is"), ("Split", 13, "axis:i"), ("Split", 18, "axis:i num_outputs:i"),
+	("Slice", 1, "axes:is ends!:is starts!:is"), ("Slice", 10, ""),
+	("Gather", 1, "axis:i"), ("GatherElements", 11, "axis:i"),
+	("GatherND", 11, ""), ("GatherND", 12, "batch_dims:i"),
+	("ScatterElements", 11, "axis:i"), ("ScatterElements", 16, "axis:i reduction:s"),
+	("ScatterND", 11, ""), ("ScatterND", 16, "reduction:s"),
+	("Cast", 6, "to!:i"), ("Cast", 19, "saturate:i to!:i"),
+	("CastLike", 15, ""), ("CastLike", 19, "saturate:i"),
+	("Pad", 2, "mode:s pads!:is value:f"), ("Pad", 11, "mode:s"),
+	("Resize", 10, "mode:s"),
+	("Resize", 11, "coordinate_transformation_mode:s cubic_coeff_a:f exclude_outside:i extrapolation_value:f mode:s nearest_mode:s"),
+	("Resize", 18, "antialias:i axes:is coordinate_transformation_mode:s cubic_coeff_a:f exclude_outside:i extrapolation_value:f keep_aspect_ratio_policy:s mode:s nearest_mode:s"),
+	("Upsample", 7, "mode:s scales!:fs"), ("Upsample", 9, "mode:s"),
+	("ReduceSum", 1, "axes:is keepdims:i"), ("ReduceSum", 13, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceMean", 1, "axes:is keepdims:i"), ("ReduceMean", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceMax", 1, "axes:is keepdims:i"), ("ReduceMax", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceMin", 1, "axes:is keepdims:i"), ("ReduceMin", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceProd", 1, "axes:is keepdims:i"), ("ReduceProd", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceL1", 1, "axes:is keepdims:i"), ("ReduceL1", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceL2", 1, "axes:is keepdims:i"), ("ReduceL2", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceLogSum", 1, "axes:is keepdims:i"), ("ReduceLogSum", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceLogSumExp", 1, "axes:is keepdims:i"), ("ReduceLogSumExp", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ReduceSumSquare", 1, "axes:is keepdims:i"), ("ReduceSumSquare", 18, "keepdims:i noop_with_empty_axes:i"),
+	("ArgMax", 1, "axis:i keepdims:i"), ("ArgMax", 12, "axis:i keepdims:i select_last_index:i"),
+	("ArgMin", 1, "axis:i keepdims:i"), ("ArgMin", 12, "axis:i keepdims:i select_last_index:i"),
+	("TopK", 1, "axis:i k!:i"), ("TopK", 10, "axis:i"), ("TopK", 11, "axis:i largest:i sorted:i"),
+	("CumSum", 11, "exclusive:i reverse:i"),
+	("Constant", 1, "value!:t"), ("Constant", 11, "sparse_value:st value:t"),
+	("Constant", 12, "sparse_value:st value:t value_float:f value_floats:fs value_int:i value_ints:is value_string:s value_strings:ss"),
+	("ConstantOfShape", 9, "value:t"),
+	("Shape", 1, ""), ("Shape", 15, "end:i start:i"),
+	("Range", 11, ""), ("Expand", 8, ""), ("Tile", 6, ""), ("Where", 9, ""), ("NonZero", 9, ""),
+	("OneHot", 9, "axis:i"),
+	("DepthToSpace", 1, "blocksize!:i"), ("DepthToSpace", 11, "blocksize!:i mode:s"),
+	("SpaceToDepth", 1, "blocksize!:i"),
+	("EyeLike", 9, "dtype:i k:i"),
+	("RandomNormal", 1, "dtype:i mean:f scale:f seed:f shape!:is"),
+	("RandomUniform", 1, "dtype:i high:f low:f seed:f shape!:is"),
+	("RandomNormalLike", 1, "dtype:i mean:f scale:f seed:f"),
+	("RandomUniformLike", 1, "dtype:i high:f low:f seed:f"),
+	("Multinomial", 7, "dtype:i sample_size:i seed:f"),
+	("Bernoulli", 15, "dtype:i seed:f"),
+	("RNN", 7, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i"),
+	("RNN", 14, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i layout:i"),
+	("GRU", 7, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i linear_before_reset:i"),
+	("GRU", 14, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i layout:i linear_before_reset:i"),
+	("LSTM", 7, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i input_forget:i"),
+	("LSTM", 14, "activation_alpha:fs activation_beta:fs activations:ss clip:f direction:s hidden_size:i input_forget:i layout:i"),
+	("If", 1, "else_branch!:g then_branch!:g"),
+	("Loop", 1, "body!:g"),
+	("Scan", 9, "body!:g num_scan_inputs!:i scan_input_axes:is scan_input_directions:is scan_output_axes:is scan_output_directions:is"),
+	("QuantizeLinear", 10, ""), ("QuantizeLinear", 13, "axis:i"), ("QuantizeLinear", 19, "axis:i saturate:i"),
+	("QuantizeLinear", 21, "axis:i block_size:i output_dtype:i saturate:i"),
+	("DequantizeLinear", 10, ""), ("DequantizeLinear", 13, "axis:i"), ("DequantizeLinear", 21, "axis:i block_size:i"),
+	("DynamicQuantizeLinear", 11, ""),
+	("NonMaxSuppression", 10, "center_point_box:i"),
+	("RoiAlign", 10, "mode:s output_height:i output_width:i sampling_ratio:i spatial_scale:f"),
+	("RoiAlign", 16, "coordinate_transformation_mode:s mode:s output_height:i output_width:i sampling_ratio:i spatial_scale:f"),
+	("GridSample", 16, "align_corners:i mode:s padding_mode:s"),
+	("Trilu", 14, "upper:i"),
+	("Compress", 9, "axis:i"),
+	("ReverseSequence", 10, "batch_axis:i time_axis:i"),
+	("Unique", 11, "axis:i sorted:i"),
+	("ConcatFromSequence", 11, "axis!:i new_axis:i"),
+	("SplitToSequence", 11, "axis:i keepdims:i"),
+	("Col2Im", 18, "dilations:is pads:is strides:is"),
+	("DFT", 17, "axis:i inverse:i onesided:i"), ("DFT", 20, "inverse:i onesided:i"),
+	("STFT", 17, "onesided:i")
+];
+
+/// Returns the earliest version of `op_type` in the schema table. This is the version the operator was introduced in
+/// for operators added in opset 10 or later; older operators may only be listed from a later version.
+pub(crate) fn since_version(op_type: &str) -> Option<i64> {
+	SCHEMAS.iter().filter(|(op, ..)| *op == op_type).map(|(_, since, _)| *since).min()
+}
+
+/// The expected type of an attribute, as an `AttributeProto.AttributeType`, and whether it is required.
+pub(crate) struct AttributeSchema<'s> {
+	pub name: &'s str,
+	pub type_code: i32,
+	pub required: bool
+}
+
+/// Returns the attributes of `op_type` at version `opset` of the default domain, or `None` if the operator is not
+/// covered by the schema table at that version.
+pub(crate) fn attributes(op_type: &str, opset: i64) -> Option<impl Iterator<Item = AttributeSchema<'static>>> {
+	let (_, _, attributes) = SCHEMAS
+		.iter()
+		.filter(|(op, since, _)| *op == op_type && *since <= opset)
+		.max_by_key(|(_, since, _)| *since)?;
+	Some(attributes.split_ascii_whitespace().map(|attribute| {
+		let (name, ty) = attribute.split_once(':').expect("schema attributes are `name:type`");
+		let (name, required) = match name.strip_suffix('!') {
+			Some(name) => (name, true),
+			None => (name, false)
+		};
+		AttributeSchema {
+			name,
+			type_code: type_code(ty),
+			required
+		}
+	}))
+}
+
+fn type_code(ty: &str) -> i32 {
+	match ty {
+		"f" => 1,
+		"i" => 2,
+		"s" => 3,
+		"t" => 4,
+		"g" => 5,
+		"fs" => 6,
+		"is" => 7,
+		"ss" => 8,
+		"ts" => 9,
+		"gs" => 10,
+		"st" => 11,
+		_ => unreachable!("unknown schema attribute type `{ty}`")
+	}
+}
+
+/// Returns a human-readable name for an `AttributeProto.AttributeType`.
+pub(crate) fn type_name(code: i32) -> &'static str {
+	match code {
+		1 => "float",
+		2 => "int",
+		3 => "string",
+		4 => "tensor",
+		5 => "graph",
+		6 => "floats",
+		7 => "ints",
+		8 => "strings",
+		9 => "tensors",
+		10 => "graphs",
+		11 => "sparse tensor",
+		12 => "sparse tensors",
+		13 => "type proto",
+		14 => "type protos",
+		_ => "unknown"
+	}
+}
+
+/// Returns the highest IR version & default domain operator set version supported by ONNX Runtime `1.{minor}`.
+pub(crate) fn runtime_limits(minor: u32) -> (i64, i64) {
+	match minor {
+		..=10 => (8, 15),
+		11 => (8, 16),
+		12..=13 => (8, 17),
+		14 => (8, 18),
+		15..=16 => (9, 19),
+		17 => (9, 20),
+		18..=19 => (10, 21),
+		_ => (10, 22)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use super::{SCHEMAS, attributes, type_code};
+
+	#[test]
+	fn test_schemas() {
+		// parse every entry to catch typos in the table
+		for (op, since, _) in SCHEMAS {
+			assert!(attributes(op, *since).is_some_and(|attributes| attributes.map(|a| a.type_code).all(|code| code > 0)));
+		}
+
+		let names = |op, opset| attributes(op, opset).map(|attributes| attributes.map(|a| (a.name, a.required)).collect::<Vec<_>>());
+		assert_eq!(names("Squeeze", 11), Some(vec![("axes", false)]));
+		assert_eq!(names("Squeeze", 13), Some(vec![]));
+		assert_eq!(names("TopK", 10), Some(vec![("axis", false)]));
+		assert_eq!(names("Concat", 17), Some(vec![("axis", true)]));
+		assert_eq!(names("Add", 6), None);
+		assert_eq!(names("NotAnOp", 17), None);
+		assert_eq!(type_code("gs"), 10);
+	}
+}
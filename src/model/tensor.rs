@@ -0,0 +1,299 @@
+use alloc::{string::String, vec::Vec};
+use core::slice;
+
+use super::proto::{Writer, for_each_field};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::TensorElementType,
+	value::{DefiniteTensorValueTypeMarker, Value, ValueType}
+};
+
+const ELEMENT_TYPES: [TensorElementType; 23] = [
+	TensorElementType::Undefined,
+	TensorElementType::Float32,
+	TensorElementType::Uint8,
+	TensorElementType::Int8,
+	TensorElementType::Uint16,
+	TensorElementType::Int16,
+	TensorElementType::Int32,
+	TensorElementType::Int64,
+	TensorElementType::String,
+	TensorElementType::Bool,
+	TensorElementType::Float16,
+	TensorElementType::Float64,
+	TensorElementType::Uint32,
+	TensorElementType::Uint64,
+	TensorElementType::Complex64,
+	TensorElementType::Complex128,
+	TensorElementType::Bfloat16,
+	TensorElementType::Float8E4M3FN,
+	TensorElementType::Float8E4M3FNUZ,
+	TensorElementType::Float8E5M2,
+	TensorElementType::Float8E5M2FNUZ,
+	TensorElementType::Uint4,
+	TensorElementType::Int4
+];
+
+/// Converts an ONNX `TensorProto.DataType` to a [`TensorElementType`], returning `None` for unknown types.
+pub(crate) fn element_type(data_type: i32) -> Option<TensorElementType> {
+	ELEMENT_TYPES
+		.into_iter()
+		.find(|&ty| ort_sys::ONNXTensorElementDataType::from(ty) as i32 == data_type)
+}
+
+/// Converts a [`TensorElementType`] to its ONNX `TensorProto.DataType`.
+pub(crate) fn data_type(ty: TensorElementType) -> i32 {
+	ort_sys::ONNXTensorElementDataType::from(ty) as i32
+}
+
+/// Returns the number of bytes `count` elements of `ty` occupy in `raw_data`, or `None` if `ty` can't be stored as
+/// raw data.
+pub(crate) fn raw_size(ty: TensorElementType, count: usize) -> Option<usize> {
+	Some(match ty {
+		TensorElementType::String | TensorElementType::Undefined => return None,
+		TensorElementType::Uint4 | TensorElementType::Int4 => count.div_ceil(2),
+		TensorElementType::Bool
+		| TensorElementType::Int8
+		| TensorElementType::Uint8
+		| TensorElementType::Float8E4M3FN
+		| TensorElementType::Float8E4M3FNUZ
+		| TensorElementType::Float8E5M2
+		| TensorElementType::Float8E5M2FNUZ => count,
+		TensorElementType::Int16 | TensorElementType::Uint16 | TensorElementType::Float16 | TensorElementType::Bfloat16 => count * 2,
+		TensorElementType::Int32 | TensorElementType::Uint32 | TensorElementType::Float32 => count * 4,
+		TensorElementType::Int64 | TensorElementType::Uint64 | TensorElementType::Float64 | TensorElementType::Complex64 => count * 8,
+		TensorElementType::Complex128 => count * 16
+	})
+}
+
+/// The contents of an [`Initializer`].
+///
+/// ONNX stores tensor data in one of several typed fields depending on the element type (e.g. `int32_data` holds
+/// `int32`, `int16`, `uint8`, `bool`, and `float16` elements), or as little-endian bytes in `raw_data`. The variant
+/// here reflects the field the data was actually stored in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorData {
+	/// Little-endian bytes (`raw_data`). Most exporters store data this way.
+	Raw(Vec<u8>),
+	Float(Vec<f32>),
+	Int32(Vec<i32>),
+	Int64(Vec<i64>),
+	Double(Vec<f64>),
+	Uint64(Vec<u64>),
+	String(Vec<Vec<u8>>),
+	/// The data lives in an external file; these are the `external_data` key-value pairs (`location`, `offset`,
+	/// `length`, ...).
+	External(Vec<(String, String)>)
+}
+
+impl Default for TensorData {
+	fn default() -> Self {
+		Self::Raw(Vec::new())
+	}
+}
+
+/// A tensor stored in a model (ONNX `TensorProto`), either as a graph initializer or as a tensor attribute.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Initializer {
+	pub name: String,
+	/// The ONNX element data type; see [`Initializer::element_type`].
+	pub data_type: i32,
+	pub dims: Vec<i64>,
+	pub data: TensorData,
+	pub doc_string: String,
+	/// Raw encoding of fields not otherwise parsed, preserved when re-encoding.
+	pub unknown_fields: Vec<u8>
+}
+
+/// Returns the typed data vector for `$variant`, replacing `$data` if it holds a different variant.
+macro_rules! typed_data {
+	($data:expr, $variant:ident) => {{
+		if !matches!($data, TensorData::$variant(_)) {
+			$data = TensorData::$variant(Vec::new());
+		}
+		match &mut $data {
+			TensorData::$variant(values) => values,
+			_ => unreachable!()
+		}
+	}};
+}
+
+impl Initializer {
+	/// Creates a new tensor with the given name, element type, shape, and data.
+	pub fn new(name: impl Into<String>, element_type: TensorElementType, dims: impl Into<Vec<i64>>, data: TensorData) -> Self {
+		Self {
+			name: name.into(),
+			data_type: data_type(element_type),
+			dims: dims.into(),
+			data,
+			..Default::default()
+		}
+	}
+
+	/// Creates a new tensor with the given name, copying the shape & data of a [`Tensor`](crate::value::Tensor).
+	///
+	/// Returns an error if the tensor's data is not accessible from the CPU.
+	pub fn from_tensor<T: DefiniteTensorValueTypeMarker + ?Sized>(name: impl Into<String>, tensor: &Value<T>) -> Result<Self> {
+		let ValueType::Tensor { ty, shape, .. } = tensor.dtype() else {
+			unreachable!("tensor value types are always tensors")
+		};
+		let data = if *ty == TensorElementType::String {
+			let (_, strings) = tensor.try_extract_strings()?;
+			TensorData::String(strings.into_iter().map(String::into_bytes).collect())
+		} else {
+			if !tensor.memory_info().is_cpu_accessible() {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Cannot create an initializer from a tensor which is not CPU-accessible"));
+			}
+			let len = raw_size(*ty, shape.num_elements())
+				.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, alloc::format!("Cannot create an initializer from a tensor of {ty}")))?;
+			if len == 0 {
+				TensorData::Raw(Vec::new())
+			} else {
+				TensorData::Raw(unsafe { slice::from_raw_parts(tensor.data_ptr().cast::<u8>(), len) }.to_vec())
+			}
+		};
+		Ok(Self::new(name, *ty, shape.to_vec(), data))
+	}
+
+	/// Returns the element type of this tensor, or `None` if the model uses a data type unknown to `ort`.
+	pub fn element_type(&self) -> Option<TensorElementType> {
+		element_type(self.data_type)
+	}
+
+	/// Returns the number of elements in this tensor, i.e. the product of its dimensions.
+	pub fn num_elements(&self) -> usize {
+		self.dims.iter().map(|&d| d.max(0) as usize).product()
+	}
+
+	/// Returns `true` if this tensor's data is stored outside of the model file.
+	pub fn is_external(&self) -> bool {
+		matches!(self.data, TensorData::External(_))
+	}
+
+	/// Returns the path of the file this tensor's data is stored in, relative to the model's directory, if it is stored
+	/// externally.
+	pub fn external_location(&self) -> Option<&str> {
+		match &self.data {
+			TensorData::External(entries) => entries.iter().find(|(key, _)| key == "location").map(|(_, value)| value.as_str()),
+			_ => None
+		}
+	}
+
+	/// Returns the elements of a `float32` tensor, or `None` if the tensor is of another type or its data isn't stored
+	/// in the model.
+	pub(crate) fn float_values(&self) -> Option<Vec<f32>> {
+		if self.element_type() != Some(TensorElementType::Float32) {
+			return None;
+		}
+		let values: Vec<f32> = match &self.data {
+			TensorData::Raw(bytes) => bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+			TensorData::Float(values) => values.clone(),
+			_ => return None
+		};
+		(values.len() == self.num_elements()).then_some(values)
+	}
+
+	/// Returns the elements of an integer or boolean tensor, or `None` if the tensor is of another type or its data
+	/// isn't stored in the model.
+	pub(crate) fn int_values(&self) -> Option<Vec<i64>> {
+		let values: Vec<i64> = match (self.element_type()?, &self.data) {
+			(TensorElementType::Int64, TensorData::Raw(bytes)) => bytes
+				.chunks_exact(8)
+				.map(|b| i64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+				.collect(),
+			(TensorElementType::Int32, TensorData::Raw(bytes)) => bytes
+				.chunks_exact(4)
+				.map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]).into())
+				.collect(),
+			(TensorElementType::Bool, TensorData::Raw(bytes)) => bytes.iter().map(|&b| b.into()).collect(),
+			(TensorElementType::Int64, TensorData::Int64(values)) => values.clone(),
+			(TensorElementType::Int32 | TensorElementType::Bool, TensorData::Int32(values)) => values.iter().map(|&v| v.into()).collect(),
+			_ => return None
+		};
+		(values.len() == self.num_elements()).then_some(values)
+	}
+
+	pub(crate) fn decode(buf: &[u8]) -> Result<Self> {
+		let mut tensor = Self::default();
+		let mut external = false;
+		let mut external_data = Vec::new();
+		let mut unknown = Vec::new();
+		for_each_field(buf, &mut unknown, |number, field| {
+			match number {
+				1 => field.push_varints(&mut tensor.dims, |v| v as i64)?,
+				2 => tensor.data_type = field.as_i32()?,
+				4 => field.push_f32s(typed_data!(tensor.data, Float))?,
+				5 => field.push_varints(typed_data!(tensor.data, Int32), |v| v as i64 as i32)?,
+				6 => typed_data!(tensor.data, String).push(field.as_bytes()?.to_vec()),
+				7 => field.push_varints(typed_data!(tensor.data, Int64), |v| v as i64)?,
+				8 => tensor.name = field.as_string()?,
+				9 => tensor.data = TensorData::Raw(field.as_bytes()?.to_vec()),
+				10 => field.push_f64s(typed_data!(tensor.data, Double))?,
+				11 => field.push_varints(typed_data!(tensor.data, Uint64), |v| v)?,
+				12 => tensor.doc_string = field.as_string()?,
+				13 => external_data.push(decode_string_pair(field.as_bytes()?)?),
+				14 => external = field.as_i32()? == 1,
+				_ => return Ok(false)
+			}
+			Ok(true)
+		})?;
+		if external || !external_data.is_empty() {
+			tensor.data = TensorData::External(external_data);
+		}
+		tensor.unknown_fields = unknown;
+		Ok(tensor)
+	}
+
+	pub(crate) fn encode(&self, w: &mut Writer) {
+		for &dim in &self.dims {
+			w.i64(1, dim);
+		}
+		w.i32(2, self.data_type);
+		w.string(8, &self.name);
+		match &self.data {
+			TensorData::Raw(bytes) => {
+				if !bytes.is_empty() {
+					w.bytes(9, bytes);
+				}
+			}
+			TensorData::Float(values) => w.packed_fixed(4, values.iter().flat_map(|v| v.to_le_bytes())),
+			TensorData::Int32(values) => w.packed_varints(5, values.iter().map(|&v| v as i64 as u64)),
+			TensorData::Int64(values) => w.packed_varints(7, values.iter().map(|&v| v as u64)),
+			TensorData::Double(values) => w.packed_fixed(10, values.iter().flat_map(|v| v.to_le_bytes())),
+			TensorData::Uint64(values) => w.packed_varints(11, values.iter().copied()),
+			TensorData::String(values) => {
+				for value in values {
+					w.bytes(6, value);
+				}
+			}
+			TensorData::External(entries) => {
+				for (key, value) in entries {
+					w.message(13, |w| encode_string_pair(w, key, value));
+				}
+				w.i32(14, 1);
+			}
+		}
+		w.string(12, &self.doc_string);
+		w.raw(&self.unknown_fields);
+	}
+}
+
+/// Decodes a `StringStringEntryProto`.
+pub(crate) fn decode_string_pair(buf: &[u8]) -> Result<(String, String)> {
+	let (mut key, mut value) = (String::new(), String::new());
+	let mut unknown = Vec::new();
+	for_each_field(buf, &mut unknown, |number, field| {
+		match number {
+			1 => key = field.as_string()?,
+			2 => value = field.as_string()?,
+			_ => return Ok(false)
+		}
+		Ok(true)
+	})?;
+	Ok((key, value))
+}
+
+pub(crate) fn encode_string_pair(w: &mut Writer, key: &str, value: &str) {
+	w.string(1, key);
+	w.string(2, value);
+}
@@ -1,4 +1,4 @@
-use alloc::{ffi::CString, sync::Arc};
+use alloc::{ffi::CString, sync::Arc, vec::Vec};
 use core::{
 	cell::UnsafeCell,
 	ffi::{c_char, c_void},
@@ -14,7 +14,7 @@ use smallvec::SmallVec;
 
 use crate::{
 	error::Result,
-	session::{SessionOutputs, SharedSessionInner, run_options::UntypedRunOptions},
+	session::{SessionOutputs, SharedSessionInner, debug::Tap, run_options::UntypedRunOptions, timer::DeadlineGuard},
 	util::{STACK_SESSION_INPUTS, STACK_SESSION_OUTPUTS},
 	value::{Value, ValueInner}
 };
@@ -58,6 +58,7 @@ unsafe impl Sync for InferenceFutInner<'_, '_> {}
 pub struct InferenceFut<'s, 'r, 'v> {
 	inner: Arc<InferenceFutInner<'r, 's>>,
 	run_options: &'r UntypedRunOptions,
+	deadline: Option<DeadlineGuard>,
 	did_receive: bool,
 	_inputs: PhantomData<&'v ()>
 }
@@ -65,10 +66,11 @@ pub struct InferenceFut<'s, 'r, 'v> {
 unsafe impl Send for InferenceFut<'_, '_, '_> {}
 
 impl<'s, 'r> InferenceFut<'s, 'r, '_> {
-	pub(crate) fn new(inner: Arc<InferenceFutInner<'r, 's>>, run_options: &'r UntypedRunOptions) -> Self {
+	pub(crate) fn new(inner: Arc<InferenceFutInner<'r, 's>>, run_options: &'r UntypedRunOptions, deadline: Option<DeadlineGuard>) -> Self {
 		Self {
 			inner,
 			run_options,
+			deadline,
 			did_receive: false,
 			_inputs: PhantomData
 		}
@@ -83,7 +85,10 @@ impl<'s, 'r> Future for InferenceFut<'s, 'r, '_> {
 
 		if let Some(v) = this.inner.try_take() {
 			this.did_receive = true;
-			return Poll::Ready(v);
+			return Poll::Ready(match this.deadline.take() {
+				Some(deadline) => deadline.finish(v),
+				None => v
+			});
 		}
 
 		this.inner.set_waker(Some(cx.waker()));
@@ -108,7 +113,8 @@ pub(crate) struct AsyncInferenceContext<'r, 's> {
 	pub(crate) output_name_ptrs: SmallVec<*const c_char, { STACK_SESSION_OUTPUTS }>,
 	pub(crate) session_inner: &'s Arc<SharedSessionInner>,
 	pub(crate) output_names: SmallVec<&'r str, { STACK_SESSION_OUTPUTS }>,
-	pub(crate) output_value_ptrs: SmallVec<*mut ort_sys::OrtValue, { STACK_SESSION_OUTPUTS }>
+	pub(crate) output_value_ptrs: SmallVec<*mut ort_sys::OrtValue, { STACK_SESSION_OUTPUTS }>,
+	pub(crate) taps: Vec<&'s Tap>
 }
 
 pub(crate) extern "system" fn async_callback(user_data: *mut c_void, _: *mut *mut ort_sys::OrtValue, _: usize, status: ort_sys::OrtStatusPtr) {
@@ -133,6 +139,7 @@ pub(crate) extern "system" fn async_callback(user_data: *mut c_void, _: *mut *mu
 		})
 		.collect();
 
-	ctx.inner.emplace_value(Ok(SessionOutputs::new(ctx.output_names, outputs)));
+	ctx.inner
+		.emplace_value(Ok(SessionOutputs::new(ctx.output_names, outputs).with_taps(ctx.taps)));
 	ctx.inner.wake();
 }
@@ -0,0 +1,908 @@
+//! Dynamic request batching on top of a [`Session`].
+
+use alloc::{
+	format,
+	string::{String, ToString},
+	sync::Arc,
+	vec,
+	vec::Vec
+};
+use core::{
+	fmt,
+	future::Future,
+	ops::Index,
+	pin::Pin,
+	task::{Context, Poll, Waker},
+	time::Duration
+};
+use std::{
+	sync::{
+		Condvar, Mutex,
+		mpsc::{self, Receiver, RecvTimeoutError, SyncSender}
+	},
+	thread::{self, JoinHandle},
+	time::Instant
+};
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	memory::Allocator,
+	session::{Session, SessionInputs},
+	tensor::{PrimitiveTensorElementType, Shape, TensorElementType},
+	value::{DynTensor, DynTensorValueType, DynValue, ValueType}
+};
+
+/// Describes how a ragged input (i.e. one whose length varies between samples, like a sequence of tokens) should be
+/// padded so that samples of different lengths can be batched together.
+///
+/// Samples are padded at the end of `axis` to the length of the longest sample in the batch. Note that outputs are
+/// **not** un-padded; each caller receives its slice of the padded output.
+#[derive(Debug, Clone)]
+pub struct Padding {
+	axis: usize,
+	value: Vec<u8>,
+	value_type: Option<TensorElementType>
+}
+
+impl Padding {
+	/// Pad the input along `axis` with zeros. `axis` must be a dynamic dimension of the input.
+	pub fn new(axis: usize) -> Self {
+		Self {
+			axis,
+			value: Vec::new(),
+			value_type: None
+		}
+	}
+
+	/// Pad with the given value instead of zero. `T` must match the element type of the input this padding is
+	/// applied to.
+	pub fn with_value<T: PrimitiveTensorElementType + Copy>(mut self, value: T) -> Self {
+		let bytes = unsafe { core::slice::from_raw_parts((&value as *const T).cast::<u8>(), core::mem::size_of::<T>()) };
+		self.value = bytes.to_vec();
+		self.value_type = Some(T::into_tensor_element_type());
+		self
+	}
+}
+
+/// Options for a [`Batcher`].
+#[derive(Debug, Clone)]
+pub struct BatcherOptions {
+	max_batch_size: usize,
+	max_wait: Duration,
+	batch_axis: usize,
+	queue_capacity: usize,
+	padding: Vec<(String, Padding)>
+}
+
+impl Default for BatcherOptions {
+	fn default() -> Self {
+		Self {
+			max_batch_size: 16,
+			max_wait: Duration::from_millis(5),
+			batch_axis: 0,
+			queue_capacity: 1024,
+			padding: Vec::new()
+		}
+	}
+}
+
+impl BatcherOptions {
+	/// Creates a new set of batcher options with the default configuration: a maximum batch size of 16, a maximum wait
+	/// time of 5ms, and batching along axis 0.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The maximum number of samples to merge into a single batch. A batch is run as soon as this many samples are
+	/// queued.
+	pub fn with_max_batch_size(mut self, size: usize) -> Self {
+		self.max_batch_size = size;
+		self
+	}
+
+	/// The maximum amount of time to wait for more samples after the first sample of a batch arrives.
+	pub fn with_max_wait(mut self, wait: Duration) -> Self {
+		self.max_wait = wait;
+		self
+	}
+
+	/// The axis along which samples are concatenated. The model's inputs and outputs must all have a dynamic dimension
+	/// at this axis.
+	pub fn with_batch_axis(mut self, axis: usize) -> Self {
+		self.batch_axis = axis;
+		self
+	}
+
+	/// The maximum number of requests which can be waiting to be batched. [`Batcher::submit`] will block when the
+	/// queue is full.
+	pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+		self.queue_capacity = capacity;
+		self
+	}
+
+	/// Pads the input named `name` according to `padding` so that ragged samples can be batched together.
+	pub fn with_padding(mut self, name: impl Into<String>, padding: Padding) -> Self {
+		self.padding.push((name.into(), padding));
+		self
+	}
+}
+
+/// Per-input configuration resolved against the session's inputs.
+#[derive(Debug)]
+struct InputSpec {
+	name: String,
+	ty: TensorElementType,
+	shape: Shape,
+	padding: Option<Padding>
+}
+
+/// A single input of a queued sample, copied into host memory.
+#[derive(Debug)]
+struct SampleTensor {
+	shape: Vec<usize>,
+	data: Vec<u8>
+}
+
+struct Request {
+	inputs: Vec<SampleTensor>,
+	batch_len: usize,
+	slot: Arc<ResultSlot>
+}
+
+#[derive(Default)]
+struct ResultSlot {
+	value: Mutex<(Option<Result<BatchOutputs>>, Option<Waker>)>,
+	ready: Condvar
+}
+
+impl ResultSlot {
+	fn complete(&self, result: Result<BatchOutputs>) {
+		let waker = {
+			let mut value = self.value.lock().unwrap_or_else(|e| e.into_inner());
+			value.0 = Some(result);
+			value.1.take()
+		};
+		self.ready.notify_all();
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+	}
+}
+
+/// Merges many single-sample (or small-batch) requests into larger batches to improve throughput.
+///
+/// Requests submitted with [`Batcher::submit`] are queued until either [`BatcherOptions::with_max_batch_size`] samples
+/// are available or [`BatcherOptions::with_max_wait`] has elapsed since the first queued sample. Inputs are then
+/// concatenated along the batch axis, the session is run once, and every output is split back along the same axis to
+/// each caller.
+///
+/// Each submitted input must have the same rank as the corresponding model input, including the batch axis (usually
+/// of size 1). All of the model's inputs and outputs must have a dynamic dimension at the batch axis. Samples whose
+/// non-batch dimensions differ are run in separate batches, unless the differing axis is padded via
+/// [`BatcherOptions::with_padding`].
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use ort::{session::{Session, batcher::{Batcher, BatcherOptions}}, value::Tensor};
+/// # fn main() -> ort::Result<()> {
+/// let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+/// let batcher = Batcher::new(session, BatcherOptions::new().with_max_batch_size(8))?;
+///
+/// let pending = (0..4)
+/// 	.map(|_| batcher.submit(ort::inputs![Tensor::<f32>::from_array(([1, 8, 8, 3], vec![0.5; 192]))?]))
+/// 	.collect::<ort::Result<Vec<_>>>()?;
+/// for pending in pending {
+/// 	let outputs = pending.wait()?;
+/// 	let (shape, _) = outputs[0].try_extract_tensor::<f32>()?;
+/// 	assert_eq!(**shape, [1, 16, 16, 3]);
+/// }
+/// # 	Ok(())
+/// # }
+/// ```
+pub struct Batcher {
+	sender: Option<SyncSender<Request>>,
+	worker: Option<JoinHandle<()>>,
+	inputs: Arc<Vec<InputSpec>>,
+	batch_axis: usize
+}
+
+impl Batcher {
+	/// Creates a new [`Batcher`] over the given session, spawning a single worker thread which runs batches.
+	///
+	/// Returns an error if any of the session's inputs or outputs is not a tensor with a dynamic dimension at the
+	/// configured batch axis, or if a padding configuration refers to an unknown input or an axis that is invalid or
+	/// not dynamic.
+	pub fn new(session: Arc<Session>, options: BatcherOptions) -> Result<Self> {
+		if options.max_batch_size == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "`max_batch_size` must be at least 1"));
+		}
+
+		let axis = options.batch_axis;
+		let mut inputs = Vec::with_capacity(session.inputs.len());
+		for input in &session.inputs {
+			let (ty, shape) = batchable_tensor(&input.name, &input.input_type, axis, "input")?;
+			let padding = options.padding.iter().find(|(name, _)| *name == input.name).map(|(_, p)| p.clone());
+			if let Some(padding) = &padding {
+				if padding.axis == axis || padding.axis >= shape.len() {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!(
+							"Cannot pad input `{}` along axis {}; the input has rank {} and is batched along axis {axis}",
+							input.name,
+							padding.axis,
+							shape.len()
+						)
+					));
+				}
+				if shape[padding.axis] != -1 {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!(
+							"Cannot pad input `{}` along axis {}; the input has a fixed dimension of {} there, but padded axes must be dynamic",
+							input.name, padding.axis, shape[padding.axis]
+						)
+					));
+				}
+				if let Some(value_type) = padding.value_type {
+					if value_type != ty {
+						return Err(Error::new_with_code(
+							ErrorCode::InvalidArgument,
+							format!("Padding value for input `{}` is of type {value_type}, but the input is of type {ty}", input.name)
+						));
+					}
+				}
+			}
+			inputs.push(InputSpec {
+				name: input.name.clone(),
+				ty,
+				shape: shape.clone(),
+				padding
+			});
+		}
+		for (name, _) in &options.padding {
+			if !inputs.iter().any(|i| &i.name == name) {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Padding was configured for unknown input `{name}`")));
+			}
+		}
+		for output in &session.outputs {
+			batchable_tensor(&output.name, &output.output_type, axis, "output")?;
+		}
+
+		let inputs = Arc::new(inputs);
+		let (sender, receiver) = mpsc::sync_channel(options.queue_capacity);
+		let worker = {
+			let inputs = Arc::clone(&inputs);
+			thread::Builder::new()
+				.name("ort-batcher".to_string())
+				.spawn(move || worker_loop(&session, &inputs, &options, receiver))
+				.map_err(Error::wrap)?
+		};
+
+		Ok(Self {
+			sender: Some(sender),
+			worker: Some(worker),
+			inputs,
+			batch_axis: axis
+		})
+	}
+
+	/// Queues a request to be run as part of a batch, returning a [`BatchFut`] which resolves to this request's slice
+	/// of the outputs.
+	///
+	/// Inputs are validated and copied immediately, so the values passed in do not need to outlive this call. An
+	/// error is returned if an input is missing, unknown, not a CPU-accessible tensor, or its type or shape is not
+	/// compatible with the model.
+	pub fn submit<'i, 'v: 'i, const N: usize>(&self, inputs: impl Into<SessionInputs<'i, 'v, N>>) -> Result<BatchFut> {
+		let mut samples: Vec<Option<SampleTensor>> = (0..self.inputs.len()).map(|_| None).collect();
+		let mut batch_len = None;
+		let mut add = |index: usize, value: &DynValue| -> Result<()> {
+			let spec = &self.inputs[index];
+			if samples[index].is_some() {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Input `{}` was provided more than once", spec.name)));
+			}
+			let sample = copy_sample(spec, value, self.batch_axis)?;
+			let len = sample.shape[self.batch_axis];
+			match batch_len {
+				None => batch_len = Some(len),
+				Some(expected) if expected != len => {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!("Input `{}` has {len} samples along the batch axis, but previous inputs had {expected}", spec.name)
+					));
+				}
+				Some(_) => {}
+			}
+			samples[index] = Some(sample);
+			Ok(())
+		};
+
+		match inputs.into() {
+			SessionInputs::ValueMap(values) => {
+				for (name, value) in &values {
+					let index = self
+						.inputs
+						.iter()
+						.position(|i| i.name == *name)
+						.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Model has no input named `{name}`")))?;
+					add(index, value)?;
+				}
+			}
+			SessionInputs::ValueSlice(values) => {
+				check_positional_len(values.len(), self.inputs.len())?;
+				for (index, value) in values.iter().enumerate() {
+					add(index, value)?;
+				}
+			}
+			SessionInputs::ValueArray(values) => {
+				check_positional_len(values.len(), self.inputs.len())?;
+				for (index, value) in values.iter().enumerate() {
+					add(index, value)?;
+				}
+			}
+		}
+
+		let inputs = samples
+			.into_iter()
+			.zip(self.inputs.iter())
+			.map(|(sample, spec)| sample.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Missing input `{}`", spec.name))))
+			.collect::<Result<Vec<_>>>()?;
+		let batch_len = batch_len.unwrap_or(0);
+		if batch_len == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Cannot submit an empty batch"));
+		}
+
+		let slot = Arc::new(ResultSlot::default());
+		let sender = self.sender.as_ref().expect("sender is only taken on drop");
+		sender
+			.send(Request {
+				inputs,
+				batch_len,
+				slot: Arc::clone(&slot)
+			})
+			.map_err(|_| Error::new("Batcher worker thread has exited"))?;
+		Ok(BatchFut { slot })
+	}
+}
+
+impl fmt::Debug for Batcher {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Batcher")
+			.field("inputs", &self.inputs)
+			.field("batch_axis", &self.batch_axis)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Drop for Batcher {
+	fn drop(&mut self) {
+		// Dropping the sender lets the worker drain any queued requests and then exit.
+		drop(self.sender.take());
+		if let Some(worker) = self.worker.take() {
+			let _ = worker.join();
+		}
+	}
+}
+
+/// A pending request submitted to a [`Batcher`].
+///
+/// This can be `.await`ed in an async context, or waited on synchronously with [`BatchFut::wait`].
+pub struct BatchFut {
+	slot: Arc<ResultSlot>
+}
+
+impl BatchFut {
+	/// Blocks the current thread until the batch containing this request has been run.
+	pub fn wait(self) -> Result<BatchOutputs> {
+		let mut value = self.slot.value.lock().unwrap_or_else(|e| e.into_inner());
+		loop {
+			if let Some(result) = value.0.take() {
+				return result;
+			}
+			value = self.slot.ready.wait(value).unwrap_or_else(|e| e.into_inner());
+		}
+	}
+}
+
+impl Future for BatchFut {
+	type Output = Result<BatchOutputs>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let mut value = self.slot.value.lock().unwrap_or_else(|e| e.into_inner());
+		match value.0.take() {
+			Some(result) => Poll::Ready(result),
+			None => {
+				value.1 = Some(cx.waker().clone());
+				Poll::Pending
+			}
+		}
+	}
+}
+
+impl fmt::Debug for BatchFut {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BatchFut").finish_non_exhaustive()
+	}
+}
+
+/// The outputs of a single request submitted to a [`Batcher`], in the same order as the session's outputs.
+#[derive(Debug)]
+pub struct BatchOutputs {
+	values: Vec<(String, DynValue)>
+}
+
+// The values are freshly allocated tensors owned exclusively by this struct.
+unsafe impl Send for BatchOutputs {}
+
+impl BatchOutputs {
+	/// Returns the output with the given name, if it exists.
+	pub fn get(&self, name: impl AsRef<str>) -> Option<&DynValue> {
+		let name = name.as_ref();
+		self.values.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+	}
+
+	/// Removes and returns the output with the given name, if it exists.
+	pub fn remove(&mut self, name: impl AsRef<str>) -> Option<DynValue> {
+		let name = name.as_ref();
+		let index = self.values.iter().position(|(k, _)| k == name)?;
+		Some(self.values.remove(index).1)
+	}
+
+	#[allow(clippy::len_without_is_empty)]
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	pub fn iter(&self) -> impl ExactSizeIterator<Item = (&str, &DynValue)> {
+		self.values.iter().map(|(k, v)| (k.as_str(), v))
+	}
+}
+
+impl IntoIterator for BatchOutputs {
+	type IntoIter = vec::IntoIter<(String, DynValue)>;
+	type Item = (String, DynValue);
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.values.into_iter()
+	}
+}
+
+impl Index<&str> for BatchOutputs {
+	type Output = DynValue;
+	fn index(&self, key: &str) -> &Self::Output {
+		self.get(key).unwrap_or_else(|| panic!("no output named `{key}`"))
+	}
+}
+
+impl Index<usize> for BatchOutputs {
+	type Output = DynValue;
+	fn index(&self, index: usize) -> &Self::Output {
+		&self.values[index].1
+	}
+}
+
+fn check_positional_len(provided: usize, expected: usize) -> Result<()> {
+	if provided != expected {
+		return Err(Error::new_with_code(
+			ErrorCode::InvalidArgument,
+			format!("{provided} inputs were provided, but the model expects {expected}; use named inputs to omit optional inputs")
+		));
+	}
+	Ok(())
+}
+
+fn batchable_tensor<'t>(name: &str, ty: &'t ValueType, axis: usize, kind: &str) -> Result<(TensorElementType, &'t Shape)> {
+	match ty {
+		ValueType::Tensor { ty, shape, .. } => {
+			if element_size(*ty).is_none() {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Model {kind} `{name}` is of type {ty}, which cannot be batched")));
+			}
+			match shape.get(axis) {
+				Some(-1) => Ok((*ty, shape)),
+				Some(dim) => Err(Error::new_with_code(
+					ErrorCode::InvalidArgument,
+					format!("Model {kind} `{name}` has a fixed dimension of {dim} at batch axis {axis}; the batch axis must be dynamic")
+				)),
+				None => Err(Error::new_with_code(
+					ErrorCode::InvalidArgument,
+					format!("Model {kind} `{name}` has rank {}, which has no batch axis {axis}", shape.len())
+				))
+			}
+		}
+		ty => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Model {kind} `{name}` is a {ty}; only tensors can be batched")))
+	}
+}
+
+fn element_size(ty: TensorElementType) -> Option<usize> {
+	match ty {
+		TensorElementType::String
+		| TensorElementType::Uint4
+		| TensorElementType::Int4
+		| TensorElementType::Float8E4M3FN
+		| TensorElementType::Float8E4M3FNUZ
+		| TensorElementType::Float8E5M2
+		| TensorElementType::Float8E5M2FNUZ
+		| TensorElementType::Undefined => None,
+		ty => Some(ty.byte_size(1))
+	}
+}
+
+fn copy_sample(spec: &InputSpec, value: &DynValue, batch_axis: usize) -> Result<SampleTensor> {
+	let (ty, shape) = match value.dtype() {
+		ValueType::Tensor { ty, shape, .. } => (*ty, shape),
+		ty => return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Input `{}` must be a tensor, got {ty}", spec.name)))
+	};
+	if ty != spec.ty {
+		return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Input `{}` must be of type {}, got {ty}", spec.name, spec.ty)));
+	}
+	if shape.len() != spec.shape.len() {
+		return Err(Error::new_with_code(
+			ErrorCode::InvalidArgument,
+			format!("Input `{}` must have rank {} (including the batch axis {batch_axis}), got shape {shape}", spec.name, spec.shape.len())
+		));
+	}
+	for (i, (&expected, &actual)) in spec.shape.iter().zip(shape.iter()).enumerate() {
+		if expected >= 0 && expected != actual {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!("Input `{}` must have size {expected} at axis {i}, got shape {shape}", spec.name)
+			));
+		}
+	}
+
+	let tensor = value.downcast_ref::<DynTensorValueType>()?;
+	let memory_info = tensor.memory_info();
+	if !memory_info.is_cpu_accessible() {
+		return Err(Error::new_with_code(
+			ErrorCode::InvalidArgument,
+			format!("Input `{}` is allocated on device `{}`, which is not CPU accessible", spec.name, memory_info.allocation_device().as_str())
+		));
+	}
+
+	let len = ty.byte_size(shape.num_elements());
+	let data = if len == 0 {
+		Vec::new()
+	} else {
+		unsafe { core::slice::from_raw_parts(tensor.data_ptr().cast::<u8>(), len) }.to_vec()
+	};
+	Ok(SampleTensor {
+		shape: shape.iter().map(|d| *d as usize).collect(),
+		data
+	})
+}
+
+fn worker_loop(session: &Session, inputs: &[InputSpec], options: &BatcherOptions, receiver: Receiver<Request>) {
+	let mut pending: Vec<Request> = Vec::new();
+	loop {
+		if pending.is_empty() {
+			match receiver.recv() {
+				Ok(request) => pending.push(request),
+				Err(_) => return
+			}
+		}
+
+		let deadline = Instant::now() + options.max_wait;
+		let mut disconnected = false;
+		while pending.iter().map(|r| r.batch_len).sum::<usize>() < options.max_batch_size {
+			let timeout = deadline.saturating_duration_since(Instant::now());
+			match receiver.recv_timeout(timeout) {
+				Ok(request) => pending.push(request),
+				Err(RecvTimeoutError::Timeout) => break,
+				Err(RecvTimeoutError::Disconnected) => {
+					disconnected = true;
+					break;
+				}
+			}
+		}
+
+		let batch = take_batch(&mut pending, inputs, options);
+		run_batch(session, inputs, options.batch_axis, batch);
+
+		if disconnected {
+			while !pending.is_empty() {
+				let batch = take_batch(&mut pending, inputs, options);
+				run_batch(session, inputs, options.batch_axis, batch);
+			}
+			return;
+		}
+	}
+}
+
+/// Takes the largest run of compatible requests from the front of the queue, up to the max batch size. Requests are
+/// compatible if all of their non-batch, non-padded dimensions are equal.
+fn take_batch(pending: &mut Vec<Request>, inputs: &[InputSpec], options: &BatcherOptions) -> Vec<Request> {
+	let key = |request: &Request| -> Vec<Vec<usize>> {
+		request
+			.inputs
+			.iter()
+			.zip(inputs)
+			.map(|(sample, spec)| {
+				let mut shape = sample.shape.clone();
+				shape[options.batch_axis] = 0;
+				if let Some(padding) = &spec.padding {
+					shape[padding.axis] = 0;
+				}
+				shape
+			})
+			.collect()
+	};
+
+	let first_key = key(&pending[0]);
+	let mut batch = Vec::new();
+	let mut size = 0;
+	let mut i = 0;
+	while i < pending.len() {
+		let request = &pending[i];
+		if (batch.is_empty() || size + request.batch_len <= options.max_batch_size) && key(request) == first_key {
+			size += request.batch_len;
+			batch.push(pending.remove(i));
+		} else {
+			i += 1;
+		}
+	}
+	batch
+}
+
+fn run_batch(session: &Session, inputs: &[InputSpec], batch_axis: usize, batch: Vec<Request>) {
+	match run_batch_inner(session, inputs, batch_axis, &batch) {
+		Ok(mut results) => {
+			for (request, result) in batch.iter().zip(results.drain(..)) {
+				request.slot.complete(Ok(result));
+			}
+		}
+		Err(e) => {
+			for request in &batch {
+				request.slot.complete(Err(Error::new_with_code(e.code(), e.message())));
+			}
+		}
+	}
+}
+
+fn run_batch_inner(session: &Session, inputs: &[InputSpec], batch_axis: usize, batch: &[Request]) -> Result<Vec<BatchOutputs>> {
+	let total: usize = batch.iter().map(|r| r.batch_len).sum();
+	let allocator = Allocator::default();
+
+	let mut values = Vec::with_capacity(inputs.len());
+	for (index, spec) in inputs.iter().enumerate() {
+		let elem = element_size(spec.ty).unwrap_or_else(|| unreachable!());
+		let mut shape = batch[0].inputs[index].shape.clone();
+		shape[batch_axis] = total;
+		if let Some(padding) = &spec.padding {
+			shape[padding.axis] = batch.iter().map(|r| r.inputs[index].shape[padding.axis]).max().unwrap_or(0);
+		}
+
+		let mut tensor = DynTensor::new(&allocator, spec.ty, Shape::from(shape.clone()))?;
+		let len = spec.ty.byte_size(shape.iter().product());
+		if len > 0 {
+			let dst = unsafe { core::slice::from_raw_parts_mut(tensor.data_ptr_mut().cast::<u8>(), len) };
+			if let Some(padding) = spec.padding.as_ref().filter(|p| !p.value.is_empty()) {
+				for chunk in dst.chunks_exact_mut(elem) {
+					chunk.copy_from_slice(&padding.value);
+				}
+			}
+
+			let mut offset = vec![0; shape.len()];
+			for request in batch {
+				let sample = &request.inputs[index];
+				copy_into(&sample.data, &sample.shape, dst, &shape, &offset, elem);
+				offset[batch_axis] += request.batch_len;
+			}
+		}
+		values.push((spec.name.as_str(), tensor.into_dyn()));
+	}
+
+	let outputs = session.run(values.iter().map(|(name, value)| (*name, value)).collect::<Vec<_>>())?;
+
+	let mut results: Vec<BatchOutputs> = batch
+		.iter()
+		.map(|_| BatchOutputs {
+			values: Vec::with_capacity(outputs.len())
+		})
+		.collect();
+	for (name, output) in outputs.iter() {
+		let (ty, shape) = match output.dtype() {
+			ValueType::Tensor { ty, shape, .. } => (*ty, shape),
+			ty => return Err(Error::new(format!("Output `{name}` is a {ty}; only tensors can be un-batched")))
+		};
+		let elem = element_size(ty).ok_or_else(|| Error::new(format!("Output `{name}` is of type {ty}, which cannot be un-batched")))?;
+		let shape: Vec<usize> = shape.iter().map(|d| *d as usize).collect();
+		if shape.get(batch_axis) != Some(&total) {
+			return Err(Error::new(format!("Output `{name}` has shape {shape:?}, expected {total} samples along batch axis {batch_axis}")));
+		}
+
+		let tensor = output.downcast_ref::<DynTensorValueType>()?;
+		let src_len = ty.byte_size(shape.iter().product());
+		let src = if src_len > 0 {
+			unsafe { core::slice::from_raw_parts(tensor.data_ptr().cast::<u8>(), src_len) }
+		} else {
+			&[]
+		};
+
+		let mut offset = vec![0; shape.len()];
+		for (request, result) in batch.iter().zip(results.iter_mut()) {
+			let mut part_shape = shape.clone();
+			part_shape[batch_axis] = request.batch_len;
+			let mut part = DynTensor::new(&allocator, ty, Shape::from(part_shape.clone()))?;
+			let len = ty.byte_size(part_shape.iter().product());
+			if len > 0 {
+				let dst = unsafe { core::slice::from_raw_parts_mut(part.data_ptr_mut().cast::<u8>(), len) };
+				copy_from(dst, &part_shape, src, &shape, &offset, elem);
+			}
+			offset[batch_axis] += request.batch_len;
+			result.values.push((name.to_string(), part.into_dyn()));
+		}
+	}
+
+	Ok(results)
+}
+
+/// Calls `f(block_offset, full_offset, len)` (in bytes) for each contiguous row of a row-major block of shape
+/// `block_shape` placed at `offset` within a larger row-major tensor of shape `full_shape`.
+fn for_each_row(block_shape: &[usize], full_shape: &[usize], offset: &[usize], elem: usize, mut f: impl FnMut(usize, usize, usize)) {
+	let rank = block_shape.len();
+	if rank == 0 {
+		f(0, 0, elem);
+		return;
+	}
+
+	let mut full_strides = vec![elem; rank];
+	let mut block_strides = vec![elem; rank];
+	for i in (0..rank - 1).rev() {
+		full_strides[i] = full_strides[i + 1] * full_shape[i + 1];
+		block_strides[i] = block_strides[i + 1] * block_shape[i + 1];
+	}
+
+	let row_len = block_shape[rank - 1] * elem;
+	let num_rows: usize = block_shape[..rank - 1].iter().product();
+	if row_len == 0 || num_rows == 0 {
+		return;
+	}
+
+	let mut index = vec![0; rank - 1];
+	for _ in 0..num_rows {
+		let mut block_pos = 0;
+		let mut full_pos = offset[rank - 1] * elem;
+		for (d, i) in index.iter().enumerate() {
+			block_pos += i * block_strides[d];
+			full_pos += (i + offset[d]) * full_strides[d];
+		}
+		f(block_pos, full_pos, row_len);
+
+		for d in (0..rank - 1).rev() {
+			index[d] += 1;
+			if index[d] < block_shape[d] {
+				break;
+			}
+			index[d] = 0;
+		}
+	}
+}
+
+/// Copies `block` into the region of `full` starting at `offset`.
+fn copy_into(block: &[u8], block_shape: &[usize], full: &mut [u8], full_shape: &[usize], offset: &[usize], elem: usize) {
+	for_each_row(block_shape, full_shape, offset, elem, |b, f, len| full[f..f + len].copy_from_slice(&block[b..b + len]));
+}
+
+/// Copies the region of `full` starting at `offset` into `block`.
+fn copy_from(block: &mut [u8], block_shape: &[usize], full: &[u8], full_shape: &[usize], offset: &[usize], elem: usize) {
+	for_each_row(block_shape, full_shape, offset, elem, |b, f, len| block[b..b + len].copy_from_slice(&full[f..f + len]));
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::sync::Arc;
+	use core::time::Duration;
+
+	use super::{Batcher, BatcherOptions, Padding, copy_from, copy_into};
+	use crate::{session::Session, value::Tensor};
+
+	#[test]
+	fn test_copy_block() {
+		// copy a [2, 2] block into a [3, 4] tensor at offset [1, 1]
+		let block = [1u8, 2, 3, 4];
+		let mut full = [0u8; 12];
+		copy_into(&block, &[2, 2], &mut full, &[3, 4], &[1, 1], 1);
+		assert_eq!(full, [0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 4, 0]);
+
+		let mut extracted = [0u8; 4];
+		copy_from(&mut extracted, &[2, 2], &full, &[3, 4], &[1, 1], 1);
+		assert_eq!(extracted, block);
+	}
+
+	#[test]
+	fn test_batcher_upsample() -> crate::Result<()> {
+		let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+		let batcher = Batcher::new(session, BatcherOptions::new().with_max_batch_size(4).with_max_wait(Duration::from_millis(50)))?;
+
+		let pending = (0..6)
+			.map(|i| batcher.submit(crate::inputs![Tensor::<f32>::from_array(([1, 2, 2, 3], vec![i as f32; 12]))?]))
+			.collect::<crate::Result<Vec<_>>>()?;
+		for (i, pending) in pending.into_iter().enumerate() {
+			let outputs = pending.wait()?;
+			let (shape, data) = outputs[0].try_extract_tensor::<f32>()?;
+			assert_eq!(**shape, [1, 4, 4, 3]);
+			assert!(data.iter().all(|x| *x == i as f32));
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_batcher_padding() -> crate::Result<()> {
+		let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+		let batcher = Batcher::new(
+			session,
+			BatcherOptions::new()
+				.with_max_batch_size(2)
+				.with_max_wait(Duration::from_millis(50))
+				.with_padding("x", Padding::new(1).with_value(-1.0_f32))
+		);
+		// upsample.onnx's input is not named `x`
+		assert!(batcher.is_err());
+
+		let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+		let name = session.inputs[0].name.clone();
+		let batcher = Batcher::new(session, BatcherOptions::new().with_padding(name, Padding::new(3)));
+		// the last axis of upsample.onnx's input is fixed at 3
+		assert!(batcher.is_err());
+
+		let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+		let name = session.inputs[0].name.clone();
+		let batcher = Batcher::new(
+			session,
+			BatcherOptions::new()
+				.with_max_batch_size(2)
+				.with_max_wait(Duration::from_millis(50))
+				.with_padding(name, Padding::new(1).with_value(-1.0_f32))
+		)?;
+
+		let a = batcher.submit(crate::inputs![Tensor::<f32>::from_array(([1, 1, 2, 3], vec![1.0; 6]))?])?;
+		let b = batcher.submit(crate::inputs![Tensor::<f32>::from_array(([1, 2, 2, 3], vec![2.0; 12]))?])?;
+
+		let a = a.wait()?;
+		let (shape, data) = a[0].try_extract_tensor::<f32>()?;
+		// `a` was padded to the length of `b`
+		assert_eq!(**shape, [1, 4, 4, 3]);
+		assert!(data[..24].iter().all(|x| *x == 1.0));
+		assert!(data[24..].iter().all(|x| *x == -1.0));
+
+		let b = b.wait()?;
+		let (shape, data) = b[0].try_extract_tensor::<f32>()?;
+		assert_eq!(**shape, [1, 4, 4, 3]);
+		assert!(data.iter().all(|x| *x == 2.0));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_batcher_rejects_invalid_inputs() -> crate::Result<()> {
+		let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+		let batcher = Batcher::new(session, BatcherOptions::new())?;
+
+		// wrong element type
+		assert!(
+			batcher
+				.submit(crate::inputs![Tensor::<i64>::from_array(([1, 2, 2, 3], vec![0; 12]))?])
+				.is_err()
+		);
+		// wrong rank
+		assert!(
+			batcher
+				.submit(crate::inputs![Tensor::<f32>::from_array(([2, 2, 3], vec![0.0; 12]))?])
+				.is_err()
+		);
+		// fixed dimension mismatch
+		assert!(
+			batcher
+				.submit(crate::inputs![Tensor::<f32>::from_array(([1, 2, 2, 4], vec![0.0; 16]))?])
+				.is_err()
+		);
+		// unknown input name
+		assert!(
+			batcher
+				.submit(crate::inputs!["nope" => Tensor::<f32>::from_array(([1, 2, 2, 3], vec![0.0; 12]))?])
+				.is_err()
+		);
+
+		Ok(())
+	}
+}
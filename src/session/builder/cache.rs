@@ -0,0 +1,206 @@
+//! The optimized model cache enabled by [`SessionBuilder::with_optimized_cache`].
+
+use alloc::{
+	borrow::Cow,
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use core::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::{GraphOptimizationLevel, SessionBuilder, impl_commit::ModelSource};
+use crate::{
+	error::{Error, Result},
+	model::Model,
+	session::Session
+};
+
+/// Bumped whenever the way cache keys are derived changes, so stale entries are never reused.
+const KEY_VERSION: &[u8] = b"ort optimized model cache v1\0";
+
+/// The state of the optimized model cache for a [`SessionBuilder`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OptimizedCache {
+	pub(crate) dir: Option<PathBuf>,
+	/// Every option set on the builder which may affect the optimized graph, in the order it was set.
+	fingerprint: Vec<String>,
+	/// The directory external data is read from for models loaded from memory, if configured.
+	external_data_dir: Option<PathBuf>
+}
+
+impl OptimizedCache {
+	/// Records an option which may affect the optimized graph.
+	pub(crate) fn record(&mut self, option: impl Into<String>) {
+		self.fingerprint.push(option.into());
+	}
+
+	/// Records a session configuration entry.
+	pub(crate) fn record_config(&mut self, key: &str, value: &str) {
+		if key == "session.model_external_initializers_file_folder_path" {
+			self.external_data_dir = Some(PathBuf::from(value));
+		}
+		self.record(format!("config:{key}={value}"));
+	}
+}
+
+impl SessionBuilder {
+	/// Creates the session from `source`, reusing an optimized model from the cache if one matches, or populating the
+	/// cache otherwise. The session is created without the cache if the optimized model can't be saved.
+	#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+	pub(crate) fn commit_cached(mut self, dir: PathBuf, source: ModelSource<'_>) -> Result<Session> {
+		// initializers provided at runtime would be baked into the optimized model
+		if !self.initializers.is_empty() {
+			crate::debug!("Bypassing optimized model cache because the session has custom initializers");
+			let session_ptr = self.create_session(source)?;
+			return self.commit_finalize(session_ptr);
+		}
+		// cache entries aren't signed, so loading one would skip the verification the source model just passed
+		#[cfg(feature = "signing")]
+		if self.verifier.verified().is_some() {
+			crate::debug!("Bypassing optimized model cache because the model's signature was verified");
+			let session_ptr = self.create_session(source)?;
+			return self.commit_finalize(session_ptr);
+		}
+
+		let key = self.cache_key(source)?;
+		for extension in ["ort", "onnx"] {
+			let cached_path = dir.join(format!("{key}.{extension}"));
+			if !cached_path.exists() {
+				continue;
+			}
+			// the cached model is already optimized
+			let mut builder = self.clone().with_optimization_level(GraphOptimizationLevel::Disable)?;
+			match builder.create_session(ModelSource::File(&cached_path)) {
+				Ok(session_ptr) => {
+					crate::debug!(cached_path = %cached_path.display(), "Loaded optimized model from cache");
+					return builder.commit_finalize(session_ptr);
+				}
+				Err(e) => {
+					crate::warn!(cached_path = %cached_path.display(), "Discarding optimized model cache entry which failed to load: {e}");
+					let _ = std::fs::remove_file(&cached_path);
+				}
+			}
+		}
+
+		if let Err(e) = std::fs::create_dir_all(&dir) {
+			crate::warn!(dir = %dir.display(), "Failed to create optimized model cache directory: {e}");
+		} else {
+			// save in ORT format if possible since it loads faster; it isn't supported by every execution provider
+			for extension in ["ort", "onnx"] {
+				// write to a temporary file & rename it once complete, so other processes never see partial files
+				let id = ort_sys::internal::random_identifier();
+				let temp_path = dir.join(format!("{key}.{id}.tmp"));
+				let mut builder = self.clone().with_optimized_model_path(&temp_path)?;
+				builder.add_config_entry("session.save_model_format", &extension.to_ascii_uppercase())?;
+				// ONNX models over 2 GB can only be saved with their initializers in a separate file; the model refers to
+				// it by name, so it keeps its unique name rather than being renamed along with the model
+				let data_path = dir.join(format!("{key}.{id}.data"));
+				if extension == "onnx" {
+					builder.add_config_entry("session.optimized_model_external_initializers_file_name", &format!("{key}.{id}.data"))?;
+				}
+				match builder.create_session(source) {
+					Ok(session_ptr) => {
+						let cached_path = dir.join(format!("{key}.{extension}"));
+						if let Err(e) = std::fs::rename(&temp_path, &cached_path) {
+							crate::warn!(cached_path = %cached_path.display(), "Failed to save optimized model to cache: {e}");
+							let _ = std::fs::remove_file(&temp_path);
+							let _ = std::fs::remove_file(&data_path);
+						}
+						return builder.commit_finalize(session_ptr);
+					}
+					Err(e) => {
+						crate::debug!(format = extension, "Failed to create session while saving optimized model: {e}");
+						let _ = std::fs::remove_file(&temp_path);
+						let _ = std::fs::remove_file(&data_path);
+					}
+				}
+			}
+			crate::warn!("Could not save optimized model to cache; creating the session without it");
+		}
+
+		let session_ptr = self.create_session(source)?;
+		self.commit_finalize(session_ptr)
+	}
+
+	/// Derives the cache key from the model, its external data, the ONNX Runtime build, the host CPU, and every option
+	/// which may affect the optimized graph.
+	fn cache_key(&self, source: ModelSource<'_>) -> Result<String> {
+		let mut hasher = Sha256::new();
+		hasher.update(KEY_VERSION);
+		let mut update = |part: &[u8]| {
+			hasher.update((part.len() as u64).to_le_bytes());
+			hasher.update(part);
+		};
+		update(crate::info().as_bytes());
+		update(host_cpu().as_bytes());
+		for option in &self.cache.fingerprint {
+			update(option.as_bytes());
+		}
+
+		let model = match source {
+			ModelSource::File(path) => Cow::Owned(std::fs::read(path).map_err(Error::wrap)?),
+			ModelSource::Memory(bytes) => Cow::Borrowed(bytes)
+		};
+		update(&Sha256::digest(&model));
+		for buffer in &self.external_initializer_buffers {
+			update(&Sha256::digest(&**buffer));
+		}
+		#[cfg(feature = "encryption")]
+		for buffer in &self.decrypted_buffers {
+			update(&Sha256::digest(&**buffer));
+		}
+		// external data on disk, which models loaded from memory can only reference if a directory is configured
+		let dir = match source {
+			ModelSource::File(path) => Some(path.parent().unwrap_or_else(|| Path::new(""))),
+			ModelSource::Memory(_) => self.cache.external_data_dir.as_deref()
+		};
+		if let (Some(dir), Ok(model)) = (dir, Model::from_bytes(&model)) {
+			let mut locations: Vec<&str> = model
+				.graph
+				.initializers
+				.iter()
+				.chain(model.graph.subgraphs().flat_map(|g| &g.initializers))
+				.filter_map(|initializer| initializer.external_location())
+				.collect();
+			locations.sort_unstable();
+			locations.dedup();
+			for location in locations {
+				update(location.as_bytes());
+				// missing files are left for ONNX Runtime to report
+				if let Ok(contents) = std::fs::read(dir.join(location)) {
+					update(&Sha256::digest(&contents));
+				}
+			}
+		}
+
+		Ok(hasher.finalize().iter().fold(String::with_capacity(64), |mut key, b| {
+			let _ = write!(&mut key, "{b:02x}");
+			key
+		}))
+	}
+}
+
+/// Identifies the host CPU's instruction set, since graphs optimized with `Level3` can contain layout transformations
+/// specific to it.
+fn host_cpu() -> String {
+	#[allow(unused_mut)]
+	let mut cpu = std::env::consts::ARCH.to_string();
+	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+	for (feature, detected) in [
+		("avx", std::arch::is_x86_feature_detected!("avx")),
+		("avx2", std::arch::is_x86_feature_detected!("avx2")),
+		("fma", std::arch::is_x86_feature_detected!("fma")),
+		("avx512f", std::arch::is_x86_feature_detected!("avx512f")),
+		("avx512bw", std::arch::is_x86_feature_detected!("avx512bw")),
+		("avx512vnni", std::arch::is_x86_feature_detected!("avx512vnni"))
+	] {
+		if detected {
+			cpu.push('+');
+			cpu.push_str(feature);
+		}
+	}
+	cpu
+}
@@ -5,7 +5,7 @@ use core::{
 	any::Any,
 	ffi::c_void,
 	marker::PhantomData,
-	mem::replace,
+	mem::{replace, take},
 	ptr::{self, NonNull}
 };
 #[cfg(feature = "std")]
@@ -15,17 +15,31 @@ use std::path::PathBuf;
 
 use smallvec::SmallVec;
 
-use super::SessionBuilder;
 #[cfg(feature = "std")]
-use crate::error::{Error, ErrorCode};
+use super::PrepackedWeights;
+use super::SessionBuilder;
+#[cfg(feature = "bundle")]
+use crate::session::bundle::Bundle;
+#[cfg(feature = "compression")]
+use crate::session::compression;
+#[cfg(feature = "encryption")]
+use crate::session::encryption::{self, KeyProvider};
+#[cfg(feature = "signing")]
+use crate::session::signing::VerifiedFile;
 use crate::{
 	AsPointer,
 	environment::get_environment,
 	error::Result,
 	execution_providers::apply_execution_providers,
 	memory::Allocator,
+	model::{Model, ModelChecker},
 	ortsys,
-	session::{InMemorySession, Input, Output, Session, SharedSessionInner, dangerous}
+	session::{InMemorySession, Input, Output, Session, SharedSessionInner, dangerous, debug}
+};
+#[cfg(feature = "std")]
+use crate::{
+	error::{Error, ErrorCode},
+	session::SessionPool
 };
 
 impl SessionBuilder {
@@ -95,6 +109,9 @@ impl SessionBuilder {
 	}
 
 	/// Loads an ONNX model from a file and builds the session.
+	///
+	/// With the `compression` feature enabled, models compressed with gzip or zstd are detected and decompressed into
+	/// memory first; see the `session::compression` module.
 	#[cfg(feature = "std")]
 	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 	pub fn commit_from_file<P>(self, model_filepath: P) -> Result<Session>
@@ -109,26 +126,185 @@ impl SessionBuilder {
 		if !model_filepath.exists() {
 			return Err(Error::new_with_code(ErrorCode::NoSuchFile, format!("File at `{}` does not exist", model_filepath.display())));
 		}
+		#[cfg(feature = "signing")]
+		if let Some(verified) = self.verifier.verify_file(model_filepath)? {
+			return self.commit_from_verified(model_filepath, verified);
+		}
+		#[cfg(feature = "compression")]
+		if let Some(model_bytes) = compression::read_model(model_filepath, self.model_compression)? {
+			return self.commit_from_decompressed(model_filepath, &model_bytes, &[]);
+		}
+		if let Some(checker) = self.model_checker.take() {
+			run_model_checker(checker.for_model_dir(model_dir(model_filepath)), &Model::from_file(model_filepath)?)?;
+		}
+		if !self.debug_taps.is_empty() {
+			let model_bytes = std::fs::read(model_filepath).map_err(Error::wrap)?;
+			// the rewritten model is loaded from memory, so external data must be resolved relative to the original file
+			self.set_external_data_dir(model_filepath)?;
+			return self.commit_from_memory(&model_bytes);
+		}
 
-		let model_path = crate::util::path_to_os_char(model_filepath);
+		self.prepare_environment()?;
+		#[cfg(feature = "model-cache")]
+		if let Some(dir) = self.cache.dir.take() {
+			return self.commit_cached(dir, ModelSource::File(model_filepath));
+		}
+		let session_ptr = self.create_session(ModelSource::File(model_filepath))?;
+		self.commit_finalize(session_ptr)
+	}
 
-		let env = get_environment()?;
-		if !self.no_env_eps {
-			apply_execution_providers(&mut self, &env.execution_providers, "environment")?;
+	/// Commits a session from the model file at `model_filepath` using the contents read when its signature was
+	/// verified, so that the files can't be swapped out between verification & ONNX Runtime loading them.
+	#[cfg(feature = "signing")]
+	fn commit_from_verified(mut self, model_filepath: &Path, verified: VerifiedFile) -> Result<Session> {
+		let VerifiedFile { model, external_files } = verified;
+		let provided: Vec<(String, usize)> = external_files
+			.iter()
+			.map(|(location, contents)| (location.clone(), contents.len()))
+			.collect();
+		for (location, contents) in external_files {
+			self = self.with_external_initializer_file_in_memory(location, contents.into())?;
+		}
+		#[cfg(feature = "compression")]
+		if let Some(model_bytes) = compression::decompress_model(model_filepath, &model, self.model_compression)? {
+			return self.commit_from_decompressed(model_filepath, &model_bytes, &provided);
+		}
+		if let Some(checker) = self.model_checker.take() {
+			let checker = provided
+				.iter()
+				.fold(checker.for_model_dir(model_dir(model_filepath)), |checker, (location, len)| checker.with_in_memory_file(location, *len));
+			run_model_checker(checker, &Model::from_bytes(&model)?)?;
 		}
+		// every external data file is covered by the signature, so all of them have been provided in memory above
+		self.commit_from_memory(&model)
+	}
 
-		if env.has_global_threadpool && !self.no_global_thread_pool {
-			ortsys![unsafe DisablePerSessionThreads(self.ptr_mut())?];
+	/// Commits a session from a model decompressed from `model_filepath`, providing any compressed external data files
+	/// next to it in memory. External data files in `provided` (as `(location, size)` pairs) have already been provided
+	/// in memory and are not looked up again.
+	#[cfg(feature = "compression")]
+	fn commit_from_decompressed(mut self, model_filepath: &Path, model_bytes: &[u8], provided: &[(String, usize)]) -> Result<Session> {
+		let dir = model_dir(model_filepath);
+		// `.ort` models can't be parsed as ONNX, but they don't have external data either
+		if let Ok(model) = Model::from_bytes(model_bytes) {
+			// the checker can't find compressed files on disk, so it's told about every file provided in memory
+			let mut checker = self.model_checker.take().map(|checker| checker.for_model_dir(dir));
+			for (location, len) in provided {
+				checker = checker.map(|checker| checker.with_in_memory_file(location, *len));
+			}
+
+			let mut locations: Vec<&str> = model
+				.graph
+				.initializers
+				.iter()
+				.chain(model.graph.subgraphs().flat_map(|g| &g.initializers))
+				.filter_map(|initializer| initializer.external_location())
+				.collect();
+			locations.sort_unstable();
+			locations.dedup();
+			for location in locations
+				.into_iter()
+				.filter(|&location| !provided.iter().any(|(provided, _)| provided == location))
+			{
+				if let Some((path, compression)) = compression::find_compressed_external_file(dir, location) {
+					let data = compression::read_external_file(&path, compression)?;
+					checker = checker.map(|checker| checker.with_in_memory_file(location, data.len()));
+					self = self.with_external_initializer_file_in_memory(location, data.into())?;
+				}
+			}
+			if let Some(checker) = checker {
+				run_model_checker(checker, &model)?;
+			}
 		}
+		// uncompressed external data is still read from the model's directory
+		self.set_external_data_dir(model_filepath)?;
+		self.commit_from_memory(model_bytes)
+	}
 
-		let mut session_ptr: *mut ort_sys::OrtSession = ptr::null_mut();
-		if let Some(prepacked_weights) = self.prepacked_weights.as_ref() {
-			ortsys![unsafe CreateSessionWithPrepackedWeightsContainer(env.ptr(), model_path.as_ptr(), self.ptr(), prepacked_weights.ptr().cast_mut(), &mut session_ptr)?; nonNull(session_ptr)];
-		} else {
-			ortsys![unsafe CreateSession(env.ptr(), model_path.as_ptr(), self.ptr(), &mut session_ptr)?; nonNull(session_ptr)];
+	/// Resolves external data files relative to the directory of `model_filepath` when the model is committed from
+	/// memory.
+	#[cfg(feature = "std")]
+	fn set_external_data_dir(&mut self, model_filepath: &Path) -> Result<()> {
+		match model_filepath.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => {
+				self.add_config_entry("session.model_external_initializers_file_folder_path", dir.to_string_lossy().as_ref())
+			}
+			_ => Ok(())
+		}
+	}
+
+	/// Builds the session from the model in `bundle`, providing its external data files in memory. The bundle's
+	/// recommended session options are not applied; use [`SessionBuilder::with_bundle_options`] for that.
+	///
+	/// See the [`bundle`](crate::session::bundle) module for more information.
+	#[cfg(feature = "bundle")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+	pub fn commit_from_bundle(mut self, bundle: &Bundle) -> Result<Session> {
+		for (location, contents) in bundle.external_data()? {
+			self = self.with_external_initializer_file_in_memory(location, contents.to_vec().into())?;
+		}
+		self.commit_from_memory(bundle.model())
+	}
+
+	/// Decrypts a model file created with [`encryption::encrypt_file`] and builds the session. The decrypted model is
+	/// zeroed once the session is created.
+	///
+	/// See the [`encryption`] module for more information.
+	#[cfg(feature = "encryption")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+	pub fn commit_from_encrypted_file(self, model_filepath: impl AsRef<Path>, key: &impl KeyProvider) -> Result<Session> {
+		let model = encryption::decrypt_file(model_filepath.as_ref(), key)?;
+		self.commit_from_memory(&model)
+	}
+
+	/// Decrypts a model encrypted with [`encryption::encrypt`] and builds the session. The decrypted model is zeroed
+	/// once the session is created.
+	#[cfg(feature = "encryption")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+	pub fn commit_from_encrypted_memory(self, encrypted: &[u8], key: &impl KeyProvider) -> Result<Session> {
+		let model = encryption::decrypt(encrypted, &key.key()?)?;
+		self.commit_from_memory(&model)
+	}
+
+	/// Loads an ONNX model from a file and builds a [`SessionPool`] of `size` sessions.
+	///
+	/// Sessions in the pool share a single [`PrepackedWeights`] container (one is created if this builder was not
+	/// configured with [`SessionBuilder::with_prepacked_weights`]) and are configured to use the environment's
+	/// allocators via [`SessionBuilder::with_env_allocators`].
+	///
+	/// [`PrepackedWeights`]: super::PrepackedWeights
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn commit_pool_from_file<P>(self, model_filepath: P, size: usize) -> Result<SessionPool>
+	where
+		P: AsRef<Path>
+	{
+		let model_filepath = model_filepath.as_ref();
+		self.commit_pool(size, |builder| builder.commit_from_file(model_filepath))
+	}
+
+	/// Loads an ONNX model from memory and builds a [`SessionPool`] of `size` sessions.
+	///
+	/// See [`SessionBuilder::commit_pool_from_file`] for details on how weights are shared between sessions.
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn commit_pool_from_memory(self, model_bytes: &[u8], size: usize) -> Result<SessionPool> {
+		self.commit_pool(size, |builder| builder.commit_from_memory(model_bytes))
+	}
+
+	#[cfg(feature = "std")]
+	fn commit_pool(mut self, size: usize, commit: impl Fn(SessionBuilder) -> Result<Session>) -> Result<SessionPool> {
+		if size == 0 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "session pool size must be at least 1"));
+		}
+
+		if self.prepacked_weights.is_none() {
+			self.prepacked_weights = Some(PrepackedWeights::new());
 		}
+		let builder = self.with_env_allocators()?;
 
-		self.commit_finalize(unsafe { NonNull::new_unchecked(session_ptr) })
+		let sessions = (0..size).map(|_| commit(builder.clone())).collect::<Result<Vec<_>>>()?;
+		SessionPool::new(sessions)
 	}
 
 	/// Load an ONNX graph from memory and commit the session
@@ -148,35 +324,78 @@ impl SessionBuilder {
 
 	/// Load an ONNX graph from memory and commit the session.
 	pub fn commit_from_memory(mut self, model_bytes: &[u8]) -> Result<Session> {
-		let mut session_ptr: *mut ort_sys::OrtSession = ptr::null_mut();
+		#[cfg(feature = "signing")]
+		self.verifier.verify_memory(model_bytes)?;
+		if let Some(checker) = self.model_checker.take() {
+			run_model_checker(checker, &Model::from_bytes(model_bytes)?)?;
+		}
+		if !self.debug_taps.is_empty() {
+			let mut model = Model::from_bytes(model_bytes)?;
+			self.tap_sites = debug::insert_taps(&mut model, &take(&mut self.debug_taps))?;
+			return self.commit_from_memory(&model.to_bytes());
+		}
+
+		self.prepare_environment()?;
+		#[cfg(feature = "model-cache")]
+		if let Some(dir) = self.cache.dir.take() {
+			return self.commit_cached(dir, ModelSource::Memory(model_bytes));
+		}
+		let session_ptr = self.create_session(ModelSource::Memory(model_bytes))?;
+		self.commit_finalize(session_ptr)
+	}
 
+	/// Registers the environment's execution providers & thread pool with this builder.
+	fn prepare_environment(&mut self) -> Result<()> {
 		let env = get_environment()?;
 		if !self.no_env_eps {
-			apply_execution_providers(&mut self, &env.execution_providers, "environment")?;
+			apply_execution_providers(self, &env.execution_providers, "environment")?;
 		}
 
 		if env.has_global_threadpool && !self.no_global_thread_pool {
 			ortsys![unsafe DisablePerSessionThreads(self.ptr_mut())?];
 		}
+		Ok(())
+	}
 
-		let model_data = model_bytes.as_ptr().cast::<c_void>();
-		let model_data_length = model_bytes.len();
-		if let Some(prepacked_weights) = self.prepacked_weights.as_ref() {
-			ortsys![
-				unsafe CreateSessionFromArrayWithPrepackedWeightsContainer(env.ptr(), model_data, model_data_length, self.ptr(), prepacked_weights.ptr().cast_mut(), &mut session_ptr)?;
-				nonNull(session_ptr)
-			];
-		} else {
-			ortsys![
-				unsafe CreateSessionFromArray(env.ptr(), model_data, model_data_length, self.ptr(), &mut session_ptr)?;
-				nonNull(session_ptr)
-			];
+	pub(crate) fn create_session(&mut self, source: ModelSource<'_>) -> Result<NonNull<ort_sys::OrtSession>> {
+		let env = get_environment()?;
+		let mut session_ptr: *mut ort_sys::OrtSession = ptr::null_mut();
+		match source {
+			#[cfg(feature = "std")]
+			ModelSource::File(model_filepath) => {
+				let model_path = crate::util::path_to_os_char(model_filepath);
+				if let Some(prepacked_weights) = self.prepacked_weights.as_ref() {
+					ortsys![unsafe CreateSessionWithPrepackedWeightsContainer(env.ptr(), model_path.as_ptr(), self.ptr(), prepacked_weights.ptr().cast_mut(), &mut session_ptr)?; nonNull(session_ptr)];
+				} else {
+					ortsys![unsafe CreateSession(env.ptr(), model_path.as_ptr(), self.ptr(), &mut session_ptr)?; nonNull(session_ptr)];
+				}
+			}
+			ModelSource::Memory(model_bytes) => {
+				let model_data = model_bytes.as_ptr().cast::<c_void>();
+				let model_data_length = model_bytes.len();
+				if let Some(prepacked_weights) = self.prepacked_weights.as_ref() {
+					ortsys![
+						unsafe CreateSessionFromArrayWithPrepackedWeightsContainer(env.ptr(), model_data, model_data_length, self.ptr(), prepacked_weights.ptr().cast_mut(), &mut session_ptr)?;
+						nonNull(session_ptr)
+					];
+				} else {
+					ortsys![
+						unsafe CreateSessionFromArray(env.ptr(), model_data, model_data_length, self.ptr(), &mut session_ptr)?;
+						nonNull(session_ptr)
+					];
+				}
+			}
 		}
+		Ok(unsafe { NonNull::new_unchecked(session_ptr) })
+	}
 
-		self.commit_finalize(unsafe { NonNull::new_unchecked(session_ptr) })
+	/// Encodes a [`Model`] (for example, one created with a [`GraphBuilder`](crate::model::GraphBuilder)) and commits
+	/// the session.
+	pub fn commit_from_model(self, model: &Model) -> Result<Session> {
+		self.commit_from_memory(&model.to_bytes())
 	}
 
-	fn commit_finalize(mut self, ptr: NonNull<ort_sys::OrtSession>) -> Result<Session> {
+	pub(crate) fn commit_finalize(mut self, ptr: NonNull<ort_sys::OrtSession>) -> Result<Session> {
 		let allocator = match &self.memory_info {
 			Some(info) => {
 				let mut allocator_ptr: *mut ort_sys::OrtAllocator = ptr::null_mut();
@@ -192,9 +411,10 @@ impl SessionBuilder {
 		let inputs = (0..num_input_nodes)
 			.map(|i| dangerous::extract_input(ptr, &allocator, i))
 			.collect::<Result<Vec<Input>>>()?;
-		let outputs = (0..num_output_nodes)
+		let mut outputs = (0..num_output_nodes)
 			.map(|i| dangerous::extract_output(ptr, &allocator, i))
 			.collect::<Result<Vec<Output>>>()?;
+		let taps = debug::split_taps(&mut outputs, take(&mut self.tap_sites));
 
 		let mut extras: SmallVec<Box<dyn Any>, 4> = self.operator_domains.drain(..).map(|d| Box::new(d) as Box<dyn Any>).collect();
 		if let Some(prepacked_weights) = self.prepacked_weights.take() {
@@ -207,15 +427,45 @@ impl SessionBuilder {
 			extras.push(Box::new(logger) as Box<dyn Any>); // Box<Arc<Box<dyn ...>>>!
 		}
 
-		Ok(Session {
+		let session = Session {
 			inner: Arc::new(SharedSessionInner {
 				session_ptr: ptr,
 				allocator,
 				_initializers: replace(&mut self.initializers, SmallVec::new()),
+				#[cfg(feature = "signing")]
+				verified_digest: self.verifier.verified(),
 				_extras: extras
 			}),
 			inputs,
-			outputs
-		})
+			outputs,
+			taps
+		};
+		for check in &self.signature_checks {
+			check(&session)?;
+		}
+		Ok(session)
+	}
+}
+
+/// Where a model is being loaded from.
+#[derive(Clone, Copy)]
+pub(crate) enum ModelSource<'m> {
+	#[cfg(feature = "std")]
+	File(&'m Path),
+	Memory(&'m [u8])
+}
+
+/// Returns the directory containing `model_filepath`, relative to which its external data files are resolved.
+#[cfg(feature = "std")]
+fn model_dir(model_filepath: &Path) -> &Path {
+	model_filepath.parent().unwrap_or_else(|| Path::new(""))
+}
+
+fn run_model_checker(checker: ModelChecker, model: &Model) -> Result<()> {
+	let report = checker.for_loaded_runtime().check(model);
+	#[cfg(feature = "tracing")]
+	for warning in report.warnings() {
+		crate::warn!("{warning}");
 	}
+	report.into_result()
 }
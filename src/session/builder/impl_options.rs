@@ -8,6 +8,14 @@ use core::{
 use std::{borrow::Cow, path::Path};
 
 use super::SessionBuilder;
+#[cfg(feature = "bundle")]
+use crate::session::bundle::Bundle;
+#[cfg(feature = "compression")]
+use crate::session::compression::Compression;
+#[cfg(feature = "encryption")]
+use crate::session::encryption::{self, KeyProvider};
+#[cfg(feature = "signing")]
+use crate::session::signing::{Signature, VerifyingKey};
 #[cfg(feature = "std")]
 use crate::util::path_to_os_char;
 use crate::{
@@ -17,8 +25,13 @@ use crate::{
 	execution_providers::{ExecutionProviderDispatch, apply_execution_providers},
 	logging::{LogLevel, LoggerFunction},
 	memory::MemoryInfo,
+	model::ModelChecker,
 	operator::OperatorDomain,
 	ortsys,
+	session::{
+		debug::DebugTap,
+		typed::{ModelInputs, ModelOutputs}
+	},
 	util::with_cstr,
 	value::DynValue
 };
@@ -84,6 +97,8 @@ impl SessionBuilder {
 	/// optimization levels.
 	pub fn with_optimization_level(mut self, opt_level: GraphOptimizationLevel) -> Result<Self> {
 		ortsys![unsafe SetSessionGraphOptimizationLevel(self.ptr_mut(), opt_level.into())?];
+		#[cfg(feature = "model-cache")]
+		self.cache.record(alloc::format!("optimization_level={opt_level:?}"));
 		Ok(self)
 	}
 
@@ -185,6 +200,8 @@ impl SessionBuilder {
 	#[cfg(feature = "std")]
 	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 	pub fn with_external_initializer_file_in_memory(mut self, file_name: impl AsRef<Path>, buffer: Cow<'static, [u8]>) -> Result<Self> {
+		#[cfg(feature = "signing")]
+		self.verifier.add_in_memory_file(file_name.as_ref(), &buffer);
 		let file_name = path_to_os_char(file_name);
 		let sizes = [buffer.len()];
 		ortsys![unsafe AddExternalInitializersFromMemory(self.ptr_mut(), &file_name.as_ptr(), &buffer.as_ptr().cast::<core::ffi::c_char>().cast_mut(), sizes.as_ptr(), 1)?];
@@ -192,6 +209,24 @@ impl SessionBuilder {
 		Ok(self)
 	}
 
+	/// Decrypts an external data file encrypted with [`encryption::encrypt`], and makes it available to the model
+	/// like [`SessionBuilder::with_external_initializer_file_in_memory`]. `file_name` is the name the model refers to
+	/// the (unencrypted) file by.
+	///
+	/// The decrypted data is zeroed when the builder is dropped.
+	#[cfg(feature = "encryption")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+	pub fn with_encrypted_external_initializer_file_in_memory(mut self, file_name: impl AsRef<Path>, encrypted: &[u8], key: &impl KeyProvider) -> Result<Self> {
+		let buffer = encryption::decrypt(encrypted, &key.key()?)?;
+		#[cfg(feature = "signing")]
+		self.verifier.add_in_memory_file(file_name.as_ref(), &buffer);
+		let file_name = path_to_os_char(file_name);
+		let sizes = [buffer.len()];
+		ortsys![unsafe AddExternalInitializersFromMemory(self.ptr_mut(), &file_name.as_ptr(), &buffer.as_ptr().cast::<core::ffi::c_char>().cast_mut(), sizes.as_ptr(), 1)?];
+		self.decrypted_buffers.push(buffer);
+		Ok(self)
+	}
+
 	pub fn with_log_id(mut self, id: impl AsRef<str>) -> Result<Self> {
 		let ptr = self.ptr_mut();
 		with_cstr(id.as_ref().as_bytes(), &|id| {
@@ -286,6 +321,151 @@ impl SessionBuilder {
 		ortsys![unsafe SetSessionLogVerbosityLevel(self.ptr_mut(), verbosity)?];
 		Ok(self)
 	}
+
+	/// Checks that the model's inputs are compatible with the [`ModelInputs`] struct `I` when the session is
+	/// committed, returning an error from `commit_*` if any field does not correspond to an input of the same element
+	/// type & rank.
+	pub fn with_model_inputs<I: ModelInputs>(mut self) -> Result<Self> {
+		self.signature_checks.push(I::check);
+		Ok(self)
+	}
+
+	/// Checks that the model's outputs are compatible with the [`ModelOutputs`] struct `O` when the session is
+	/// committed, returning an error from `commit_*` if any field does not correspond to an output of the same element
+	/// type & rank.
+	pub fn with_model_outputs<O: ModelOutputs>(mut self) -> Result<Self> {
+		self.signature_checks.push(O::check);
+		Ok(self)
+	}
+
+	/// Checks the model with `checker` before the session is created, returning an error from `commit_*` listing every
+	/// problem of [`Severity::Error`](crate::model::Severity::Error) found. Warnings are logged.
+	///
+	/// Unless configured otherwise, the checker targets the version of the loaded ONNX Runtime binary and, for
+	/// [`SessionBuilder::commit_from_file`], resolves external data relative to the model's directory.
+	pub fn with_model_checker(mut self, checker: ModelChecker) -> Result<Self> {
+		self.model_checker = Some(checker);
+		Ok(self)
+	}
+
+	/// Requires the model to be signed by one of `public_keys` before the session is created. The model and every
+	/// external data file it references are verified before anything is parsed, and `commit_*` returns an error with
+	/// [`ErrorCode::SignatureVerificationFailed`] if the model isn't signed, has been modified, or wasn't signed by a
+	/// trusted key. The verified digest is available from [`Session::verified_digest`].
+	///
+	/// Models committed from a file are verified against their detached signature (`model.onnx.sig` for `model.onnx`),
+	/// and models committed from memory against the signature given to [`SessionBuilder::with_model_signature`]. See
+	/// the [`signing`](crate::session::signing) module for how to sign models.
+	///
+	/// The session is created from the exact bytes that were verified: the model & its external data files are read
+	/// into memory once, so they can't be replaced on disk after verification.
+	///
+	/// [`ErrorCode::SignatureVerificationFailed`]: crate::error::ErrorCode::SignatureVerificationFailed
+	/// [`Session::verified_digest`]: crate::session::Session::verified_digest
+	#[cfg(feature = "signing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+	pub fn with_signature_verification(mut self, public_keys: impl IntoIterator<Item = VerifyingKey>) -> Result<Self> {
+		self.verifier.trust(public_keys)?;
+		Ok(self)
+	}
+
+	/// Applies the session options recommended by `bundle`'s manifest. Options not set in the manifest are left as they
+	/// are. See the [`bundle`](crate::session::bundle) module for more information.
+	#[cfg(feature = "bundle")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+	pub fn with_bundle_options(mut self, bundle: &Bundle) -> Result<Self> {
+		let options = &bundle.manifest().session_options;
+		if let Some(level) = options.optimization_level {
+			self = self.with_optimization_level(level)?;
+		}
+		if let Some(threads) = options.intra_threads {
+			self = self.with_intra_threads(threads)?;
+		}
+		if let Some(threads) = options.inter_threads {
+			self = self.with_inter_threads(threads)?;
+		}
+		if let Some(enable) = options.parallel_execution {
+			self = self.with_parallel_execution(enable)?;
+		}
+		if let Some(enable) = options.memory_pattern {
+			self = self.with_memory_pattern(enable)?;
+		}
+		for (key, value) in &options.config {
+			self.add_config_entry(key, value)?;
+		}
+		Ok(self)
+	}
+
+	/// Decompresses models committed with [`SessionBuilder::commit_from_file`] using `compression`, instead of
+	/// detecting whether they are compressed from their magic bytes. See the
+	/// [`compression`](crate::session::compression) module for more information.
+	#[cfg(feature = "compression")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+	pub fn with_model_compression(mut self, compression: Compression) -> Result<Self> {
+		self.model_compression = Some(compression);
+		Ok(self)
+	}
+
+	/// Caches the optimized model in `dir`, so subsequent sessions created from the same model & options skip graph
+	/// optimization, which can take a significant amount of time for large models.
+	///
+	/// Cached models are keyed by a hash of the model & its external data, the options set on this builder which may
+	/// affect the optimized graph (optimization level, configuration entries, and execution providers & their options),
+	/// the ONNX Runtime build, and the host CPU's instruction set - changing any of these produces a new cache entry
+	/// rather than reusing a stale one. Entries are written atomically, so multiple processes can safely share a cache
+	/// directory. Stale entries are never removed automatically; delete the directory to clear the cache.
+	///
+	/// Models saved in ONNX format (when the execution providers don't support the ORT format) store their initializers
+	/// in a separate file in `dir`, so models larger than 2 GB can be cached too.
+	///
+	/// The cache is bypassed for sessions with [custom initializers](SessionBuilder::with_initializer), and for models
+	/// verified with `SessionBuilder::with_signature_verification`, since cache entries aren't signed. If the optimized
+	/// model can't be saved (e.g. because the directory isn't writable), the session is created as usual.
+	///
+	/// ```no_run
+	/// # use ort::session::{builder::GraphOptimizationLevel, Session};
+	/// # fn main() -> ort::Result<()> {
+	/// let session = Session::builder()?
+	/// 	.with_optimization_level(GraphOptimizationLevel::Level3)?
+	/// 	.with_optimized_cache("model-cache")?
+	/// 	.commit_from_file("model.onnx")?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "model-cache")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "model-cache")))]
+	pub fn with_optimized_cache(mut self, dir: impl AsRef<Path>) -> Result<Self> {
+		self.cache.dir = Some(dir.as_ref().to_path_buf());
+		Ok(self)
+	}
+
+	/// Provides the signature to verify the model against when [signature
+	/// verification](SessionBuilder::with_signature_verification) is enabled. This is required for models committed
+	/// from memory; for models committed from a file, it is used instead of the detached signature next to the model.
+	#[cfg(feature = "signing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+	pub fn with_model_signature(mut self, signature: Signature) -> Result<Self> {
+		self.verifier.set_signature(signature);
+		Ok(self)
+	}
+
+	/// Exposes intermediate values of the model selected by `taps` for numerical debugging. See the
+	/// [`debug`](crate::session::debug) module for an example.
+	///
+	/// When the session is committed, the model is parsed & each selected value in the main graph is added as an
+	/// extra graph output. Tapped values are fetched on every [`Session::run`](crate::session::Session::run) call and
+	/// returned via [`SessionOutputs::taps`](crate::session::SessionOutputs::taps) rather than alongside the regular
+	/// outputs. Committing fails if a tap does not match any value.
+	///
+	/// Since the rewritten model is loaded from memory, models with weights in external data files must be committed
+	/// with [`SessionBuilder::commit_from_file`] (so that the data files can be found next to the model), or have their
+	/// data files provided with [`SessionBuilder::with_external_initializer_file_in_memory`]. Exposing values as
+	/// outputs also prevents ONNX Runtime from fusing away the nodes which produce them, so tapped sessions may run
+	/// slower than untapped ones.
+	pub fn with_debug_taps<T: Into<DebugTap>>(mut self, taps: impl IntoIterator<Item = T>) -> Result<Self> {
+		self.debug_taps.extend(taps.into_iter().map(Into::into));
+		Ok(self)
+	}
 }
 
 /// ONNX Runtime provides various graph optimizations to improve performance. Graph optimizations are essentially
@@ -317,7 +497,7 @@ impl SessionBuilder {
 /// - When layout optimizations are enabled, the offline mode can only be used on compatible hardware to the environment
 ///   when the offline model is saved. For example, if model has layout optimized for AVX2, the offline model would
 ///   require CPUs that support AVX2.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GraphOptimizationLevel {
 	/// Disables all graph optimizations.
 	Disable,
@@ -1,4 +1,4 @@
-use alloc::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc, vec::Vec};
 use core::{
 	any::Any,
 	ptr::{self, NonNull}
@@ -6,8 +6,32 @@ use core::{
 
 use smallvec::SmallVec;
 
-use crate::{AsPointer, error::Result, logging::LoggerFunction, memory::MemoryInfo, operator::OperatorDomain, ortsys, util::with_cstr, value::DynValue};
+#[cfg(feature = "model-cache")]
+use self::cache::OptimizedCache;
+#[cfg(feature = "compression")]
+use crate::session::compression::Compression;
+#[cfg(feature = "encryption")]
+use crate::session::encryption::Plaintext;
+#[cfg(feature = "signing")]
+use crate::session::signing::Verifier;
+use crate::{
+	AsPointer,
+	error::Result,
+	logging::LoggerFunction,
+	memory::MemoryInfo,
+	model::ModelChecker,
+	operator::OperatorDomain,
+	ortsys,
+	session::{
+		Session,
+		debug::{DebugTap, TapSite}
+	},
+	util::with_cstr,
+	value::DynValue
+};
 
+#[cfg(feature = "model-cache")]
+mod cache;
 mod impl_commit;
 mod impl_config_keys;
 mod impl_options;
@@ -38,9 +62,21 @@ pub struct SessionBuilder {
 	operator_domains: SmallVec<Arc<OperatorDomain>, 4>,
 	initializers: SmallVec<Arc<DynValue>, 4>,
 	external_initializer_buffers: SmallVec<Cow<'static, [u8]>, 4>,
+	#[cfg(feature = "encryption")]
+	decrypted_buffers: SmallVec<Plaintext, 4>,
 	prepacked_weights: Option<PrepackedWeights>,
 	thread_manager: Option<Arc<dyn Any>>,
 	logger: Option<Arc<LoggerFunction>>,
+	signature_checks: Vec<fn(&Session) -> Result<()>>,
+	debug_taps: Vec<DebugTap>,
+	tap_sites: Vec<TapSite>,
+	model_checker: Option<ModelChecker>,
+	#[cfg(feature = "signing")]
+	verifier: Verifier,
+	#[cfg(feature = "compression")]
+	model_compression: Option<Compression>,
+	#[cfg(feature = "model-cache")]
+	pub(crate) cache: OptimizedCache,
 	no_global_thread_pool: bool,
 	no_env_eps: bool
 }
@@ -59,9 +95,21 @@ impl Clone for SessionBuilder {
 			operator_domains: self.operator_domains.clone(),
 			initializers: self.initializers.clone(),
 			external_initializer_buffers: self.external_initializer_buffers.clone(),
+			#[cfg(feature = "encryption")]
+			decrypted_buffers: self.decrypted_buffers.clone(),
 			prepacked_weights: self.prepacked_weights.clone(),
 			thread_manager: self.thread_manager.clone(),
 			logger: self.logger.clone(),
+			signature_checks: self.signature_checks.clone(),
+			debug_taps: self.debug_taps.clone(),
+			tap_sites: self.tap_sites.clone(),
+			model_checker: self.model_checker.clone(),
+			#[cfg(feature = "signing")]
+			verifier: self.verifier.clone(),
+			#[cfg(feature = "compression")]
+			model_compression: self.model_compression,
+			#[cfg(feature = "model-cache")]
+			cache: self.cache.clone(),
 			no_global_thread_pool: self.no_global_thread_pool,
 			no_env_eps: self.no_env_eps
 		}
@@ -97,15 +145,29 @@ impl SessionBuilder {
 			operator_domains: SmallVec::new(),
 			initializers: SmallVec::new(),
 			external_initializer_buffers: SmallVec::new(),
+			#[cfg(feature = "encryption")]
+			decrypted_buffers: SmallVec::new(),
 			prepacked_weights: None,
 			thread_manager: None,
 			logger: None,
+			signature_checks: Vec::new(),
+			debug_taps: Vec::new(),
+			tap_sites: Vec::new(),
+			model_checker: None,
+			#[cfg(feature = "signing")]
+			verifier: Verifier::default(),
+			#[cfg(feature = "compression")]
+			model_compression: None,
+			#[cfg(feature = "model-cache")]
+			cache: OptimizedCache::default(),
 			no_global_thread_pool: false,
 			no_env_eps: false
 		})
 	}
 
 	pub(crate) fn add_config_entry(&mut self, key: &str, value: &str) -> Result<()> {
+		#[cfg(feature = "model-cache")]
+		self.cache.record_config(key, value);
 		let ptr = self.ptr_mut();
 		with_cstr(key.as_bytes(), &|key| {
 			with_cstr(value.as_bytes(), &|value| {
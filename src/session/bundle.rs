@@ -0,0 +1,427 @@
+//! Single-file model bundles, holding a model together with its external data, adapters, and any other files it needs.
+//!
+//! A bundle is a tar archive (optionally compressed with gzip or zstd when the `compression` feature is enabled) with
+//! a `manifest.json` file at its root:
+//!
+//! ```json
+//! {
+//! 	"version": 1,
+//! 	"model": "model.onnx",
+//! 	"adapters": { "formal": "adapters/formal.onnx_adapter" },
+//! 	"metadata": { "name": "my-model", "revision": "2024-06-01" },
+//! 	"session_options": {
+//! 		"optimization_level": "level3",
+//! 		"intra_threads": 4,
+//! 		"config": { "session.disable_prepacking": "1" }
+//! 	}
+//! }
+//! ```
+//!
+//! Only `model` is required. External data files referenced by the model are looked up relative to the model's
+//! directory within the bundle. Any other files (like tokenizers or label lists) can be read with [`Bundle::file`].
+//!
+//! ```no_run
+//! # use ort::session::{Session, bundle::Bundle};
+//! # fn main() -> ort::Result<()> {
+//! let bundle = Bundle::from_file("model.tar.zst")?;
+//! let session = Session::builder()?.with_bundle_options(&bundle)?.commit_from_bundle(&bundle)?;
+//! let labels = bundle.file_str("labels.txt")?;
+//! let adapter = bundle.adapter("formal", None)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc::{
+	collections::BTreeMap,
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use std::{
+	io::Read,
+	path::{Component, Path}
+};
+
+use super::builder::GraphOptimizationLevel;
+use crate::{
+	adapter::Adapter,
+	error::{Error, ErrorCode, Result},
+	memory::Allocator,
+	model::Model,
+	util::json::Json
+};
+
+/// The name of the manifest file at the root of every bundle.
+pub const MANIFEST_NAME: &str = "manifest.json";
+/// The latest version of the manifest format.
+pub const MANIFEST_VERSION: i64 = 1;
+
+/// A model bundle loaded into memory.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+	manifest: Manifest,
+	files: BTreeMap<String, Vec<u8>>
+}
+
+impl Bundle {
+	/// Reads a bundle from a file.
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let bytes = std::fs::read(path).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read `{}`: {e}", path.display())))?;
+		Self::from_memory(&bytes)
+	}
+
+	/// Reads a bundle from the bytes of its archive.
+	pub fn from_memory(bytes: &[u8]) -> Result<Self> {
+		#[cfg(feature = "compression")]
+		if let Some(compression) = super::compression::Compression::detect(bytes) {
+			return Self::from_archive(&compression.decompress(bytes)?);
+		}
+		Self::from_archive(bytes)
+	}
+
+	fn from_archive(bytes: &[u8]) -> Result<Self> {
+		let mut files = BTreeMap::new();
+		let mut archive = tar::Archive::new(bytes);
+		for entry in archive.entries().map_err(|e| invalid(format!("failed to read archive: {e}")))? {
+			let mut entry = entry.map_err(|e| invalid(format!("failed to read archive: {e}")))?;
+			if !entry.header().entry_type().is_file() {
+				continue;
+			}
+			let path = entry.path().map_err(|e| invalid(format!("failed to read archive: {e}")))?;
+			let name = normalize(&path).ok_or_else(|| invalid(format!("file `{}` is outside of the bundle", path.display())))?;
+			// the size comes from the entry's header, so don't trust it beyond what the archive could possibly contain
+			let mut contents = Vec::with_capacity(usize::try_from(entry.size()).unwrap_or(usize::MAX).min(bytes.len()));
+			entry
+				.read_to_end(&mut contents)
+				.map_err(|e| invalid(format!("failed to read `{name}`: {e}")))?;
+			files.insert(name, contents);
+		}
+
+		let manifest = files.get(MANIFEST_NAME).ok_or_else(|| invalid(format!("missing `{MANIFEST_NAME}`")))?;
+		let manifest = core::str::from_utf8(manifest).map_err(|_| invalid(format!("`{MANIFEST_NAME}` is not valid UTF-8")))?;
+		let manifest = Manifest::parse(manifest)?;
+		for path in core::iter::once(&manifest.model).chain(manifest.adapters.iter().map(|(_, path)| path)) {
+			if !files.contains_key(path) {
+				return Err(invalid(format!("`{path}` is listed in the manifest, but is not in the bundle")));
+			}
+		}
+		Ok(Self { manifest, files })
+	}
+
+	/// Returns the bundle's manifest.
+	pub fn manifest(&self) -> &Manifest {
+		&self.manifest
+	}
+
+	/// Returns the bytes of the model.
+	pub fn model(&self) -> &[u8] {
+		&self.files[&self.manifest.model]
+	}
+
+	/// Returns the contents of the file at `path` within the bundle, if it exists.
+	pub fn file(&self, path: &str) -> Option<&[u8]> {
+		normalize(Path::new(path)).and_then(|path| self.files.get(&path)).map(Vec::as_slice)
+	}
+
+	/// Returns the contents of the UTF-8 text file at `path` within the bundle.
+	pub fn file_str(&self, path: &str) -> Result<&str> {
+		let file = self
+			.file(path)
+			.ok_or_else(|| Error::new_with_code(ErrorCode::NoSuchFile, format!("File `{path}` is not in the bundle")))?;
+		core::str::from_utf8(file).map_err(|_| Error::new_with_code(ErrorCode::InvalidArgument, format!("File `{path}` in the bundle is not valid UTF-8")))
+	}
+
+	/// Returns an iterator over the paths of every file in the bundle, including the manifest & model.
+	pub fn file_names(&self) -> impl Iterator<Item = &str> {
+		self.files.keys().map(String::as_str)
+	}
+
+	/// Loads the adapter named `name` in the manifest. See [`Adapter::from_memory`].
+	pub fn adapter(&self, name: &str, allocator: Option<&Allocator>) -> Result<Adapter> {
+		let path = self
+			.manifest
+			.adapters
+			.iter()
+			.find(|(adapter, _)| adapter == name)
+			.map(|(_, path)| path)
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Bundle has no adapter named `{name}`")))?;
+		Adapter::from_memory(&self.files[path], allocator)
+	}
+
+	/// Returns the external data files referenced by the model, as pairs of the location recorded in the model and the
+	/// file's contents.
+	pub(crate) fn external_data(&self) -> Result<Vec<(String, &[u8])>> {
+		// `.ort` models can't be parsed as ONNX, but they don't have external data either
+		let Ok(model) = Model::from_bytes(self.model()) else {
+			return Ok(Vec::new());
+		};
+		let dir = Path::new(&self.manifest.model).parent().unwrap_or_else(|| Path::new(""));
+		let mut external_data: Vec<(String, &[u8])> = Vec::new();
+		for initializer in model
+			.graph
+			.initializers
+			.iter()
+			.chain(model.graph.subgraphs().flat_map(|g| &g.initializers))
+		{
+			let Some(location) = initializer.external_location() else {
+				continue;
+			};
+			if external_data.iter().any(|(l, _)| l == location) {
+				continue;
+			}
+			let contents = normalize(&dir.join(location))
+				.and_then(|path| self.files.get(&path))
+				.ok_or_else(|| invalid(format!("the model references external data file `{location}`, which is not in the bundle")))?;
+			external_data.push((location.to_string(), contents));
+		}
+		Ok(external_data)
+	}
+}
+
+/// The manifest of a [`Bundle`], describing its contents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+	/// The path of the model (`.onnx` or `.ort`) within the bundle.
+	pub model: String,
+	/// Named LoRA adapters (`.onnx_adapter` files) as pairs of their name and path within the bundle.
+	pub adapters: Vec<(String, String)>,
+	/// Free-form metadata, like the model's name or revision.
+	pub metadata: Vec<(String, String)>,
+	/// Session options recommended for the model, which can be applied with
+	/// [`SessionBuilder::with_bundle_options`](crate::session::builder::SessionBuilder::with_bundle_options).
+	pub session_options: RecommendedOptions
+}
+
+impl Manifest {
+	/// Parses a manifest from JSON.
+	pub fn parse(json: &str) -> Result<Self> {
+		let json = Json::parse(json).map_err(|e| invalid(format!("`{MANIFEST_NAME}` is malformed: {}", e.message())))?;
+		if json.as_object().is_none() {
+			return Err(invalid("the manifest must be a JSON object"));
+		}
+		if let Some(version) = json.get("version") {
+			match version.as_i64() {
+				Some(version) if (1..=MANIFEST_VERSION).contains(&version) => {}
+				_ => return Err(invalid("unsupported manifest version"))
+			}
+		}
+		let model = json
+			.get("model")
+			.and_then(Json::as_str)
+			.ok_or_else(|| invalid("the manifest has no `model`"))?;
+		let adapters = string_map(&json, "adapters")?
+			.into_iter()
+			.map(|(name, path)| Ok((name, normalize(Path::new(&path)).ok_or_else(|| invalid(format!("adapter `{path}` is outside of the bundle")))?)))
+			.collect::<Result<_>>()?;
+		Ok(Self {
+			model: normalize(Path::new(model)).ok_or_else(|| invalid(format!("model `{model}` is outside of the bundle")))?,
+			adapters,
+			metadata: string_map(&json, "metadata")?,
+			session_options: match json.get("session_options") {
+				Some(options) => RecommendedOptions::from_json(options)?,
+				None => RecommendedOptions::default()
+			}
+		})
+	}
+
+	/// Returns the value of the metadata entry `key`, if present.
+	pub fn metadata(&self, key: &str) -> Option<&str> {
+		self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+	}
+}
+
+/// Session options recommended by a bundle's manifest. Options which aren't set are left at their defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecommendedOptions {
+	/// `"disable"`, `"level1"`, `"level2"`, or `"level3"`.
+	pub optimization_level: Option<GraphOptimizationLevel>,
+	pub intra_threads: Option<usize>,
+	pub inter_threads: Option<usize>,
+	pub parallel_execution: Option<bool>,
+	pub memory_pattern: Option<bool>,
+	/// Session configuration entries, like `session.disable_prepacking`.
+	pub config: Vec<(String, String)>
+}
+
+impl RecommendedOptions {
+	fn from_json(json: &Json) -> Result<Self> {
+		if json.as_object().is_none() {
+			return Err(invalid("`session_options` must be an object"));
+		}
+		let optimization_level = match json.get("optimization_level").map(|level| level.as_str()) {
+			None => None,
+			Some(Some("disable")) => Some(GraphOptimizationLevel::Disable),
+			Some(Some("level1")) => Some(GraphOptimizationLevel::Level1),
+			Some(Some("level2")) => Some(GraphOptimizationLevel::Level2),
+			Some(Some("level3")) => Some(GraphOptimizationLevel::Level3),
+			Some(_) => return Err(invalid("`optimization_level` must be one of `disable`, `level1`, `level2`, or `level3`"))
+		};
+		let threads = |key: &str| match json.get(key) {
+			None => Ok(None),
+			Some(n) => n
+				.as_i64()
+				.and_then(|n| usize::try_from(n).ok())
+				.map(Some)
+				.ok_or_else(|| invalid(format!("`{key}` must be a non-negative integer")))
+		};
+		let flag = |key: &str| match json.get(key) {
+			None => Ok(None),
+			Some(Json::Bool(b)) => Ok(Some(*b)),
+			Some(_) => Err(invalid(format!("`{key}` must be a boolean")))
+		};
+		Ok(Self {
+			optimization_level,
+			intra_threads: threads("intra_threads")?,
+			inter_threads: threads("inter_threads")?,
+			parallel_execution: flag("parallel_execution")?,
+			memory_pattern: flag("memory_pattern")?,
+			config: string_map(json, "config")?
+		})
+	}
+}
+
+fn string_map(json: &Json, key: &str) -> Result<Vec<(String, String)>> {
+	let Some(map) = json.get(key) else {
+		return Ok(Vec::new());
+	};
+	map.as_object()
+		.ok_or_else(|| invalid(format!("`{key}` must be an object")))?
+		.iter()
+		.map(|(k, v)| match v.as_str() {
+			Some(v) => Ok((k.clone(), v.to_string())),
+			None => Err(invalid(format!("`{key}.{k}` must be a string")))
+		})
+		.collect()
+}
+
+/// Normalizes a path within the bundle to `/`-separated components, returning `None` if it escapes the bundle's root.
+fn normalize(path: &Path) -> Option<String> {
+	let mut components: Vec<&str> = Vec::new();
+	for component in path.components() {
+		match component {
+			Component::Normal(c) => components.push(c.to_str()?),
+			Component::CurDir => {}
+			Component::ParentDir => {
+				components.pop()?;
+			}
+			Component::RootDir | Component::Prefix(_) => return None
+		}
+	}
+	(!components.is_empty()).then(|| components.join("/"))
+}
+
+fn invalid(message: impl core::fmt::Display) -> Error {
+	Error::new_with_code(ErrorCode::InvalidArgument, format!("Invalid model bundle: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::{Bundle, Manifest, RecommendedOptions, normalize};
+	use crate::{
+		model::{GraphBuilder, Initializer, Node, TensorData},
+		session::builder::GraphOptimizationLevel,
+		tensor::TensorElementType
+	};
+
+	fn archive(files: &[(&str, &[u8])]) -> std::io::Result<Vec<u8>> {
+		let mut builder = tar::Builder::new(Vec::new());
+		for (path, contents) in files {
+			let mut header = tar::Header::new_gnu();
+			header.set_size(contents.len() as u64);
+			header.set_mode(0o644);
+			header.set_cksum();
+			builder.append_data(&mut header, path, *contents)?;
+		}
+		builder.into_inner()
+	}
+
+	#[test]
+	fn test_manifest() -> crate::Result<()> {
+		let manifest = Manifest::parse(
+			r#"{
+				"version": 1,
+				"model": "./model/model.onnx",
+				"adapters": { "formal": "adapters/../adapters/formal.onnx_adapter" },
+				"metadata": { "name": "test" },
+				"session_options": { "optimization_level": "level1", "intra_threads": 2, "memory_pattern": false, "config": { "a": "1" } },
+				"future_field": [1, 2, 3]
+			}"#
+		)?;
+		assert_eq!(manifest.model, "model/model.onnx");
+		assert_eq!(manifest.adapters, [("formal".to_string(), "adapters/formal.onnx_adapter".to_string())]);
+		assert_eq!(manifest.metadata("name"), Some("test"));
+		assert_eq!(
+			manifest.session_options,
+			RecommendedOptions {
+				optimization_level: Some(GraphOptimizationLevel::Level1),
+				intra_threads: Some(2),
+				memory_pattern: Some(false),
+				config: vec![("a".to_string(), "1".to_string())],
+				..RecommendedOptions::default()
+			}
+		);
+
+		assert!(Manifest::parse(r#"{}"#).is_err());
+		assert!(Manifest::parse(r#"{"model": "../model.onnx"}"#).is_err());
+		assert!(Manifest::parse(r#"{"model": "model.onnx", "version": 2}"#).is_err());
+		assert!(Manifest::parse(r#"{"model": "model.onnx", "metadata": {"a": 1}}"#).is_err());
+		assert!(Manifest::parse(r#"{"model": "model.onnx", "session_options": {"intra_threads": -1}}"#).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_bundle() -> crate::Result<()> {
+		let model = GraphBuilder::new("bundled")
+			.with_input("x", TensorElementType::Float32, [2_i64])
+			.with_output("y", TensorElementType::Float32, [2_i64])
+			.with_raw_initializer(Initializer::new(
+				"bias",
+				TensorElementType::Float32,
+				[2],
+				TensorData::External(vec![("location".to_string(), "weights/bias.bin".to_string())])
+			))
+			.with_node(Node::new("Add").with_inputs(["x", "bias"]).with_outputs(["y"]))
+			.build()?
+			.to_bytes();
+		let manifest = br#"{ "model": "graph/model.onnx" }"#;
+		let files: [(&str, &[u8]); 4] = [
+			("manifest.json", manifest),
+			("graph/model.onnx", &model),
+			("./graph/weights/bias.bin", &[0; 8]),
+			(
+				"labels.txt",
+				b"cat
+dog
+"
+			)
+		];
+		let bundle = Bundle::from_memory(&archive(&files).map_err(crate::Error::wrap)?)?;
+		assert_eq!(bundle.model(), &model[..]);
+		assert_eq!(bundle.file_str("labels.txt")?.lines().count(), 2);
+		assert!(bundle.file("missing.txt").is_none());
+		assert_eq!(bundle.file_names().count(), 4);
+		let external_data = bundle.external_data()?;
+		assert_eq!(external_data.len(), 1);
+		assert_eq!(external_data[0].0, "weights/bias.bin");
+		assert!(bundle.adapter("missing", None).is_err());
+
+		// missing external data
+		let bundle = Bundle::from_memory(&archive(&files[..2]).map_err(crate::Error::wrap)?)?;
+		assert!(bundle.external_data().is_err());
+		// missing model & manifest
+		assert!(Bundle::from_memory(&archive(&[files[0]]).map_err(crate::Error::wrap)?).is_err());
+		assert!(Bundle::from_memory(&archive(&files[1..]).map_err(crate::Error::wrap)?).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_normalize() {
+		assert_eq!(normalize(Path::new("./a/b/../c")).as_deref(), Some("a/c"));
+		assert_eq!(normalize(Path::new("a/../../b")), None);
+		assert_eq!(normalize(Path::new("/etc/passwd")), None);
+		assert_eq!(normalize(Path::new(".")), None);
+	}
+}
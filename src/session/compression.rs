@@ -0,0 +1,188 @@
+//! Transparent loading of compressed models.
+//!
+//! With the `compression` feature enabled, [`SessionBuilder::commit_from_file`] detects models compressed with gzip
+//! or zstd (e.g. `model.onnx.gz`, `model.onnx.zst`, or `model.ort.zst`) by their magic bytes, and decompresses them
+//! into memory before creating the session. Uncompressed models are loaded directly from disk as usual.
+//!
+//! External data files next to a compressed model may be compressed too: if the model references `model.data` and
+//! only `model.data.zst` or `model.data.gz` exists, it is decompressed into memory and provided to ONNX Runtime in its
+//! place. Uncompressed external data files are still read from the model's directory.
+//!
+//! ```no_run
+//! # use ort::session::Session;
+//! # fn main() -> ort::Result<()> {
+//! let session = Session::builder()?.commit_from_file("model.onnx.zst")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SessionBuilder::commit_from_file`]: crate::session::builder::SessionBuilder::commit_from_file
+
+use alloc::{format, vec::Vec};
+use std::{
+	fs::File,
+	io::{BufReader, Read},
+	path::{Path, PathBuf}
+};
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// A compression format supported for models and their external data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+	/// gzip, as produced by `gzip model.onnx`.
+	Gzip,
+	/// Zstandard, as produced by `zstd model.onnx`. Decompresses considerably faster than gzip.
+	Zstd
+}
+
+impl Compression {
+	const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+	const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+	/// Detects the compression format of `bytes` from its magic bytes, returning `None` if it isn't compressed (or is
+	/// compressed with an unsupported format). Only the first 4 bytes are inspected.
+	pub fn detect(bytes: &[u8]) -> Option<Self> {
+		if bytes.starts_with(&Self::ZSTD_MAGIC) {
+			Some(Self::Zstd)
+		} else if bytes.starts_with(&Self::GZIP_MAGIC) {
+			Some(Self::Gzip)
+		} else {
+			None
+		}
+	}
+
+	/// The file extension conventionally used for this format, without the leading dot.
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Gzip => "gz",
+			Self::Zstd => "zst"
+		}
+	}
+
+	/// Decompresses all of `reader` into memory.
+	pub fn decompress(self, reader: impl Read) -> Result<Vec<u8>> {
+		let mut decompressed = Vec::new();
+		let result = match self {
+			Self::Gzip => flate2::read::MultiGzDecoder::new(reader).read_to_end(&mut decompressed),
+			Self::Zstd => zstd::Decoder::new(reader).and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+		};
+		result.map_err(|e| Error::new_with_code(ErrorCode::InvalidArgument, format!("Failed to decompress {self:?} data: {e}")))?;
+		Ok(decompressed)
+	}
+}
+
+/// Reads & decompresses the model at `path`, returning `None` if it isn't compressed. If `forced` is set, the file is
+/// decompressed with that format regardless of its magic bytes.
+pub(crate) fn read_model(path: &Path, forced: Option<Compression>) -> Result<Option<Vec<u8>>> {
+	let mut reader = BufReader::new(open(path)?);
+	let compression = match forced {
+		Some(compression) => compression,
+		None => {
+			let magic = std::io::BufRead::fill_buf(&mut reader).map_err(|e| read_error(path, e))?;
+			match Compression::detect(magic) {
+				Some(compression) => compression,
+				None => return Ok(None)
+			}
+		}
+	};
+	crate::debug!(?compression, model_path = %path.display(), "Decompressing model");
+	compression.decompress(reader).map(Some).map_err(|e| with_path(e, path))
+}
+
+/// Decompresses `bytes`, the contents of the model file at `path`, if they are compressed (or `forced` is set).
+/// Returns `None` for uncompressed models.
+#[cfg(feature = "signing")]
+pub(crate) fn decompress_model(path: &Path, bytes: &[u8], forced: Option<Compression>) -> Result<Option<Vec<u8>>> {
+	let Some(compression) = forced.or_else(|| Compression::detect(bytes)) else {
+		return Ok(None);
+	};
+	crate::debug!(?compression, model_path = %path.display(), "Decompressing model");
+	compression.decompress(bytes).map(Some).map_err(|e| with_path(e, path))
+}
+
+/// Locates the external data file `location` relative to `dir`. Returns the path of the file and the format it is
+/// compressed with, or `None` if only the uncompressed file exists (or no file exists at all).
+pub(crate) fn find_compressed_external_file(dir: &Path, location: &str) -> Option<(PathBuf, Compression)> {
+	if dir.join(location).exists() {
+		return None;
+	}
+	[Compression::Zstd, Compression::Gzip].into_iter().find_map(|compression| {
+		let path = dir.join(format!("{location}.{}", compression.extension()));
+		path.exists().then_some((path, compression))
+	})
+}
+
+/// Reads & decompresses the external data file at `path`.
+pub(crate) fn read_external_file(path: &Path, compression: Compression) -> Result<Vec<u8>> {
+	compression.decompress(BufReader::new(open(path)?)).map_err(|e| with_path(e, path))
+}
+
+fn open(path: &Path) -> Result<File> {
+	File::open(path).map_err(|e| read_error(path, e))
+}
+
+fn read_error(path: &Path, e: std::io::Error) -> Error {
+	Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read `{}`: {e}", path.display()))
+}
+
+fn with_path(e: Error, path: &Path) -> Error {
+	Error::new_with_code(e.code(), format!("{} (in `{}`)", e.message(), path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+
+	use super::{Compression, find_compressed_external_file, read_external_file, read_model};
+
+	fn compress(data: &[u8], compression: Compression) -> std::io::Result<Vec<u8>> {
+		match compression {
+			Compression::Gzip => {
+				let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+				encoder.write_all(data)?;
+				encoder.finish()
+			}
+			Compression::Zstd => zstd::encode_all(data, 0)
+		}
+	}
+
+	#[test]
+	fn test_roundtrip() -> crate::Result<()> {
+		let data: Vec<u8> = (0..4096_u32).flat_map(|i| (i % 7).to_le_bytes()).collect();
+		assert_eq!(Compression::detect(&data), None);
+		for compression in [Compression::Gzip, Compression::Zstd] {
+			let compressed = compress(&data, compression).map_err(crate::Error::wrap)?;
+			assert!(compressed.len() < data.len());
+			assert_eq!(Compression::detect(&compressed), Some(compression));
+			assert_eq!(compression.decompress(&compressed[..])?, data);
+
+			let truncated = &compressed[..compressed.len() / 2];
+			assert!(compression.decompress(truncated).is_err());
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_files() -> crate::Result<()> {
+		let dir = std::env::temp_dir().join(format!("ort-test-compression-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).map_err(crate::Error::wrap)?;
+		let data = b"definitely an onnx model".repeat(16);
+		std::fs::write(dir.join("model.onnx"), &data).map_err(crate::Error::wrap)?;
+		std::fs::write(dir.join("model.onnx.zst"), compress(&data, Compression::Zstd).map_err(crate::Error::wrap)?).map_err(crate::Error::wrap)?;
+		std::fs::write(dir.join("weights.bin.gz"), compress(&data, Compression::Gzip).map_err(crate::Error::wrap)?).map_err(crate::Error::wrap)?;
+
+		assert_eq!(read_model(&dir.join("model.onnx"), None)?, None);
+		assert_eq!(read_model(&dir.join("model.onnx.zst"), None)?.as_deref(), Some(&data[..]));
+		assert!(read_model(&dir.join("model.onnx"), Some(Compression::Gzip)).is_err());
+
+		assert_eq!(find_compressed_external_file(&dir, "model.onnx"), None);
+		assert_eq!(find_compressed_external_file(&dir, "missing.bin"), None);
+		let (path, compression) = find_compressed_external_file(&dir, "weights.bin").expect("compressed file should be found");
+		assert_eq!(compression, Compression::Gzip);
+		assert_eq!(read_external_file(&path, compression)?, data);
+
+		std::fs::remove_dir_all(dir).map_err(crate::Error::wrap)?;
+		Ok(())
+	}
+}
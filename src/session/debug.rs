@@ -0,0 +1,453 @@
+//! Debug taps, which expose a model's intermediate tensors for numerical debugging.
+//!
+//! When a model produces wrong results on one execution provider (or after quantization, or after an upgrade of ONNX
+//! Runtime), the quickest way to find the culprit is to compare intermediate values between a good run and a bad run.
+//! [`SessionBuilder::with_debug_taps`] rewrites the model when the session is committed so that the selected
+//! intermediate tensors become additional graph outputs. They are returned separately via
+//! [`SessionOutputs::taps`], so the session's regular [`outputs`](Session::outputs) are unchanged.
+//!
+//! ```no_run
+//! # use ort::{session::{Session, debug::{DebugTap, Tolerance, compare_taps}}, execution_providers::CUDAExecutionProvider, value::TensorRef};
+//! # fn main() -> ort::Result<()> {
+//! let taps = [DebugTap::op_type("Conv"), DebugTap::name("*/attention/*")];
+//! let cpu = Session::builder()?.with_debug_taps(taps.clone())?.commit_from_file("model.onnx")?;
+//! let cuda = Session::builder()?
+//! 	.with_execution_providers([CUDAExecutionProvider::default().build().error_on_failure()])?
+//! 	.with_debug_taps(taps)?
+//! 	.commit_from_file("model.onnx")?;
+//!
+//! let input = ndarray::Array4::<f32>::zeros((1, 3, 224, 224));
+//! let expected = cpu.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
+//! let actual = cuda.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
+//! if let Some(divergence) = compare_taps(expected.taps(), actual.taps(), Tolerance::default())? {
+//! 	println!("{divergence}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SessionBuilder::with_debug_taps`]: crate::session::builder::SessionBuilder::with_debug_taps
+//! [`SessionOutputs::taps`]: crate::session::SessionOutputs::taps
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	model::Model,
+	session::{Output, Session},
+	tensor::TensorElementType,
+	value::{DynValue, ValueType}
+};
+
+/// Selects intermediate values of a model to expose with
+/// [`SessionBuilder::with_debug_taps`](crate::session::builder::SessionBuilder::with_debug_taps).
+///
+/// Strings convert to [`DebugTap::Name`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugTap {
+	/// Taps every value whose name matches this pattern. `*` matches any run of characters (including none), and `?`
+	/// matches exactly one character; a pattern with neither matches one value by its exact name.
+	Name(String),
+	/// Taps every output of nodes with this operator type, e.g. `"MatMul"`.
+	OpType(String)
+}
+
+impl DebugTap {
+	/// Taps values whose name matches a glob pattern. See [`DebugTap::Name`].
+	pub fn name(pattern: impl Into<String>) -> Self {
+		Self::Name(pattern.into())
+	}
+
+	/// Taps the outputs of every node with the given operator type.
+	pub fn op_type(op_type: impl Into<String>) -> Self {
+		Self::OpType(op_type.into())
+	}
+
+	fn matches(&self, value: &str, op_type: &str) -> bool {
+		match self {
+			Self::Name(pattern) => glob_matches(pattern, value),
+			Self::OpType(ty) => ty == op_type
+		}
+	}
+}
+
+impl From<&str> for DebugTap {
+	fn from(pattern: &str) -> Self {
+		Self::name(pattern)
+	}
+}
+
+impl From<String> for DebugTap {
+	fn from(pattern: String) -> Self {
+		Self::Name(pattern)
+	}
+}
+
+/// An intermediate value exposed by a debug tap.
+#[derive(Debug)]
+pub struct Tap {
+	/// Name of the tapped value.
+	pub name: String,
+	/// Type of the tapped value.
+	pub output_type: ValueType,
+	/// Name of the node producing the value; may be empty.
+	pub node: String,
+	/// Index of the node producing the value in the model's (topologically sorted) main graph.
+	pub node_index: usize,
+	/// Operator type of the node producing the value.
+	pub op_type: String
+}
+
+/// The value of a [`Tap`] produced by one inference call. See [`SessionOutputs::taps`].
+///
+/// [`SessionOutputs::taps`]: crate::session::SessionOutputs::taps
+#[derive(Debug)]
+pub struct TappedValue<'s> {
+	/// The tap this value was produced for.
+	pub tap: &'s Tap,
+	/// The tapped value.
+	pub value: DynValue
+}
+
+/// A tapped value located while rewriting the model, before the session is created.
+#[derive(Debug, Clone)]
+pub(crate) struct TapSite {
+	name: String,
+	node: String,
+	node_index: usize,
+	op_type: String
+}
+
+/// Adds every intermediate value of the main graph selected by `taps` as an output of `model`, returning the tapped
+/// values in node order.
+pub(crate) fn insert_taps(model: &mut Model, taps: &[DebugTap]) -> Result<Vec<TapSite>> {
+	let mut sites: Vec<TapSite> = Vec::new();
+	let mut matched = alloc::vec![false; taps.len()];
+	for (node_index, node) in model.graph.nodes.iter().enumerate() {
+		for output in node.outputs.iter().filter(|output| !output.is_empty()) {
+			let mut selected = false;
+			for (tap, matched) in taps.iter().zip(matched.iter_mut()) {
+				if tap.matches(output, &node.op_type) {
+					*matched = true;
+					selected = true;
+				}
+			}
+			if selected && !model.graph.outputs.iter().any(|o| &o.name == output) {
+				sites.push(TapSite {
+					name: output.clone(),
+					node: node.name.clone(),
+					node_index,
+					op_type: node.op_type.clone()
+				});
+			}
+		}
+	}
+
+	if let Some((tap, _)) = taps.iter().zip(&matched).find(|(_, matched)| !**matched) {
+		return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Debug tap {tap:?} does not match any intermediate value of the model")));
+	}
+
+	for site in &sites {
+		model.add_output(&site.name)?;
+	}
+	Ok(sites)
+}
+
+/// Moves the outputs added for `sites` out of the session's regular outputs.
+pub(crate) fn split_taps(outputs: &mut Vec<Output>, sites: Vec<TapSite>) -> Vec<Tap> {
+	sites
+		.into_iter()
+		.filter_map(|site| {
+			let index = outputs.iter().position(|output| output.name == site.name)?;
+			let output = outputs.remove(index);
+			Some(Tap {
+				name: site.name,
+				output_type: output.output_type,
+				node: site.node,
+				node_index: site.node_index,
+				op_type: site.op_type
+			})
+		})
+		.collect()
+}
+
+impl Session {
+	/// Returns the intermediate values exposed by
+	/// [`SessionBuilder::with_debug_taps`](crate::session::builder::SessionBuilder::with_debug_taps), in the order
+	/// of the nodes producing them.
+	pub fn taps(&self) -> &[Tap] {
+		&self.taps
+	}
+}
+
+/// Matches `name` against a pattern where `*` matches any run of characters and `?` matches one character.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let name: Vec<char> = name.chars().collect();
+	let (mut p, mut n) = (0, 0);
+	// position of the last `*` in the pattern, and the position in `name` it is currently matched up to
+	let mut star: Option<(usize, usize)> = None;
+	while n < name.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				star = Some((p, n));
+				p += 1;
+			}
+			Some(&c) if c == '?' || c == name[n] => {
+				p += 1;
+				n += 1;
+			}
+			_ => match star {
+				// let the last `*` swallow one more character & retry
+				Some((star_p, star_n)) => {
+					star = Some((star_p, star_n + 1));
+					p = star_p + 1;
+					n = star_n + 1;
+				}
+				None => return false
+			}
+		}
+	}
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// How far apart two tapped values may be before [`compare_taps`] reports them as diverging.
+///
+/// Two elements `expected` & `actual` are considered equal if `|expected - actual| <= absolute + relative *
+/// |expected|`, like NumPy's `isclose`. `NaN`s compare equal to each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+	/// The allowed absolute difference.
+	pub absolute: f64,
+	/// The allowed difference relative to the magnitude of the expected value.
+	pub relative: f64
+}
+
+impl Tolerance {
+	/// Creates a new tolerance from an absolute & relative difference.
+	pub fn new(absolute: f64, relative: f64) -> Self {
+		Self { absolute, relative }
+	}
+
+	/// Requires values to be exactly equal.
+	pub fn exact() -> Self {
+		Self::new(0.0, 0.0)
+	}
+
+	fn allows(&self, expected: f64, actual: f64) -> bool {
+		if expected.is_nan() || actual.is_nan() {
+			return expected.is_nan() && actual.is_nan();
+		}
+		// covers infinities of the same sign, for which the difference below would be NaN
+		expected == actual || (expected - actual).abs() <= self.absolute + self.relative * expected.abs()
+	}
+}
+
+impl Default for Tolerance {
+	/// An absolute tolerance of `1e-5` and a relative tolerance of `1e-3`, which is suitable for comparing `f32`
+	/// computations across execution providers.
+	fn default() -> Self {
+		Self::new(1e-5, 1e-3)
+	}
+}
+
+/// How a tapped value differed between two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+	/// The values have different shapes.
+	Shape { expected: Vec<i64>, actual: Vec<i64> },
+	/// An element differs by more than the tolerance. `index` is the index of the first such element in the flattened
+	/// tensor, and `max_difference` is the largest absolute difference across all elements.
+	Value {
+		index: usize,
+		expected: f64,
+		actual: f64,
+		max_difference: f64
+	}
+}
+
+/// The first tapped value to differ between two runs, as reported by [`compare_taps`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+	/// Name of the diverging value.
+	pub name: String,
+	/// Name of the node producing the value; may be empty.
+	pub node: String,
+	/// Index of the node producing the value in the model's main graph.
+	pub node_index: usize,
+	/// Operator type of the node producing the value.
+	pub op_type: String,
+	/// How the value differs.
+	pub mismatch: Mismatch
+}
+
+impl fmt::Display for Divergence {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.node.is_empty() {
+			write!(f, "Node #{} ({})", self.node_index, self.op_type)?;
+		} else {
+			write!(f, "Node `{}` ({})", self.node, self.op_type)?;
+		}
+		write!(f, " output `{}` diverges: ", self.name)?;
+		match &self.mismatch {
+			Mismatch::Shape { expected, actual } => write!(f, "expected shape {expected:?}, got {actual:?}"),
+			Mismatch::Value {
+				index,
+				expected,
+				actual,
+				max_difference
+			} => write!(f, "element {index} is {actual}, expected {expected} (max difference {max_difference})")
+		}
+	}
+}
+
+/// Compares the tapped values of two inference calls - for example, two runs of the same session with different
+/// execution providers - and returns the first value (in node order) which differs by more than `tolerance`.
+///
+/// Values are matched by name; taps only present in one of the runs are ignored, as are non-numeric values like
+/// string tensors, sequences, and maps. Elements are compared as `f64`s, so a value may have a different element type
+/// in each run, e.g. when comparing a model against its `f16` conversion. Since nodes are visited in topological order,
+/// the returned node is the earliest one among the tapped nodes to diverge, which is usually where the problem
+/// originates.
+pub fn compare_taps(expected: &[TappedValue<'_>], actual: &[TappedValue<'_>], tolerance: Tolerance) -> Result<Option<Divergence>> {
+	for expected in expected {
+		let Some(actual) = actual.iter().find(|actual| actual.tap.name == expected.tap.name) else {
+			continue;
+		};
+		if let Some(mismatch) = compare_values(&expected.value, &actual.value, tolerance)? {
+			return Ok(Some(Divergence {
+				name: expected.tap.name.clone(),
+				node: expected.tap.node.clone(),
+				node_index: expected.tap.node_index,
+				op_type: expected.tap.op_type.clone(),
+				mismatch
+			}));
+		}
+	}
+	Ok(None)
+}
+
+pub(crate) fn compare_values(expected: &DynValue, actual: &DynValue, tolerance: Tolerance) -> Result<Option<Mismatch>> {
+	let (ValueType::Tensor { ty: expected_ty, .. }, ValueType::Tensor { ty: actual_ty, .. }) = (expected.dtype(), actual.dtype()) else {
+		return Ok(None);
+	};
+	let (Some((expected_shape, expected_data)), Some((actual_shape, actual_data))) = (to_f64(expected, *expected_ty)?, to_f64(actual, *actual_ty)?) else {
+		return Ok(None);
+	};
+	if expected_shape != actual_shape {
+		return Ok(Some(Mismatch::Shape {
+			expected: expected_shape,
+			actual: actual_shape
+		}));
+	}
+	Ok(compare_elements(&expected_data, &actual_data, tolerance))
+}
+
+fn compare_elements(expected: &[f64], actual: &[f64], tolerance: Tolerance) -> Option<Mismatch> {
+	let mut first = None;
+	let mut max_difference = 0.0_f64;
+	for (index, (&e, &a)) in expected.iter().zip(actual).enumerate() {
+		if !tolerance.allows(e, a) {
+			first.get_or_insert((index, e, a));
+			max_difference = max_difference.max(if e.is_nan() || a.is_nan() { f64::NAN } else { (e - a).abs() });
+		}
+	}
+	first.map(|(index, expected, actual)| Mismatch::Value {
+		index,
+		expected,
+		actual,
+		max_difference
+	})
+}
+
+/// Extracts the shape & elements of a numeric tensor as `f64`s, or `None` for non-numeric tensors.
+fn to_f64(value: &DynValue, ty: TensorElementType) -> Result<Option<(Vec<i64>, Vec<f64>)>> {
+	macro_rules! extract {
+		($t:ty, $convert:expr) => {{
+			let (shape, data) = value.try_extract_tensor::<$t>()?;
+			Some((shape.to_vec(), data.iter().copied().map($convert).collect()))
+		}};
+	}
+	Ok(match ty {
+		TensorElementType::Float32 => extract!(f32, f64::from),
+		TensorElementType::Float64 => extract!(f64, |x| x),
+		#[cfg(feature = "half")]
+		TensorElementType::Float16 => extract!(half::f16, f64::from),
+		#[cfg(feature = "half")]
+		TensorElementType::Bfloat16 => extract!(half::bf16, f64::from),
+		TensorElementType::Int8 => extract!(i8, f64::from),
+		TensorElementType::Int16 => extract!(i16, f64::from),
+		TensorElementType::Int32 => extract!(i32, f64::from),
+		TensorElementType::Int64 => extract!(i64, |x| x as f64),
+		TensorElementType::Uint8 => extract!(u8, f64::from),
+		TensorElementType::Uint16 => extract!(u16, f64::from),
+		TensorElementType::Uint32 => extract!(u32, f64::from),
+		TensorElementType::Uint64 => extract!(u64, |x| x as f64),
+		TensorElementType::Bool => extract!(bool, |x| if x { 1.0 } else { 0.0 }),
+		_ => None
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{string::ToString, vec};
+
+	use super::{DebugTap, Mismatch, Tolerance, compare_elements, glob_matches, insert_taps};
+	use crate::{
+		model::{GraphBuilder, Node},
+		tensor::TensorElementType
+	};
+
+	#[test]
+	fn test_glob() {
+		assert!(glob_matches("conv1/out", "conv1/out"));
+		assert!(!glob_matches("conv1/out", "conv1/outs"));
+		assert!(glob_matches("*", ""));
+		assert!(glob_matches("*/attention/*", "layer.0/attention/softmax"));
+		assert!(!glob_matches("*/attention/*", "layer.0/mlp/gelu"));
+		assert!(glob_matches("layer.?/*", "layer.3/mlp/gelu"));
+		assert!(!glob_matches("layer.?/*", "layer.12/mlp/gelu"));
+		assert!(glob_matches("*a*b", "xxaxxbxab"));
+		assert!(!glob_matches("*a*b", "xxaxxbxa"));
+	}
+
+	#[test]
+	fn test_insert_taps() -> crate::Result<()> {
+		let mut model = GraphBuilder::new("mlp")
+			.with_input("x", TensorElementType::Float32, [4_i64])
+			.with_output("y", TensorElementType::Float32, [4_i64])
+			.with_node(Node::new("Relu").with_name("relu").with_inputs(["x"]).with_outputs(["hidden/relu"]))
+			.with_node(Node::new("Neg").with_inputs(["hidden/relu"]).with_outputs(["hidden/neg"]))
+			.with_node(Node::new("Relu").with_inputs(["hidden/neg"]).with_outputs(["y"]))
+			.build()?;
+
+		let sites = insert_taps(&mut model.clone(), &["hidden/*".into(), DebugTap::op_type("Relu")])?;
+		// graph outputs are never tapped
+		assert_eq!(sites.iter().map(|site| site.name.as_str()).collect::<vec::Vec<_>>(), ["hidden/relu", "hidden/neg"]);
+		assert_eq!((sites[0].node.as_str(), sites[1].node_index, sites[1].op_type.as_str()), ("relu", 1, "Neg"));
+
+		let err = insert_taps(&mut model.clone(), &["hidden/*".into(), DebugTap::op_type("Softmax")]).expect_err("unmatched tap");
+		assert!(err.to_string().contains("OpType(\"Softmax\") does not match any intermediate value"), "{err}");
+
+		insert_taps(&mut model, &[DebugTap::name("hidden/neg")])?;
+		assert_eq!(model.graph.outputs.iter().map(|output| output.name.as_str()).collect::<vec::Vec<_>>(), ["y", "hidden/neg"]);
+		model.validate()
+	}
+
+	#[test]
+	fn test_compare() {
+		let tolerance = Tolerance::new(1e-3, 0.01);
+		assert_eq!(compare_elements(&[1.0, 100.0, f64::NAN, f64::INFINITY], &[1.0005, 100.9, f64::NAN, f64::INFINITY], tolerance), None);
+		assert_eq!(
+			compare_elements(&[1.0, 2.0, 3.0, 4.0], &[1.0, 2.5, 3.0, 1.0], tolerance),
+			Some(Mismatch::Value {
+				index: 1,
+				expected: 2.0,
+				actual: 2.5,
+				max_difference: 3.0
+			})
+		);
+		assert!(matches!(compare_elements(&[1.0], &[f64::NAN], Tolerance::exact()), Some(Mismatch::Value { index: 0, .. })));
+	}
+}
@@ -0,0 +1,391 @@
+//! Loading of encrypted models, so plaintext `.onnx` files never need to be stored on disk.
+//!
+//! Models are encrypted with [`encrypt`] (or [`encrypt_file`]) using AES-256-GCM or ChaCha20-Poly1305, producing a
+//! container consisting of a small [`Header`] followed by the ciphertext. At runtime,
+//! [`SessionBuilder::commit_from_encrypted_file`] decrypts the model into memory which is zeroed when it is dropped,
+//! and commits the session from it. The key is supplied by a [`KeyProvider`]: it can be read from an environment
+//! variable with [`EnvKey`], from a file with [`FileKey`], or from anywhere else with a closure.
+//!
+//! ```no_run
+//! # use ort::session::{Session, encryption::{self, Algorithm, EnvKey, Key}};
+//! # fn main() -> ort::Result<()> {
+//! // at build time
+//! let key = Key::generate();
+//! encryption::encrypt_file("model.onnx", "model.onnx.enc", Algorithm::Aes256Gcm, &key)?;
+//!
+//! // on the device, with the hex-encoded key in `MODEL_KEY`
+//! let session = Session::builder()?.commit_from_encrypted_file("model.onnx.enc", &EnvKey::new("MODEL_KEY"))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Models with external data can be loaded by encrypting each external data file separately and registering it with
+//! [`SessionBuilder::with_encrypted_external_initializer_file_in_memory`].
+//!
+//! Note that ONNX Runtime keeps its own copy of the model's weights for the lifetime of the session, which `ort`
+//! cannot zero.
+//!
+//! [`SessionBuilder::commit_from_encrypted_file`]: crate::session::builder::SessionBuilder::commit_from_encrypted_file
+//! [`SessionBuilder::with_encrypted_external_initializer_file_in_memory`]: crate::session::builder::SessionBuilder::with_encrypted_external_initializer_file_in_memory
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, ops::Deref};
+use std::path::{Path, PathBuf};
+
+use aes_gcm::{
+	Aes256Gcm,
+	aead::{AeadCore, AeadInPlace, KeyInit, OsRng, rand_core::RngCore}
+};
+use chacha20poly1305::ChaCha20Poly1305;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// The magic bytes at the start of every encrypted model container.
+pub const MAGIC: [u8; 4] = *b"ORTE";
+/// The version of the container format written by [`encrypt`].
+pub const FORMAT_VERSION: u8 = 1;
+
+const NONCE_LEN: usize = 12;
+
+/// An authenticated encryption algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+	/// AES-256 in Galois/Counter Mode. Fastest on CPUs with AES instructions.
+	Aes256Gcm,
+	/// ChaCha20-Poly1305. Faster than AES-GCM on CPUs without AES instructions, like many ARM SoCs.
+	ChaCha20Poly1305
+}
+
+impl Algorithm {
+	fn id(self) -> u8 {
+		match self {
+			Self::Aes256Gcm => 1,
+			Self::ChaCha20Poly1305 => 2
+		}
+	}
+
+	fn from_id(id: u8) -> Option<Self> {
+		match id {
+			1 => Some(Self::Aes256Gcm),
+			2 => Some(Self::ChaCha20Poly1305),
+			_ => None
+		}
+	}
+}
+
+/// The header of an encrypted model container.
+///
+/// The container starts with the 4 bytes of [`MAGIC`], followed by the format version, the algorithm ID (`1` for
+/// AES-256-GCM, `2` for ChaCha20-Poly1305), the length of the nonce, and a reserved zero byte. The nonce comes next,
+/// then the ciphertext with its 16-byte authentication tag. The whole header is authenticated along with the
+/// ciphertext, so it can't be modified without decryption failing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+	pub version: u8,
+	pub algorithm: Algorithm,
+	pub nonce: [u8; NONCE_LEN]
+}
+
+impl Header {
+	/// The length of an encoded header in bytes.
+	pub const LEN: usize = 8 + NONCE_LEN;
+
+	/// Parses the header at the start of an encrypted model container.
+	pub fn parse(container: &[u8]) -> Result<Header> {
+		if container.len() < Self::LEN || container[..4] != MAGIC {
+			return Err(invalid("not an encrypted model"));
+		}
+		let version = container[4];
+		if version != FORMAT_VERSION {
+			return Err(invalid(format!("unsupported container version {version}")));
+		}
+		let algorithm = Algorithm::from_id(container[5]).ok_or_else(|| invalid(format!("unknown algorithm ID {}", container[5])))?;
+		if usize::from(container[6]) != NONCE_LEN || container[7] != 0 {
+			return Err(invalid("malformed header"));
+		}
+		let mut nonce = [0; NONCE_LEN];
+		nonce.copy_from_slice(&container[8..Self::LEN]);
+		Ok(Header { version, algorithm, nonce })
+	}
+
+	fn encode(&self) -> [u8; Self::LEN] {
+		let mut header = [0; Self::LEN];
+		header[..4].copy_from_slice(&MAGIC);
+		header[4] = self.version;
+		header[5] = self.algorithm.id();
+		header[6] = NONCE_LEN as u8;
+		header[8..].copy_from_slice(&self.nonce);
+		header
+	}
+}
+
+fn invalid(message: impl fmt::Display) -> Error {
+	Error::new_with_code(ErrorCode::InvalidArgument, format!("Failed to decrypt model: {message}"))
+}
+
+/// A 256-bit key, which is zeroed when dropped.
+#[derive(Clone)]
+pub struct Key(Zeroizing<[u8; 32]>);
+
+impl Key {
+	/// Creates a key from its raw bytes.
+	pub fn new(bytes: [u8; 32]) -> Self {
+		Self(Zeroizing::new(bytes))
+	}
+
+	/// Creates a key from a slice of raw bytes, returning an error if the slice isn't 32 bytes long.
+	pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+		let mut key = Zeroizing::new([0; 32]);
+		if bytes.len() != key.len() {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Encryption keys must be 32 bytes long, got {}", bytes.len())));
+		}
+		key.copy_from_slice(bytes);
+		Ok(Self(key))
+	}
+
+	/// Parses a key from 64 hexadecimal digits. Surrounding whitespace is ignored.
+	pub fn from_hex(hex: &str) -> Result<Self> {
+		let hex = hex.trim().as_bytes();
+		let mut key = Zeroizing::new([0; 32]);
+		if hex.len() != key.len() * 2 {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Hex-encoded encryption keys must be 64 digits long"));
+		}
+		for (byte, digits) in key.iter_mut().zip(hex.chunks_exact(2)) {
+			let digit = |d: u8| {
+				char::from(d)
+					.to_digit(16)
+					.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Hex-encoded encryption key contains a non-hex digit"))
+			};
+			*byte = (digit(digits[0])? * 16 + digit(digits[1])?) as u8;
+		}
+		Ok(Self(key))
+	}
+
+	/// Generates a random key using the operating system's random number generator.
+	pub fn generate() -> Self {
+		let mut key = Zeroizing::new([0; 32]);
+		OsRng.fill_bytes(&mut *key);
+		Self(key)
+	}
+
+	/// Returns the raw bytes of the key.
+	pub fn as_bytes(&self) -> &[u8; 32] {
+		&self.0
+	}
+}
+
+impl fmt::Debug for Key {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Key(<redacted>)")
+	}
+}
+
+/// Supplies the key to decrypt a model with.
+///
+/// Implemented by [`Key`] itself, [`EnvKey`], [`FileKey`], and closures returning `Result<Key>`, which can be used to
+/// fetch keys from a hardware security module or a key management service:
+///
+/// ```no_run
+/// # use ort::session::{Session, encryption::Key};
+/// # fn fetch_key_from_tpm() -> ort::Result<[u8; 32]> { unimplemented!() }
+/// # fn main() -> ort::Result<()> {
+/// let session =
+/// 	Session::builder()?.commit_from_encrypted_file("model.onnx.enc", &|| Ok(Key::new(fetch_key_from_tpm()?)))?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait KeyProvider {
+	fn key(&self) -> Result<Key>;
+}
+
+impl KeyProvider for Key {
+	fn key(&self) -> Result<Key> {
+		Ok(self.clone())
+	}
+}
+
+impl<F: Fn() -> Result<Key>> KeyProvider for F {
+	fn key(&self) -> Result<Key> {
+		self()
+	}
+}
+
+/// Reads a hex-encoded key from an environment variable.
+#[derive(Debug, Clone)]
+pub struct EnvKey(String);
+
+impl EnvKey {
+	pub fn new(variable: impl Into<String>) -> Self {
+		Self(variable.into())
+	}
+}
+
+impl KeyProvider for EnvKey {
+	fn key(&self) -> Result<Key> {
+		let hex = Zeroizing::new(
+			std::env::var(&self.0)
+				.map_err(|e| Error::new_with_code(ErrorCode::InvalidArgument, format!("Failed to read encryption key from `{}`: {e}", self.0)))?
+		);
+		Key::from_hex(&hex)
+	}
+}
+
+/// Reads a key from a file, which may contain either the 32 raw bytes of the key or 64 hexadecimal digits.
+#[derive(Debug, Clone)]
+pub struct FileKey(PathBuf);
+
+impl FileKey {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self(path.into())
+	}
+}
+
+impl KeyProvider for FileKey {
+	fn key(&self) -> Result<Key> {
+		let contents = Zeroizing::new(
+			std::fs::read(&self.0)
+				.map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read encryption key from `{}`: {e}", self.0.display())))?
+		);
+		if contents.len() == 32 {
+			return Key::from_slice(&contents);
+		}
+		match core::str::from_utf8(&contents) {
+			Ok(hex) => Key::from_hex(hex),
+			Err(_) => Key::from_slice(&contents)
+		}
+	}
+}
+
+/// Decrypted data, which is zeroed when dropped.
+#[derive(Clone)]
+pub struct Plaintext(Zeroizing<Vec<u8>>);
+
+impl Deref for Plaintext {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl fmt::Debug for Plaintext {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Plaintext({} bytes)", self.0.len())
+	}
+}
+
+/// Encrypts `plaintext` (usually the bytes of an `.onnx` file, or one of its external data files) with a random
+/// nonce, returning a container which can be decrypted with [`decrypt`].
+pub fn encrypt(plaintext: &[u8], algorithm: Algorithm, key: &Key) -> Result<Vec<u8>> {
+	let nonce = match algorithm {
+		Algorithm::Aes256Gcm => Aes256Gcm::generate_nonce(&mut OsRng),
+		Algorithm::ChaCha20Poly1305 => ChaCha20Poly1305::generate_nonce(&mut OsRng)
+	};
+	let header = Header {
+		version: FORMAT_VERSION,
+		algorithm,
+		nonce: nonce.into()
+	}
+	.encode();
+
+	let mut container = Vec::with_capacity(Header::LEN + plaintext.len() + 16);
+	container.extend_from_slice(&header);
+	container.extend_from_slice(plaintext);
+	let mut ciphertext = container.split_off(Header::LEN);
+	let result = match algorithm {
+		Algorithm::Aes256Gcm => Aes256Gcm::new(key.as_bytes().into()).encrypt_in_place(&nonce, &header, &mut ciphertext),
+		Algorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_bytes().into()).encrypt_in_place(&nonce, &header, &mut ciphertext)
+	};
+	result.map_err(|_| Error::new_with_code(ErrorCode::GenericFailure, "Failed to encrypt model"))?;
+	container.append(&mut ciphertext);
+	Ok(container)
+}
+
+/// Decrypts a container created by [`encrypt`].
+///
+/// Returns an error if the container is malformed, or if it can't be authenticated, i.e. the key is wrong or the
+/// container has been corrupted or tampered with.
+pub fn decrypt(container: &[u8], key: &Key) -> Result<Plaintext> {
+	let header = Header::parse(container)?;
+	let (aad, ciphertext) = container.split_at(Header::LEN);
+	let mut buffer = Zeroizing::new(ciphertext.to_vec());
+	let nonce = (&header.nonce).into();
+	let result = match header.algorithm {
+		Algorithm::Aes256Gcm => Aes256Gcm::new(key.as_bytes().into()).decrypt_in_place(nonce, aad, &mut *buffer),
+		Algorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_bytes().into()).decrypt_in_place(nonce, aad, &mut *buffer)
+	};
+	result.map_err(|_| invalid("authentication failed; the key is wrong, or the model is corrupted"))?;
+	Ok(Plaintext(buffer))
+}
+
+/// Encrypts the file at `input` with [`encrypt`], writing the container to `output`.
+pub fn encrypt_file(input: impl AsRef<Path>, output: impl AsRef<Path>, algorithm: Algorithm, key: &Key) -> Result<()> {
+	let (input, output) = (input.as_ref(), output.as_ref());
+	let plaintext =
+		Zeroizing::new(std::fs::read(input).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read `{}`: {e}", input.display())))?);
+	let container = encrypt(&plaintext, algorithm, key)?;
+	std::fs::write(output, container).map_err(|e| Error::new(format!("Failed to write `{}`: {e}", output.display())))
+}
+
+/// Reads & decrypts the file at `path`.
+pub(crate) fn decrypt_file(path: &Path, key: &impl KeyProvider) -> Result<Plaintext> {
+	let container = std::fs::read(path).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read model `{}`: {e}", path.display())))?;
+	decrypt(&container, &key.key()?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Algorithm, EnvKey, FileKey, Header, Key, KeyProvider, decrypt, encrypt};
+
+	#[test]
+	fn test_roundtrip() -> crate::Result<()> {
+		let key = Key::generate();
+		let plaintext = b"not really an onnx model";
+		for algorithm in [Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+			let container = encrypt(plaintext, algorithm, &key)?;
+			assert_eq!(Header::parse(&container)?.algorithm, algorithm);
+			assert_eq!(container.len(), Header::LEN + plaintext.len() + 16);
+			assert_eq!(&*decrypt(&container, &key)?, plaintext);
+
+			// a different key, a modified header, or a modified ciphertext should all fail to authenticate
+			assert!(decrypt(&container, &Key::generate()).is_err());
+			let mut tampered = container.clone();
+			tampered[Header::LEN - 1] ^= 1;
+			assert!(decrypt(&tampered, &key).is_err());
+			let mut tampered = container.clone();
+			tampered[Header::LEN] ^= 1;
+			assert!(decrypt(&tampered, &key).is_err());
+		}
+		assert!(decrypt(b"ORTE", &key).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_keys() -> crate::Result<()> {
+		let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1F";
+		let key = Key::from_hex(hex)?;
+		assert_eq!(key.as_bytes()[..3], [0, 1, 2]);
+		assert_eq!(key.as_bytes()[31], 0x1f);
+		assert!(Key::from_hex(&hex[1..]).is_err());
+		assert!(Key::from_hex(&hex.replace('F', "g")).is_err());
+		assert!(Key::from_slice(&[0; 16]).is_err());
+		assert_eq!(format!("{key:?}"), "Key(<redacted>)");
+
+		std::env::set_var("ORT_TEST_ENCRYPTION_KEY", format!("{hex}\n"));
+		assert_eq!(EnvKey::new("ORT_TEST_ENCRYPTION_KEY").key()?.as_bytes(), key.as_bytes());
+		assert!(EnvKey::new("ORT_TEST_ENCRYPTION_KEY_MISSING").key().is_err());
+
+		let dir = std::env::temp_dir().join(format!("ort-test-keys-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).map_err(crate::Error::wrap)?;
+		std::fs::write(dir.join("raw.key"), key.as_bytes()).map_err(crate::Error::wrap)?;
+		std::fs::write(dir.join("hex.key"), hex).map_err(crate::Error::wrap)?;
+		assert_eq!(FileKey::new(dir.join("raw.key")).key()?.as_bytes(), key.as_bytes());
+		assert_eq!(FileKey::new(dir.join("hex.key")).key()?.as_bytes(), key.as_bytes());
+		std::fs::remove_dir_all(dir).map_err(crate::Error::wrap)?;
+
+		let provider = || Key::from_hex(hex);
+		assert_eq!(provider.key()?.as_bytes(), key.as_bytes());
+		Ok(())
+	}
+}
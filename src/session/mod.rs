@@ -3,7 +3,7 @@
 //! ```
 //! # use ort::{session::Session, value::TensorRef};
 //! # fn main() -> ort::Result<()> {
-//! let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+//! let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 //! let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
 //! let outputs = session.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
 //! # 	Ok(())
@@ -38,19 +38,54 @@ use crate::{
 
 #[cfg(feature = "std")]
 mod r#async;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod batcher;
 pub mod builder;
+#[cfg(feature = "bundle")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+pub mod bundle;
+#[cfg(feature = "compression")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+pub mod compression;
+pub mod debug;
+#[cfg(feature = "encryption")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+pub mod encryption;
 pub mod input;
 pub mod output;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod pool;
+pub mod profiling;
 pub mod run_options;
+#[cfg(feature = "signing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+pub mod signing;
+#[cfg(feature = "std")]
+mod timer;
+pub mod typed;
+mod validate;
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use ort_derive::{ModelInputs, ModelOutputs};
+
 #[cfg(feature = "std")]
 pub use self::r#async::InferenceFut;
 #[cfg(feature = "std")]
 use self::r#async::{AsyncInferenceContext, InferenceFutInner};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use self::batcher::{BatchFut, BatchOutputs, Batcher, BatcherOptions};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use self::pool::{PooledSession, SessionPool};
 use self::{builder::SessionBuilder, run_options::UntypedRunOptions};
 pub use self::{
 	input::{SessionInputValue, SessionInputs},
 	output::SessionOutputs,
-	run_options::{HasSelectedOutputs, NoSelectedOutputs, RunOptions, SelectedOutputMarker}
+	run_options::{HasSelectedOutputs, NoSelectedOutputs, RunOptions, SelectedOutputMarker},
+	typed::{ModelInputs, ModelOutputs}
 };
 
 /// Holds onto an [`ort_sys::OrtSession`] pointer and its associated allocator.
@@ -62,6 +97,8 @@ pub struct SharedSessionInner {
 	session_ptr: NonNull<ort_sys::OrtSession>,
 	pub(crate) allocator: Allocator,
 	_initializers: SmallVec<Arc<DynValue>, 4>,
+	#[cfg(feature = "signing")]
+	verified_digest: Option<signing::Digest>,
 	/// Additional things we may need to hold onto for the duration of this session, like `OperatorDomain`s and
 	/// DLL handles for operator libraries.
 	_extras: SmallVec<Box<dyn Any>, 4>
@@ -90,19 +127,48 @@ impl Drop for SharedSessionInner {
 /// ```
 /// # use ort::{session::Session, value::TensorRef};
 /// # fn main() -> ort::Result<()> {
-/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 /// let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
 /// let outputs = session.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
 /// # 	Ok(())
 /// # }
 /// ```
+///
+/// ONNX Runtime allows a single session to be run concurrently from multiple threads, so all of the `run` methods
+/// take `&self`. A [`Session`] can be shared between threads by reference or via an [`Arc`] without needing to be
+/// wrapped in a `Mutex`:
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use ort::{session::Session, value::TensorRef};
+/// # fn main() -> ort::Result<()> {
+/// let session = Arc::new(Session::builder()?.commit_from_file("tests/data/upsample.onnx")?);
+///
+/// let handles: Vec<_> = (0..4)
+/// 	.map(|_| {
+/// 		let session = Arc::clone(&session);
+/// 		std::thread::spawn(move || -> ort::Result<()> {
+/// 			let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
+/// 			let outputs = session.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
+/// 			assert_eq!(outputs[0].try_extract_array::<f32>()?.shape(), [1, 128, 128, 3]);
+/// 			Ok(())
+/// 		})
+/// 	})
+/// 	.collect();
+/// for handle in handles {
+/// 	handle.join().expect("thread panicked")?;
+/// }
+/// # 	Ok(())
+/// # }
+/// ```
 #[derive(Debug)]
 pub struct Session {
 	pub(crate) inner: Arc<SharedSessionInner>,
 	/// Information about the graph's inputs.
 	pub inputs: Vec<Input>,
 	/// Information about the graph's outputs.
-	pub outputs: Vec<Output>
+	pub outputs: Vec<Output>,
+	pub(crate) taps: Vec<debug::Tap>
 }
 
 /// A [`Session`] where the graph data is stored in memory.
@@ -200,13 +266,13 @@ impl Session {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{run_options::RunOptions, Session}, tensor::TensorElementType, value::{Value, ValueType, TensorRef}};
 	/// # fn main() -> ort::Result<()> {
-	/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
 	/// let outputs = session.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
 	/// # 	Ok(())
 	/// # }
 	/// ```
-	pub fn run<'s, 'i, 'v: 'i, const N: usize>(&'s mut self, input_values: impl Into<SessionInputs<'i, 'v, N>>) -> Result<SessionOutputs<'s, 's>> {
+	pub fn run<'s, 'i, 'v: 'i, const N: usize>(&'s self, input_values: impl Into<SessionInputs<'i, 'v, N>>) -> Result<SessionOutputs<'s, 's>> {
 		match input_values.into() {
 			SessionInputs::ValueSlice(input_values) => {
 				self.run_inner(self.inputs.iter().map(|input| input.name.as_str()).collect(), input_values.iter().collect(), None)
@@ -228,7 +294,7 @@ impl Session {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::RunOptions}, value::{Value, ValueType, TensorRef}, tensor::TensorElementType};
 	/// # fn main() -> ort::Result<()> {
-	/// # 	let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// # 	let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// # 	let input = Value::from_array(ndarray::Array4::<f32>::zeros((1, 64, 64, 3)))?;
 	/// let run_options = Arc::new(RunOptions::new()?);
 	///
@@ -247,7 +313,7 @@ impl Session {
 	/// # }
 	/// ```
 	pub fn run_with_options<'r, 's: 'r, 'i, 'v: 'i, O: SelectedOutputMarker, const N: usize>(
-		&'s mut self,
+		&'s self,
 		input_values: impl Into<SessionInputs<'i, 'v, N>>,
 		run_options: &'r RunOptions<O>
 	) -> Result<SessionOutputs<'r, 's>> {
@@ -264,6 +330,21 @@ impl Session {
 		}
 	}
 
+	/// Appends the names of all [debug taps](debug) not explicitly requested by the user to the outputs of a run,
+	/// returning the appended taps.
+	fn request_taps<'r, 's: 'r>(
+		&'s self,
+		output_names: &mut SmallVec<&'r str, { STACK_SESSION_OUTPUTS }>,
+		output_tensors: &mut SmallVec<Option<DynValue>, { STACK_SESSION_OUTPUTS }>
+	) -> Vec<&'s debug::Tap> {
+		let taps: Vec<&debug::Tap> = self.taps.iter().filter(|tap| !output_names.contains(&tap.name.as_str())).collect();
+		for tap in &taps {
+			output_names.push(&tap.name);
+			output_tensors.push(None);
+		}
+		taps
+	}
+
 	fn run_inner<'i, 'r, 's: 'r, 'v: 'i>(
 		&'s self,
 		input_names: SmallVec<&str, { STACK_SESSION_INPUTS }>,
@@ -281,10 +362,15 @@ impl Session {
 			));
 		}
 
-		let (output_names, mut output_tensors) = match run_options {
+		if run_options.is_some_and(|r| r.validate_inputs) {
+			self.validate_inputs_inner(&input_names, &input_values)?;
+		}
+
+		let (mut output_names, mut output_tensors) = match run_options {
 			Some(r) => r.outputs.resolve_outputs(&self.outputs),
 			None => (self.outputs.iter().map(|o| o.name.as_str()).collect(), iter::repeat_with(|| None).take(self.outputs.len()).collect())
 		};
+		let taps = self.request_taps(&mut output_names, &mut output_tensors);
 		let output_value_ptrs: SmallVec<*mut ort_sys::OrtValue, { STACK_SESSION_OUTPUTS }> = output_tensors
 			.iter_mut()
 			.map(|c| match c {
@@ -296,7 +382,13 @@ impl Session {
 
 		let run_options_ptr = if let Some(run_options) = &run_options { run_options.ptr.as_ptr() } else { ptr::null() };
 
-		with_cstr_ptr_array(&input_names, &|input_name_ptrs| {
+		#[cfg(feature = "std")]
+		let deadline = match run_options {
+			Some(run_options) => run_options.arm_deadline()?,
+			None => None
+		};
+
+		let result = with_cstr_ptr_array(&input_names, &|input_name_ptrs| {
 			with_cstr_ptr_array(&output_names, &|output_name_ptrs| {
 				ortsys![
 					unsafe Run(
@@ -312,7 +404,13 @@ impl Session {
 				];
 				Ok(())
 			})
-		})?;
+		});
+		#[cfg(feature = "std")]
+		let result = match deadline {
+			Some(deadline) => deadline.finish(result),
+			None => result
+		};
+		result?;
 
 		let outputs = output_tensors
 			.into_iter()
@@ -328,15 +426,15 @@ impl Session {
 			})
 			.collect();
 
-		Ok(SessionOutputs::new(output_names, outputs))
+		Ok(SessionOutputs::new(output_names, outputs).with_taps(taps))
 	}
 
-	pub fn run_binding<'b, 's: 'b>(&'s mut self, binding: &'b IoBinding) -> Result<SessionOutputs<'b, 's>> {
+	pub fn run_binding<'b, 's: 'b>(&'s self, binding: &'b IoBinding) -> Result<SessionOutputs<'b, 's>> {
 		self.run_binding_inner(binding, None)
 	}
 
 	pub fn run_binding_with_options<'r, 'b, 's: 'b>(
-		&'s mut self,
+		&'s self,
 		binding: &'b IoBinding,
 		run_options: &'r RunOptions<NoSelectedOutputs>
 	) -> Result<SessionOutputs<'b, 's>> {
@@ -349,7 +447,19 @@ impl Session {
 		run_options: Option<&'r RunOptions<NoSelectedOutputs>>
 	) -> Result<SessionOutputs<'b, 's>> {
 		let run_options_ptr = if let Some(run_options) = run_options { run_options.ptr() } else { ptr::null() };
-		ortsys![unsafe RunWithBinding(self.inner.ptr().cast_mut(), run_options_ptr, binding.ptr())?];
+		#[cfg(feature = "std")]
+		let deadline = match run_options {
+			Some(run_options) => run_options.inner.arm_deadline()?,
+			None => None
+		};
+		let status = ortsys![unsafe RunWithBinding(self.inner.ptr().cast_mut(), run_options_ptr, binding.ptr())];
+		let result = unsafe { status_to_result(status) };
+		#[cfg(feature = "std")]
+		let result = match deadline {
+			Some(deadline) => deadline.finish(result),
+			None => result
+		};
+		result?;
 
 		let mut count = binding.output_values.len();
 		if count > 0 {
@@ -394,7 +504,7 @@ impl Session {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::RunOptions}, value::{Value, ValueType, TensorRef}, tensor::TensorElementType};
 	/// # fn main() -> ort::Result<()> { tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
-	/// let mut session = Session::builder()?.with_intra_threads(2)?.commit_from_file("tests/data/upsample.onnx")?;
+	/// let session = Session::builder()?.with_intra_threads(2)?.commit_from_file("tests/data/upsample.onnx")?;
 	/// let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
 	/// let options = RunOptions::new()?;
 	/// let outputs = session.run_async(ort::inputs![TensorRef::from_array_view(&input)?], &options)?.await?;
@@ -404,7 +514,7 @@ impl Session {
 	#[cfg(feature = "std")]
 	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 	pub fn run_async<'r, 's: 'r, 'i, 'v: 'i + 's, O: SelectedOutputMarker, const N: usize>(
-		&'s mut self,
+		&'s self,
 		input_values: impl Into<SessionInputs<'i, 'v, N>>,
 		run_options: &'r RunOptions<O>
 	) -> Result<InferenceFut<'s, 'r, 'v>> {
@@ -428,6 +538,10 @@ impl Session {
 		input_values: SmallVec<&SessionInputValue<'v>, { STACK_SESSION_INPUTS }>,
 		run_options: &'r UntypedRunOptions
 	) -> Result<InferenceFut<'s, 'r, 'v>> {
+		if run_options.validate_inputs {
+			self.validate_inputs_inner(&input_names, &input_values)?;
+		}
+
 		let input_name_ptrs = input_names
 			.into_iter()
 			.map(|name| CString::new(name.as_bytes()).map(|s| s.into_raw().cast_const()))
@@ -444,7 +558,8 @@ impl Session {
 			}));
 		}
 
-		let (output_names, mut output_tensors) = run_options.outputs.resolve_outputs(&self.outputs);
+		let (mut output_names, mut output_tensors) = run_options.outputs.resolve_outputs(&self.outputs);
+		let taps = self.request_taps(&mut output_names, &mut output_tensors);
 		let output_name_ptrs = output_names
 			.iter()
 			.map(|n| CString::new(*n).unwrap_or_else(|_| unreachable!()))
@@ -459,6 +574,7 @@ impl Session {
 			.collect();
 
 		let async_inner = Arc::new(InferenceFutInner::new());
+		let deadline = run_options.arm_deadline()?;
 
 		// AsyncInferenceContext can get pretty huge so we should see if we can bump MSRV to 1.82 and use `Box::new_uninit()`
 		// if it causes problems
@@ -472,6 +588,7 @@ impl Session {
 			output_name_ptrs,
 			output_names,
 			output_value_ptrs: output_tensor_ptrs,
+			taps,
 			session_inner: &self.inner
 		}));
 
@@ -490,7 +607,7 @@ impl Session {
 			)?
 		];
 
-		Ok(InferenceFut::new(async_inner, run_options))
+		Ok(InferenceFut::new(async_inner, run_options, deadline))
 	}
 
 	/// Gets the session model metadata. See [`ModelMetadata`] for more info.
@@ -500,6 +617,14 @@ impl Session {
 		Ok(ModelMetadata::new(unsafe { NonNull::new_unchecked(metadata_ptr) }))
 	}
 
+	/// Returns the digest of the model & external data this session was created from, if it was verified against a
+	/// signature. See [`SessionBuilder::with_signature_verification`].
+	#[cfg(feature = "signing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+	pub fn verified_digest(&self) -> Option<&signing::Digest> {
+		self.inner.verified_digest.as_ref()
+	}
+
 	/// Returns the time that profiling was started, in nanoseconds.
 	pub fn profiling_start_ns(&self) -> Result<u64> {
 		let mut out = 0;
@@ -510,6 +635,9 @@ impl Session {
 	/// Ends profiling for this session.
 	///
 	/// Note that this must be explicitly called at the end of profiling, otherwise the profiling file will be empty.
+	///
+	/// Returns the path of the profiling file, which can be parsed with
+	/// [`Profile::from_file`](profiling::Profile::from_file).
 	pub fn end_profiling(&mut self) -> Result<String> {
 		let mut profiling_name: *mut c_char = ptr::null_mut();
 
@@ -561,8 +689,10 @@ pub enum WorkloadType {
 
 // https://github.com/microsoft/onnxruntime/issues/114
 unsafe impl Send for Session {}
-// Allowing `Sync` segfaults with CUDA, DirectML, and seemingly any EP other than the CPU EP. I'm not certain if it's a
-// temporary bug in ONNX Runtime or a wontfix. Maybe this impl should be removed just to be safe?
+// `OrtApi::Run` is documented as being safe to call concurrently on the same `OrtSession`; all other state we hold is
+// either immutable after creation (`inputs`/`outputs`) or already `Sync` (`SharedSessionInner`). Note that some EPs
+// (e.g. DirectML) do not support concurrent runs on a single session, in which case runs must be serialized by the
+// user.
 unsafe impl Sync for Session {}
 
 impl AsPointer for Session {
@@ -1,4 +1,4 @@
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use core::{
 	ffi::c_void,
 	iter::FusedIterator,
@@ -11,6 +11,7 @@ use smallvec::SmallVec;
 
 use crate::{
 	memory::Allocator,
+	session::debug::{Tap, TappedValue},
 	util::STACK_SESSION_OUTPUTS,
 	value::{DynValue, ValueRef, ValueRefMut}
 };
@@ -22,7 +23,7 @@ use crate::{
 /// ```
 /// # use ort::{value::TensorRef, session::{builder::GraphOptimizationLevel, Session}};
 /// # fn main() -> ort::Result<()> {
-/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 /// let input = ndarray::Array4::<f32>::zeros((1, 64, 64, 3));
 /// let outputs = session.run(ort::inputs![TensorRef::from_array_view(&input)?])?;
 ///
@@ -40,7 +41,8 @@ pub struct SessionOutputs<'r, 's> {
 	keys: SmallVec<&'r str, { STACK_SESSION_OUTPUTS }>,
 	values: SmallVec<DynValue, { STACK_SESSION_OUTPUTS }>,
 	effective_len: usize,
-	backing_ptr: Option<(&'s Allocator, *mut c_void)>
+	backing_ptr: Option<(&'s Allocator, *mut c_void)>,
+	taps: Vec<TappedValue<'s>>
 }
 
 unsafe impl Send for SessionOutputs<'_, '_> {}
@@ -52,10 +54,24 @@ impl<'r, 's> SessionOutputs<'r, 's> {
 			effective_len: output_names.len(),
 			keys: output_names,
 			values: output_values,
-			backing_ptr: None
+			backing_ptr: None,
+			taps: Vec::new()
 		}
 	}
 
+	/// Splits the values of `taps`, which were requested after all regular outputs, off into [`SessionOutputs::taps`].
+	pub(crate) fn with_taps(mut self, taps: Vec<&'s Tap>) -> Self {
+		let start = self.keys.len() - taps.len();
+		self.keys.truncate(start);
+		self.effective_len = start;
+		self.taps = taps
+			.into_iter()
+			.zip(self.values.drain(start..))
+			.map(|(tap, value)| TappedValue { tap, value })
+			.collect();
+		self
+	}
+
 	pub(crate) fn new_backed(
 		output_names: SmallVec<&'r str, { STACK_SESSION_OUTPUTS }>,
 		output_values: SmallVec<DynValue, { STACK_SESSION_OUTPUTS }>,
@@ -67,7 +83,8 @@ impl<'r, 's> SessionOutputs<'r, 's> {
 			effective_len: output_names.len(),
 			keys: output_names,
 			values: output_values,
-			backing_ptr: Some((allocator, backing_ptr))
+			backing_ptr: Some((allocator, backing_ptr)),
+			taps: Vec::new()
 		}
 	}
 
@@ -76,7 +93,8 @@ impl<'r, 's> SessionOutputs<'r, 's> {
 			effective_len: 0,
 			keys: SmallVec::new(),
 			values: SmallVec::new(),
-			backing_ptr: None
+			backing_ptr: None,
+			taps: Vec::new()
 		}
 	}
 
@@ -170,6 +188,22 @@ impl<'r, 's> SessionOutputs<'r, 's> {
 			effective_len: self.effective_len
 		}
 	}
+
+	/// Returns the intermediate values exposed by
+	/// [`SessionBuilder::with_debug_taps`](crate::session::builder::SessionBuilder::with_debug_taps), in the order of
+	/// the nodes producing them. These are not included in the regular outputs.
+	///
+	/// Taps are not fetched by runs with an [`IoBinding`](crate::io_binding::IoBinding), for which this is always
+	/// empty.
+	pub fn taps(&self) -> &[TappedValue<'s>] {
+		&self.taps
+	}
+
+	/// Returns the value of the tapped intermediate value named `name`.
+	pub fn tap(&self, name: impl AsRef<str>) -> Option<&DynValue> {
+		let name = name.as_ref();
+		self.taps.iter().find(|tapped| tapped.tap.name == name).map(|tapped| &tapped.value)
+	}
 }
 
 impl<'x, 'r> IntoIterator for &'x SessionOutputs<'r, '_> {
@@ -198,6 +232,7 @@ impl<'r, 's> IntoIterator for SessionOutputs<'r, 's> {
 		let this = ManuallyDrop::new(self);
 		let keys = unsafe { ptr::read(&this.keys) }.into_iter();
 		let values = unsafe { ptr::read(&this.values) }.into_iter();
+		drop(unsafe { ptr::read(&this.taps) });
 		IntoIter {
 			keys,
 			values,
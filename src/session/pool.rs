@@ -0,0 +1,349 @@
+//! A pool of identical [`Session`]s which can be checked out for exclusive use.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{
+	fmt,
+	future::Future,
+	mem::ManuallyDrop,
+	ops::Deref,
+	pin::Pin,
+	task::{Context, Poll, Waker}
+};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	io_binding::IoBinding,
+	session::{NoSelectedOutputs, RunOptions, Session, SessionOutputs}
+};
+
+/// A single entry in a [`SessionPool`]: a [`Session`] along with its own reusable [`IoBinding`] & [`RunOptions`].
+#[derive(Debug)]
+struct PoolSlot {
+	// `binding` holds a reference to the session, so it must be dropped first.
+	binding: IoBinding,
+	run_options: RunOptions<NoSelectedOutputs>,
+	session: Session
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+	idle: Vec<PoolSlot>,
+	wakers: VecDeque<Waker>
+}
+
+/// A pool of identical [`Session`]s, each with their own [`IoBinding`] and [`RunOptions`].
+///
+/// Though [`Session::run`] can be called concurrently, some workloads (like those using [`IoBinding`], or EPs that do
+/// not support concurrent runs on a single session) require exclusive access to a session. [`SessionPool`] creates a
+/// fixed number of sessions from a single [`SessionBuilder`], which can be checked out with
+/// [`SessionPool::checkout`], [`SessionPool::try_checkout`], or [`SessionPool::checkout_async`]. The returned
+/// [`PooledSession`] guard returns the session to the pool when it is dropped.
+///
+/// Sessions in the pool share their prepacked weights via [`PrepackedWeights`], and are configured to use the
+/// environment's allocators (see [`SessionBuilder::with_env_allocators`]), so creating a pool of `N` sessions does not
+/// require `N` times the memory.
+///
+/// ```
+/// # use ort::{session::{Session, SessionPool}, value::Tensor};
+/// # fn main() -> ort::Result<()> {
+/// let pool = Session::builder()?.commit_pool_from_file("tests/data/upsample.onnx", 2)?;
+///
+/// let mut session = pool.checkout()?;
+/// let (input_name, output_name) = (session.inputs[0].name.clone(), session.outputs[0].name.clone());
+/// session
+/// 	.binding_mut()
+/// 	.bind_input(input_name, &Tensor::from_array(ndarray::Array4::<f32>::zeros((1, 64, 64, 3)))?)?;
+/// session
+/// 	.binding_mut()
+/// 	.bind_output(output_name, Tensor::from_array(ndarray::Array4::<f32>::zeros((1, 128, 128, 3)))?)?;
+///
+/// let outputs = session.run_binding()?;
+/// # 	Ok(())
+/// # }
+/// ```
+///
+/// [`SessionBuilder`]: crate::session::builder::SessionBuilder
+/// [`SessionBuilder::with_env_allocators`]: crate::session::builder::SessionBuilder::with_env_allocators
+/// [`PrepackedWeights`]: crate::session::builder::PrepackedWeights
+pub struct SessionPool {
+	state: Mutex<PoolState>,
+	available: Condvar,
+	size: usize
+}
+
+impl SessionPool {
+	pub(crate) fn new(sessions: Vec<Session>) -> Result<Self> {
+		let size = sessions.len();
+		let idle = sessions
+			.into_iter()
+			.map(|session| {
+				Ok(PoolSlot {
+					binding: session.create_binding()?,
+					run_options: RunOptions::new()?,
+					session
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Ok(Self {
+			state: Mutex::new(PoolState { idle, wakers: VecDeque::new() }),
+			available: Condvar::new(),
+			size
+		})
+	}
+
+	/// Returns the total number of sessions managed by this pool, including those currently checked out.
+	#[allow(clippy::len_without_is_empty)]
+	pub fn len(&self) -> usize {
+		self.size
+	}
+
+	/// Returns the number of sessions that are currently available to be checked out.
+	pub fn available(&self) -> usize {
+		self.lock_state().idle.len()
+	}
+
+	/// Checks out a session from the pool, blocking the current thread until one becomes available.
+	pub fn checkout(&self) -> Result<PooledSession<'_>> {
+		let mut state = self.lock_state();
+		loop {
+			if let Some(slot) = state.idle.pop() {
+				return Ok(PooledSession::new(self, slot));
+			}
+			state = self
+				.available
+				.wait(state)
+				.map_err(|_| Error::new_with_code(ErrorCode::RuntimeException, "session pool mutex was poisoned"))?;
+		}
+	}
+
+	/// Checks out a session from the pool if one is immediately available, returning `None` otherwise.
+	pub fn try_checkout(&self) -> Option<PooledSession<'_>> {
+		self.lock_state().idle.pop().map(|slot| PooledSession::new(self, slot))
+	}
+
+	/// Returns a future which resolves to a checked out session once one becomes available.
+	///
+	/// ```
+	/// # use ort::session::Session;
+	/// # fn main() -> ort::Result<()> { tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+	/// let pool = Session::builder()?.commit_pool_from_file("tests/data/upsample.onnx", 1)?;
+	/// let session = pool.checkout_async().await;
+	/// assert!(pool.try_checkout().is_none());
+	/// # 	Ok(())
+	/// # }) }
+	/// ```
+	pub fn checkout_async(&self) -> Checkout<'_> {
+		Checkout { pool: self }
+	}
+
+	fn lock_state(&self) -> MutexGuard<'_, PoolState> {
+		// Nothing that runs while the lock is held can panic, so the mutex can never be poisoned in practice.
+		self.state.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn release(&self, slot: PoolSlot) {
+		// Don't let a terminated run poison the next checkout.
+		let _ = slot.run_options.unterminate();
+
+		let wakers = {
+			let mut state = self.lock_state();
+			state.idle.push(slot);
+			core::mem::take(&mut state.wakers)
+		};
+		self.available.notify_one();
+		// Wake all pending async checkouts; any that lose the race will simply re-register their waker.
+		for waker in wakers {
+			waker.wake();
+		}
+	}
+}
+
+impl fmt::Debug for SessionPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SessionPool")
+			.field("size", &self.size)
+			.field("available", &self.available())
+			.finish()
+	}
+}
+
+/// A future returned by [`SessionPool::checkout_async`].
+#[derive(Debug)]
+pub struct Checkout<'p> {
+	pool: &'p SessionPool
+}
+
+impl<'p> Future for Checkout<'p> {
+	type Output = PooledSession<'p>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let pool = self.pool;
+		let mut state = pool.lock_state();
+		match state.idle.pop() {
+			Some(slot) => Poll::Ready(PooledSession::new(pool, slot)),
+			None => {
+				if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+					state.wakers.push_back(cx.waker().clone());
+				}
+				Poll::Pending
+			}
+		}
+	}
+}
+
+/// A [`Session`] checked out from a [`SessionPool`].
+///
+/// This type derefs to [`Session`], and additionally provides access to the slot's reusable [`IoBinding`] and
+/// [`RunOptions`]. The session is returned to the pool when this guard is dropped.
+pub struct PooledSession<'p> {
+	pool: &'p SessionPool,
+	slot: ManuallyDrop<PoolSlot>
+}
+
+impl<'p> PooledSession<'p> {
+	fn new(pool: &'p SessionPool, slot: PoolSlot) -> Self {
+		Self { pool, slot: ManuallyDrop::new(slot) }
+	}
+
+	/// Returns the underlying [`Session`].
+	pub fn session(&self) -> &Session {
+		&self.slot.session
+	}
+
+	/// Returns this slot's [`IoBinding`].
+	///
+	/// Bindings are **not** cleared when the session is returned to the pool, so values bound by a previous user may
+	/// still be present. Use [`IoBinding::clear`] if this is not desired.
+	pub fn binding(&self) -> &IoBinding {
+		&self.slot.binding
+	}
+
+	/// Returns this slot's [`IoBinding`].
+	///
+	/// Bindings are **not** cleared when the session is returned to the pool, so values bound by a previous user may
+	/// still be present. Use [`IoBinding::clear`] if this is not desired.
+	pub fn binding_mut(&mut self) -> &mut IoBinding {
+		&mut self.slot.binding
+	}
+
+	/// Returns this slot's [`RunOptions`].
+	pub fn run_options(&self) -> &RunOptions<NoSelectedOutputs> {
+		&self.slot.run_options
+	}
+
+	/// Returns this slot's [`RunOptions`].
+	pub fn run_options_mut(&mut self) -> &mut RunOptions<NoSelectedOutputs> {
+		&mut self.slot.run_options
+	}
+
+	/// Runs the session using this slot's [`IoBinding`] and [`RunOptions`].
+	pub fn run_binding(&self) -> Result<SessionOutputs<'_, '_>> {
+		let slot = &*self.slot;
+		slot.session.run_binding_with_options(&slot.binding, &slot.run_options)
+	}
+}
+
+impl Deref for PooledSession<'_> {
+	type Target = Session;
+
+	fn deref(&self) -> &Self::Target {
+		&self.slot.session
+	}
+}
+
+impl fmt::Debug for PooledSession<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PooledSession")
+			.field("session", &self.slot.session)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Drop for PooledSession<'_> {
+	fn drop(&mut self) {
+		let slot = unsafe { ManuallyDrop::take(&mut self.slot) };
+		self.pool.release(slot);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::SessionPool;
+	use crate::{session::Session, value::Tensor};
+
+	fn pool(size: usize) -> crate::Result<SessionPool> {
+		Session::builder()?.commit_pool_from_file("tests/data/upsample.onnx", size)
+	}
+
+	#[test]
+	fn test_checkout_exhaustion() -> crate::Result<()> {
+		let pool = pool(2)?;
+		assert_eq!(pool.len(), 2);
+
+		let a = pool.try_checkout().expect("pool should have an idle session");
+		let b = pool.checkout()?;
+		assert_eq!(pool.available(), 0);
+		assert!(pool.try_checkout().is_none());
+
+		drop(a);
+		assert_eq!(pool.available(), 1);
+		drop(b);
+		assert_eq!(pool.available(), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkout_blocks_until_release() -> crate::Result<()> {
+		let pool = pool(1)?;
+		std::thread::scope(|s| -> crate::Result<()> {
+			let guard = pool.checkout()?;
+			let waiter = s.spawn(|| -> crate::Result<()> {
+				let session = pool.checkout()?;
+				session.run(crate::inputs![Tensor::<f32>::from_array(([1, 4, 4, 3], vec![0.0; 48]))?])?;
+				Ok(())
+			});
+			std::thread::sleep(std::time::Duration::from_millis(50));
+			assert!(!waiter.is_finished());
+			drop(guard);
+			waiter.join().expect("waiter thread panicked")
+		})
+	}
+
+	#[test]
+	fn test_checkout_async() -> crate::Result<()> {
+		let pool = pool(1)?;
+		let rt = tokio::runtime::Builder::new_current_thread().build().expect("failed to build runtime");
+		rt.block_on(async {
+			let guard = pool.checkout_async().await;
+			let waiter = pool.checkout_async();
+			drop(guard);
+			let session = waiter.await;
+			assert_eq!(pool.available(), 0);
+			drop(session);
+		});
+		assert_eq!(pool.available(), 1);
+		Ok(())
+	}
+
+	#[test]
+	fn test_run_binding() -> crate::Result<()> {
+		let pool = pool(2)?;
+		let mut session = pool.checkout()?;
+
+		let input = Tensor::<f32>::from_array(([1, 4, 4, 3], vec![1.0; 48]))?;
+		let (input_name, output_name) = (session.inputs[0].name.clone(), session.outputs[0].name.clone());
+		session.binding_mut().bind_input(input_name, &input)?;
+		session
+			.binding_mut()
+			.bind_output(output_name, Tensor::<f32>::from_array(([1, 8, 8, 3], vec![0.0; 192]))?)?;
+
+		let outputs = session.run_binding()?;
+		let (shape, data) = outputs[0].try_extract_tensor::<f32>()?;
+		assert_eq!(**shape, [1, 8, 8, 3]);
+		assert!(data.iter().all(|x| *x == 1.0));
+
+		Ok(())
+	}
+}
@@ -0,0 +1,265 @@
+//! Parsing & analysis of ONNX Runtime profiling output.
+//!
+//! When profiling is enabled with [`SessionBuilder::with_profiling`], ONNX Runtime records the time taken by session
+//! initialization, each run, and every operator kernel, and writes them to a Chrome trace JSON file when
+//! [`Session::end_profiling`] is called. [`Profile`] reads this file into typed [`ProfileEvent`]s, and
+//! [`Profile::report`] aggregates kernel timings per operator type & per node.
+//!
+//! ```no_run
+//! # use ort::session::{Session, profiling::Profile};
+//! # fn main() -> ort::Result<()> {
+//! let mut session = Session::builder()?
+//! 	.with_profiling("profile")?
+//! 	.commit_from_file("tests/data/upsample.onnx")?;
+//! // ... run the session a few times ...
+//! let profile = Profile::from_file(session.end_profiling()?)?;
+//!
+//! let report = profile.report();
+//! println!("{report}");
+//! std::fs::write("profile.csv", report.to_csv()).unwrap();
+//! # 	Ok(())
+//! # }
+//! ```
+//!
+//! Reports from two profiles can be compared with [`ProfileReport::diff`] to catch performance regressions, e.g. after
+//! upgrading ONNX Runtime or changing execution providers.
+//!
+//! [`SessionBuilder::with_profiling`]: crate::session::builder::SessionBuilder::with_profiling
+//! [`Session::end_profiling`]: crate::session::Session::end_profiling
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	util::json::Json
+};
+
+mod report;
+
+pub use self::report::{Aggregate, DiffEntry, ProfileDiff, ProfileReport};
+
+/// The category of a [`ProfileEvent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventCategory {
+	/// A session-level event, like session initialization or a single run of the model.
+	Session,
+	/// An event for a single node of the graph; see [`NodeInfo`].
+	Node,
+	/// Any other event category, e.g. events emitted by execution providers.
+	Other(String)
+}
+
+/// The element type & shape of a node's input or output, as recorded by the profiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeShape {
+	/// The ONNX element type name, e.g. `float` or `int64`.
+	pub element_type: String,
+	pub shape: Vec<i64>
+}
+
+/// Details of the node a [`ProfileEvent`] refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfo {
+	/// The name of the node in the graph.
+	pub name: String,
+	/// The operator type of the node, e.g. `Conv`.
+	pub op_type: String,
+	/// The execution provider the node was assigned to, e.g. `CPUExecutionProvider`.
+	pub provider: Option<String>,
+	/// The index of the node in the graph.
+	pub index: Option<usize>,
+	pub inputs: Vec<TypeShape>,
+	pub outputs: Vec<TypeShape>
+}
+
+/// A single event recorded by the ONNX Runtime profiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEvent {
+	pub category: EventCategory,
+	/// The raw name of the event, e.g. `session_initialization`, `model_run`, or `<node name>_kernel_time`.
+	pub name: String,
+	pub process_id: u64,
+	pub thread_id: u64,
+	/// The time the event started, relative to the start of profiling.
+	pub start: Duration,
+	pub duration: Duration,
+	/// Information about the node this event refers to, if this is a [`EventCategory::Node`] event.
+	pub node: Option<NodeInfo>
+}
+
+impl ProfileEvent {
+	/// Returns `true` if this event measures the execution time of a node's kernel, as opposed to e.g. the fences
+	/// around it.
+	pub fn is_kernel(&self) -> bool {
+		self.category == EventCategory::Node && self.node.is_some() && self.name.ends_with("_kernel_time")
+	}
+
+	fn from_json(event: &Json) -> Result<Self> {
+		let field = |key: &str| event.get(key).ok_or_else(|| invalid_event(key));
+		let name = field("name")?.as_str().ok_or_else(|| invalid_event("name"))?;
+		let category = match field("cat")?.as_str().ok_or_else(|| invalid_event("cat"))? {
+			"Session" => EventCategory::Session,
+			"Node" => EventCategory::Node,
+			other => EventCategory::Other(other.into())
+		};
+		let int = |key: &str| event.get(key).and_then(Json::as_i64).map_or(0, |x| x.max(0) as u64);
+
+		let node = match (&category, event.get("args")) {
+			(EventCategory::Node, Some(args)) => args.get("op_name").and_then(Json::as_str).map(|op_type| NodeInfo {
+				name: ["_kernel_time", "_fence_before", "_fence_after"]
+					.iter()
+					.find_map(|suffix| name.strip_suffix(suffix))
+					.unwrap_or(name)
+					.into(),
+				op_type: op_type.into(),
+				provider: args.get("provider").and_then(Json::as_str).map(String::from),
+				index: args.get("node_index").and_then(Json::as_i64).and_then(|x| usize::try_from(x).ok()),
+				inputs: type_shapes(args.get("input_type_shape")),
+				outputs: type_shapes(args.get("output_type_shape"))
+			}),
+			_ => None
+		};
+
+		Ok(Self {
+			category,
+			name: name.into(),
+			process_id: int("pid"),
+			thread_id: int("tid"),
+			start: Duration::from_micros(int("ts")),
+			duration: Duration::from_micros(int("dur")),
+			node
+		})
+	}
+}
+
+fn invalid_event(key: &str) -> Error {
+	Error::new_with_code(ErrorCode::InvalidArgument, alloc::format!("Failed to parse profile: event has missing or invalid field `{key}`"))
+}
+
+/// Parses shapes in the form `[{"float": [1, 3, 224, 224]}, ...]`.
+fn type_shapes(value: Option<&Json>) -> Vec<TypeShape> {
+	value
+		.and_then(Json::as_array)
+		.unwrap_or_default()
+		.iter()
+		.filter_map(Json::as_object)
+		.filter_map(|entries| entries.first())
+		.map(|(element_type, shape)| TypeShape {
+			element_type: element_type.clone(),
+			shape: shape.as_array().unwrap_or_default().iter().filter_map(Json::as_i64).collect()
+		})
+		.collect()
+}
+
+/// A profile recorded by ONNX Runtime; see the [module-level documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+	events: Vec<ProfileEvent>
+}
+
+impl Profile {
+	/// Parses a profile from the contents of the JSON file written by ONNX Runtime.
+	pub fn parse(json: &str) -> Result<Self> {
+		let events = match Json::parse(json)? {
+			Json::Array(events) => events,
+			// Some tools wrap trace events in an object
+			json => match json.get("traceEvents") {
+				Some(Json::Array(events)) => events.clone(),
+				_ => return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Failed to parse profile: expected an array of events"))
+			}
+		};
+		Ok(Self {
+			events: events.iter().map(ProfileEvent::from_json).collect::<Result<_>>()?
+		})
+	}
+
+	/// Reads a profile from the file at `path`, i.e. the path returned by
+	/// [`Session::end_profiling`](crate::session::Session::end_profiling).
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let json = std::fs::read_to_string(path)
+			.map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, alloc::format!("Failed to read profile `{}`: {e}", path.display())))?;
+		Self::parse(&json)
+	}
+
+	/// Returns all events in the profile, in the order they were recorded.
+	pub fn events(&self) -> &[ProfileEvent] {
+		&self.events
+	}
+
+	/// Returns events measuring the execution time of node kernels; see [`ProfileEvent::is_kernel`].
+	pub fn kernel_events(&self) -> impl Iterator<Item = &ProfileEvent> + '_ {
+		self.events.iter().filter(|e| e.is_kernel())
+	}
+
+	/// Returns the `model_run` events, one for each run of the session while profiling was enabled.
+	pub fn runs(&self) -> impl Iterator<Item = &ProfileEvent> + '_ {
+		self.session_events("model_run")
+	}
+
+	/// Returns the time taken to initialize the session, if it was recorded.
+	pub fn session_initialization(&self) -> Option<Duration> {
+		self.session_events("session_initialization").map(|e| e.duration).next()
+	}
+
+	fn session_events<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s ProfileEvent> + 's {
+		self.events.iter().filter(move |e| e.category == EventCategory::Session && e.name == name)
+	}
+
+	/// Aggregates kernel timings per operator type & per node.
+	pub fn report(&self) -> ProfileReport {
+		ProfileReport::new(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use core::time::Duration;
+
+	use super::{EventCategory, Profile};
+
+	pub(super) const PROFILE: &str = r#"[
+{"cat" : "Session","pid" :10,"tid" :20,"dur" :1500,"ts" :5,"ph" : "X","name" :"session_initialization","args" : {}},
+{"cat" : "Node","pid" :10,"tid" :20,"dur" :1,"ts" :2000,"ph" : "X","name" :"conv_fence_before","args" : {"op_name" : "Conv"}},
+{"cat" : "Node","pid" :10,"tid" :20,"dur" :100,"ts" :2001,"ph" : "X","name" :"conv_kernel_time","args" : {"op_name" : "Conv","provider" : "CPUExecutionProvider","node_index" : "0","input_type_shape" : [{"float":[1,3,8,8]},{"float":[4,3,3,3]}],"output_type_shape" : [{"float":[1,4,8,8]}],"thread_scheduling_stats" : {"main_thread" : {"thread_pool_name" : "session-1-intra-op"}}}},
+{"cat" : "Node","pid" :10,"tid" :20,"dur" :10,"ts" :2101,"ph" : "X","name" :"relu_kernel_time","args" : {"op_name" : "Relu","provider" : "CPUExecutionProvider","node_index" : "1"}},
+{"cat" : "Node","pid" :10,"tid" :21,"dur" :30,"ts" :2111,"ph" : "X","name" :"relu2_kernel_time","args" : {"op_name" : "Relu","provider" : "CPUExecutionProvider","node_index" : "2"}},
+{"cat" : "Session","pid" :10,"tid" :20,"dur" :200,"ts" :1990,"ph" : "X","name" :"model_run","args" : {}},
+{"cat" : "Node","pid" :10,"tid" :20,"dur" :300,"ts" :3001,"ph" : "X","name" :"conv_kernel_time","args" : {"op_name" : "Conv","provider" : "CPUExecutionProvider","node_index" : "0"}},
+{"cat" : "Node","pid" :10,"tid" :20,"dur" :10,"ts" :3301,"ph" : "X","name" :"relu_kernel_time","args" : {"op_name" : "Relu","provider" : "CPUExecutionProvider","node_index" : "1"}},
+{"cat" : "Node","pid" :10,"tid" :21,"dur" :50,"ts" :3311,"ph" : "X","name" :"relu2_kernel_time","args" : {"op_name" : "Relu","provider" : "CPUExecutionProvider","node_index" : "2"}},
+{"cat" : "Session","pid" :10,"tid" :20,"dur" :400,"ts" :2990,"ph" : "X","name" :"model_run","args" : {}}
+]
+"#;
+
+	#[test]
+	fn test_parse_profile() -> crate::Result<()> {
+		let profile = Profile::parse(PROFILE)?;
+		assert_eq!(profile.events().len(), 10);
+		assert_eq!(profile.session_initialization(), Some(Duration::from_micros(1500)));
+		assert_eq!(profile.runs().count(), 2);
+		assert_eq!(profile.kernel_events().count(), 6);
+
+		let conv = &profile.events()[2];
+		assert_eq!(conv.category, EventCategory::Node);
+		assert_eq!(conv.thread_id, 20);
+		assert_eq!(conv.start, Duration::from_micros(2001));
+		let node = conv.node.as_ref().expect("conv should have node info");
+		assert_eq!(node.name, "conv");
+		assert_eq!(node.op_type, "Conv");
+		assert_eq!(node.provider.as_deref(), Some("CPUExecutionProvider"));
+		assert_eq!(node.index, Some(0));
+		assert_eq!(node.inputs[1].element_type, "float");
+		assert_eq!(node.inputs[1].shape, [4, 3, 3, 3]);
+		assert_eq!(node.outputs[0].shape, [1, 4, 8, 8]);
+
+		assert!(!profile.events()[1].is_kernel());
+		assert!(Profile::parse(r#"[{"cat": "Session"}]"#).is_err());
+		Ok(())
+	}
+}
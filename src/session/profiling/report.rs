@@ -0,0 +1,477 @@
+use alloc::{
+	collections::BTreeMap,
+	format,
+	string::{String, ToString},
+	vec,
+	vec::Vec
+};
+use core::{fmt, time::Duration};
+
+use super::{NodeInfo, Profile, ProfileEvent};
+
+/// Aggregated kernel timings for a single operator type or node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate {
+	/// The node name for per-node aggregates, or the operator type for per-operator type aggregates.
+	pub name: String,
+	pub op_type: String,
+	/// The execution provider the kernels ran on, or `None` if they ran on multiple providers.
+	pub provider: Option<String>,
+	/// The number of times the kernel(s) were executed.
+	pub calls: usize,
+	pub total: Duration,
+	pub min: Duration,
+	pub max: Duration,
+	/// The median time of a single call.
+	pub p50: Duration,
+	/// The 99th percentile time of a single call.
+	pub p99: Duration,
+	/// The fraction (`0.0..=1.0`) of total run time spent in these kernels.
+	pub share: f64
+}
+
+impl Aggregate {
+	fn new(name: &str, events: &[&ProfileEvent], denominator: Duration) -> Self {
+		fn node(event: &ProfileEvent) -> &NodeInfo {
+			event.node.as_ref().expect("kernel events have node info")
+		}
+
+		let mut times: Vec<Duration> = events.iter().map(|e| e.duration).collect();
+		times.sort_unstable();
+		let total: Duration = times.iter().sum();
+		let first = node(events[0]);
+		Self {
+			name: name.to_string(),
+			op_type: first.op_type.clone(),
+			provider: first
+				.provider
+				.clone()
+				.filter(|provider| events.iter().all(|e| node(e).provider.as_ref() == Some(provider))),
+			calls: times.len(),
+			total,
+			min: times[0],
+			max: times[times.len() - 1],
+			p50: percentile(&times, 50),
+			p99: percentile(&times, 99),
+			share: if denominator.is_zero() { 0.0 } else { total.as_secs_f64() / denominator.as_secs_f64() }
+		}
+	}
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+	let rank = (p * sorted.len()).div_ceil(100).max(1);
+	sorted[rank - 1]
+}
+
+/// Summary of a [`Profile`], with kernel timings aggregated per operator type & per node.
+///
+/// The [`Display`](fmt::Display) implementation renders the report as a table; use [`ProfileReport::to_csv`] for a
+/// machine-readable version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+	pub session_initialization: Option<Duration>,
+	/// The number of runs recorded in the profile.
+	pub runs: usize,
+	/// The total time of all runs.
+	pub run_time: Duration,
+	/// The total time of all kernels. This may be larger than `run_time` if nodes are executed in parallel.
+	pub kernel_time: Duration,
+	/// Per-operator type aggregates, sorted by descending total time.
+	pub op_types: Vec<Aggregate>,
+	/// Per-node aggregates, sorted by descending total time.
+	pub nodes: Vec<Aggregate>
+}
+
+impl ProfileReport {
+	pub(super) fn new(profile: &Profile) -> Self {
+		let run_time: Duration = profile.runs().map(|e| e.duration).sum();
+		let kernel_time: Duration = profile.kernel_events().map(|e| e.duration).sum();
+		// if no runs were recorded, report the share of kernel time instead
+		let denominator = if run_time.is_zero() { kernel_time } else { run_time };
+
+		let mut op_types: BTreeMap<&str, Vec<&ProfileEvent>> = BTreeMap::new();
+		let mut nodes: BTreeMap<&str, Vec<&ProfileEvent>> = BTreeMap::new();
+		for event in profile.kernel_events() {
+			let node = event.node.as_ref().expect("kernel events have node info");
+			op_types.entry(&node.op_type).or_default().push(event);
+			nodes.entry(&node.name).or_default().push(event);
+		}
+
+		let aggregate = |groups: BTreeMap<&str, Vec<&ProfileEvent>>| {
+			let mut aggregates: Vec<Aggregate> = groups
+				.into_iter()
+				.map(|(name, events)| Aggregate::new(name, &events, denominator))
+				.collect();
+			aggregates.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+			aggregates
+		};
+		Self {
+			session_initialization: profile.session_initialization(),
+			runs: profile.runs().count(),
+			run_time,
+			kernel_time,
+			op_types: aggregate(op_types),
+			nodes: aggregate(nodes)
+		}
+	}
+
+	/// Renders the report as CSV, with one row per operator type & node. The `level` column is either `op_type` or
+	/// `node`, and times are in microseconds.
+	pub fn to_csv(&self) -> String {
+		let mut out = String::from("level,name,op_type,provider,calls,total_us,min_us,max_us,p50_us,p99_us,share\n");
+		for (level, aggregates) in [("op_type", &self.op_types), ("node", &self.nodes)] {
+			for a in aggregates {
+				out.push_str(&csv_row(&[
+					level.to_string(),
+					a.name.clone(),
+					a.op_type.clone(),
+					a.provider.clone().unwrap_or_default(),
+					a.calls.to_string(),
+					a.total.as_micros().to_string(),
+					a.min.as_micros().to_string(),
+					a.max.as_micros().to_string(),
+					a.p50.as_micros().to_string(),
+					a.p99.as_micros().to_string(),
+					format!("{:.6}", a.share)
+				]));
+			}
+		}
+		out
+	}
+
+	/// Compares this report against a `baseline` report, e.g. one recorded with a previous version of ONNX Runtime.
+	///
+	/// Times are compared per run, so the two profiles do not need to have recorded the same number of runs.
+	///
+	/// ```no_run
+	/// # use ort::session::profiling::Profile;
+	/// # fn main() -> ort::Result<()> {
+	/// let baseline = Profile::from_file("baseline.json")?.report();
+	/// let current = Profile::from_file("current.json")?.report();
+	/// let diff = current.diff(&baseline);
+	/// for regression in diff.regressions(0.1) {
+	/// 	eprintln!("{} is {:+.1}% slower", regression.name, regression.change().unwrap_or(f64::INFINITY) * 100.);
+	/// }
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	pub fn diff(&self, baseline: &ProfileReport) -> ProfileDiff {
+		ProfileDiff {
+			baseline_run_time: per_run(baseline.run_time, baseline.runs),
+			current_run_time: per_run(self.run_time, self.runs),
+			op_types: diff_aggregates(&baseline.op_types, baseline.runs, &self.op_types, self.runs),
+			nodes: diff_aggregates(&baseline.nodes, baseline.runs, &self.nodes, self.runs)
+		}
+	}
+}
+
+impl fmt::Display for ProfileReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(init) = self.session_initialization {
+			writeln!(f, "Session initialization: {} ms", millis(init))?;
+		}
+		writeln!(f, "Runs: {} ({} ms total, {} ms kernel time)", self.runs, millis(self.run_time), millis(self.kernel_time))?;
+
+		const HEADERS: [&str; 8] = ["calls", "total (ms)", "min (ms)", "max (ms)", "p50 (ms)", "p99 (ms)", "share", "provider"];
+		for (title, aggregates) in [("op type", &self.op_types), ("node", &self.nodes)] {
+			writeln!(f)?;
+			let mut headers = vec![title];
+			if title == "node" {
+				headers.push("op type");
+			}
+			headers.extend(HEADERS);
+			let rows = aggregates
+				.iter()
+				.map(|a| {
+					let mut row = vec![a.name.clone()];
+					if title == "node" {
+						row.push(a.op_type.clone());
+					}
+					row.extend([
+						a.calls.to_string(),
+						millis(a.total),
+						millis(a.min),
+						millis(a.max),
+						millis(a.p50),
+						millis(a.p99),
+						format!("{:.1}%", a.share * 100.),
+						a.provider.clone().unwrap_or_else(|| "(multiple)".into())
+					]);
+					row
+				})
+				.collect::<Vec<_>>();
+			write_table(f, &headers, &rows)?;
+		}
+		Ok(())
+	}
+}
+
+/// Per-run times of a single operator type or node in two profiles; see [`ProfileReport::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+	pub name: String,
+	pub op_type: String,
+	/// The mean time per run in the baseline profile, or `None` if the node/operator type did not appear in it.
+	pub baseline: Option<Duration>,
+	/// The mean time per run in the current profile, or `None` if the node/operator type did not appear in it.
+	pub current: Option<Duration>
+}
+
+impl DiffEntry {
+	/// Returns the relative change from the baseline to the current profile, e.g. `0.25` if the current profile is
+	/// 25% slower. Returns `None` if the entry is missing from either profile.
+	pub fn change(&self) -> Option<f64> {
+		relative_change(self.baseline?, self.current?)
+	}
+
+	fn delta_micros(&self) -> i128 {
+		self.current.unwrap_or_default().as_micros() as i128 - self.baseline.unwrap_or_default().as_micros() as i128
+	}
+}
+
+/// The difference between two [`ProfileReport`]s; see [`ProfileReport::diff`].
+///
+/// Like [`ProfileReport`], this can be rendered as a table via [`Display`](fmt::Display) or as CSV via
+/// [`ProfileDiff::to_csv`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileDiff {
+	/// The mean time per run in the baseline profile.
+	pub baseline_run_time: Duration,
+	/// The mean time per run in the current profile.
+	pub current_run_time: Duration,
+	/// Per-operator type differences, sorted by descending absolute change.
+	pub op_types: Vec<DiffEntry>,
+	/// Per-node differences, sorted by descending absolute change.
+	pub nodes: Vec<DiffEntry>
+}
+
+impl ProfileDiff {
+	/// Returns the relative change in mean run time, e.g. `0.25` if runs in the current profile are 25% slower.
+	pub fn run_time_change(&self) -> Option<f64> {
+		relative_change(self.baseline_run_time, self.current_run_time)
+	}
+
+	/// Returns nodes which are more than `threshold` slower than in the baseline (e.g. `0.1` for 10% slower), or which
+	/// did not appear in the baseline at all.
+	pub fn regressions(&self, threshold: f64) -> impl Iterator<Item = &DiffEntry> + '_ {
+		self.nodes.iter().filter(move |e| match (e.baseline, e.current) {
+			(None, Some(_)) => true,
+			(Some(_), Some(_)) => e.change().is_some_and(|change| change > threshold),
+			_ => false
+		})
+	}
+
+	/// Renders the diff as CSV, with one row per operator type & node. The `level` column is either `op_type` or
+	/// `node`, and times are in microseconds per run; missing entries have empty cells.
+	pub fn to_csv(&self) -> String {
+		let mut out = String::from("level,name,op_type,baseline_us,current_us,change\n");
+		for (level, entries) in [("op_type", &self.op_types), ("node", &self.nodes)] {
+			for e in entries {
+				out.push_str(&csv_row(&[
+					level.to_string(),
+					e.name.clone(),
+					e.op_type.clone(),
+					e.baseline.map(|d| d.as_micros().to_string()).unwrap_or_default(),
+					e.current.map(|d| d.as_micros().to_string()).unwrap_or_default(),
+					e.change().map(|c| format!("{c:.6}")).unwrap_or_default()
+				]));
+			}
+		}
+		out
+	}
+}
+
+impl fmt::Display for ProfileDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Run time: {} ms -> {} ms ({})", millis(self.baseline_run_time), millis(self.current_run_time), percent_change(self.run_time_change()))?;
+		for (title, entries) in [("op type", &self.op_types), ("node", &self.nodes)] {
+			writeln!(f)?;
+			let mut headers = vec![title];
+			if title == "node" {
+				headers.push("op type");
+			}
+			headers.extend(["baseline (ms)", "current (ms)", "change"]);
+			let rows = entries
+				.iter()
+				.map(|e| {
+					let mut row = vec![e.name.clone()];
+					if title == "node" {
+						row.push(e.op_type.clone());
+					}
+					row.extend([
+						e.baseline.map(millis).unwrap_or_else(|| "-".into()),
+						e.current.map(millis).unwrap_or_else(|| "-".into()),
+						match (e.baseline, e.current) {
+							(None, _) => "new".into(),
+							(_, None) => "removed".into(),
+							_ => percent_change(e.change())
+						}
+					]);
+					row
+				})
+				.collect::<Vec<_>>();
+			write_table(f, &headers, &rows)?;
+		}
+		Ok(())
+	}
+}
+
+fn diff_aggregates(baseline: &[Aggregate], baseline_runs: usize, current: &[Aggregate], current_runs: usize) -> Vec<DiffEntry> {
+	let mut entries: Vec<DiffEntry> = current
+		.iter()
+		.map(|a| DiffEntry {
+			name: a.name.clone(),
+			op_type: a.op_type.clone(),
+			baseline: baseline.iter().find(|b| b.name == a.name).map(|b| per_run(b.total, baseline_runs)),
+			current: Some(per_run(a.total, current_runs))
+		})
+		.collect();
+	entries.extend(baseline.iter().filter(|b| !current.iter().any(|a| a.name == b.name)).map(|b| DiffEntry {
+		name: b.name.clone(),
+		op_type: b.op_type.clone(),
+		baseline: Some(per_run(b.total, baseline_runs)),
+		current: None
+	}));
+	entries.sort_by(|a, b| b.delta_micros().abs().cmp(&a.delta_micros().abs()).then_with(|| a.name.cmp(&b.name)));
+	entries
+}
+
+fn per_run(total: Duration, runs: usize) -> Duration {
+	match u32::try_from(runs) {
+		Ok(0) | Err(_) => total,
+		Ok(runs) => total / runs
+	}
+}
+
+fn relative_change(baseline: Duration, current: Duration) -> Option<f64> {
+	if baseline.is_zero() {
+		return None;
+	}
+	Some(current.as_nanos() as f64 / baseline.as_nanos() as f64 - 1.)
+}
+
+fn percent_change(change: Option<f64>) -> String {
+	change.map(|c| format!("{:+.1}%", c * 100.)).unwrap_or_else(|| "-".into())
+}
+
+fn millis(d: Duration) -> String {
+	format!("{:.3}", d.as_secs_f64() * 1e3)
+}
+
+fn csv_row(fields: &[String]) -> String {
+	let mut row = fields
+		.iter()
+		.map(|field| {
+			if field.contains([',', '"', '\n']) {
+				format!("\"{}\"", field.replace('"', "\"\""))
+			} else {
+				field.clone()
+			}
+		})
+		.collect::<Vec<_>>()
+		.join(",");
+	row.push('\n');
+	row
+}
+
+/// Writes an aligned table. Columns containing only numbers are right-aligned.
+fn write_table(f: &mut fmt::Formatter<'_>, headers: &[&str], rows: &[Vec<String>]) -> fmt::Result {
+	let widths: Vec<usize> = (0..headers.len())
+		.map(|i| {
+			rows.iter()
+				.map(|row| row[i].chars().count())
+				.chain([headers[i].len()])
+				.max()
+				.unwrap_or_default()
+		})
+		.collect();
+	let numeric: Vec<bool> = (0..headers.len())
+		.map(|i| !rows.is_empty() && rows.iter().all(|row| row[i].chars().all(|c| c.is_ascii_digit() || ".+-%".contains(c))))
+		.collect();
+
+	let write_row = |f: &mut fmt::Formatter<'_>, cells: &[&str]| -> fmt::Result {
+		for (i, cell) in cells.iter().enumerate() {
+			if i > 0 {
+				f.write_str("  ")?;
+			}
+			if numeric[i] {
+				write!(f, "{cell:>width$}", width = widths[i])?;
+			} else if i == cells.len() - 1 {
+				f.write_str(cell)?;
+			} else {
+				write!(f, "{cell:<width$}", width = widths[i])?;
+			}
+		}
+		writeln!(f)
+	};
+	write_row(f, headers)?;
+	let rules: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+	write_row(f, &rules.iter().map(String::as_str).collect::<Vec<_>>())?;
+	for row in rows {
+		write_row(f, &row.iter().map(String::as_str).collect::<Vec<_>>())?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::string::ToString;
+	use core::time::Duration;
+
+	use super::super::{Profile, tests::PROFILE};
+
+	#[test]
+	fn test_report() -> crate::Result<()> {
+		let report = Profile::parse(PROFILE)?.report();
+		assert_eq!(report.runs, 2);
+		assert_eq!(report.run_time, Duration::from_micros(600));
+		assert_eq!(report.kernel_time, Duration::from_micros(500));
+
+		assert_eq!(report.op_types.len(), 2);
+		let conv = &report.op_types[0];
+		assert_eq!(conv.name, "Conv");
+		assert_eq!(conv.calls, 2);
+		assert_eq!(conv.total, Duration::from_micros(400));
+		assert_eq!(conv.p50, Duration::from_micros(100));
+		assert_eq!(conv.p99, Duration::from_micros(300));
+		assert!((conv.share - 400. / 600.).abs() < 1e-9);
+		assert_eq!(conv.provider.as_deref(), Some("CPUExecutionProvider"));
+
+		let relu = &report.op_types[1];
+		assert_eq!((relu.calls, relu.min, relu.max, relu.p50), (4, Duration::from_micros(10), Duration::from_micros(50), Duration::from_micros(10)));
+
+		assert_eq!(report.nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), ["conv", "relu2", "relu"]);
+		assert_eq!(report.nodes[1].op_type, "Relu");
+
+		let csv = report.to_csv();
+		assert_eq!(csv.lines().count(), 6);
+		assert!(csv.contains("\nnode,relu2,Relu,CPUExecutionProvider,2,80,30,50,30,50,0.133333\n"));
+		assert!(report.to_string().contains("relu2"));
+		Ok(())
+	}
+
+	#[test]
+	fn test_diff() -> crate::Result<()> {
+		let baseline = Profile::parse(PROFILE)?.report();
+		let current = Profile::parse(&PROFILE.replace(r#""dur" :300"#, r#""dur" :500"#).replace("relu2", "gelu"))?.report();
+
+		let diff = current.diff(&baseline);
+		assert_eq!(diff.baseline_run_time, Duration::from_micros(300));
+		assert_eq!(diff.op_types[0].name, "Conv");
+		assert_eq!(diff.op_types[0].baseline, Some(Duration::from_micros(200)));
+		assert_eq!(diff.op_types[0].current, Some(Duration::from_micros(300)));
+		assert_eq!(diff.op_types[0].change(), Some(0.5));
+
+		let regressions: Vec<_> = diff.regressions(0.1).map(|e| e.name.as_str()).collect();
+		assert_eq!(regressions, ["conv", "gelu"]);
+		assert!(diff.nodes.iter().any(|e| e.name == "relu2" && e.current.is_none()));
+
+		assert!(
+			diff.to_csv()
+				.starts_with("level,name,op_type,baseline_us,current_us,change\nop_type,Conv,Conv,200,300,0.500000\n")
+		);
+		assert!(diff.to_string().contains("removed"));
+		Ok(())
+	}
+}
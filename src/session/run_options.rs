@@ -5,9 +5,15 @@ use core::{
 	mem,
 	ptr::{self, NonNull}
 };
+#[cfg(feature = "std")]
+use core::{sync::atomic::AtomicBool, time::Duration};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 use smallvec::SmallVec;
 
+#[cfg(feature = "std")]
+use super::timer::{DeadlineGuard, RunDeadline};
 use crate::{
 	AsPointer,
 	adapter::{Adapter, AdapterInner},
@@ -25,7 +31,7 @@ use crate::{
 /// # use std::sync::Arc;
 /// # use ort::{session::{Session, run_options::{RunOptions, OutputSelector}}, memory::Allocator, value::Tensor};
 /// # fn main() -> ort::Result<()> {
-/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 /// let input = Tensor::<f32>::new(&Allocator::default(), [1_usize, 64, 64, 3])?;
 ///
 /// let output0 = session.outputs[0].name.as_str();
@@ -104,7 +110,7 @@ impl OutputSelector {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::{RunOptions, OutputSelector}}, memory::Allocator, value::Tensor};
 	/// # fn main() -> ort::Result<()> {
-	/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// let input = Tensor::<f32>::new(&Allocator::default(), [1_usize, 64, 64, 3])?;
 	///
 	/// let output0 = session.outputs[0].name.as_str();
@@ -138,9 +144,11 @@ impl OutputSelector {
 /// Types that specify whether a [`RunOptions`] was configured with an [`OutputSelector`].
 pub trait SelectedOutputMarker {}
 /// Marks that a [`RunOptions`] was not configured with an [`OutputSelector`].
+#[derive(Debug)]
 pub struct NoSelectedOutputs;
 impl SelectedOutputMarker for NoSelectedOutputs {}
 /// Marks that a [`RunOptions`] was configured with an [`OutputSelector`].
+#[derive(Debug)]
 pub struct HasSelectedOutputs;
 impl SelectedOutputMarker for HasSelectedOutputs {}
 
@@ -148,6 +156,12 @@ impl SelectedOutputMarker for HasSelectedOutputs {}
 pub(crate) struct UntypedRunOptions {
 	pub(crate) ptr: NonNull<ort_sys::OrtRunOptions>,
 	pub(crate) outputs: OutputSelector,
+	pub(crate) validate_inputs: bool,
+	#[cfg(feature = "std")]
+	pub(crate) deadline: Option<RunDeadline>,
+	/// Whether a run currently holds an armed deadline on these options; see [`DeadlineGuard::arm`].
+	#[cfg(feature = "std")]
+	pub(crate) armed: Arc<AtomicBool>,
 	adapters: Vec<Arc<AdapterInner>>
 }
 
@@ -156,6 +170,15 @@ impl UntypedRunOptions {
 		ortsys![unsafe RunOptionsSetTerminate(self.ptr.as_ptr())?];
 		Ok(())
 	}
+
+	/// Arms this run's deadline, if one is configured. The returned guard must be [finished](DeadlineGuard::finish)
+	/// or dropped before the run options are.
+	#[cfg(feature = "std")]
+	pub(crate) fn arm_deadline(&self) -> Result<Option<DeadlineGuard>> {
+		self.deadline
+			.map(|deadline| DeadlineGuard::arm(self.ptr, Arc::clone(&self.armed), deadline))
+			.transpose()
+	}
 }
 
 // https://onnxruntime.ai/docs/api/c/struct_ort_api.html#ac2a08cac0a657604bd5899e0d1a13675
@@ -204,6 +227,11 @@ impl RunOptions {
 			inner: UntypedRunOptions {
 				ptr: unsafe { NonNull::new_unchecked(run_options_ptr) },
 				outputs: OutputSelector::default(),
+				validate_inputs: false,
+				#[cfg(feature = "std")]
+				deadline: None,
+				#[cfg(feature = "std")]
+				armed: Arc::new(AtomicBool::new(false)),
 				adapters: Vec::new()
 			},
 			_marker: PhantomData
@@ -220,7 +248,7 @@ impl<O: SelectedOutputMarker> RunOptions<O> {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::{RunOptions, OutputSelector}}, memory::Allocator, value::Tensor};
 	/// # fn main() -> ort::Result<()> {
-	/// let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// let input = Tensor::<f32>::new(&Allocator::default(), [1_usize, 64, 64, 3])?;
 	///
 	/// let output0 = session.outputs[0].name.as_str();
@@ -272,7 +300,7 @@ impl<O: SelectedOutputMarker> RunOptions<O> {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::{RunOptions, OutputSelector}}, value::Value};
 	/// # fn main() -> ort::Result<()> {
-	/// # 	let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// # 	let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// # 	let input = Value::from_array(ndarray::Array4::<f32>::zeros((1, 64, 64, 3)))?;
 	/// let run_options = Arc::new(RunOptions::new()?);
 	///
@@ -300,7 +328,7 @@ impl<O: SelectedOutputMarker> RunOptions<O> {
 	/// # use std::sync::Arc;
 	/// # use ort::{session::{Session, run_options::{RunOptions, OutputSelector}}, value::Value};
 	/// # fn main() -> ort::Result<()> {
-	/// # 	let mut session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// # 	let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
 	/// # 	let input = Value::from_array(ndarray::Array4::<f32>::zeros((1, 64, 64, 3)))?;
 	/// let run_options = Arc::new(RunOptions::new()?);
 	///
@@ -321,6 +349,114 @@ impl<O: SelectedOutputMarker> RunOptions<O> {
 		Ok(())
 	}
 
+	/// Enables or disables validation of inputs before running the session. By default, this is disabled.
+	///
+	/// When enabled, inputs are checked against the session's [`Input`]s before being passed to ONNX Runtime, and a
+	/// mismatch is reported as a descriptive error with one of [`ErrorCode::UnknownInput`],
+	/// [`ErrorCode::MissingInput`], [`ErrorCode::InputTypeMismatch`], or [`ErrorCode::InputShapeMismatch`]. See
+	/// [`Session::validate_inputs`] for the full list of checks.
+	///
+	/// ```
+	/// # use ort::{session::{Session, run_options::RunOptions}, value::Tensor, ErrorCode};
+	/// # fn main() -> ort::Result<()> {
+	/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// let options = RunOptions::new()?.with_input_validation(true);
+	///
+	/// let input = Tensor::<f32>::from_array(([1, 64, 64, 4], vec![0.0; 64 * 64 * 4]))?;
+	/// let err = session.run_with_options(ort::inputs![&input], &options).unwrap_err();
+	/// assert_eq!(err.code(), ErrorCode::InputShapeMismatch);
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	///
+	/// [`Input`]: crate::session::Input
+	/// [`ErrorCode::UnknownInput`]: crate::ErrorCode::UnknownInput
+	/// [`ErrorCode::MissingInput`]: crate::ErrorCode::MissingInput
+	/// [`ErrorCode::InputTypeMismatch`]: crate::ErrorCode::InputTypeMismatch
+	/// [`ErrorCode::InputShapeMismatch`]: crate::ErrorCode::InputShapeMismatch
+	/// [`Session::validate_inputs`]: crate::session::Session::validate_inputs
+	pub fn with_input_validation(mut self, enable: bool) -> Self {
+		self.set_input_validation(enable);
+		self
+	}
+
+	/// Enables or disables validation of inputs before running the session; see [`RunOptions::with_input_validation`].
+	pub fn set_input_validation(&mut self, enable: bool) {
+		self.inner.validate_inputs = enable;
+	}
+
+	/// Automatically [terminates](RunOptions::terminate) runs which take longer than `timeout`, measured from the start
+	/// of each run. A run terminated this way returns an error with the code [`ErrorCode::Timeout`], distinguishing it
+	/// from a run terminated via [`RunOptions::terminate`].
+	///
+	/// Deadlines are tracked by a single timer thread shared by all runs. Because a deadline terminates the underlying
+	/// run options, only one run at a time may use a given [`RunOptions`] with a deadline; starting another run with
+	/// the same options while the first is still in progress fails with [`ErrorCode::InvalidArgument`]. Use a separate
+	/// [`RunOptions`] for each concurrent run.
+	///
+	/// ```no_run
+	/// # use std::time::Duration;
+	/// # use ort::{session::{Session, run_options::RunOptions}, value::Tensor, ErrorCode};
+	/// # fn main() -> ort::Result<()> {
+	/// # 	let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	/// # 	let input = Tensor::<f32>::from_array(([1, 64, 64, 3], vec![0.0; 64 * 64 * 3]))?;
+	/// let options = RunOptions::new()?.with_timeout(Duration::from_millis(50));
+	/// match session.run_with_options(ort::inputs![&input], &options) {
+	/// 	Ok(outputs) => { /* ... */ }
+	/// 	Err(e) if e.code() == ErrorCode::Timeout => eprintln!("inference took too long"),
+	/// 	Err(e) => return Err(e)
+	/// }
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	///
+	/// [`ErrorCode::Timeout`]: crate::ErrorCode::Timeout
+	/// [`ErrorCode::InvalidArgument`]: crate::ErrorCode::InvalidArgument
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.set_timeout(timeout);
+		self
+	}
+
+	/// Automatically terminates runs which take longer than `timeout`; see [`RunOptions::with_timeout`].
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn set_timeout(&mut self, timeout: Duration) {
+		self.inner.deadline = Some(RunDeadline::Timeout(timeout));
+	}
+
+	/// Automatically [terminates](RunOptions::terminate) runs which have not completed by `deadline`. Runs started
+	/// after the deadline has passed fail immediately. A run terminated this way returns an error with the code
+	/// [`ErrorCode::Timeout`].
+	///
+	/// This is useful to enforce a latency budget across multiple runs (e.g. for all steps of a generation loop), or to
+	/// propagate a request deadline from an inference server.
+	///
+	/// As with [`RunOptions::with_timeout`], concurrent runs must each use their own [`RunOptions`].
+	///
+	/// [`ErrorCode::Timeout`]: crate::ErrorCode::Timeout
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn with_deadline(mut self, deadline: Instant) -> Self {
+		self.set_deadline(deadline);
+		self
+	}
+
+	/// Automatically terminates runs which have not completed by `deadline`; see [`RunOptions::with_deadline`].
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn set_deadline(&mut self, deadline: Instant) {
+		self.inner.deadline = Some(RunDeadline::At(deadline));
+	}
+
+	/// Removes any timeout or deadline set with [`RunOptions::with_timeout`] or [`RunOptions::with_deadline`].
+	#[cfg(feature = "std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+	pub fn clear_deadline(&mut self) {
+		self.inner.deadline = None;
+	}
+
 	/// Adds a custom configuration option to the `RunOptions`.
 	///
 	/// This can be used to, for example, configure the graph ID when using compute graphs with an execution provider
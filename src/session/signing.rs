@@ -0,0 +1,591 @@
+//! Verification of models against detached Ed25519 signatures, so that only models produced by a trusted party are
+//! ever run.
+//!
+//! A model is signed with [`sign_file`], which writes a detached signature next to it (`model.onnx` is signed by
+//! `model.onnx.sig`). The signature covers the model and every external data file it references, so neither can be
+//! swapped out or modified. Sessions verify the signature when they are committed if configured with
+//! [`SessionBuilder::with_signature_verification`]; a model that isn't signed, was modified, or was signed by a key
+//! that isn't trusted is rejected with [`ErrorCode::SignatureVerificationFailed`] before ONNX Runtime ever sees it.
+//!
+//! ```no_run
+//! # use ort::session::{Session, signing::{self, SigningKey, VerifyingKey}};
+//! # fn main() -> ort::Result<()> {
+//! // at build time
+//! let signing_key = SigningKey::generate();
+//! let digest = signing::sign_file("model.onnx", &signing_key)?;
+//! println!("signed {digest} with {}", signing_key.verifying_key());
+//!
+//! // on the device, with the hex-encoded public key from above
+//! let trusted = VerifyingKey::from_hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c")?;
+//! let session = Session::builder()?
+//! 	.with_signature_verification([trusted])?
+//! 	.commit_from_file("model.onnx")?;
+//! println!("running {}", session.verified_digest().expect("model was verified"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Models loaded from memory are verified against a [`Signature`] provided with
+//! [`SessionBuilder::with_model_signature`]. Their external data files must be provided in memory too, via
+//! [`SessionBuilder::with_external_initializer_file_in_memory`].
+//!
+//! [`SessionBuilder::with_signature_verification`]: crate::session::builder::SessionBuilder::with_signature_verification
+//! [`SessionBuilder::with_model_signature`]: crate::session::builder::SessionBuilder::with_model_signature
+//! [`SessionBuilder::with_external_initializer_file_in_memory`]: crate::session::builder::SessionBuilder::with_external_initializer_file_in_memory
+
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec
+};
+use core::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use ed25519_dalek::Verifier as _;
+use rand_core::OsRng;
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	model::{Graph, Model}
+};
+
+/// The magic bytes at the start of every signature file.
+pub const MAGIC: [u8; 4] = *b"ORTS";
+/// The version of the signature format written by [`Signature::to_bytes`].
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Separates the signed digest from any other use of the same key.
+const DOMAIN: &[u8] = b"ort model signature v1\0";
+
+/// A private key used to sign models. The key is zeroed when dropped.
+#[derive(Clone)]
+pub struct SigningKey(ed25519_dalek::SigningKey);
+
+impl SigningKey {
+	/// Generates a random key using the operating system's random number generator.
+	pub fn generate() -> Self {
+		Self(ed25519_dalek::SigningKey::generate(&mut OsRng))
+	}
+
+	/// Creates a key from its 32-byte secret.
+	pub fn from_bytes(secret: &[u8; 32]) -> Self {
+		Self(ed25519_dalek::SigningKey::from_bytes(secret))
+	}
+
+	/// Returns the 32-byte secret of this key.
+	pub fn to_bytes(&self) -> [u8; 32] {
+		self.0.to_bytes()
+	}
+
+	/// Returns the public key which verifies signatures made with this key.
+	pub fn verifying_key(&self) -> VerifyingKey {
+		VerifyingKey(self.0.verifying_key())
+	}
+}
+
+impl fmt::Debug for SigningKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("SigningKey(<redacted>)")
+	}
+}
+
+/// A public key trusted to sign models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingKey(ed25519_dalek::VerifyingKey);
+
+impl VerifyingKey {
+	/// Creates a key from its 32-byte encoding, returning an error if it is not a valid Ed25519 public key.
+	pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self> {
+		ed25519_dalek::VerifyingKey::from_bytes(bytes)
+			.map(Self)
+			.map_err(|_| Error::new_with_code(ErrorCode::InvalidArgument, "Invalid Ed25519 public key"))
+	}
+
+	/// Parses a key from 64 hexadecimal digits, as displayed by this type's [`Display`](fmt::Display)
+	/// implementation. Surrounding whitespace is ignored.
+	pub fn from_hex(hex: &str) -> Result<Self> {
+		let bytes =
+			decode_hex::<32>(hex).ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, "Hex-encoded public keys must be 64 hex digits long"))?;
+		Self::from_bytes(&bytes)
+	}
+
+	/// Returns the 32-byte encoding of this key.
+	pub fn to_bytes(&self) -> [u8; 32] {
+		self.0.to_bytes()
+	}
+}
+
+impl fmt::Display for VerifyingKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write_hex(f, self.0.as_bytes())
+	}
+}
+
+/// The SHA-256 digest covering a model and its external data, which is what a [`Signature`] signs.
+///
+/// Its hex encoding (via [`Display`](fmt::Display)) identifies exactly which artifact a session is running.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+	/// Returns the raw bytes of the digest.
+	pub fn as_bytes(&self) -> &[u8; 32] {
+		&self.0
+	}
+
+	fn compute(model: &[u8], external_files: &[(&str, [u8; 32])]) -> Self {
+		let mut hasher = Sha256::new();
+		hasher.update(DOMAIN);
+		hasher.update(Sha256::digest(model));
+		hasher.update((external_files.len() as u64).to_le_bytes());
+		for (name, hash) in external_files {
+			hasher.update((name.len() as u64).to_le_bytes());
+			hasher.update(name.as_bytes());
+			hasher.update(hash);
+		}
+		Self(hasher.finalize().into())
+	}
+}
+
+impl fmt::Display for Digest {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write_hex(f, &self.0)
+	}
+}
+
+impl fmt::Debug for Digest {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Digest({self})")
+	}
+}
+
+/// A detached signature of a model and its external data files.
+///
+/// Encoded by [`Signature::to_bytes`] as the 4 bytes of [`MAGIC`], the format version, 3 reserved zero bytes, and the
+/// 64-byte Ed25519 signature, followed by the names of the external data files the signature covers: their count as a
+/// little-endian `u32`, then each name as a little-endian `u32` length and UTF-8 bytes. The names are part of the
+/// signed digest, so they can't be modified without verification failing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+	signature: [u8; 64],
+	external_files: Vec<String>
+}
+
+impl Signature {
+	const HEADER_LEN: usize = 8 + 64;
+
+	/// Returns the path of the detached signature for the model at `model_path`, i.e. `model.onnx.sig` for
+	/// `model.onnx`.
+	pub fn path_for(model_path: impl AsRef<Path>) -> PathBuf {
+		let mut path = model_path.as_ref().as_os_str().to_owned();
+		path.push(".sig");
+		PathBuf::from(path)
+	}
+
+	/// Returns the names of the external data files covered by this signature, relative to the model's directory.
+	pub fn external_files(&self) -> &[String] {
+		&self.external_files
+	}
+
+	/// Encodes the signature.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(Self::HEADER_LEN + 4 + self.external_files.iter().map(|f| 4 + f.len()).sum::<usize>());
+		bytes.extend_from_slice(&MAGIC);
+		bytes.extend_from_slice(&[FORMAT_VERSION, 0, 0, 0]);
+		bytes.extend_from_slice(&self.signature);
+		bytes.extend_from_slice(&(self.external_files.len() as u32).to_le_bytes());
+		for file in &self.external_files {
+			bytes.extend_from_slice(&(file.len() as u32).to_le_bytes());
+			bytes.extend_from_slice(file.as_bytes());
+		}
+		bytes
+	}
+
+	/// Decodes a signature encoded with [`Signature::to_bytes`].
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+		if bytes.len() < Self::HEADER_LEN + 4 || bytes[..4] != MAGIC {
+			return Err(failed("not a model signature"));
+		}
+		if bytes[4] != FORMAT_VERSION {
+			return Err(failed(format!("unsupported signature version {}", bytes[4])));
+		}
+		let mut signature = [0; 64];
+		signature.copy_from_slice(&bytes[8..Self::HEADER_LEN]);
+
+		let mut rest = &bytes[Self::HEADER_LEN..];
+		let read_u32 = |rest: &mut &[u8]| -> Result<usize> {
+			let (n, tail) = rest.split_first_chunk::<4>().ok_or_else(|| failed("signature is truncated"))?;
+			*rest = tail;
+			Ok(u32::from_le_bytes(*n) as usize)
+		};
+		let count = read_u32(&mut rest)?;
+		let mut external_files = Vec::new();
+		for _ in 0..count {
+			let len = read_u32(&mut rest)?;
+			if rest.len() < len {
+				return Err(failed("signature is truncated"));
+			}
+			let (name, tail) = rest.split_at(len);
+			rest = tail;
+			let name = core::str::from_utf8(name).map_err(|_| failed("external data file name is not valid UTF-8"))?;
+			external_files.push(check_external_file_name(name).map_err(|e| failed(e.message()))?.to_string());
+		}
+		if !rest.is_empty() {
+			return Err(failed("signature has trailing data"));
+		}
+		Ok(Self { signature, external_files })
+	}
+}
+
+/// Signs the model `model`, returning a detached [`Signature`].
+///
+/// Every external data file referenced by the model must be provided in `external_files` as a `(name, contents)` pair,
+/// where `name` is the file's location as recorded in the model.
+pub fn sign(model: &[u8], external_files: &[(&str, &[u8])], key: &SigningKey) -> Result<Signature> {
+	sign_with(model, key, |name| {
+		external_files
+			.iter()
+			.find(|(file, _)| *file == name)
+			.map(|(_, contents)| Sha256::digest(contents).into())
+			.ok_or_else(|| Error::new_with_code(ErrorCode::InvalidArgument, format!("Model references external data file `{name}`, which was not provided")))
+	})
+	.map(|(signature, _)| signature)
+}
+
+/// Signs the model at `model_path` and the external data files it references, writing the detached signature to
+/// [`Signature::path_for(model_path)`](Signature::path_for). Returns the digest which was signed.
+pub fn sign_file(model_path: impl AsRef<Path>, key: &SigningKey) -> Result<Digest> {
+	let model_path = model_path.as_ref();
+	let model = read(model_path)?;
+	let dir = model_path.parent().unwrap_or_else(|| Path::new(""));
+	let (signature, digest) = sign_with(&model, key, |name| hash_file(&dir.join(name)))?;
+	let signature_path = Signature::path_for(model_path);
+	std::fs::write(&signature_path, signature.to_bytes()).map_err(|e| Error::new(format!("Failed to write `{}`: {e}", signature_path.display())))?;
+	Ok(digest)
+}
+
+fn sign_with(model: &[u8], key: &SigningKey, mut hash_external: impl FnMut(&str) -> Result<[u8; 32]>) -> Result<(Signature, Digest)> {
+	let parsed = Model::from_bytes(model)?;
+	let mut names = Vec::new();
+	collect_external_files(&parsed.graph, &mut names)?;
+	names.sort_unstable();
+	names.dedup();
+
+	let hashes = names
+		.iter()
+		.map(|name| Ok((name.as_str(), hash_external(name)?)))
+		.collect::<Result<Vec<_>>>()?;
+	let digest = Digest::compute(model, &hashes);
+	let signature = ed25519_dalek::Signer::sign(&key.0, digest.as_bytes()).to_bytes();
+	Ok((Signature { signature, external_files: names }, digest))
+}
+
+fn collect_external_files(graph: &Graph, names: &mut Vec<String>) -> Result<()> {
+	for initializer in graph.initializers.iter().chain(graph.subgraphs().flat_map(|g| &g.initializers)) {
+		if initializer.is_external() {
+			let location = initializer.external_location().ok_or_else(|| {
+				Error::new_with_code(ErrorCode::InvalidArgument, format!("Initializer `{}` has external data with no location", initializer.name))
+			})?;
+			names.push(check_external_file_name(location)?.to_string());
+		}
+	}
+	Ok(())
+}
+
+/// Verifies `signature` of the model `model`, returning the verified digest if it was made by one of the `trusted`
+/// keys.
+///
+/// Every external data file covered by the signature must be provided in `external_files` as a `(name, contents)` pair.
+pub fn verify(model: &[u8], signature: &Signature, external_files: &[(&str, &[u8])], trusted: &[VerifyingKey]) -> Result<Digest> {
+	verify_with(model, signature, trusted, |name| {
+		external_files
+			.iter()
+			.find(|(file, _)| *file == name)
+			.map(|(_, contents)| Sha256::digest(contents).into())
+			.ok_or_else(|| failed(format!("external data file `{name}` was not provided")))
+	})
+}
+
+/// Verifies the model at `model_path` and its external data files against the detached signature at
+/// [`Signature::path_for(model_path)`](Signature::path_for), returning the verified digest if it was made by one of
+/// the `trusted` keys.
+pub fn verify_file(model_path: impl AsRef<Path>, trusted: &[VerifyingKey]) -> Result<Digest> {
+	let model_path = model_path.as_ref();
+	let signature = read_signature(model_path)?;
+	let model = read(model_path)?;
+	let dir = model_path.parent().unwrap_or_else(|| Path::new(""));
+	verify_with(&model, &signature, trusted, |name| hash_file(&dir.join(name)))
+}
+
+fn verify_with(model: &[u8], signature: &Signature, trusted: &[VerifyingKey], mut hash_external: impl FnMut(&str) -> Result<[u8; 32]>) -> Result<Digest> {
+	let hashes = signature
+		.external_files
+		.iter()
+		.map(|name| Ok((name.as_str(), hash_external(name)?)))
+		.collect::<Result<Vec<_>>>()?;
+	let digest = Digest::compute(model, &hashes);
+	let ed25519_signature = ed25519_dalek::Signature::from_bytes(&signature.signature);
+	if trusted.iter().any(|key| key.0.verify(digest.as_bytes(), &ed25519_signature).is_ok()) {
+		Ok(digest)
+	} else {
+		Err(failed("the model or its external data has been modified, or it was not signed by a trusted key"))
+	}
+}
+
+/// The contents of a model file & its external data files (excluding those provided in memory), as read for
+/// verification by [`Verifier::verify_file`].
+#[derive(Debug)]
+pub(crate) struct VerifiedFile {
+	pub(crate) model: Vec<u8>,
+	pub(crate) external_files: Vec<(String, Vec<u8>)>
+}
+
+/// The signature verification state of a [`SessionBuilder`](crate::session::builder::SessionBuilder).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Verifier {
+	trusted: Vec<VerifyingKey>,
+	signature: Option<Signature>,
+	/// Hashes of external data files provided in memory, which take precedence over files on disk.
+	in_memory_files: Vec<(String, [u8; 32])>,
+	verified: Option<Digest>
+}
+
+impl Verifier {
+	pub(crate) fn trust(&mut self, keys: impl IntoIterator<Item = VerifyingKey>) -> Result<()> {
+		self.trusted.extend(keys);
+		if self.trusted.is_empty() {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, "Signature verification requires at least one trusted key"));
+		}
+		Ok(())
+	}
+
+	pub(crate) fn set_signature(&mut self, signature: Signature) {
+		self.signature = Some(signature);
+	}
+
+	pub(crate) fn add_in_memory_file(&mut self, name: &Path, contents: &[u8]) {
+		self.in_memory_files
+			.push((name.to_string_lossy().into_owned(), Sha256::digest(contents).into()));
+	}
+
+	/// The digest of the model the session is being committed from, if it has been verified.
+	pub(crate) fn verified(&self) -> Option<Digest> {
+		self.verified
+	}
+
+	fn hash_in_memory(&self, name: &str) -> Option<[u8; 32]> {
+		self.in_memory_files.iter().rev().find(|(file, _)| file == name).map(|(_, hash)| *hash)
+	}
+
+	/// Verifies the model at `path`, if verification is enabled. Verification happens only once per commit, so
+	/// committing the (possibly rewritten) model from memory afterwards won't verify it again.
+	///
+	/// Returns the contents of the model & external data files that were verified, which must be used to create the
+	/// session instead of reading the files again; otherwise they could be swapped out after verification.
+	pub(crate) fn verify_file(&mut self, path: &Path) -> Result<Option<VerifiedFile>> {
+		if self.trusted.is_empty() {
+			return Ok(None);
+		}
+		let signature = match self.signature.take() {
+			Some(signature) => signature,
+			None => read_signature(path)?
+		};
+		let model = read(path)?;
+		let dir = path.parent().unwrap_or_else(|| Path::new(""));
+		let mut external_files = Vec::new();
+		let digest = verify_with(&model, &signature, &self.trusted, |name| match self.hash_in_memory(name) {
+			Some(hash) => Ok(hash),
+			None => {
+				let contents = read(&dir.join(name))?;
+				let hash = Sha256::digest(&contents).into();
+				external_files.push((name.to_string(), contents));
+				Ok(hash)
+			}
+		})?;
+		self.trusted.clear();
+		self.verified = Some(digest);
+		Ok(Some(VerifiedFile { model, external_files }))
+	}
+
+	/// Verifies the in-memory model `model`, if verification is enabled.
+	pub(crate) fn verify_memory(&mut self, model: &[u8]) -> Result<()> {
+		if self.trusted.is_empty() {
+			return Ok(());
+		}
+		let signature = self
+			.signature
+			.take()
+			.ok_or_else(|| failed("the model is not signed; provide its signature with `SessionBuilder::with_model_signature`"))?;
+		let digest = verify_with(model, &signature, &self.trusted, |name| {
+			self.hash_in_memory(name)
+				.ok_or_else(|| failed(format!("external data file `{name}` of a model loaded from memory must also be provided in memory")))
+		})?;
+		self.trusted.clear();
+		self.verified = Some(digest);
+		Ok(())
+	}
+}
+
+fn read_signature(model_path: &Path) -> Result<Signature> {
+	let signature_path = Signature::path_for(model_path);
+	match std::fs::read(&signature_path) {
+		Ok(bytes) => Signature::from_bytes(&bytes),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+			Err(failed(format!("the model is not signed; no signature was found at `{}`", signature_path.display())))
+		}
+		Err(e) => Err(failed(format!("failed to read `{}`: {e}", signature_path.display())))
+	}
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+	std::fs::read(path).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read `{}`: {e}", path.display())))
+}
+
+fn hash_file(path: &Path) -> Result<[u8; 32]> {
+	let mut file = std::fs::File::open(path).map_err(|e| Error::new_with_code(ErrorCode::NoSuchFile, format!("Failed to read `{}`: {e}", path.display())))?;
+	let mut hasher = Sha256::new();
+	std::io::copy(&mut file, &mut hasher).map_err(|e| Error::new(format!("Failed to read `{}`: {e}", path.display())))?;
+	Ok(hasher.finalize().into())
+}
+
+/// External data must live in or below the model's directory.
+fn check_external_file_name(name: &str) -> Result<&str> {
+	if Path::new(name)
+		.components()
+		.all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+		&& !name.is_empty()
+	{
+		Ok(name)
+	} else {
+		Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("External data file `{name}` is outside of the model's directory")))
+	}
+}
+
+fn failed(message: impl fmt::Display) -> Error {
+	Error::new_with_code(ErrorCode::SignatureVerificationFailed, format!("Model signature verification failed: {message}"))
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+	bytes.iter().try_for_each(|b| write!(f, "{b:02x}"))
+}
+
+fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+	let hex = hex.trim().as_bytes();
+	if hex.len() != N * 2 {
+		return None;
+	}
+	let mut bytes = [0; N];
+	for (byte, digits) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+		let digit = |d: u8| char::from(d).to_digit(16);
+		*byte = (digit(digits[0])? * 16 + digit(digits[1])?) as u8;
+	}
+	Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Digest, Signature, SigningKey, VerifyingKey, sign, sign_file, verify, verify_file};
+	use crate::{
+		error::ErrorCode,
+		model::{GraphBuilder, Initializer, Node, TensorData},
+		tensor::TensorElementType
+	};
+
+	fn build_model(external: bool) -> crate::Result<Vec<u8>> {
+		let data = if external {
+			TensorData::External(vec![("location".to_string(), "weights/bias.bin".to_string()), ("length".to_string(), "8".to_string())])
+		} else {
+			TensorData::Raw(vec![0; 8])
+		};
+		let model = GraphBuilder::new("signed")
+			.with_input("x", TensorElementType::Float32, [2_i64])
+			.with_output("y", TensorElementType::Float32, [2_i64])
+			.with_raw_initializer(Initializer::new("bias", TensorElementType::Float32, [2], data))
+			.with_node(Node::new("Add").with_inputs(["x", "bias"]).with_outputs(["y"]))
+			.build()?;
+		Ok(model.to_bytes())
+	}
+
+	#[test]
+	fn test_sign_verify() -> crate::Result<()> {
+		let key = SigningKey::generate();
+		let trusted = [SigningKey::generate().verifying_key(), key.verifying_key()];
+
+		let model = build_model(false)?;
+		let signature = sign(&model, &[], &key)?;
+		assert_eq!(Signature::from_bytes(&signature.to_bytes())?, signature);
+		let digest = verify(&model, &signature, &[], &trusted)?;
+		assert_eq!(verify(&model, &signature, &[], &trusted[1..])?, digest);
+
+		let mut tampered = model.clone();
+		*tampered.last_mut().expect("model is not empty") ^= 1;
+		let error = verify(&tampered, &signature, &[], &trusted).expect_err("tampered model should not verify");
+		assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+		assert!(verify(&model, &signature, &[], &trusted[..1]).is_err());
+
+		let bias = [0_u8; 8];
+		let model = build_model(true)?;
+		assert!(sign(&model, &[], &key).is_err());
+		let signature = sign(&model, &[("weights/bias.bin", &bias)], &key)?;
+		assert_eq!(signature.external_files(), ["weights/bias.bin"]);
+		verify(&model, &signature, &[("weights/bias.bin", &bias)], &trusted)?;
+		assert!(verify(&model, &signature, &[("weights/bias.bin", &[1; 8])], &trusted).is_err());
+		assert!(verify(&model, &signature, &[], &trusted).is_err());
+
+		// the external file names are covered by the signature
+		let mut renamed = signature.to_bytes();
+		*renamed.last_mut().expect("signature is not empty") = b'x';
+		let renamed = Signature::from_bytes(&renamed)?;
+		assert!(verify(&model, &renamed, &[("weights/bias.bix", &bias)], &trusted).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_files() -> crate::Result<()> {
+		let dir = std::env::temp_dir().join(format!("ort-test-signing-{}", std::process::id()));
+		std::fs::create_dir_all(dir.join("weights")).map_err(crate::Error::wrap)?;
+		let model_path = dir.join("model.onnx");
+		std::fs::write(&model_path, build_model(true)?).map_err(crate::Error::wrap)?;
+		std::fs::write(dir.join("weights/bias.bin"), [0; 8]).map_err(crate::Error::wrap)?;
+
+		let key = SigningKey::generate();
+		let error = verify_file(&model_path, &[key.verifying_key()]).expect_err("unsigned model should not verify");
+		assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+
+		let digest = sign_file(&model_path, &key)?;
+		assert!(Signature::path_for(&model_path).ends_with("model.onnx.sig"));
+		assert_eq!(verify_file(&model_path, &[key.verifying_key()])?, digest);
+
+		std::fs::write(dir.join("weights/bias.bin"), [1; 8]).map_err(crate::Error::wrap)?;
+		assert!(verify_file(&model_path, &[key.verifying_key()]).is_err());
+		std::fs::remove_dir_all(dir).map_err(crate::Error::wrap)?;
+		Ok(())
+	}
+
+	#[test]
+	fn test_encoding() -> crate::Result<()> {
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let public = key.verifying_key();
+		assert_eq!(VerifyingKey::from_hex(&format!("{public}\n"))?, public);
+		assert!(VerifyingKey::from_hex("00").is_err());
+		assert_eq!(format!("{key:?}"), "SigningKey(<redacted>)");
+		assert_eq!(SigningKey::from_bytes(&key.to_bytes()).verifying_key(), public);
+
+		let digest = Digest::compute(b"model", &[]);
+		assert_eq!(format!("{digest}").len(), 64);
+		assert_eq!(format!("{digest:?}"), format!("Digest({digest})"));
+
+		assert!(Signature::from_bytes(b"ORTS").is_err());
+		let mut bytes = Signature {
+			signature: [0; 64],
+			external_files: vec!["../secret".to_string()]
+		}
+		.to_bytes();
+		assert!(Signature::from_bytes(&bytes).is_err());
+		bytes.truncate(Signature::HEADER_LEN + 4);
+		bytes[Signature::HEADER_LEN..].copy_from_slice(&0_u32.to_le_bytes());
+		assert!(Signature::from_bytes(&bytes)?.external_files().is_empty());
+		Ok(())
+	}
+}
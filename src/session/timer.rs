@@ -0,0 +1,260 @@
+//! A single shared timer thread which terminates runs that exceed their [`RunOptions`] deadline.
+//!
+//! [`RunOptions`]: super::RunOptions
+
+use alloc::{boxed::Box, collections::BTreeMap, format, sync::Arc};
+use core::{
+	ptr::NonNull,
+	sync::atomic::{AtomicBool, AtomicU64, Ordering},
+	time::Duration
+};
+use std::{
+	sync::{Condvar, Mutex, MutexGuard, OnceLock},
+	thread,
+	time::Instant
+};
+
+use crate::{
+	error::{Error, ErrorCode, Result},
+	ortsys
+};
+
+/// When a run started with a [`RunOptions`](super::RunOptions) should be terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RunDeadline {
+	/// Terminate the run if it takes longer than the given duration, measured from the start of each run.
+	Timeout(Duration),
+	/// Terminate the run if it has not completed by the given instant.
+	At(Instant)
+}
+
+impl RunDeadline {
+	fn resolve(&self, now: Instant) -> Instant {
+		match self {
+			RunDeadline::Timeout(timeout) => now + *timeout,
+			RunDeadline::At(instant) => *instant
+		}
+	}
+
+	pub(crate) fn error(&self) -> Error {
+		let message = match self {
+			RunDeadline::Timeout(timeout) => format!("Inference was terminated because it exceeded its timeout of {timeout:?}"),
+			RunDeadline::At(_) => "Inference was terminated because it exceeded its deadline".into()
+		};
+		Error::new_with_code(ErrorCode::Timeout, message)
+	}
+}
+
+struct Entry {
+	run_options: NonNull<ort_sys::OrtRunOptions>,
+	fired: Arc<AtomicBool>
+}
+
+// `OrtApi::RunOptionsSetTerminate` is safe to call from any thread.
+unsafe impl Send for Entry {}
+
+struct Timer {
+	entries: Mutex<BTreeMap<(Instant, u64), Entry>>,
+	changed: Condvar,
+	next_id: AtomicU64
+}
+
+impl Timer {
+	fn get() -> &'static Timer {
+		static TIMER: OnceLock<&'static Timer> = OnceLock::new();
+		TIMER.get_or_init(|| {
+			let timer: &'static Timer = Box::leak(Box::new(Timer {
+				entries: Mutex::new(BTreeMap::new()),
+				changed: Condvar::new(),
+				next_id: AtomicU64::new(0)
+			}));
+			thread::Builder::new()
+				.name("ort-run-timer".into())
+				.spawn(move || timer.run())
+				.expect("failed to spawn run timer thread");
+			timer
+		})
+	}
+
+	fn lock(&self) -> MutexGuard<'_, BTreeMap<(Instant, u64), Entry>> {
+		// Nothing that runs while the lock is held can panic, so the mutex can never be poisoned in practice.
+		self.entries.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn run(&self) {
+		let mut entries = self.lock();
+		loop {
+			let now = Instant::now();
+			while let Some(entry) = entries.first_entry() {
+				if entry.key().0 > now {
+					break;
+				}
+				let entry = entry.remove();
+				entry.fired.store(true, Ordering::Release);
+				let _ = terminate(entry.run_options);
+			}
+
+			entries = match entries.keys().next() {
+				Some((deadline, _)) => {
+					let wait = deadline.saturating_duration_since(now);
+					self.changed.wait_timeout(entries, wait).unwrap_or_else(|e| e.into_inner()).0
+				}
+				None => self.changed.wait(entries).unwrap_or_else(|e| e.into_inner())
+			};
+		}
+	}
+}
+
+fn terminate(run_options: NonNull<ort_sys::OrtRunOptions>) -> Result<()> {
+	ortsys![unsafe RunOptionsSetTerminate(run_options.as_ptr())?];
+	Ok(())
+}
+
+fn unterminate(run_options: NonNull<ort_sys::OrtRunOptions>) -> Result<()> {
+	ortsys![unsafe RunOptionsUnsetTerminate(run_options.as_ptr())?];
+	Ok(())
+}
+
+/// An armed deadline for a single run. The deadline is disarmed when this guard is dropped or
+/// [finished](DeadlineGuard::finish).
+///
+/// The guard must not outlive the `OrtRunOptions` it was armed for.
+pub(crate) struct DeadlineGuard {
+	key: (Instant, u64),
+	run_options: NonNull<ort_sys::OrtRunOptions>,
+	/// Set while a deadline is armed on `run_options`; shared with the owning `RunOptions`.
+	armed: Arc<AtomicBool>,
+	fired: Arc<AtomicBool>,
+	deadline: RunDeadline
+}
+
+// See `Entry`.
+unsafe impl Send for DeadlineGuard {}
+
+impl DeadlineGuard {
+	/// Schedules `run_options` to be terminated once `deadline` passes. Returns a timeout error if the deadline has
+	/// already passed.
+	///
+	/// Termination applies to the `OrtRunOptions` as a whole, so a deadline firing would also abort any other run using
+	/// the same options. To avoid that, arming fails if `armed` indicates another run already holds a deadline on
+	/// `run_options`.
+	pub(crate) fn arm(run_options: NonNull<ort_sys::OrtRunOptions>, armed: Arc<AtomicBool>, deadline: RunDeadline) -> Result<Self> {
+		if armed.swap(true, Ordering::AcqRel) {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				"These `RunOptions` are already in use by another run with a deadline; use separate `RunOptions` for concurrent runs with a timeout or deadline"
+			));
+		}
+
+		let now = Instant::now();
+		let at = deadline.resolve(now);
+		if at <= now {
+			armed.store(false, Ordering::Release);
+			return Err(deadline.error());
+		}
+
+		let timer = Timer::get();
+		let key = (at, timer.next_id.fetch_add(1, Ordering::Relaxed));
+		let fired = Arc::new(AtomicBool::new(false));
+		let is_next = {
+			let mut entries = timer.lock();
+			entries.insert(
+				key,
+				Entry {
+					run_options,
+					fired: Arc::clone(&fired)
+				}
+			);
+			entries.keys().next() == Some(&key)
+		};
+		if is_next {
+			timer.changed.notify_one();
+		}
+
+		Ok(Self {
+			key,
+			run_options,
+			armed,
+			fired,
+			deadline
+		})
+	}
+
+	/// Disarms the deadline. If it had already fired, the run options' termination flag is reset (so that it does not
+	/// affect future runs), and an error `result` is replaced with a timeout error.
+	pub(crate) fn finish<T>(self, result: Result<T>) -> Result<T> {
+		// Disarm first; once the entry is removed, the timer can no longer fire.
+		Timer::get().lock().remove(&self.key);
+		if !self.fired.load(Ordering::Acquire) {
+			return result;
+		}
+
+		let _ = unterminate(self.run_options);
+		result.map_err(|_| self.deadline.error())
+	}
+}
+
+impl Drop for DeadlineGuard {
+	fn drop(&mut self) {
+		Timer::get().lock().remove(&self.key);
+		self.armed.store(false, Ordering::Release);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::sync::Arc;
+	use core::time::Duration;
+	use std::time::Instant;
+
+	use super::{DeadlineGuard, RunDeadline};
+	use crate::{ErrorCode, session::RunOptions};
+
+	#[test]
+	fn test_deadline_fires() -> crate::Result<()> {
+		let options = RunOptions::new()?;
+		let guard = DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), RunDeadline::Timeout(Duration::from_millis(10)))?;
+		let fired = Arc::clone(&guard.fired);
+		std::thread::sleep(Duration::from_millis(100));
+		assert!(fired.load(core::sync::atomic::Ordering::Acquire));
+
+		let err = guard
+			.finish::<()>(Err(crate::Error::new("terminated")))
+			.expect_err("run should have timed out");
+		assert_eq!(err.code(), ErrorCode::Timeout);
+		Ok(())
+	}
+
+	#[test]
+	fn test_deadline_disarmed() -> crate::Result<()> {
+		let options = RunOptions::new()?;
+		let guard = DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), RunDeadline::Timeout(Duration::from_secs(60)))?;
+		let fired = Arc::clone(&guard.fired);
+		guard.finish(Ok(()))?;
+		assert!(!fired.load(core::sync::atomic::Ordering::Acquire));
+
+		assert_eq!(
+			DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), RunDeadline::At(Instant::now()))
+				.err()
+				.map(|e| e.code()),
+			Some(ErrorCode::Timeout)
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn test_deadline_rejects_shared_options() -> crate::Result<()> {
+		let options = RunOptions::new()?;
+		let deadline = RunDeadline::Timeout(Duration::from_secs(60));
+		let guard = DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), deadline)?;
+		assert_eq!(
+			DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), deadline)
+				.err()
+				.map(|e| e.code()),
+			Some(ErrorCode::InvalidArgument)
+		);
+
+		guard.finish(Ok(()))?;
+		DeadlineGuard::arm(options.inner.ptr, Arc::clone(&options.inner.armed), deadline)?.finish(Ok(()))
+	}
+}
@@ -0,0 +1,399 @@
+//! Strongly-typed model inputs & outputs.
+//!
+//! Instead of building [`SessionInputs`] with [`inputs!`](crate::inputs) and extracting outputs by name, a model's
+//! inputs and outputs can be described with plain structs implementing [`ModelInputs`] and [`ModelOutputs`]. With the
+//! `derive` feature enabled, these traits can be derived:
+//!
+//! ```ignore
+//! use ort::{
+//! 	session::{ModelInputs, ModelOutputs, Session},
+//! 	value::{Tensor, TensorRef}
+//! };
+//!
+//! #[derive(ModelInputs)]
+//! struct Inputs<'a> {
+//! 	input_ids: TensorRef<'a, i64>,
+//! 	#[ort(name = "attention_mask")]
+//! 	mask: TensorRef<'a, i64>
+//! }
+//!
+//! #[derive(ModelOutputs)]
+//! struct Outputs {
+//! 	logits: Tensor<f32>
+//! }
+//!
+//! let session = Session::builder()?.with_model_inputs::<Inputs>()?.with_model_outputs::<Outputs>()?.commit_from_file("model.onnx")?;
+//! let outputs: Outputs = session.run_typed(Inputs { input_ids, mask })?;
+//! ```
+//!
+//! Struct fields map to the graph input/output of the same name; use `#[ort(name = "...")]` to map a field to a
+//! differently named input/output (for example, names containing `:` or `.`). Fields can be any type implementing
+//! [`IntoModelInput`] (for inputs) or [`FromModelOutput`] (for outputs), which includes [`Tensor`], [`TensorRef`],
+//! [`DynValue`], and (with the `ndarray` feature) `ndarray` arrays & views.
+
+use alloc::{format, vec::Vec};
+use core::fmt::{self, Debug};
+
+use super::{Session, SessionInputValue, SessionInputs, SessionOutputs};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	tensor::{PrimitiveTensorElementType, TensorElementType},
+	value::{DynTensor, DynTensorValueType, DynValue, Tensor, TensorRef, TensorRefMut, TensorValueType, ValueType}
+};
+
+/// The type of value a field of a [`ModelInputs`] or [`ModelOutputs`] struct expects, used to validate the struct
+/// against a [`Session`]'s inputs & outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueSignature {
+	/// The element type of the tensor, or `None` if any element type (or a non-tensor value) is accepted.
+	pub element_type: Option<TensorElementType>,
+	/// The number of dimensions of the tensor, or `None` if tensors of any rank are accepted.
+	pub rank: Option<usize>
+}
+
+impl ValueSignature {
+	/// A signature which accepts any value.
+	pub const ANY: ValueSignature = ValueSignature { element_type: None, rank: None };
+
+	/// A signature which accepts a tensor of the given element type & rank.
+	pub fn tensor(element_type: TensorElementType, rank: Option<usize>) -> Self {
+		Self {
+			element_type: Some(element_type),
+			rank
+		}
+	}
+
+	fn check(&self, kind: Kind, field: &str, name: &str, ty: &ValueType) -> Result<()> {
+		if self == &Self::ANY {
+			return Ok(());
+		}
+
+		let (ty, shape) = match ty {
+			ValueType::Tensor { ty, shape, .. } => (*ty, shape),
+			ty => {
+				return Err(Error::new_with_code(kind.type_mismatch(), format!("Field `{field}` expects a tensor, but model {kind} `{name}` is a {ty}")));
+			}
+		};
+		if let Some(element_type) = self.element_type {
+			if element_type != ty {
+				return Err(Error::new_with_code(
+					kind.type_mismatch(),
+					format!("Field `{field}` expects a tensor of {element_type}, but model {kind} `{name}` is a tensor of {ty}")
+				));
+			}
+		}
+		if let Some(rank) = self.rank {
+			if rank != shape.len() {
+				return Err(Error::new_with_code(
+					kind.shape_mismatch(),
+					format!("Field `{field}` expects a tensor of rank {rank}, but model {kind} `{name}` has shape {shape}")
+				));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Whether a field belongs to a [`ModelInputs`] or [`ModelOutputs`] struct.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+	Input,
+	Output
+}
+
+impl Kind {
+	fn type_mismatch(self) -> ErrorCode {
+		match self {
+			Self::Input => ErrorCode::InputTypeMismatch,
+			Self::Output => ErrorCode::OutputTypeMismatch
+		}
+	}
+
+	fn shape_mismatch(self) -> ErrorCode {
+		match self {
+			Self::Input => ErrorCode::InputShapeMismatch,
+			Self::Output => ErrorCode::OutputShapeMismatch
+		}
+	}
+}
+
+impl fmt::Display for Kind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Input => "input",
+			Self::Output => "output"
+		})
+	}
+}
+
+/// Describes a single field of a [`ModelInputs`] or [`ModelOutputs`] struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSignature {
+	/// The name of the struct field.
+	pub field: &'static str,
+	/// The name of the model input or output this field maps to.
+	pub name: &'static str,
+	/// The type of value this field expects.
+	pub signature: ValueSignature
+}
+
+/// A field of a [`ModelInputs`] struct; see the [module-level documentation](self) for more information.
+pub trait IntoModelInput {
+	/// The type of value this field expects.
+	fn signature() -> ValueSignature;
+
+	/// Converts this field into a value that can be passed to [`Session::run`].
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v;
+}
+
+/// A field of a [`ModelOutputs`] struct; see the [module-level documentation](self) for more information.
+pub trait FromModelOutput: Sized {
+	/// The type of value this field expects.
+	fn signature() -> ValueSignature;
+
+	/// Converts a session output into this field.
+	fn from_model_output(value: DynValue) -> Result<Self>;
+}
+
+/// A struct describing all of the inputs passed to a model.
+///
+/// This trait is usually implemented via `#[derive(ModelInputs)]` (requires the `derive` feature); see the
+/// [module-level documentation](self) for more information.
+pub trait ModelInputs {
+	/// Returns the names & expected types of each input, in field order.
+	fn signature() -> Vec<FieldSignature>;
+
+	/// Converts this struct into [`SessionInputs`].
+	fn into_session_inputs<'v>(self) -> Result<SessionInputs<'v, 'v>>
+	where
+		Self: 'v;
+
+	/// Checks that every field of this struct corresponds to an input of `session` with a compatible type.
+	fn check(session: &Session) -> Result<()> {
+		for FieldSignature { field, name, signature } in Self::signature() {
+			let input = session.inputs.iter().find(|i| i.name == name).ok_or_else(|| {
+				Error::new_with_code(ErrorCode::UnknownInput, format!("Field `{field}` refers to model input `{name}`, which does not exist"))
+			})?;
+			signature.check(Kind::Input, field, name, &input.input_type)?;
+		}
+		Ok(())
+	}
+}
+
+/// A struct describing (some or all of) the outputs of a model.
+///
+/// This trait is usually implemented via `#[derive(ModelOutputs)]` (requires the `derive` feature); see the
+/// [module-level documentation](self) for more information.
+pub trait ModelOutputs: Sized {
+	/// Returns the names & expected types of each output, in field order.
+	fn signature() -> Vec<FieldSignature>;
+
+	/// Extracts this struct from [`SessionOutputs`].
+	fn from_session_outputs(outputs: SessionOutputs<'_, '_>) -> Result<Self>;
+
+	/// Checks that every field of this struct corresponds to an output of `session` with a compatible type.
+	fn check(session: &Session) -> Result<()> {
+		for FieldSignature { field, name, signature } in Self::signature() {
+			let output = session.outputs.iter().find(|o| o.name == name).ok_or_else(|| {
+				Error::new_with_code(ErrorCode::UnknownOutput, format!("Field `{field}` refers to model output `{name}`, which does not exist"))
+			})?;
+			signature.check(Kind::Output, field, name, &output.output_type)?;
+		}
+		Ok(())
+	}
+}
+
+/// Used by `#[derive(ModelInputs)]` to convert a single field, attaching the field name to any error.
+#[doc(hidden)]
+pub fn __input_field<'v, T: IntoModelInput + 'v>(field: &str, name: &str, value: T) -> Result<SessionInputValue<'v>> {
+	value
+		.into_model_input()
+		.map_err(|e| Error::new_with_code(e.code(), format!("Failed to convert field `{field}` into model input `{name}`: {}", e.message())))
+}
+
+/// Used by `#[derive(ModelOutputs)]` to extract a single field, attaching the field name to any error.
+#[doc(hidden)]
+pub fn __output_field<T: FromModelOutput>(outputs: &mut SessionOutputs<'_, '_>, field: &str, name: &str) -> Result<T> {
+	let value = outputs
+		.remove(name)
+		.ok_or_else(|| Error::new_with_code(ErrorCode::MissingOutput, format!("Field `{field}` refers to model output `{name}`, which was not returned")))?;
+	T::from_model_output(value)
+		.map_err(|e| Error::new_with_code(e.code(), format!("Failed to extract field `{field}` from model output `{name}`: {}", e.message())))
+}
+
+impl<T: PrimitiveTensorElementType + Debug> IntoModelInput for Tensor<T> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), None)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>> {
+		Ok(self.into())
+	}
+}
+
+impl<T: PrimitiveTensorElementType + Debug> IntoModelInput for &Tensor<T> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), None)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v
+	{
+		Ok(self.into())
+	}
+}
+
+impl<'a, T: PrimitiveTensorElementType + Debug> IntoModelInput for TensorRef<'a, T> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), None)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v
+	{
+		Ok(self.into())
+	}
+}
+
+impl<'a, T: PrimitiveTensorElementType + Debug> IntoModelInput for TensorRefMut<'a, T> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), None)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v
+	{
+		Ok(self.into())
+	}
+}
+
+impl IntoModelInput for DynTensor {
+	fn signature() -> ValueSignature {
+		ValueSignature::ANY
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>> {
+		Ok(self.into())
+	}
+}
+
+impl IntoModelInput for DynValue {
+	fn signature() -> ValueSignature {
+		ValueSignature::ANY
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>> {
+		Ok(self.into())
+	}
+}
+
+impl IntoModelInput for &DynValue {
+	fn signature() -> ValueSignature {
+		ValueSignature::ANY
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v
+	{
+		Ok(self.into())
+	}
+}
+
+#[cfg(feature = "ndarray")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+impl<T: PrimitiveTensorElementType + Debug + Clone + 'static, D: ndarray::Dimension + 'static> IntoModelInput for ndarray::Array<T, D> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), D::NDIM)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>> {
+		Ok(Tensor::from_array(self)?.into())
+	}
+}
+
+#[cfg(feature = "ndarray")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+impl<'a, T: PrimitiveTensorElementType + Debug + Clone + 'static, D: ndarray::Dimension + 'static> IntoModelInput for ndarray::ArrayView<'a, T, D> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), D::NDIM)
+	}
+
+	fn into_model_input<'v>(self) -> Result<SessionInputValue<'v>>
+	where
+		Self: 'v
+	{
+		Ok(TensorRef::from_array_view(self)?.into())
+	}
+}
+
+impl<T: PrimitiveTensorElementType + Debug> FromModelOutput for Tensor<T> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), None)
+	}
+
+	fn from_model_output(value: DynValue) -> Result<Self> {
+		value.downcast::<TensorValueType<T>>().map_err(type_mismatch)
+	}
+}
+
+impl FromModelOutput for DynTensor {
+	fn signature() -> ValueSignature {
+		ValueSignature::ANY
+	}
+
+	fn from_model_output(value: DynValue) -> Result<Self> {
+		value.downcast::<DynTensorValueType>().map_err(type_mismatch)
+	}
+}
+
+impl FromModelOutput for DynValue {
+	fn signature() -> ValueSignature {
+		ValueSignature::ANY
+	}
+
+	fn from_model_output(value: DynValue) -> Result<Self> {
+		Ok(value)
+	}
+}
+
+#[cfg(feature = "ndarray")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+impl<T: PrimitiveTensorElementType + Clone + 'static, D: ndarray::Dimension + 'static> FromModelOutput for ndarray::Array<T, D> {
+	fn signature() -> ValueSignature {
+		ValueSignature::tensor(T::into_tensor_element_type(), D::NDIM)
+	}
+
+	fn from_model_output(value: DynValue) -> Result<Self> {
+		value
+			.try_extract_array::<T>()
+			.map_err(type_mismatch)?
+			.to_owned()
+			.into_dimensionality::<D>()
+			.map_err(|e| Error::new_with_code(ErrorCode::OutputShapeMismatch, format!("Output has an unexpected number of dimensions: {e}")))
+	}
+}
+
+/// Reports a failure to extract an output as the requested type as [`ErrorCode::OutputTypeMismatch`].
+fn type_mismatch(e: Error) -> Error {
+	Error::new_with_code(ErrorCode::OutputTypeMismatch, e.message())
+}
+
+impl Session {
+	/// Runs the session with strongly-typed inputs & outputs; see [`ModelInputs`] and [`ModelOutputs`].
+	///
+	/// To validate the structs against the model once when the session is created instead of on every run, see
+	/// [`SessionBuilder::with_model_inputs`] & [`SessionBuilder::with_model_outputs`].
+	///
+	/// [`SessionBuilder::with_model_inputs`]: crate::session::builder::SessionBuilder::with_model_inputs
+	/// [`SessionBuilder::with_model_outputs`]: crate::session::builder::SessionBuilder::with_model_outputs
+	pub fn run_typed<'v, I: ModelInputs + 'v, O: ModelOutputs>(&self, inputs: I) -> Result<O> {
+		let outputs = self.run(inputs.into_session_inputs()?)?;
+		O::from_session_outputs(outputs)
+	}
+}
@@ -0,0 +1,287 @@
+//! Pre-run validation of session inputs against the model's declared inputs.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use smallvec::SmallVec;
+
+use super::{Session, SessionInputValue, SessionInputs};
+use crate::{
+	error::{Error, ErrorCode, Result},
+	util::STACK_SESSION_INPUTS,
+	value::ValueType
+};
+
+impl Session {
+	/// Checks that `inputs` match this session's [`Input`](super::Input)s without running the model, returning a
+	/// descriptive error if they do not.
+	///
+	/// The following conditions are checked:
+	/// - Every input name refers to an input of the model ([`ErrorCode::UnknownInput`]). If a similarly named input
+	///   exists, it is suggested in the error message.
+	/// - Every required (i.e. non-[`ValueType::Optional`]) input of the model is provided
+	///   ([`ErrorCode::MissingInput`]).
+	/// - Each value is of the same kind (tensor, sequence, map) as the model input, and tensors have the expected
+	///   element type ([`ErrorCode::InputTypeMismatch`]).
+	/// - Tensors have the expected rank & fixed dimensions, and dynamic dimensions with the same symbolic name (e.g.
+	///   `batch_size`) have the same size across all inputs ([`ErrorCode::InputShapeMismatch`]).
+	///
+	/// The same checks can be performed automatically before every run by enabling
+	/// [`RunOptions::with_input_validation`](super::RunOptions::with_input_validation).
+	///
+	/// ```
+	/// # use ort::{session::Session, value::Tensor, ErrorCode};
+	/// # fn main() -> ort::Result<()> {
+	/// let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+	///
+	/// let input = Tensor::<i32>::from_array(([1, 64, 64, 3], vec![0; 64 * 64 * 3]))?;
+	/// let err = session.validate_inputs(ort::inputs![&input]).unwrap_err();
+	/// assert_eq!(err.code(), ErrorCode::InputTypeMismatch);
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	pub fn validate_inputs<'i, 'v: 'i, const N: usize>(&self, inputs: impl Into<SessionInputs<'i, 'v, N>>) -> Result<()> {
+		match inputs.into() {
+			SessionInputs::ValueSlice(values) => self.validate_inputs_inner(&self.positional_input_names(values.len())?, &values.iter().collect::<Vec<_>>()),
+			SessionInputs::ValueArray(values) => self.validate_inputs_inner(&self.positional_input_names(values.len())?, &values.iter().collect::<Vec<_>>()),
+			SessionInputs::ValueMap(values) => {
+				self.validate_inputs_inner(&values.iter().map(|(k, _)| k.as_ref()).collect::<Vec<_>>(), &values.iter().map(|(_, v)| v).collect::<Vec<_>>())
+			}
+		}
+	}
+
+	fn positional_input_names(&self, len: usize) -> Result<SmallVec<&str, { STACK_SESSION_INPUTS }>> {
+		if len > self.inputs.len() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!("{len} inputs were provided, but the model only accepts {}.", self.inputs.len())
+			));
+		}
+		Ok(self.inputs.iter().take(len).map(|input| input.name.as_str()).collect())
+	}
+
+	pub(crate) fn validate_inputs_inner(&self, names: &[&str], values: &[&SessionInputValue<'_>]) -> Result<()> {
+		for name in names {
+			if !self.inputs.iter().any(|input| input.name == *name) {
+				let message = match suggest(name, self.inputs.iter().map(|input| input.name.as_str())) {
+					Some(suggestion) => format!("Model has no input named `{name}`; did you mean `{suggestion}`?"),
+					None => format!(
+						"Model has no input named `{name}`; expected one of {}",
+						self.inputs.iter().map(|input| format!("`{}`", input.name)).collect::<Vec<_>>().join(", ")
+					)
+				};
+				return Err(Error::new_with_code(ErrorCode::UnknownInput, message));
+			}
+		}
+
+		let missing: Vec<&str> = self
+			.inputs
+			.iter()
+			.filter(|input| !matches!(input.input_type, ValueType::Optional(_)) && !names.contains(&input.name.as_str()))
+			.map(|input| input.name.as_str())
+			.collect();
+		if !missing.is_empty() {
+			return Err(Error::new_with_code(
+				ErrorCode::MissingInput,
+				format!("Missing required input(s): {}", missing.iter().map(|name| format!("`{name}`")).collect::<Vec<_>>().join(", "))
+			));
+		}
+
+		// (symbol, size, input name) for each symbolic dimension seen so far
+		let mut symbols: Vec<(&str, i64, &str)> = Vec::new();
+		for (name, value) in names.iter().zip(values) {
+			let input = self.inputs.iter().find(|input| input.name == *name).expect("checked above");
+			let expected = match &input.input_type {
+				ValueType::Optional(inner) => inner,
+				ty => ty
+			};
+			check_value(name, expected, value.dtype(), &mut symbols)?;
+		}
+		Ok(())
+	}
+}
+
+fn check_value<'a>(name: &'a str, expected: &'a ValueType, actual: &ValueType, symbols: &mut Vec<(&'a str, i64, &'a str)>) -> Result<()> {
+	match (expected, actual) {
+		(
+			ValueType::Tensor {
+				ty: expected_ty,
+				shape: expected_shape,
+				dimension_symbols
+			},
+			ValueType::Tensor { ty, shape, .. }
+		)
+		| (
+			ValueType::SparseTensor {
+				ty: expected_ty,
+				shape: expected_shape,
+				dimension_symbols
+			},
+			ValueType::SparseTensor { ty, shape, .. }
+		) => {
+			if expected_ty != ty {
+				return Err(Error::new_with_code(
+					ErrorCode::InputTypeMismatch,
+					format!("Input `{name}` expects a tensor of {expected_ty}, but a tensor of {ty} was provided")
+				));
+			}
+			if expected_shape.len() != shape.len() {
+				return Err(Error::new_with_code(
+					ErrorCode::InputShapeMismatch,
+					format!(
+						"Input `{name}` expects a tensor of rank {} (shape {}), but a tensor of rank {} (shape {shape}) was provided",
+						expected_shape.len(),
+						describe_shape(expected_shape, dimension_symbols),
+						shape.len()
+					)
+				));
+			}
+			for (axis, (&expected_dim, &dim)) in expected_shape.iter().zip(shape.iter()).enumerate() {
+				if expected_dim >= 0 {
+					if expected_dim != dim {
+						return Err(Error::new_with_code(
+							ErrorCode::InputShapeMismatch,
+							format!(
+								"Input `{name}` expects size {expected_dim} at axis {axis} (shape {}), but a tensor of shape {shape} was provided",
+								describe_shape(expected_shape, dimension_symbols)
+							)
+						));
+					}
+					continue;
+				}
+
+				let symbol = dimension_symbols.get(axis).map(String::as_str).unwrap_or_default();
+				if symbol.is_empty() {
+					continue;
+				}
+				match symbols.iter().find(|(s, ..)| *s == symbol) {
+					Some((_, size, other)) if *size != dim => {
+						return Err(Error::new_with_code(
+							ErrorCode::InputShapeMismatch,
+							format!("Dimension `{symbol}` is {dim} at axis {axis} of input `{name}`, but was {size} in input `{other}`")
+						));
+					}
+					Some(_) => {}
+					None => symbols.push((symbol, dim, name))
+				}
+			}
+			Ok(())
+		}
+		(ValueType::Sequence(_), ValueType::Sequence(_))
+		| (ValueType::Map { .. }, ValueType::Map { .. })
+		| (ValueType::Optional(_), ValueType::Optional(_)) => Ok(()),
+		(expected, actual) => {
+			Err(Error::new_with_code(ErrorCode::InputTypeMismatch, format!("Input `{name}` expects a {expected}, but a {actual} was provided")))
+		}
+	}
+}
+
+/// Formats a model input's shape with symbolic dimension names in place of `-1`, e.g. `[batch_size, 3, -1]`.
+fn describe_shape(shape: &[i64], symbols: &[String]) -> String {
+	let dims: Vec<String> = shape
+		.iter()
+		.enumerate()
+		.map(|(i, dim)| match symbols.get(i) {
+			Some(symbol) if *dim < 0 && !symbol.is_empty() => symbol.clone(),
+			_ => format!("{dim}")
+		})
+		.collect();
+	format!("[{}]", dims.join(", "))
+}
+
+/// Returns the candidate most similar to `name`, if any is close enough to plausibly be a typo.
+fn suggest<'c>(name: &str, candidates: impl Iterator<Item = &'c str>) -> Option<&'c str> {
+	let max_distance = (name.chars().count() / 3).max(1);
+	candidates
+		.map(|candidate| (edit_distance(&name.to_lowercase(), &candidate.to_lowercase()), candidate))
+		.filter(|(distance, _)| *distance <= max_distance)
+		.min_by_key(|(distance, _)| *distance)
+		.map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut prev: Vec<usize> = (0..=b.len()).collect();
+	let mut cur = vec![0; b.len() + 1];
+	for (i, ca) in a.chars().enumerate() {
+		cur[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let cost = usize::from(ca != *cb);
+			cur[j + 1] = (prev[j + 1] + 1).min(cur[j] + 1).min(prev[j] + cost);
+		}
+		core::mem::swap(&mut prev, &mut cur);
+	}
+	prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{describe_shape, edit_distance, suggest};
+	use crate::{
+		ErrorCode,
+		session::{RunOptions, Session, SessionInputs},
+		value::Tensor
+	};
+
+	#[test]
+	fn test_edit_distance() {
+		assert_eq!(edit_distance("input_ids", "input_ids"), 0);
+		assert_eq!(edit_distance("input_id", "input_ids"), 1);
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(suggest("inptu_ids", ["attention_mask", "input_ids"].into_iter()), Some("input_ids"));
+		assert_eq!(suggest("pixel_values", ["attention_mask", "input_ids"].into_iter()), None);
+	}
+
+	#[test]
+	fn test_describe_shape() {
+		assert_eq!(describe_shape(&[-1, 3, -1], &["batch".into(), String::new(), String::new()]), "[batch, 3, -1]");
+	}
+
+	#[test]
+	fn test_validate_inputs() -> crate::Result<()> {
+		let session = Session::builder()?.commit_from_file("tests/data/upsample.onnx")?;
+		let name = session.inputs[0].name.clone();
+
+		let input = Tensor::<f32>::from_array(([1, 4, 4, 3], vec![0.0; 48]))?;
+		session.validate_inputs(crate::inputs![&input])?;
+		session.validate_inputs(crate::inputs![name.as_str() => &input])?;
+
+		let err = session
+			.validate_inputs(crate::inputs!["up_sampling2d_inptu:0" => &input])
+			.expect_err("validation should fail");
+		assert_eq!(err.code(), ErrorCode::UnknownInput);
+		assert!(err.message().contains(&format!("did you mean `{name}`")));
+
+		let err = session
+			.validate_inputs(SessionInputs::<0>::ValueMap(Vec::new()))
+			.expect_err("validation should fail");
+		assert_eq!(err.code(), ErrorCode::MissingInput);
+
+		let wrong_type = Tensor::<i64>::from_array(([1, 4, 4, 3], vec![0; 48]))?;
+		assert_eq!(
+			session
+				.validate_inputs(crate::inputs![&wrong_type])
+				.expect_err("validation should fail")
+				.code(),
+			ErrorCode::InputTypeMismatch
+		);
+
+		let wrong_dim = Tensor::<f32>::from_array(([1, 4, 4, 4], vec![0.0; 64]))?;
+		assert_eq!(
+			session
+				.validate_inputs(crate::inputs![&wrong_dim])
+				.expect_err("validation should fail")
+				.code(),
+			ErrorCode::InputShapeMismatch
+		);
+
+		let wrong_rank = Tensor::<f32>::from_array(([4, 4, 3], vec![0.0; 48]))?;
+		let options = RunOptions::new()?.with_input_validation(true);
+		let err = session
+			.run_with_options(crate::inputs![&wrong_rank], &options)
+			.expect_err("validation should fail");
+		assert_eq!(err.code(), ErrorCode::InputShapeMismatch);
+
+		Ok(())
+	}
+}
@@ -131,31 +131,51 @@ impl Trainer {
 		inputs: impl Into<SessionInputs<'i1, 'v1, N1>>,
 		labels: impl Into<SessionInputs<'i2, 'v2, N2>>
 	) -> Result<SessionOutputs<'s, 's>> {
-		match inputs.into() {
-			SessionInputs::ValueSlice(input_values) => match labels.into() {
-				SessionInputs::ValueSlice(labels) => self.step_inner(input_values.iter().chain(labels).map(Some), None),
-				SessionInputs::ValueArray(labels) => self.step_inner(input_values.iter().chain(labels.iter()).map(Some), None),
+		self.step_dispatch(inputs.into(), labels.into(), None)
+	}
+
+	/// Performs a training step like [`Trainer::step`], using the given [`RunOptions`]. This can be used to terminate a
+	/// long-running step, e.g. with [`RunOptions::with_timeout`].
+	pub fn step_with_options<'r, 's: 'r, 'i1, 'v1: 'i1, 'i2: 'i1, 'v2: 'i2 + 'i1, const N1: usize, const N2: usize>(
+		&'s self,
+		inputs: impl Into<SessionInputs<'i1, 'v1, N1>>,
+		labels: impl Into<SessionInputs<'i2, 'v2, N2>>,
+		run_options: &'r RunOptions
+	) -> Result<SessionOutputs<'r, 's>> {
+		self.step_dispatch(inputs.into(), labels.into(), Some(run_options))
+	}
+
+	fn step_dispatch<'r, 's: 'r, 'i1, 'v1: 'i1, 'i2: 'i1, 'v2: 'i2 + 'i1, const N1: usize, const N2: usize>(
+		&'s self,
+		inputs: SessionInputs<'i1, 'v1, N1>,
+		labels: SessionInputs<'i2, 'v2, N2>,
+		run_options: Option<&'r RunOptions>
+	) -> Result<SessionOutputs<'r, 's>> {
+		match inputs {
+			SessionInputs::ValueSlice(input_values) => match labels {
+				SessionInputs::ValueSlice(labels) => self.step_inner(input_values.iter().chain(labels).map(Some), run_options),
+				SessionInputs::ValueArray(labels) => self.step_inner(input_values.iter().chain(labels.iter()).map(Some), run_options),
 				SessionInputs::ValueMap(labels) => {
 					let labels = mapped_inputs(&self.train_input_names, &labels);
-					self.step_inner(input_values.iter().map(Some).chain(labels), None)
+					self.step_inner(input_values.iter().map(Some).chain(labels), run_options)
 				}
 			},
-			SessionInputs::ValueArray(input_values) => match labels.into() {
-				SessionInputs::ValueSlice(labels) => self.step_inner(input_values.iter().chain(labels).map(Some), None),
-				SessionInputs::ValueArray(labels) => self.step_inner(input_values.iter().chain(labels.iter()).map(Some), None),
+			SessionInputs::ValueArray(input_values) => match labels {
+				SessionInputs::ValueSlice(labels) => self.step_inner(input_values.iter().chain(labels).map(Some), run_options),
+				SessionInputs::ValueArray(labels) => self.step_inner(input_values.iter().chain(labels.iter()).map(Some), run_options),
 				SessionInputs::ValueMap(labels) => {
 					let labels = mapped_inputs(&self.train_input_names, &labels);
-					self.step_inner(input_values.iter().map(Some).chain(labels), None)
+					self.step_inner(input_values.iter().map(Some).chain(labels), run_options)
 				}
 			},
 			SessionInputs::ValueMap(input_values) => {
 				let input_values = mapped_inputs(&self.train_input_names, &input_values);
-				match labels.into() {
-					SessionInputs::ValueSlice(labels) => self.step_inner(input_values.into_iter().chain(labels.iter().map(Some)), None),
-					SessionInputs::ValueArray(labels) => self.step_inner(input_values.into_iter().chain(labels.iter().map(Some)), None),
+				match labels {
+					SessionInputs::ValueSlice(labels) => self.step_inner(input_values.into_iter().chain(labels.iter().map(Some)), run_options),
+					SessionInputs::ValueArray(labels) => self.step_inner(input_values.into_iter().chain(labels.iter().map(Some)), run_options),
 					SessionInputs::ValueMap(labels) => {
 						let labels = mapped_inputs(&self.train_input_names, &labels);
-						self.step_inner(input_values.into_iter().chain(labels), None)
+						self.step_inner(input_values.into_iter().chain(labels), run_options)
 					}
 				}
 			}
@@ -173,7 +193,16 @@ impl Trainer {
 
 		let run_options_ptr = if let Some(run_options) = &run_options { run_options.ptr() } else { ptr::null() };
 
-		trainsys![unsafe TrainStep(self.ptr.as_ptr(), run_options_ptr, input_ort_values.len(), input_ort_values.as_ptr(), output_tensor_ptrs.len(), output_tensor_ptrs.as_mut_ptr())?];
+		let deadline = match run_options {
+			Some(run_options) => run_options.inner.arm_deadline()?,
+			None => None
+		};
+		let status = trainsys![unsafe TrainStep(self.ptr.as_ptr(), run_options_ptr, input_ort_values.len(), input_ort_values.as_ptr(), output_tensor_ptrs.len(), output_tensor_ptrs.as_mut_ptr())];
+		let result = unsafe { status_to_result(status) };
+		match deadline {
+			Some(deadline) => deadline.finish(result)?,
+			None => result?
+		};
 
 		let outputs = output_tensor_ptrs
 			.into_iter()
@@ -192,31 +221,51 @@ impl Trainer {
 		inputs: impl Into<SessionInputs<'i1, 'v1, N1>>,
 		labels: impl Into<SessionInputs<'i2, 'v2, N2>>
 	) -> Result<SessionOutputs<'s, 's>> {
-		match inputs.into() {
-			SessionInputs::ValueSlice(input_values) => match labels.into() {
-				SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.iter().chain(labels).map(Some), None),
-				SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.iter().chain(labels.iter()).map(Some), None),
+		self.eval_step_dispatch(inputs.into(), labels.into(), None)
+	}
+
+	/// Performs a evaluation step like [`Trainer::eval_step`], using the given [`RunOptions`]. This can be used to
+	/// terminate a long-running step, e.g. with [`RunOptions::with_timeout`].
+	pub fn eval_step_with_options<'r, 's: 'r, 'i1, 'v1: 'i1, 'i2: 'i1, 'v2: 'i2 + 'i1, const N1: usize, const N2: usize>(
+		&'s self,
+		inputs: impl Into<SessionInputs<'i1, 'v1, N1>>,
+		labels: impl Into<SessionInputs<'i2, 'v2, N2>>,
+		run_options: &'r RunOptions
+	) -> Result<SessionOutputs<'r, 's>> {
+		self.eval_step_dispatch(inputs.into(), labels.into(), Some(run_options))
+	}
+
+	fn eval_step_dispatch<'r, 's: 'r, 'i1, 'v1: 'i1, 'i2: 'i1, 'v2: 'i2 + 'i1, const N1: usize, const N2: usize>(
+		&'s self,
+		inputs: SessionInputs<'i1, 'v1, N1>,
+		labels: SessionInputs<'i2, 'v2, N2>,
+		run_options: Option<&'r RunOptions>
+	) -> Result<SessionOutputs<'r, 's>> {
+		match inputs {
+			SessionInputs::ValueSlice(input_values) => match labels {
+				SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.iter().chain(labels).map(Some), run_options),
+				SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.iter().chain(labels.iter()).map(Some), run_options),
 				SessionInputs::ValueMap(labels) => {
 					let labels = mapped_inputs(&self.eval_input_names, &labels);
-					self.eval_step_inner(input_values.iter().map(Some).chain(labels), None)
+					self.eval_step_inner(input_values.iter().map(Some).chain(labels), run_options)
 				}
 			},
-			SessionInputs::ValueArray(input_values) => match labels.into() {
-				SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.iter().chain(labels).map(Some), None),
-				SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.iter().chain(labels.iter()).map(Some), None),
+			SessionInputs::ValueArray(input_values) => match labels {
+				SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.iter().chain(labels).map(Some), run_options),
+				SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.iter().chain(labels.iter()).map(Some), run_options),
 				SessionInputs::ValueMap(labels) => {
 					let labels = mapped_inputs(&self.eval_input_names, &labels);
-					self.eval_step_inner(input_values.iter().map(Some).chain(labels), None)
+					self.eval_step_inner(input_values.iter().map(Some).chain(labels), run_options)
 				}
 			},
 			SessionInputs::ValueMap(input_values) => {
 				let input_values = mapped_inputs(&self.eval_input_names, &input_values);
-				match labels.into() {
-					SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.into_iter().chain(labels.iter().map(Some)), None),
-					SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.into_iter().chain(labels.iter().map(Some)), None),
+				match labels {
+					SessionInputs::ValueSlice(labels) => self.eval_step_inner(input_values.into_iter().chain(labels.iter().map(Some)), run_options),
+					SessionInputs::ValueArray(labels) => self.eval_step_inner(input_values.into_iter().chain(labels.iter().map(Some)), run_options),
 					SessionInputs::ValueMap(labels) => {
 						let labels = mapped_inputs(&self.eval_input_names, &labels);
-						self.eval_step_inner(input_values.into_iter().chain(labels), None)
+						self.eval_step_inner(input_values.into_iter().chain(labels), run_options)
 					}
 				}
 			}
@@ -234,7 +283,16 @@ impl Trainer {
 
 		let run_options_ptr = if let Some(run_options) = &run_options { run_options.ptr() } else { ptr::null() };
 
-		trainsys![unsafe EvalStep(self.ptr.as_ptr(), run_options_ptr, input_ort_values.len(), input_ort_values.as_ptr(), output_tensor_ptrs.len(), output_tensor_ptrs.as_mut_ptr())?];
+		let deadline = match run_options {
+			Some(run_options) => run_options.inner.arm_deadline()?,
+			None => None
+		};
+		let status = trainsys![unsafe EvalStep(self.ptr.as_ptr(), run_options_ptr, input_ort_values.len(), input_ort_values.as_ptr(), output_tensor_ptrs.len(), output_tensor_ptrs.as_mut_ptr())];
+		let result = unsafe { status_to_result(status) };
+		match deadline {
+			Some(deadline) => deadline.finish(result)?,
+			None => result?
+		};
 
 		let outputs = output_tensor_ptrs
 			.into_iter()
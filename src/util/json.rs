@@ -0,0 +1,279 @@
+//! A minimal JSON reader, sufficient for the Chrome trace files written by ONNX Runtime's profiler and model bundle
+//! manifests.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::error::{Error, ErrorCode, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>)
+}
+
+impl Json {
+	/// Parses a JSON document. Arrays & objects may be nested at most [`MAX_DEPTH`] levels deep.
+	pub(crate) fn parse(s: &str) -> Result<Json> {
+		let mut parser = Parser {
+			bytes: s.as_bytes(),
+			pos: 0,
+			depth: 0
+		};
+		let value = parser.value()?;
+		parser.skip_whitespace();
+		if parser.pos != parser.bytes.len() {
+			return Err(parser.error("trailing characters"));
+		}
+		Ok(value)
+	}
+
+	pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+		match self {
+			Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None
+		}
+	}
+
+	pub(crate) fn as_str(&self) -> Option<&str> {
+		match self {
+			Json::String(s) => Some(s),
+			_ => None
+		}
+	}
+
+	/// Returns this value as an integer. ONNX Runtime writes some integers (like node indices) as strings, so numeric
+	/// strings are accepted too.
+	pub(crate) fn as_i64(&self) -> Option<i64> {
+		match self {
+			Json::Number(n) if *n == (*n as i64) as f64 => Some(*n as i64),
+			Json::String(s) => s.parse().ok(),
+			_ => None
+		}
+	}
+
+	pub(crate) fn as_array(&self) -> Option<&[Json]> {
+		match self {
+			Json::Array(values) => Some(values),
+			_ => None
+		}
+	}
+
+	pub(crate) fn as_object(&self) -> Option<&[(String, Json)]> {
+		match self {
+			Json::Object(entries) => Some(entries),
+			_ => None
+		}
+	}
+}
+
+/// The maximum nesting depth of arrays & objects accepted by [`Json::parse`], so that malicious input can't overflow
+/// the stack.
+pub(crate) const MAX_DEPTH: usize = 128;
+
+struct Parser<'s> {
+	bytes: &'s [u8],
+	pos: usize,
+	depth: usize
+}
+
+impl Parser<'_> {
+	fn error(&self, message: &str) -> Error {
+		Error::new_with_code(ErrorCode::InvalidArgument, format!("Failed to parse JSON: {message} at byte {}", self.pos))
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.pos < self.bytes.len() && matches!(self.bytes[self.pos], b' ' | b'\t' | b'\n' | b'\r') {
+			self.pos += 1;
+		}
+	}
+
+	fn peek(&mut self) -> Option<u8> {
+		self.skip_whitespace();
+		self.bytes.get(self.pos).copied()
+	}
+
+	fn expect(&mut self, byte: u8) -> Result<()> {
+		if self.peek() != Some(byte) {
+			return Err(self.error(&format!("expected `{}`", byte as char)));
+		}
+		self.pos += 1;
+		Ok(())
+	}
+
+	fn literal(&mut self, literal: &str, value: Json) -> Result<Json> {
+		if !self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+			return Err(self.error("unexpected token"));
+		}
+		self.pos += literal.len();
+		Ok(value)
+	}
+
+	fn value(&mut self) -> Result<Json> {
+		match self.peek() {
+			Some(b'{') => self.nested(Self::object),
+			Some(b'[') => self.nested(Self::array),
+			Some(b'"') => self.string().map(Json::String),
+			Some(b't') => self.literal("true", Json::Bool(true)),
+			Some(b'f') => self.literal("false", Json::Bool(false)),
+			Some(b'n') => self.literal("null", Json::Null),
+			Some(b'-' | b'0'..=b'9') => self.number(),
+			Some(_) => Err(self.error("unexpected token")),
+			None => Err(self.error("unexpected end of input"))
+		}
+	}
+
+	fn nested(&mut self, parse: fn(&mut Self) -> Result<Json>) -> Result<Json> {
+		if self.depth == MAX_DEPTH {
+			return Err(self.error("too deeply nested"));
+		}
+		self.depth += 1;
+		let value = parse(self);
+		self.depth -= 1;
+		value
+	}
+
+	fn object(&mut self) -> Result<Json> {
+		self.expect(b'{')?;
+		let mut entries = Vec::new();
+		if self.peek() == Some(b'}') {
+			self.pos += 1;
+			return Ok(Json::Object(entries));
+		}
+		loop {
+			if self.peek() != Some(b'"') {
+				return Err(self.error("expected object key"));
+			}
+			let key = self.string()?;
+			self.expect(b':')?;
+			entries.push((key, self.value()?));
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b'}') => {
+					self.pos += 1;
+					return Ok(Json::Object(entries));
+				}
+				_ => return Err(self.error("expected `,` or `}`"))
+			}
+		}
+	}
+
+	fn array(&mut self) -> Result<Json> {
+		self.expect(b'[')?;
+		let mut values = Vec::new();
+		if self.peek() == Some(b']') {
+			self.pos += 1;
+			return Ok(Json::Array(values));
+		}
+		loop {
+			values.push(self.value()?);
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b']') => {
+					self.pos += 1;
+					return Ok(Json::Array(values));
+				}
+				_ => return Err(self.error("expected `,` or `]`"))
+			}
+		}
+	}
+
+	fn number(&mut self) -> Result<Json> {
+		let start = self.pos;
+		while self.pos < self.bytes.len() && matches!(self.bytes[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+			self.pos += 1;
+		}
+		core::str::from_utf8(&self.bytes[start..self.pos])
+			.ok()
+			.and_then(|s| s.parse().ok())
+			.map(Json::Number)
+			.ok_or_else(|| self.error("invalid number"))
+	}
+
+	fn string(&mut self) -> Result<String> {
+		self.expect(b'"')?;
+		let mut out = String::new();
+		loop {
+			let start = self.pos;
+			while self.pos < self.bytes.len() && !matches!(self.bytes[self.pos], b'"' | b'\\') {
+				self.pos += 1;
+			}
+			out.push_str(core::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| self.error("invalid UTF-8 in string"))?);
+			match self.bytes.get(self.pos) {
+				Some(b'"') => {
+					self.pos += 1;
+					return Ok(out);
+				}
+				Some(b'\\') => {
+					self.pos += 1;
+					let escape = self.bytes.get(self.pos).copied().ok_or_else(|| self.error("unterminated string"))?;
+					self.pos += 1;
+					match escape {
+						b'"' => out.push('"'),
+						b'\\' => out.push('\\'),
+						b'/' => out.push('/'),
+						b'b' => out.push('\u{8}'),
+						b'f' => out.push('\u{c}'),
+						b'n' => out.push('\n'),
+						b'r' => out.push('\r'),
+						b't' => out.push('\t'),
+						b'u' => {
+							let high = self.hex4()?;
+							let c = if (0xD800..0xDC00).contains(&high) && self.bytes[self.pos..].starts_with(b"\\u") {
+								self.pos += 2;
+								let low = self.hex4()?;
+								char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF))
+							} else {
+								char::from_u32(high)
+							};
+							out.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+						}
+						_ => return Err(self.error("invalid escape sequence"))
+					}
+				}
+				_ => return Err(self.error("unterminated string"))
+			}
+		}
+	}
+
+	fn hex4(&mut self) -> Result<u32> {
+		let digits = self
+			.bytes
+			.get(self.pos..self.pos + 4)
+			.ok_or_else(|| self.error("invalid unicode escape"))?;
+		let value = core::str::from_utf8(digits)
+			.ok()
+			.and_then(|s| u32::from_str_radix(s, 16).ok())
+			.ok_or_else(|| self.error("invalid unicode escape"))?;
+		self.pos += 4;
+		Ok(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::format;
+
+	use super::{Json, MAX_DEPTH};
+
+	#[test]
+	fn test_parse() -> crate::Result<()> {
+		let json = Json::parse(r#" {"a": [1, -2.5e1, "3"], "bé\n": {"c": null, "d": true}, "e": []} "#)?;
+		let a = json.get("a").and_then(Json::as_array).expect("`a` should be an array");
+		assert_eq!(a[0].as_i64(), Some(1));
+		assert_eq!(a[1], Json::Number(-25.0));
+		assert_eq!(a[2].as_i64(), Some(3));
+		assert_eq!(json.get("b\u{e9}\n").and_then(|b| b.get("d")), Some(&Json::Bool(true)));
+		assert!(Json::parse("[1, 2").is_err());
+		assert!(Json::parse("{} x").is_err());
+
+		let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+		Json::parse(&nested(MAX_DEPTH))?;
+		assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+		assert!(Json::parse(&"[".repeat(1_000_000)).is_err());
+		Ok(())
+	}
+}
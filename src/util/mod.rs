@@ -18,6 +18,8 @@ use smallvec::SmallVec;
 
 use crate::Result;
 
+pub(crate) mod json;
+
 // maximum number of session inputs to store on stack (~32 bytes per, + 16 bytes for run_async)
 pub(crate) const STACK_SESSION_INPUTS: usize = 6;
 // maximum number of session inputs to store on stack (~40 bytes per, + 16 bytes for run_async)
@@ -0,0 +1,601 @@
+use alloc::{format, sync::Arc, vec};
+use core::{
+	ffi::c_void,
+	fmt::{self, Debug},
+	marker::PhantomData,
+	ptr::{self, NonNull},
+	slice
+};
+
+use super::{DowncastableTarget, DynValue, Value, ValueInner, ValueRef, ValueRefMut, ValueType, ValueTypeMarker, impl_tensor::Tensor};
+use crate::{
+	AsPointer,
+	error::{Error, ErrorCode, Result},
+	memory::Allocator,
+	ortsys,
+	tensor::{IntoTensorElementType, PrimitiveTensorElementType, Shape, SymbolicDimensions, TensorElementType},
+	value::r#type::extract_data_type_from_tensor_info
+};
+
+pub trait SparseTensorValueTypeMarker: ValueTypeMarker {
+	private_trait!();
+}
+
+#[derive(Debug)]
+pub struct DynSparseTensorValueType;
+impl ValueTypeMarker for DynSparseTensorValueType {
+	fn fmt(f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("DynSparseTensor")
+	}
+
+	private_impl!();
+}
+impl SparseTensorValueTypeMarker for DynSparseTensorValueType {
+	private_impl!();
+}
+
+impl DowncastableTarget for DynSparseTensorValueType {
+	fn can_downcast(dtype: &ValueType) -> bool {
+		matches!(dtype, ValueType::SparseTensor { .. })
+	}
+
+	private_impl!();
+}
+
+#[derive(Debug)]
+pub struct SparseTensorValueType<T: IntoTensorElementType + Debug>(PhantomData<T>);
+impl<T: IntoTensorElementType + Debug> ValueTypeMarker for SparseTensorValueType<T> {
+	fn fmt(f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("SparseTensor<")?;
+		<TensorElementType as fmt::Display>::fmt(&T::into_tensor_element_type(), f)?;
+		f.write_str(">")
+	}
+
+	private_impl!();
+}
+impl<T: IntoTensorElementType + Debug> SparseTensorValueTypeMarker for SparseTensorValueType<T> {
+	private_impl!();
+}
+
+impl<T: IntoTensorElementType + Debug> DowncastableTarget for SparseTensorValueType<T> {
+	fn can_downcast(dtype: &ValueType) -> bool {
+		match dtype {
+			ValueType::SparseTensor { ty, .. } => *ty == T::into_tensor_element_type(),
+			_ => false
+		}
+	}
+
+	private_impl!();
+}
+
+/// A sparse tensor [`Value`] whose data type is unknown.
+pub type DynSparseTensor = Value<DynSparseTensorValueType>;
+/// A strongly-typed sparse tensor [`Value`], which stores only its non-zero values & their indices.
+///
+/// Sparse tensors can be created from indices in [COO](SparseTensor::from_coo), [CSR](SparseTensor::from_csr), or
+/// [block sparse](SparseTensor::from_block_sparse) format, and passed to sessions like any other value, which is
+/// useful for models with high-dimensional sparse features (like bag-of-words or TF-IDF vectors).
+///
+/// ```
+/// # use ort::value::{SparseFormat, SparseTensor};
+/// # fn main() -> ort::Result<()> {
+/// // [[0, 2, 0],
+/// //  [1, 0, 3]]
+/// let tensor = SparseTensor::<f32>::from_coo([2_i64, 3], &[0, 1, 1, 0, 1, 2], &[2.0, 1.0, 3.0])?;
+/// assert_eq!(tensor.sparse_format()?, SparseFormat::Coo);
+/// assert_eq!(tensor.extract_values(), [2.0, 1.0, 3.0]);
+///
+/// let dense = tensor.to_dense()?;
+/// assert_eq!(dense.extract_tensor().1, [0.0, 2.0, 0.0, 1.0, 0.0, 3.0]);
+/// # 	Ok(())
+/// # }
+/// ```
+pub type SparseTensor<T> = Value<SparseTensorValueType<T>>;
+
+/// A reference to a sparse tensor [`Value`] whose data type is unknown.
+pub type DynSparseTensorRef<'v> = ValueRef<'v, DynSparseTensorValueType>;
+/// A mutable reference to a sparse tensor [`Value`] whose data type is unknown.
+pub type DynSparseTensorRefMut<'v> = ValueRefMut<'v, DynSparseTensorValueType>;
+/// A reference to a strongly-typed sparse tensor [`Value`].
+pub type SparseTensorRef<'v, T> = ValueRef<'v, SparseTensorValueType<T>>;
+/// A mutable reference to a strongly-typed sparse tensor [`Value`].
+pub type SparseTensorRefMut<'v, T> = ValueRefMut<'v, SparseTensorValueType<T>>;
+
+/// The format a [`SparseTensor`]'s indices are stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseFormat {
+	/// Coordinate format: each value has either a linear index into the flattened dense tensor, or one index per
+	/// dimension.
+	Coo,
+	/// Compressed sparse row format, for 2-dimensional tensors: each value has a column ("inner") index, and each row
+	/// has an offset ("outer" index) into the values where it starts.
+	Csr,
+	/// Block sparse format: values are stored in dense blocks, with the indices of each block stored separately.
+	BlockSparse
+}
+
+impl SparseFormat {
+	fn from_sys(format: ort_sys::OrtSparseFormat) -> Result<Self> {
+		match format {
+			ort_sys::OrtSparseFormat::ORT_SPARSE_COO => Ok(Self::Coo),
+			ort_sys::OrtSparseFormat::ORT_SPARSE_CSRC => Ok(Self::Csr),
+			ort_sys::OrtSparseFormat::ORT_SPARSE_BLOCK_SPARSE => Ok(Self::BlockSparse),
+			ort_sys::OrtSparseFormat::ORT_SPARSE_UNDEFINED => Err(Error::new_with_code(ErrorCode::InvalidArgument, "Sparse tensor has no data"))
+		}
+	}
+}
+
+impl<T: PrimitiveTensorElementType + Debug> SparseTensor<T> {
+	/// Creates a sparse tensor of shape `dense_shape` from indices in [COO format](SparseFormat::Coo).
+	///
+	/// `indices` contains either one linear index into the flattened dense tensor per value, or one index per dimension
+	/// per value (i.e. `values.len() * dense_shape.len()` indices, where the indices of each value are contiguous). The
+	/// indices & values are copied into memory owned by ONNX Runtime.
+	///
+	/// ```
+	/// # use ort::value::SparseTensor;
+	/// # fn main() -> ort::Result<()> {
+	/// // Linear indices...
+	/// let a = SparseTensor::<i64>::from_coo([2_i64, 3], &[1, 5], &[7, 9])?;
+	/// // ...are equivalent to per-dimension indices.
+	/// let b = SparseTensor::<i64>::from_coo([2_i64, 3], &[0, 1, 1, 2], &[7, 9])?;
+	/// assert_eq!(a.to_dense()?.extract_tensor().1, b.to_dense()?.extract_tensor().1);
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	pub fn from_coo(dense_shape: impl Into<Shape>, indices: &[i64], values: &[T]) -> Result<SparseTensor<T>> {
+		let mut tensor = Self::new_unfilled(dense_shape.into())?;
+		let allocator = Allocator::default();
+		let values_shape = [values.len() as i64];
+		ortsys![
+			unsafe FillSparseTensorCoo(
+				tensor.ptr_mut(),
+				allocator.memory_info().ptr(),
+				values_shape.as_ptr(),
+				values_shape.len(),
+				values.as_ptr().cast::<c_void>(),
+				indices.as_ptr(),
+				indices.len()
+			)?
+		];
+		Ok(tensor)
+	}
+
+	/// Creates a 2-dimensional sparse tensor of shape `dense_shape` from indices in [CSR format](SparseFormat::Csr).
+	///
+	/// `inner_indices` contains the column index of each value, and `outer_indices` contains the offset into `values`
+	/// at which each row starts, followed by `values.len()` (i.e. `dense_shape[0] + 1` offsets in total). The indices
+	/// & values are copied into memory owned by ONNX Runtime.
+	///
+	/// ```
+	/// # use ort::value::{SparseFormat, SparseTensor};
+	/// # fn main() -> ort::Result<()> {
+	/// // [[0, 2, 0],
+	/// //  [1, 0, 3]]
+	/// let tensor = SparseTensor::<f32>::from_csr([2_i64, 3], &[1, 0, 2], &[0, 1, 3], &[2.0, 1.0, 3.0])?;
+	/// assert_eq!(tensor.sparse_format()?, SparseFormat::Csr);
+	/// assert_eq!(tensor.to_dense()?.extract_tensor().1, [0.0, 2.0, 0.0, 1.0, 0.0, 3.0]);
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	pub fn from_csr(dense_shape: impl Into<Shape>, inner_indices: &[i64], outer_indices: &[i64], values: &[T]) -> Result<SparseTensor<T>> {
+		let mut tensor = Self::new_unfilled(dense_shape.into())?;
+		let allocator = Allocator::default();
+		let values_shape = [values.len() as i64];
+		ortsys![
+			unsafe FillSparseTensorCsr(
+				tensor.ptr_mut(),
+				allocator.memory_info().ptr(),
+				values_shape.as_ptr(),
+				values_shape.len(),
+				values.as_ptr().cast::<c_void>(),
+				inner_indices.as_ptr(),
+				inner_indices.len(),
+				outer_indices.as_ptr(),
+				outer_indices.len()
+			)?
+		];
+		Ok(tensor)
+	}
+
+	/// Creates a sparse tensor of shape `dense_shape` from data in [block sparse format](SparseFormat::BlockSparse).
+	///
+	/// `values` contains the dense blocks, with shape `values_shape`; `indices` contains the position of each block,
+	/// with shape `indices_shape`. See the ONNX Runtime documentation for `FillSparseTensorBlockSparse` for the exact
+	/// layout expected. The indices & values are copied into memory owned by ONNX Runtime.
+	pub fn from_block_sparse(
+		dense_shape: impl Into<Shape>,
+		values_shape: impl Into<Shape>,
+		values: &[T],
+		indices_shape: impl Into<Shape>,
+		indices: &[i32]
+	) -> Result<SparseTensor<T>> {
+		let values_shape: Shape = values_shape.into();
+		if values_shape.num_elements() != values.len() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!("Block sparse values have shape {values_shape} ({} elements), but {} values were provided", values_shape.num_elements(), values.len())
+			));
+		}
+		let indices_shape: Shape = indices_shape.into();
+		if indices_shape.num_elements() != indices.len() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!(
+					"Block sparse indices have shape {indices_shape} ({} elements), but {} indices were provided",
+					indices_shape.num_elements(),
+					indices.len()
+				)
+			));
+		}
+
+		let mut tensor = Self::new_unfilled(dense_shape.into())?;
+		let allocator = Allocator::default();
+		ortsys![
+			unsafe FillSparseTensorBlockSparse(
+				tensor.ptr_mut(),
+				allocator.memory_info().ptr(),
+				values_shape.as_ptr(),
+				values_shape.len(),
+				values.as_ptr().cast::<c_void>(),
+				indices_shape.as_ptr(),
+				indices_shape.len(),
+				indices.as_ptr()
+			)?
+		];
+		Ok(tensor)
+	}
+
+	/// Creates a sparse tensor of shape `dense_shape` from an [`ndarray`] array of per-dimension indices in [COO
+	/// format](SparseFormat::Coo), with one row per value.
+	///
+	/// ```
+	/// # use ort::value::SparseTensor;
+	/// # fn main() -> ort::Result<()> {
+	/// let indices = ndarray::array![[0_i64, 1], [1, 2]];
+	/// let values = ndarray::array![7_i64, 9];
+	/// let tensor = SparseTensor::from_coo_array([2_i64, 3], indices.view(), values.view())?;
+	/// assert_eq!(tensor.coo_indices()?, [0, 1, 1, 2]);
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "ndarray")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+	pub fn from_coo_array(dense_shape: impl Into<Shape>, indices: ndarray::ArrayView2<'_, i64>, values: ndarray::ArrayView1<'_, T>) -> Result<SparseTensor<T>>
+	where
+		T: Clone
+	{
+		let dense_shape: Shape = dense_shape.into();
+		if indices.nrows() != values.len() || indices.ncols() != dense_shape.len() {
+			return Err(Error::new_with_code(
+				ErrorCode::InvalidArgument,
+				format!(
+					"COO indices must have shape [{}, {}] for {} values of a tensor with shape {dense_shape}, but have shape {:?}",
+					values.len(),
+					dense_shape.len(),
+					values.len(),
+					indices.shape()
+				)
+			));
+		}
+		Self::from_coo(dense_shape, &contiguous(indices), &contiguous(values))
+	}
+
+	/// Creates a 2-dimensional sparse tensor of shape `dense_shape` from [`ndarray`] arrays of indices in [CSR
+	/// format](SparseFormat::Csr). See [`SparseTensor::from_csr`] for the meaning of each array.
+	#[cfg(feature = "ndarray")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+	pub fn from_csr_array(
+		dense_shape: impl Into<Shape>,
+		inner_indices: ndarray::ArrayView1<'_, i64>,
+		outer_indices: ndarray::ArrayView1<'_, i64>,
+		values: ndarray::ArrayView1<'_, T>
+	) -> Result<SparseTensor<T>>
+	where
+		T: Clone
+	{
+		Self::from_csr(dense_shape, &contiguous(inner_indices), &contiguous(outer_indices), &contiguous(values))
+	}
+
+	fn new_unfilled(dense_shape: Shape) -> Result<SparseTensor<T>> {
+		let allocator = Allocator::default();
+		let mut value_ptr: *mut ort_sys::OrtValue = ptr::null_mut();
+		ortsys![
+			unsafe CreateSparseTensorAsOrtValue(
+				allocator.ptr().cast_mut(),
+				dense_shape.as_ptr(),
+				dense_shape.len(),
+				T::into_tensor_element_type().into(),
+				&mut value_ptr
+			)?;
+			nonNull(value_ptr)
+		];
+		let rank = dense_shape.len();
+		Ok(Value {
+			inner: Arc::new(ValueInner {
+				ptr: unsafe { NonNull::new_unchecked(value_ptr) },
+				dtype: ValueType::SparseTensor {
+					ty: T::into_tensor_element_type(),
+					shape: dense_shape,
+					dimension_symbols: SymbolicDimensions::empty(rank)
+				},
+				drop: true,
+				memory_info: None,
+				_backing: None
+			}),
+			_markers: PhantomData
+		})
+	}
+
+	/// Returns a view of this sparse tensor's non-zero values.
+	pub fn extract_values(&self) -> &[T] {
+		self.try_extract_values().expect("Failed to extract sparse tensor values")
+	}
+
+	/// Converts this sparse tensor to a dense [`Tensor`], with all elements not stored in this tensor set to zero.
+	///
+	/// # Errors
+	/// Returns an error if the tensor is in [block sparse format](SparseFormat::BlockSparse), which is not yet
+	/// supported, or if any of its indices are out of bounds.
+	pub fn to_dense(&self) -> Result<Tensor<T>>
+	where
+		T: Clone + Default + 'static
+	{
+		self.try_to_dense()
+	}
+
+	/// Converts from a strongly-typed [`SparseTensor<T>`] to a type-erased [`DynSparseTensor`].
+	#[inline]
+	pub fn upcast(self) -> DynSparseTensor {
+		unsafe { self.transmute_type() }
+	}
+}
+
+impl<Type: SparseTensorValueTypeMarker + ?Sized> Value<Type> {
+	/// Returns the format this sparse tensor's indices are stored in.
+	pub fn sparse_format(&self) -> Result<SparseFormat> {
+		let mut format = ort_sys::OrtSparseFormat::ORT_SPARSE_UNDEFINED;
+		ortsys![unsafe GetSparseTensorFormat(self.ptr(), &mut format)?];
+		SparseFormat::from_sys(format)
+	}
+
+	/// Returns the shape of the dense tensor this sparse tensor represents.
+	pub fn dense_shape(&self) -> Result<&Shape> {
+		match self.dtype() {
+			ValueType::SparseTensor { shape, .. } => Ok(shape),
+			t => Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cannot get the dense shape of {t}, which is not a sparse tensor")))
+		}
+	}
+
+	/// Returns the shape of this sparse tensor's values; `[N]` for `N` values in [COO](SparseFormat::Coo) or
+	/// [CSR](SparseFormat::Csr) format.
+	pub fn values_shape(&self) -> Result<Shape> {
+		let mut info_ptr: *mut ort_sys::OrtTensorTypeAndShapeInfo = ptr::null_mut();
+		ortsys![unsafe GetSparseTensorValuesTypeAndShape(self.ptr(), &mut info_ptr)?; nonNull(info_ptr)];
+		let ty = unsafe { extract_data_type_from_tensor_info(info_ptr) };
+		ortsys![unsafe ReleaseTensorTypeAndShapeInfo(info_ptr)];
+		match ty {
+			ValueType::Tensor { shape, .. } => Ok(shape),
+			_ => unreachable!()
+		}
+	}
+
+	/// Attempts to get a view of this sparse tensor's non-zero values as type `T`.
+	///
+	/// See also the infallible counterpart, [`SparseTensor::extract_values`], for typed [`SparseTensor<T>`]s.
+	///
+	/// # Errors
+	/// May return an error if:
+	/// - This is a [`DynValue`], and the value is not actually a sparse tensor.
+	/// - The provided type `T` does not match the tensor's element type.
+	pub fn try_extract_values<T: PrimitiveTensorElementType>(&self) -> Result<&[T]> {
+		let expected_ty = T::into_tensor_element_type();
+		match self.dtype() {
+			ValueType::SparseTensor { ty, .. } if *ty == expected_ty => {}
+			ValueType::SparseTensor { ty, .. } => {
+				return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cannot extract SparseTensor<{expected_ty}> from SparseTensor<{ty}>")));
+			}
+			t => return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cannot extract a SparseTensor<{expected_ty}> from {t}")))
+		}
+
+		let len = self.values_shape()?.num_elements();
+		let mut values_ptr: *const c_void = ptr::null();
+		ortsys![unsafe GetSparseTensorValues(self.ptr(), &mut values_ptr)?];
+		Ok(unsafe { raw_slice(values_ptr.cast::<T>(), len) })
+	}
+
+	/// Returns a view of this sparse tensor's indices in [COO format](SparseFormat::Coo); either one linear index or
+	/// one index per dimension for each value.
+	pub fn coo_indices(&self) -> Result<&[i64]> {
+		self.expect_format(SparseFormat::Coo)?;
+		unsafe { self.indices(ort_sys::OrtSparseIndicesFormat::ORT_SPARSE_COO_INDICES) }
+	}
+
+	/// Returns views of this sparse tensor's inner (column) & outer (row offset) indices in [CSR
+	/// format](SparseFormat::Csr).
+	pub fn csr_indices(&self) -> Result<(&[i64], &[i64])> {
+		self.expect_format(SparseFormat::Csr)?;
+		let inner = unsafe { self.indices(ort_sys::OrtSparseIndicesFormat::ORT_SPARSE_CSR_INNER_INDICES)? };
+		let outer = unsafe { self.indices(ort_sys::OrtSparseIndicesFormat::ORT_SPARSE_CSR_OUTER_INDICES)? };
+		Ok((inner, outer))
+	}
+
+	/// Returns the shape & a view of this sparse tensor's block indices in [block sparse
+	/// format](SparseFormat::BlockSparse).
+	pub fn block_sparse_indices(&self) -> Result<(Shape, &[i32])> {
+		self.expect_format(SparseFormat::BlockSparse)?;
+		let indices_format = ort_sys::OrtSparseIndicesFormat::ORT_SPARSE_BLOCK_SPARSE_INDICES;
+		let mut info_ptr: *mut ort_sys::OrtTensorTypeAndShapeInfo = ptr::null_mut();
+		ortsys![unsafe GetSparseTensorIndicesTypeShape(self.ptr(), indices_format, &mut info_ptr)?; nonNull(info_ptr)];
+		let ty = unsafe { extract_data_type_from_tensor_info(info_ptr) };
+		ortsys![unsafe ReleaseTensorTypeAndShapeInfo(info_ptr)];
+		let shape = match ty {
+			ValueType::Tensor { shape, .. } => shape,
+			_ => unreachable!()
+		};
+		Ok((shape, unsafe { self.indices(indices_format)? }))
+	}
+
+	/// Attempts to convert this sparse tensor to a dense [`Tensor`] of type `T`, with all elements not stored in this
+	/// tensor set to zero.
+	///
+	/// See also [`SparseTensor::to_dense`] for typed [`SparseTensor<T>`]s.
+	///
+	/// # Errors
+	/// May return an error if:
+	/// - This is a [`DynValue`], and the value is not actually a sparse tensor.
+	/// - The provided type `T` does not match the tensor's element type.
+	/// - The tensor is in [block sparse format](SparseFormat::BlockSparse), which is not yet supported.
+	/// - Any of the tensor's indices are out of bounds.
+	pub fn try_to_dense<T: PrimitiveTensorElementType + Clone + Default + Debug + 'static>(&self) -> Result<Tensor<T>> {
+		let values = self.try_extract_values::<T>()?;
+		let dense_shape = self.dense_shape()?.clone();
+		let len = dense_shape.num_elements();
+		let mut dense = vec![T::default(); len];
+		let out_of_bounds = || Error::new_with_code(ErrorCode::InvalidArgument, format!("Sparse tensor index is out of bounds for dense shape {dense_shape}"));
+		match self.sparse_format()? {
+			SparseFormat::Coo => {
+				let indices = self.coo_indices()?;
+				if indices.len() == values.len() {
+					for (&index, value) in indices.iter().zip(values) {
+						*usize::try_from(index).ok().and_then(|i| dense.get_mut(i)).ok_or_else(out_of_bounds)? = value.clone();
+					}
+				} else if indices.len() == values.len() * dense_shape.len() {
+					for (coords, value) in indices.chunks_exact(dense_shape.len().max(1)).zip(values) {
+						let mut index = 0;
+						for (&coord, &dim) in coords.iter().zip(dense_shape.iter()) {
+							if coord < 0 || coord >= dim {
+								return Err(out_of_bounds());
+							}
+							index = index * dim + coord;
+						}
+						dense[index as usize] = value.clone();
+					}
+				} else {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!("Sparse tensor has {} COO indices, which is invalid for {} values of rank {}", indices.len(), values.len(), dense_shape.len())
+					));
+				}
+			}
+			SparseFormat::Csr => {
+				let (inner, outer) = self.csr_indices()?;
+				let [_, cols] = dense_shape[..] else {
+					return Err(Error::new_with_code(
+						ErrorCode::InvalidArgument,
+						format!("CSR sparse tensors must be 2-dimensional, but have shape {dense_shape}")
+					));
+				};
+				for (row, bounds) in outer.windows(2).enumerate() {
+					let (start, end) = (usize::try_from(bounds[0]).map_err(|_| out_of_bounds())?, usize::try_from(bounds[1]).map_err(|_| out_of_bounds())?);
+					let (columns, row_values) = inner.get(start..end).zip(values.get(start..end)).ok_or_else(out_of_bounds)?;
+					for (&col, value) in columns.iter().zip(row_values) {
+						if col < 0 || col >= cols {
+							return Err(out_of_bounds());
+						}
+						*dense.get_mut(row * cols as usize + col as usize).ok_or_else(out_of_bounds)? = value.clone();
+					}
+				}
+			}
+			SparseFormat::BlockSparse => {
+				return Err(Error::new_with_code(ErrorCode::NotImplemented, "Converting block sparse tensors to dense tensors is not yet supported"));
+			}
+		}
+		Tensor::from_array((dense_shape, dense))
+	}
+
+	fn expect_format(&self, expected: SparseFormat) -> Result<()> {
+		let format = self.sparse_format()?;
+		if format != expected {
+			return Err(Error::new_with_code(ErrorCode::InvalidArgument, format!("Cannot get {expected:?} indices of a sparse tensor in {format:?} format")));
+		}
+		Ok(())
+	}
+
+	unsafe fn indices<I>(&self, format: ort_sys::OrtSparseIndicesFormat) -> Result<&[I]> {
+		let mut len = 0;
+		let mut indices_ptr: *const c_void = ptr::null();
+		ortsys![unsafe GetSparseTensorIndices(self.ptr(), format, &mut len, &mut indices_ptr)?];
+		Ok(unsafe { raw_slice(indices_ptr.cast::<I>(), len) })
+	}
+}
+
+/// Creates a slice from ONNX Runtime data, which may be null if it is empty.
+unsafe fn raw_slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+	if ptr.is_null() { &[] } else { unsafe { slice::from_raw_parts(ptr, len) } }
+}
+
+#[cfg(feature = "ndarray")]
+fn contiguous<T: Clone, D: ndarray::Dimension>(array: ndarray::ArrayView<'_, T, D>) -> alloc::borrow::Cow<'_, [T]> {
+	match array.to_slice() {
+		Some(slice) => alloc::borrow::Cow::Borrowed(slice),
+		None => alloc::borrow::Cow::Owned(array.iter().cloned().collect::<alloc::vec::Vec<_>>())
+	}
+}
+
+impl<T: IntoTensorElementType + Debug> From<Value<SparseTensorValueType<T>>> for DynValue {
+	fn from(value: Value<SparseTensorValueType<T>>) -> Self {
+		value.into_dyn()
+	}
+}
+impl From<Value<DynSparseTensorValueType>> for DynValue {
+	fn from(value: Value<DynSparseTensorValueType>) -> Self {
+		value.into_dyn()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{DynSparseTensor, SparseFormat, SparseTensor};
+	use crate::{
+		tensor::{Shape, SymbolicDimensions, TensorElementType},
+		value::ValueType
+	};
+
+	#[test]
+	fn test_coo() -> crate::Result<()> {
+		let tensor = SparseTensor::<f32>::from_coo([3_i64, 4], &[1, 6, 11], &[1.0, 2.0, 3.0])?;
+		assert_eq!(
+			tensor.dtype(),
+			&ValueType::SparseTensor {
+				ty: TensorElementType::Float32,
+				shape: Shape::new([3, 4]),
+				dimension_symbols: SymbolicDimensions::empty(2)
+			}
+		);
+		assert_eq!(tensor.sparse_format()?, SparseFormat::Coo);
+		assert_eq!(tensor.coo_indices()?, [1, 6, 11]);
+		assert_eq!(&*tensor.values_shape()?, [3]);
+		assert!(tensor.csr_indices().is_err());
+
+		let dense = tensor.to_dense()?;
+		assert_eq!(dense.extract_tensor().1, [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 3.0]);
+
+		let per_dimension = SparseTensor::<f32>::from_coo([3_i64, 4], &[0, 1, 1, 2, 2, 3], &[1.0, 2.0, 3.0])?;
+		assert_eq!(per_dimension.to_dense()?.extract_tensor().1, dense.extract_tensor().1);
+		Ok(())
+	}
+
+	#[test]
+	fn test_csr() -> crate::Result<()> {
+		let tensor = SparseTensor::<i32>::from_csr([3_i64, 3], &[0, 2, 1], &[0, 2, 2, 3], &[5, 6, 7])?;
+		assert_eq!(tensor.sparse_format()?, SparseFormat::Csr);
+		let (inner, outer) = tensor.csr_indices()?;
+		assert_eq!((inner, outer), (&[0, 2, 1][..], &[0, 2, 2, 3][..]));
+		assert_eq!(tensor.to_dense()?.extract_tensor().1, [5, 0, 6, 0, 0, 0, 0, 7, 0]);
+		Ok(())
+	}
+
+	#[test]
+	fn test_downcast() -> crate::Result<()> {
+		let value = SparseTensor::<i64>::from_coo([4_i64], &[2], &[42])?.into_dyn();
+		assert!(value.downcast_ref::<super::SparseTensorValueType<f32>>().is_err());
+		assert!(value.view().downcast::<crate::value::DynTensorValueType>().is_err());
+
+		let tensor: DynSparseTensor = value.downcast()?;
+		assert!(tensor.try_extract_values::<f32>().is_err());
+		assert_eq!(tensor.try_extract_values::<i64>()?, [42]);
+		assert_eq!(tensor.try_to_dense::<i64>()?.extract_tensor().1, [0, 0, 42, 0]);
+		Ok(())
+	}
+}
@@ -14,7 +14,7 @@
 //! # }
 //! ```
 //!
-//! ONNX Runtime also supports [`Sequence`]s and [`Map`]s, though they are less commonly used.
+//! ONNX Runtime also supports [`Sequence`]s, [`Map`]s, and [`SparseTensor`]s, though they are less commonly used.
 
 use alloc::{boxed::Box, format, sync::Arc};
 use core::{
@@ -28,6 +28,7 @@ use core::{
 
 mod impl_map;
 mod impl_sequence;
+mod impl_sparse_tensor;
 mod impl_tensor;
 pub(crate) mod r#type;
 
@@ -36,6 +37,10 @@ pub use self::{
 	impl_sequence::{
 		DynSequence, DynSequenceRef, DynSequenceRefMut, DynSequenceValueType, Sequence, SequenceRef, SequenceRefMut, SequenceValueType, SequenceValueTypeMarker
 	},
+	impl_sparse_tensor::{
+		DynSparseTensor, DynSparseTensorRef, DynSparseTensorRefMut, DynSparseTensorValueType, SparseFormat, SparseTensor, SparseTensorRef, SparseTensorRefMut,
+		SparseTensorValueType, SparseTensorValueTypeMarker
+	},
 	impl_tensor::{
 		DefiniteTensorValueTypeMarker, DynTensor, DynTensorRef, DynTensorRefMut, DynTensorValueType, OwnedTensorArrayData, Tensor, TensorArrayData,
 		TensorArrayDataMut, TensorArrayDataParts, TensorRef, TensorRefMut, TensorValueType, TensorValueTypeMarker, ToShape
@@ -189,8 +194,8 @@ impl<Type: ValueTypeMarker + ?Sized> DerefMut for ValueRefMut<'_, Type> {
 	}
 }
 
-/// A [`Value`] contains data for inputs/outputs in ONNX Runtime graphs. [`Value`]s can be a [`Tensor`], [`Sequence`]
-/// (aka array/vector), or [`Map`].
+/// A [`Value`] contains data for inputs/outputs in ONNX Runtime graphs. [`Value`]s can be a [`Tensor`],
+/// [`SparseTensor`], [`Sequence`] (aka array/vector), or [`Map`].
 ///
 /// ## Creation
 /// Values can be created via methods like [`Tensor::from_array`], or as the output from running a [`Session`].
@@ -234,7 +239,7 @@ pub struct Value<Type: ValueTypeMarker + ?Sized = DynValueTypeMarker> {
 	pub(crate) _markers: PhantomData<Type>
 }
 
-/// A dynamic value, which could be a [`Tensor`], [`Sequence`], or [`Map`].
+/// A dynamic value, which could be a [`Tensor`], [`SparseTensor`], [`Sequence`], or [`Map`].
 ///
 /// To attempt to convert a dynamic value to a strongly typed value, use [`DynValue::downcast`]. You can also attempt to
 /// extract data from dynamic values directly using `try_extract_*` methods; see [`Value`] for more information.
@@ -299,6 +304,9 @@ impl SequenceValueTypeMarker for DynValueTypeMarker {
 impl TensorValueTypeMarker for DynValueTypeMarker {
 	private_impl!();
 }
+impl SparseTensorValueTypeMarker for DynValueTypeMarker {
+	private_impl!();
+}
 
 unsafe impl<Type: ValueTypeMarker + ?Sized> Send for Value<Type> {}
 unsafe impl<Type: ValueTypeMarker + ?Sized> Sync for Value<Type> {}
@@ -368,7 +376,7 @@ impl<Type: ValueTypeMarker + ?Sized> Value<Type> {
 		unsafe { self.transmute_type() }
 	}
 
-	/// Returns `true` if this value is a tensor, or `false` if it is another type (sequence, map).
+	/// Returns `true` if this value is a dense tensor, or `false` if it is another type (sparse tensor, sequence, map).
 	///
 	/// ```
 	/// # use ort::value::Tensor;
@@ -385,6 +393,23 @@ impl<Type: ValueTypeMarker + ?Sized> Value<Type> {
 		result == 1
 	}
 
+	/// Returns `true` if this value is a [`SparseTensor`], or `false` if it is another type.
+	///
+	/// ```
+	/// # use ort::value::SparseTensor;
+	/// # fn main() -> ort::Result<()> {
+	/// let value = SparseTensor::<f32>::from_coo([3_i64], &[1], &[1.0])?.into_dyn();
+	/// assert!(value.is_sparse_tensor());
+	/// assert!(!value.is_tensor());
+	/// # 	Ok(())
+	/// # }
+	/// ```
+	pub fn is_sparse_tensor(&self) -> bool {
+		let mut result = 0;
+		ortsys![unsafe IsSparseTensor(self.ptr(), &mut result).expect("infallible")];
+		result == 1
+	}
+
 	#[inline(always)]
 	pub(crate) unsafe fn transmute_type<OtherType: ValueTypeMarker + ?Sized>(self) -> Value<OtherType> {
 		unsafe { transmute::<Value<Type>, Value<OtherType>>(self) }
@@ -44,7 +44,10 @@ use crate::{
 /// # 	Ok(())
 /// # }
 /// ```
+///
+/// More kinds of values may be supported in the future, so matches on a `ValueType` need a wildcard arm.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
 pub enum ValueType {
 	/// Value is a tensor/multi-dimensional array.
 	Tensor {
@@ -61,6 +64,16 @@ pub enum ValueType {
 		shape: Shape,
 		dimension_symbols: SymbolicDimensions
 	},
+	/// Value is a sparse tensor, which stores only its non-zero values & their indices; see
+	/// [`SparseTensor`](super::SparseTensor).
+	SparseTensor {
+		/// Element type of the tensor.
+		ty: TensorElementType,
+		/// Shape of the dense tensor this sparse tensor represents. As with [`ValueType::Tensor`], dynamic dimensions
+		/// of an input/output are `-1`.
+		shape: Shape,
+		dimension_symbols: SymbolicDimensions
+	},
 	/// A sequence (vector) of other `Value`s.
 	///
 	/// [Per ONNX spec](https://onnx.ai/onnx/intro/concepts.html#other-types), only sequences of tensors and maps are allowed.
@@ -90,11 +103,19 @@ impl ValueType {
 		let mut ty: ort_sys::ONNXType = ort_sys::ONNXType::ONNX_TYPE_UNKNOWN;
 		ortsys![unsafe GetOnnxTypeFromTypeInfo(typeinfo_ptr, &mut ty).expect("infallible")];
 		let io_type = match ty {
-			ort_sys::ONNXType::ONNX_TYPE_TENSOR | ort_sys::ONNXType::ONNX_TYPE_SPARSETENSOR => {
+			ort_sys::ONNXType::ONNX_TYPE_TENSOR => {
 				let mut info_ptr: *const ort_sys::OrtTensorTypeAndShapeInfo = ptr::null_mut();
 				ortsys![unsafe CastTypeInfoToTensorInfo(typeinfo_ptr, &mut info_ptr).expect("infallible")];
 				unsafe { extract_data_type_from_tensor_info(info_ptr) }
 			}
+			ort_sys::ONNXType::ONNX_TYPE_SPARSETENSOR => {
+				let mut info_ptr: *const ort_sys::OrtTensorTypeAndShapeInfo = ptr::null_mut();
+				ortsys![unsafe CastTypeInfoToTensorInfo(typeinfo_ptr, &mut info_ptr).expect("infallible")];
+				match unsafe { extract_data_type_from_tensor_info(info_ptr) } {
+					ValueType::Tensor { ty, shape, dimension_symbols } => ValueType::SparseTensor { ty, shape, dimension_symbols },
+					_ => unreachable!()
+				}
+			}
 			ort_sys::ONNXType::ONNX_TYPE_SEQUENCE => {
 				let mut info_ptr: *const ort_sys::OrtSequenceTypeInfo = ptr::null_mut();
 				ortsys![unsafe CastTypeInfoToSequenceTypeInfo(typeinfo_ptr, &mut info_ptr).expect("infallible")];
@@ -161,6 +182,9 @@ impl ValueType {
 
 	/// Returns the shape of this value type if it is a tensor, or `None` if it is a sequence or map.
 	///
+	/// Sparse tensors aren't dense tensors, so this also returns `None` for [`ValueType::SparseTensor`]; match on the
+	/// variant to get the shape of the dense tensor a sparse tensor represents.
+	///
 	/// ```
 	/// # use ort::value::{Tensor, DynValue};
 	/// # fn main() -> ort::Result<()> {
@@ -181,6 +205,8 @@ impl ValueType {
 
 	/// Returns the element type of this value type if it is a tensor, or `None` if it is a sequence or map.
 	///
+	/// Like [`ValueType::tensor_shape`], this returns `None` for [`ValueType::SparseTensor`].
+	///
 	/// ```
 	/// # use ort::{tensor::TensorElementType, value::Tensor};
 	/// # fn main() -> ort::Result<()> {
@@ -204,6 +230,13 @@ impl ValueType {
 		matches!(self, ValueType::Tensor { .. })
 	}
 
+	/// Returns `true` if this value type is a sparse tensor.
+	#[inline]
+	#[must_use]
+	pub fn is_sparse_tensor(&self) -> bool {
+		matches!(self, ValueType::SparseTensor { .. })
+	}
+
 	/// Returns `true` if this value type is a sequence.
 	#[inline]
 	#[must_use]
@@ -223,24 +256,12 @@ impl fmt::Display for ValueType {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			ValueType::Tensor { ty, shape, dimension_symbols } => {
-				write!(f, "Tensor<{ty}>(")?;
-				for (i, dimension) in shape.iter().copied().enumerate() {
-					if dimension == -1 {
-						let sym = &dimension_symbols[i];
-						if sym.is_empty() {
-							f.write_str("dyn")?;
-						} else {
-							f.write_str(sym)?;
-						}
-					} else {
-						dimension.fmt(f)?;
-					}
-					if i != shape.len() - 1 {
-						f.write_str(", ")?;
-					}
-				}
-				f.write_str(")")?;
-				Ok(())
+				write!(f, "Tensor<{ty}>")?;
+				fmt_dimensions(f, shape, dimension_symbols)
+			}
+			ValueType::SparseTensor { ty, shape, dimension_symbols } => {
+				write!(f, "SparseTensor<{ty}>")?;
+				fmt_dimensions(f, shape, dimension_symbols)
 			}
 			ValueType::Map { key, value } => write!(f, "Map<{key}, {value}>"),
 			ValueType::Sequence(inner) => write!(f, "Sequence<{inner}>"),
@@ -249,6 +270,26 @@ impl fmt::Display for ValueType {
 	}
 }
 
+fn fmt_dimensions(f: &mut fmt::Formatter<'_>, shape: &Shape, dimension_symbols: &SymbolicDimensions) -> fmt::Result {
+	f.write_str("(")?;
+	for (i, dimension) in shape.iter().copied().enumerate() {
+		if dimension == -1 {
+			let sym = &dimension_symbols[i];
+			if sym.is_empty() {
+				f.write_str("dyn")?;
+			} else {
+				f.write_str(sym)?;
+			}
+		} else {
+			write!(f, "{dimension}")?;
+		}
+		if i != shape.len() - 1 {
+			f.write_str(", ")?;
+		}
+	}
+	f.write_str(")")
+}
+
 pub(crate) unsafe fn extract_data_type_from_tensor_info(info_ptr: *const ort_sys::OrtTensorTypeAndShapeInfo) -> ValueType {
 	let mut type_sys = ort_sys::ONNXTensorElementDataType::ONNX_TENSOR_ELEMENT_DATA_TYPE_UNDEFINED;
 	ortsys![unsafe GetTensorElementType(info_ptr, &mut type_sys).expect("infallible")];
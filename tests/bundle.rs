@@ -0,0 +1,63 @@
+#![cfg(feature = "bundle")]
+
+use ort::{
+	inputs,
+	session::{Session, builder::GraphOptimizationLevel, bundle::Bundle, run_options::RunOptions},
+	value::Tensor
+};
+
+mod common;
+
+fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+	let mut builder = tar::Builder::new(Vec::new());
+	for (path, contents) in files {
+		let mut header = tar::Header::new_gnu();
+		header.set_size(contents.len() as u64);
+		header.set_mode(0o644);
+		builder.append_data(&mut header, path, *contents).unwrap();
+	}
+	builder.into_inner().unwrap()
+}
+
+#[test]
+fn bundle_with_external_data() -> ort::Result<()> {
+	let bias = common::bias();
+	let model = common::add_bias_model("model.onnx.data")?.to_bytes();
+	let manifest = br#"{
+		"model": "model/model.onnx",
+		"metadata": { "revision": "3" },
+		"session_options": { "optimization_level": "level1", "intra_threads": 1 }
+	}"#;
+	let bundle = Bundle::from_memory(&archive(&[
+		("manifest.json", manifest),
+		("model/model.onnx", &model),
+		("model/model.onnx.data", &bias),
+		("labels.txt", b"positive\nnegative\n")
+	]))?;
+	assert_eq!(bundle.manifest().metadata("revision"), Some("3"));
+	assert_eq!(bundle.manifest().session_options.optimization_level, Some(GraphOptimizationLevel::Level1));
+	assert_eq!(bundle.file_str("labels.txt")?.lines().collect::<Vec<_>>(), ["positive", "negative"]);
+
+	let session = Session::builder()?.with_bundle_options(&bundle)?.commit_from_bundle(&bundle)?;
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+	assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [1.5, -0.5]);
+	Ok(())
+}
+
+#[test]
+fn bundle_with_adapter() -> ort::Result<()> {
+	let model = std::fs::read("tests/data/lora_model.onnx").unwrap();
+	let adapter = std::fs::read("tests/data/adapter.orl").unwrap();
+	let manifest = br#"{ "model": "lora_model.onnx", "adapters": { "default": "adapters/adapter.orl" } }"#;
+	let bundle = Bundle::from_memory(&archive(&[("manifest.json", manifest), ("lora_model.onnx", &model), ("adapters/adapter.orl", &adapter)]))?;
+
+	let session = Session::builder()?.commit_from_bundle(&bundle)?;
+	let lora = bundle.adapter("default", None)?;
+	let mut run_options = RunOptions::new()?;
+	run_options.add_adapter(&lora)?;
+	let input = Tensor::<f32>::from_array(([4, 4], vec![1.0; 16]))?;
+	let with_adapter = session.run_with_options(inputs![input.view()], &run_options)?;
+	let without_adapter = session.run(inputs![input.view()])?;
+	assert_ne!(with_adapter[0].try_extract_tensor::<f32>()?.1, without_adapter[0].try_extract_tensor::<f32>()?.1);
+	Ok(())
+}
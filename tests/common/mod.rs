@@ -0,0 +1,29 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use ort::{
+	model::{GraphBuilder, Initializer, Model, Node, TensorData},
+	tensor::TensorElementType
+};
+
+/// The contents of the external data file of [`add_bias_model`]: the `f32` bias `[1.0, -1.0]`.
+pub fn bias() -> Vec<u8> {
+	[1.0_f32, -1.0].iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+/// Initializer data of `len` bytes stored in the external data file `location`.
+pub fn external_data(location: &str, len: usize) -> TensorData {
+	TensorData::External(vec![("location".to_owned(), location.to_owned()), ("length".to_owned(), len.to_string())])
+}
+
+/// A model which adds a bias, stored in the external data file `location`, to its input `x` of shape `[2]` and
+/// returns the result as `y`. With [`bias`] as the external data, `x = [0.5, 0.5]` gives `y = [1.5, -0.5]`.
+pub fn add_bias_model(location: &str) -> ort::Result<Model> {
+	GraphBuilder::new("add_bias")
+		.with_input("x", TensorElementType::Float32, [2_i64])
+		.with_output("y", TensorElementType::Float32, [2_i64])
+		.with_raw_initializer(Initializer::new("bias", TensorElementType::Float32, [2], external_data(location, bias().len())))
+		.with_node(Node::new("Add").with_inputs(["x", "bias"]).with_outputs(["y"]))
+		.build()
+}
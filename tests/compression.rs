@@ -0,0 +1,54 @@
+#![cfg(feature = "compression")]
+
+use std::io::Write;
+
+use ort::{
+	inputs,
+	model::{GraphBuilder, Initializer, ModelChecker, Node},
+	session::Session,
+	tensor::TensorElementType,
+	value::Tensor
+};
+
+mod common;
+use self::common::external_data;
+
+#[test]
+fn compressed_model_with_external_data() -> ort::Result<()> {
+	let bias = common::bias();
+	let scale: Vec<u8> = [2.0_f32, 2.0].iter().flat_map(|x| x.to_le_bytes()).collect();
+	let model = GraphBuilder::new("scale_bias")
+		.with_input("x", TensorElementType::Float32, [2_i64])
+		.with_output("y", TensorElementType::Float32, [2_i64])
+		.with_raw_initializer(Initializer::new("bias", TensorElementType::Float32, [2], external_data("bias.data", bias.len())))
+		.with_raw_initializer(Initializer::new("scale", TensorElementType::Float32, [2], external_data("scale.data", scale.len())))
+		.with_node(Node::new("Mul").with_inputs(["x", "scale"]).with_outputs(["scaled"]))
+		.with_node(Node::new("Add").with_inputs(["scaled", "bias"]).with_outputs(["y"]))
+		.build()?;
+
+	let dir = std::env::temp_dir().join(format!("ort-compression-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	// the model & one external data file are compressed, the other external data file isn't
+	std::fs::write(dir.join("model.onnx.zst"), zstd::encode_all(&model.to_bytes()[..], 3).unwrap()).unwrap();
+	let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+	encoder.write_all(&bias).unwrap();
+	std::fs::write(dir.join("bias.data.gz"), encoder.finish().unwrap()).unwrap();
+	std::fs::write(dir.join("scale.data"), &scale).unwrap();
+
+	let session = Session::builder()?
+		.with_model_checker(ModelChecker::new())?
+		.commit_from_file(dir.join("model.onnx.zst"))?;
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+	assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [2.0, 0.0]);
+
+	// the checker resolves external data relative to the compressed model's directory
+	std::fs::remove_file(dir.join("scale.data")).unwrap();
+	let error = Session::builder()?
+		.with_model_checker(ModelChecker::new())?
+		.commit_from_file(dir.join("model.onnx.zst"))
+		.unwrap_err();
+	assert!(error.to_string().contains("`scale.data`, which does not exist"), "{error}");
+
+	std::fs::remove_dir_all(dir).unwrap();
+	Ok(())
+}
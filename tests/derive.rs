@@ -0,0 +1,109 @@
+#![cfg(all(feature = "derive", feature = "ndarray"))]
+
+use std::path::Path;
+
+use ndarray::{Array4, ArrayView4};
+use ort::{
+	error::ErrorCode,
+	session::{ModelInputs, ModelOutputs, Session},
+	value::{Tensor, TensorRef}
+};
+
+#[derive(ModelInputs)]
+struct UpsampleInputs<'a> {
+	#[ort(name = "up_sampling2d_input:0")]
+	image: TensorRef<'a, f32>
+}
+
+#[derive(ModelInputs)]
+struct UpsampleArrayInputs<'a> {
+	#[ort(name = "up_sampling2d_input:0")]
+	image: ArrayView4<'a, f32>
+}
+
+#[derive(ModelOutputs)]
+struct UpsampleOutputs {
+	#[ort(name = "Identity:0")]
+	image: Tensor<f32>
+}
+
+#[derive(ModelOutputs)]
+struct UpsampleArrayOutputs {
+	#[ort(name = "Identity:0")]
+	image: Array4<f32>
+}
+
+#[derive(ModelInputs)]
+struct WrongTypeInputs {
+	#[ort(name = "up_sampling2d_input:0")]
+	_image: Tensor<i64>
+}
+
+#[derive(ModelOutputs)]
+struct WrongRankOutputs {
+	#[ort(name = "Identity:0")]
+	_image: ndarray::Array2<f32>
+}
+
+#[derive(ModelOutputs)]
+struct MissingOutputs {
+	_logits: Tensor<f32>
+}
+
+fn builder() -> ort::Result<ort::session::builder::SessionBuilder> {
+	ort::init().with_name("integration_test").commit()?;
+	Session::builder()
+}
+
+fn model_path() -> std::path::PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("upsample.onnx")
+}
+
+#[test]
+fn derive_run_typed() -> ort::Result<()> {
+	let session = builder()?
+		.with_model_inputs::<UpsampleInputs>()?
+		.with_model_outputs::<UpsampleOutputs>()?
+		.commit_from_file(model_path())?;
+
+	let image = Array4::<f32>::from_elem((1, 4, 4, 3), 0.5);
+	let outputs: UpsampleOutputs = session.run_typed(UpsampleInputs {
+		image: TensorRef::from_array_view(&image)?
+	})?;
+	let (shape, data) = outputs.image.extract_tensor();
+	assert_eq!(**shape, [1, 8, 8, 3]);
+	assert!(data.iter().all(|x| *x == 0.5));
+
+	let outputs: UpsampleArrayOutputs = session.run_typed(UpsampleArrayInputs { image: image.view() })?;
+	assert_eq!(outputs.image.shape(), [1, 8, 8, 3]);
+
+	Ok(())
+}
+
+#[test]
+fn derive_signature_mismatch() -> ort::Result<()> {
+	let err = builder()?
+		.with_model_inputs::<WrongTypeInputs>()?
+		.commit_from_file(model_path())
+		.unwrap_err();
+	assert_eq!(err.code(), ErrorCode::InputTypeMismatch);
+	let err = builder()?
+		.with_model_outputs::<WrongRankOutputs>()?
+		.commit_from_file(model_path())
+		.unwrap_err();
+	assert_eq!(err.code(), ErrorCode::OutputShapeMismatch);
+	let err = builder()?
+		.with_model_outputs::<MissingOutputs>()?
+		.commit_from_file(model_path())
+		.unwrap_err();
+	assert_eq!(err.code(), ErrorCode::UnknownOutput);
+
+	let session = builder()?.commit_from_file(model_path())?;
+	let image = Array4::<f32>::zeros((1, 4, 4, 3));
+	let result: ort::Result<MissingOutputs> = session.run_typed(UpsampleArrayInputs { image: image.view() });
+	assert_eq!(result.err().map(|e| e.code()), Some(ErrorCode::MissingOutput));
+	let result: ort::Result<WrongRankOutputs> = session.run_typed(UpsampleArrayInputs { image: image.view() });
+	assert_eq!(result.err().map(|e| e.code()), Some(ErrorCode::OutputShapeMismatch));
+
+	Ok(())
+}
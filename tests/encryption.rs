@@ -0,0 +1,35 @@
+#![cfg(feature = "encryption")]
+
+use ort::{
+	inputs,
+	session::{
+		Session,
+		encryption::{self, Algorithm, Key}
+	},
+	value::Tensor
+};
+
+mod common;
+
+#[test]
+fn encrypted_model_with_external_data() -> ort::Result<()> {
+	let bias = common::bias();
+	let model = common::add_bias_model("bias.bin")?;
+
+	let key = Key::generate();
+	let encrypted_model = encryption::encrypt(&model.to_bytes(), Algorithm::ChaCha20Poly1305, &key)?;
+	let encrypted_bias = encryption::encrypt(&bias, Algorithm::Aes256Gcm, &key)?;
+
+	let session = Session::builder()?
+		.with_encrypted_external_initializer_file_in_memory("bias.bin", &encrypted_bias, &key)?
+		.commit_from_encrypted_memory(&encrypted_model, &key)?;
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+	assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [1.5, -0.5]);
+
+	assert!(
+		Session::builder()?
+			.commit_from_encrypted_memory(&encrypted_model, &Key::generate())
+			.is_err()
+	);
+	Ok(())
+}
@@ -0,0 +1,145 @@
+use ort::{
+	generation::{
+		GenerationOptions, Generator, StopReason,
+		beam::{BeamSearch, BeamSearchOptions}
+	},
+	model::{Dimension, GraphBuilder, Model, Node},
+	session::Session,
+	tensor::TensorElementType,
+	value::Tensor
+};
+
+const VOCAB_SIZE: i64 = 7;
+
+/// A tiny decoder whose next token depends on every token seen so far: `(sum(tokens) + len(tokens)) % VOCAB_SIZE`.
+///
+/// The sum is taken over the `present.0.key` output, which is the `past_key_values.0.key` input with the new tokens
+/// appended, and the length is taken from the attention mask. So the model only predicts the right token if the KV
+/// cache is fed back between steps & the attention mask grows with it.
+fn tiny_decoder() -> ort::Result<Model> {
+	let cache_shape = || [Dimension::from("batch_size"), 1.into(), Dimension::from("past_sequence_length"), 1.into()];
+	GraphBuilder::new("tiny_decoder")
+		.with_input("input_ids", TensorElementType::Int64, [Dimension::from("batch_size"), Dimension::from("sequence_length")])
+		.with_input("attention_mask", TensorElementType::Int64, [Dimension::from("batch_size"), Dimension::from("total_sequence_length")])
+		.with_input("past_key_values.0.key", TensorElementType::Float32, cache_shape())
+		.with_output("logits", TensorElementType::Float32, [Dimension::from("batch_size"), 1.into(), VOCAB_SIZE.into()])
+		.with_output("present.0.key", TensorElementType::Float32, cache_shape())
+		.with_initializer("vocab", &Tensor::from_array(([VOCAB_SIZE as usize], (0..VOCAB_SIZE).map(|i| i as f32).collect::<Vec<_>>()))?)?
+		.with_initializer("vocab_size", &Tensor::from_array(([1], vec![VOCAB_SIZE as f32]))?)?
+		.with_initializer("scale", &Tensor::from_array(([1], vec![-10.0_f32]))?)?
+		.with_initializer("cache_axes", &Tensor::from_array(([2], vec![1_i64, 3]))?)?
+		.with_initializer("reduce_cache_axes", &Tensor::from_array(([3], vec![1_i64, 2, 3]))?)?
+		.with_initializer("reduce_mask_axes", &Tensor::from_array(([1], vec![1_i64]))?)?
+		.with_initializer("target_axes", &Tensor::from_array(([2], vec![1_i64, 2]))?)?
+		// present = concat(past, input_ids as [batch, 1, sequence, 1])
+		.with_node(Node::new("Cast").with_inputs(["input_ids"]).with_outputs(["ids_float"]).with_attribute("to", 1_i64))
+		.with_node(Node::new("Unsqueeze").with_inputs(["ids_float", "cache_axes"]).with_outputs(["ids_cache"]))
+		.with_node(
+			Node::new("Concat")
+				.with_inputs(["past_key_values.0.key", "ids_cache"])
+				.with_outputs(["present.0.key"])
+				.with_attribute("axis", 2_i64)
+		)
+		// target = (sum(present) + sum(attention_mask)) % VOCAB_SIZE, as [batch, 1, 1]
+		.with_node(
+			Node::new("ReduceSum")
+				.with_inputs(["present.0.key", "reduce_cache_axes"])
+				.with_outputs(["token_sum"])
+				.with_attribute("keepdims", 0_i64)
+		)
+		.with_node(Node::new("Cast").with_inputs(["attention_mask"]).with_outputs(["mask_float"]).with_attribute("to", 1_i64))
+		.with_node(
+			Node::new("ReduceSum")
+				.with_inputs(["mask_float", "reduce_mask_axes"])
+				.with_outputs(["length"])
+				.with_attribute("keepdims", 0_i64)
+		)
+		.with_node(Node::new("Add").with_inputs(["token_sum", "length"]).with_outputs(["total"]))
+		.with_node(Node::new("Mod").with_inputs(["total", "vocab_size"]).with_outputs(["target_flat"]).with_attribute("fmod", 1_i64))
+		.with_node(Node::new("Unsqueeze").with_inputs(["target_flat", "target_axes"]).with_outputs(["target"]))
+		// logits = -10 * ((vocab - target + VOCAB_SIZE) % VOCAB_SIZE), so the target token scores highest, followed by
+		// the tokens after it
+		.with_node(Node::new("Sub").with_inputs(["vocab", "target"]).with_outputs(["offset"]))
+		.with_node(Node::new("Add").with_inputs(["offset", "vocab_size"]).with_outputs(["offset_positive"]))
+		.with_node(Node::new("Mod").with_inputs(["offset_positive", "vocab_size"]).with_outputs(["distance"]).with_attribute("fmod", 1_i64))
+		.with_node(Node::new("Mul").with_inputs(["distance", "scale"]).with_outputs(["logits"]))
+		.build()
+}
+
+/// The token [`tiny_decoder`] predicts after `tokens`.
+fn next_token(tokens: &[i64]) -> i64 {
+	(tokens.iter().sum::<i64>() + tokens.len() as i64) % VOCAB_SIZE
+}
+
+#[test]
+fn generate_with_kv_cache() -> ort::Result<()> {
+	let session = Session::builder()?.commit_from_model(&tiny_decoder()?)?;
+	let mut generator = Generator::new(&session, GenerationOptions::default().with_max_tokens(5))?;
+	assert_eq!(generator.layout().cache.len(), 1);
+	assert_eq!(generator.layout().cache[0].output, "present.0.key");
+
+	let mut expected = vec![1, 2];
+	generator.push_tokens(&expected)?;
+	for _ in 0..3 {
+		let token = generator.step()?;
+		assert_eq!(token, next_token(&expected));
+		expected.push(token);
+		assert_eq!(generator.tokens(), expected);
+	}
+	// tokens pushed between steps are processed along with the cached ones
+	generator.push_tokens(&[6])?;
+	expected.push(6);
+	let generated = generator.generate().collect::<ort::Result<Vec<_>>>()?;
+	for &token in &generated {
+		assert_eq!(token, next_token(&expected));
+		expected.push(token);
+	}
+	assert_eq!(generated.len(), 5);
+	assert_eq!(generator.stop_reason(), Some(StopReason::MaxTokens));
+	assert_eq!(generator.tokens(), expected);
+
+	// trimming the cache rewinds the sequence
+	generator.truncate(3)?;
+	assert_eq!(generator.step()?, expected[3]);
+	Ok(())
+}
+
+#[test]
+fn beam_search_reorders_kv_cache() -> ort::Result<()> {
+	let session = Session::builder()?.commit_from_model(&tiny_decoder()?)?;
+	let options = BeamSearchOptions::default()
+		.with_num_beams(3)
+		.with_num_return_sequences(2)
+		.with_max_tokens(4)
+		.with_pad_token_id(0);
+	let prompts: [&[i64]; 2] = [&[1, 2], &[3]];
+	let results = BeamSearch::new(&session, options)?.search(&prompts)?;
+	assert_eq!(results.len(), 2);
+
+	for (prompt, hypotheses) in prompts.iter().zip(&results) {
+		assert_eq!(hypotheses.len(), 2);
+		// the model is (almost) certain of the next token, so the best hypothesis follows it at every step, and the
+		// runner-up deviates exactly once (to the second most likely token) - as long as each beam's cache follows it
+		// when beams are reordered
+		let deviations = |tokens: &[i64]| {
+			let mut sequence = prompt.to_vec();
+			let mut deviations = 0;
+			for &token in tokens {
+				let next = next_token(&sequence);
+				if token != next {
+					assert_eq!(token, (next + 1) % VOCAB_SIZE);
+					deviations += 1;
+				}
+				sequence.push(token);
+			}
+			deviations
+		};
+		for (hypothesis, expected_deviations) in hypotheses.iter().zip([0, 1]) {
+			assert_eq!(hypothesis.tokens.len(), 4);
+			assert_eq!(deviations(&hypothesis.tokens), expected_deviations);
+			assert!((hypothesis.log_prob + 10.0 * expected_deviations as f32).abs() < 0.01);
+		}
+		assert!(hypotheses[0].score > hypotheses[1].score);
+	}
+	Ok(())
+}
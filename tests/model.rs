@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "half")]
+use ort::model::float16::{Float16Converter, HalfType, verify};
+use ort::{
+	inputs,
+	model::{
+		AttributeValue, Dimension, GraphBuilder, Initializer, Model, ModelChecker, Node, RenderOptions, TensorData, TypeInfo,
+		quantize::{CalibrationMethod, DynamicQuantizer, StaticQuantizer}
+	},
+	session::{
+		Session,
+		debug::{Mismatch, Tolerance, compare_taps}
+	},
+	tensor::TensorElementType,
+	value::Tensor
+};
+
+mod common;
+
+fn data_path(name: &str) -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join(name)
+}
+
+#[test]
+fn inspect_custom_op_model() -> ort::Result<()> {
+	let model = Model::from_file(data_path("custom_op_test.onnx"))?;
+	assert_eq!(model.ir_version, 3);
+	assert_eq!(model.opset_version(""), Some(7));
+
+	let op_types = model.op_types();
+	assert_eq!(op_types.get(&("test.customop", "CustomOpOne")), Some(&1));
+	assert_eq!(op_types.get(&("test.customop", "CustomOpTwo")), Some(&1));
+	assert_eq!(model.custom_domains().into_iter().collect::<Vec<_>>(), ["test.customop"]);
+	assert_eq!(model.custom_domain_nodes().len(), 2);
+
+	let input = &model.graph.inputs[0];
+	assert_eq!(input.name, "input_1");
+	let ty = input.ty.as_ref().expect("input should have a type");
+	assert_eq!(ty.element_type(), Some(TensorElementType::Float32));
+	assert_eq!(ty.shape(), Some(&[Dimension::Value(3), Dimension::Value(5)][..]));
+	Ok(())
+}
+
+#[test]
+fn inspect_vectorizer_model() -> ort::Result<()> {
+	let model = Model::from_file(data_path("vectorizer.onnx"))?;
+	assert_eq!(model.producer_name, "skl2onnx");
+	assert_eq!(model.opset_version("com.microsoft"), Some(1));
+	assert_eq!(model.metadata_props, [("custom_key".to_owned(), "custom_value".to_owned())]);
+	// `com.microsoft` ops are implemented by ONNX Runtime
+	assert!(model.custom_domains().is_empty());
+
+	let tokenizer = model
+		.graph
+		.nodes
+		.iter()
+		.find(|node| node.op_type == "Tokenizer")
+		.expect("model should have a tokenizer");
+	assert_eq!(tokenizer.domain, "com.microsoft");
+	assert!(
+		model
+			.graph
+			.nodes
+			.iter()
+			.any(|node| matches!(node.attribute("mode"), Some(AttributeValue::String(_))))
+	);
+
+	let shape = model.graph.initializer("shape_tensor").expect("model should have a shape initializer");
+	assert_eq!(shape.element_type(), Some(TensorElementType::Int64));
+	assert_eq!(model.parameter_count(), 1);
+
+	assert!(matches!(model.graph.inputs[0].ty, Some(TypeInfo::Tensor { elem_type: 8, .. })));
+	Ok(())
+}
+
+#[test]
+fn roundtrip_models() -> ort::Result<()> {
+	for name in ["custom_op_test.onnx", "vectorizer.onnx", "upsample.onnx", "lora_model.onnx"] {
+		let model = Model::from_file(data_path(name))?;
+		model.validate()?;
+		assert_eq!(Model::from_bytes(&model.to_bytes())?, model, "{name}");
+	}
+	Ok(())
+}
+
+#[test]
+fn build_and_run_graph() -> ort::Result<()> {
+	let model = GraphBuilder::new("add_bias")
+		.with_input("x", TensorElementType::Float32, [Dimension::from("batch"), 2.into()])
+		.with_output("y", TensorElementType::Float32, [Dimension::from("batch"), 2.into()])
+		.with_initializer("bias", &Tensor::from_array(([2], vec![1.0_f32, -1.0]))?)?
+		.with_node(Node::new("Add").with_inputs(["x", "bias"]).with_outputs(["y"]))
+		.build()?;
+
+	let session = Session::builder()?.commit_from_model(&model)?;
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2, 2], vec![0.0_f32, 0.0, 2.0, 2.0]))?])?;
+	let (shape, data) = outputs["y"].try_extract_tensor::<f32>()?;
+	assert_eq!(**shape, [2, 2]);
+	assert_eq!(data, [1.0, -1.0, 3.0, 1.0]);
+	Ok(())
+}
+
+#[test]
+fn edit_model() -> ort::Result<()> {
+	let mut model = Model::from_file(data_path("upsample.onnx"))?;
+	model.rename_input("up_sampling2d_input:0", "image")?;
+	let output = model.graph.outputs[0].name.clone();
+	model.rename_output(&output, "upsampled")?;
+	model.validate()?;
+
+	let session = Session::builder()?.commit_from_memory(&model.to_bytes())?;
+	assert_eq!(session.inputs[0].name, "image");
+	assert_eq!(session.outputs[0].name, "upsampled");
+	Ok(())
+}
+
+#[test]
+fn debug_taps() -> ort::Result<()> {
+	let model = GraphBuilder::new("taps")
+		.with_input("x", TensorElementType::Float32, [3_i64])
+		.with_output("y", TensorElementType::Float32, [3_i64])
+		.with_node(Node::new("Relu").with_name("relu").with_inputs(["x"]).with_outputs(["hidden/relu"]))
+		.with_node(Node::new("Neg").with_inputs(["hidden/relu"]).with_outputs(["y"]))
+		.build()?;
+
+	let session = Session::builder()?.with_debug_taps(["hidden/*"])?.commit_from_model(&model)?;
+	assert_eq!(session.outputs.len(), 1);
+	assert_eq!(session.taps()[0].node, "relu");
+
+	let run = |x: Vec<f32>| session.run(inputs!["x" => Tensor::from_array(([3], x)).unwrap()]);
+	let expected = run(vec![-1.0, 0.5, 2.0])?;
+	assert_eq!(expected.len(), 1);
+	assert_eq!(expected.tap("hidden/relu").unwrap().try_extract_tensor::<f32>()?.1, [0.0, 0.5, 2.0]);
+
+	let actual = run(vec![-1.0, 0.5, 2.5])?;
+	let divergence = compare_taps(expected.taps(), actual.taps(), Tolerance::default())?.expect("taps should diverge");
+	assert_eq!(divergence.node, "relu");
+	assert!(matches!(divergence.mismatch, Mismatch::Value { index: 2, .. }));
+	Ok(())
+}
+
+#[test]
+fn debug_taps_with_external_data() -> ort::Result<()> {
+	let bias = common::bias();
+	let model = GraphBuilder::new("add_bias_relu")
+		.with_input("x", TensorElementType::Float32, [2_i64])
+		.with_output("y", TensorElementType::Float32, [2_i64])
+		.with_raw_initializer(Initializer::new("bias", TensorElementType::Float32, [2], common::external_data("bias.bin", bias.len())))
+		.with_node(Node::new("Add").with_inputs(["x", "bias"]).with_outputs(["hidden/sum"]))
+		.with_node(Node::new("Relu").with_inputs(["hidden/sum"]).with_outputs(["y"]))
+		.build()?;
+
+	let dir = std::env::temp_dir().join(format!("ort-debug-taps-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	let model_path = dir.join("model.onnx");
+	std::fs::write(&model_path, model.to_bytes()).unwrap();
+	std::fs::write(dir.join("bias.bin"), &bias).unwrap();
+
+	let session = Session::builder()?.with_debug_taps(["hidden/*"])?.commit_from_file(&model_path)?;
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+	assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [1.5, 0.0]);
+	assert_eq!(outputs.tap("hidden/sum").unwrap().try_extract_tensor::<f32>()?.1, [1.5, -0.5]);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+	Ok(())
+}
+
+#[test]
+fn render_model() -> ort::Result<()> {
+	let model = Model::from_file(data_path("vectorizer.onnx"))?;
+	let dot = model.to_dot(&RenderOptions::new());
+	let mermaid = model.to_mermaid(&RenderOptions::new());
+	for node in &model.graph.nodes {
+		assert!(dot.contains(&node.op_type), "{dot}");
+		assert!(mermaid.contains(&node.op_type), "{mermaid}");
+	}
+	assert!(dot.contains("shape_tensor: Tensor<i64>"), "{dot}");
+	Ok(())
+}
+
+#[test]
+fn check_models() -> ort::Result<()> {
+	for name in ["custom_op_test.onnx", "vectorizer.onnx", "upsample.onnx", "lora_model.onnx"] {
+		let model = Model::from_file(data_path(name))?;
+		let report = ModelChecker::new().with_external_data_dir(data_path("")).check(&model);
+		assert!(!report.has_errors(), "{name}:\n{report}");
+	}
+	Ok(())
+}
+
+#[test]
+fn quantize_model() -> ort::Result<()> {
+	let weights: Vec<f32> = (0..32).map(|i| (i as f32 - 16.0) / 8.0).collect();
+	let model = GraphBuilder::new("linear")
+		.with_input("x", TensorElementType::Float32, [Dimension::from("batch"), 8.into()])
+		.with_output("y", TensorElementType::Float32, [Dimension::from("batch"), 4.into()])
+		.with_raw_initializer(Initializer::new("w", TensorElementType::Float32, [8, 4], TensorData::Float(weights)))
+		.with_node(Node::new("MatMul").with_inputs(["x", "w"]).with_outputs(["y"]))
+		.build()?;
+	let inputs: Vec<Tensor<f32>> = (0..4)
+		.map(|i| Tensor::from_array(([1, 8], (0..8).map(|j| ((i * 8 + j) % 5) as f32 - 2.0).collect::<Vec<_>>())))
+		.collect::<ort::Result<_>>()?;
+
+	let run = |model: &Model| -> ort::Result<Vec<f32>> {
+		let session = Session::builder()?.with_quant_qdq(true)?.commit_from_model(model)?;
+		let outputs = session.run(inputs!["x" => &inputs[0]])?;
+		Ok(outputs["y"].try_extract_tensor::<f32>()?.1.to_vec())
+	};
+	let expected = run(&model)?;
+
+	let dynamic = DynamicQuantizer::new().quantize(&model)?;
+	assert!(dynamic.graph.nodes.iter().any(|node| node.op_type == "MatMulInteger"));
+	for (e, a) in expected.iter().zip(run(&dynamic)?) {
+		assert!((e - a).abs() < 0.2, "{expected:?}");
+	}
+
+	let quantizer = StaticQuantizer::new().with_calibration_method(CalibrationMethod::Entropy);
+	let calibration = quantizer.calibrate(&model, Session::builder()?, inputs.iter().map(|x| inputs!["x" => x]))?;
+	assert!(calibration.get("x").is_some() && calibration.get("y").is_some());
+	let qdq = quantizer.quantize(&model, &calibration)?;
+	assert!(qdq.graph.nodes.iter().any(|node| node.op_type == "QuantizeLinear"));
+	for (e, a) in expected.iter().zip(run(&qdq)?) {
+		assert!((e - a).abs() < 0.5, "{expected:?}");
+	}
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "half")]
+fn convert_float16() -> ort::Result<()> {
+	let model = GraphBuilder::new("attention_scores")
+		.with_input("q", TensorElementType::Float32, [2_i64, 4])
+		.with_output("scores", TensorElementType::Float32, [2_i64, 3])
+		.with_raw_initializer(Initializer::new("k", TensorElementType::Float32, [4, 3], TensorData::Float((0..12).map(|i| i as f32 / 12.0).collect())))
+		.with_node(Node::new("MatMul").with_inputs(["q", "k"]).with_outputs(["logits"]))
+		.with_node(Node::new("Softmax").with_inputs(["logits"]).with_outputs(["scores"]))
+		.build()?;
+	let converted = Float16Converter::new(HalfType::Float16)
+		.with_blocked_op_types(["Softmax"])
+		.with_keep_io_types(true)
+		.convert(&model)?;
+	assert_eq!(converted.graph.initializer("k").and_then(Initializer::element_type), Some(TensorElementType::Float16));
+
+	let q = Tensor::from_array(([2, 4], vec![0.5_f32, -1.0, 2.0, 0.0, 1.5, 1.5, -0.5, 1.0]))?;
+	assert_eq!(verify(&model, &converted, inputs!["q" => &q], Tolerance::new(1e-3, 1e-2))?, None);
+
+	let unconverted_io = Float16Converter::new(HalfType::Float16).convert(&model)?;
+	assert_eq!(verify(&model, &unconverted_io, inputs!["q" => &q], Tolerance::new(1e-3, 1e-2))?, None);
+	Ok(())
+}
+
+#[test]
+fn convert_opset() -> ort::Result<()> {
+	let model = Model::from_file(data_path("upsample.onnx"))?;
+	assert_eq!(model.opset_version(""), Some(12));
+	let upgraded = model.convert_opset(18)?;
+	assert_eq!(upgraded.opset_version(""), Some(18));
+	// tf2onnx uses `tf_half_pixel_for_nn` coordinates, which `Resize-10` doesn't support
+	let error = model.convert_opset(10).unwrap_err();
+	assert!(
+		error
+			.to_string()
+			.contains("`Resize` node `Resize__27`: `tf_half_pixel_for_nn` coordinates"),
+		"{error}"
+	);
+	let downgraded = model.convert_opset(11)?;
+
+	let image = Tensor::from_array(([1, 2, 2, 3], (0..12).map(|i| i as f32).collect::<Vec<_>>()))?;
+	let run = |model: &Model| -> ort::Result<Vec<f32>> {
+		let session = Session::builder()?.commit_from_memory(&model.to_bytes())?;
+		let outputs = session.run(inputs![&image])?;
+		Ok(outputs[0].try_extract_tensor::<f32>()?.1.to_vec())
+	};
+	let expected = run(&model)?;
+	assert_eq!(run(&upgraded)?, expected);
+	assert_eq!(run(&downgraded)?, expected);
+	Ok(())
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "model-cache")]
+
+use ort::{
+	execution_providers::CPUExecutionProvider,
+	inputs,
+	session::{Session, builder::GraphOptimizationLevel},
+	value::Tensor
+};
+
+mod common;
+
+fn cached_models(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+	std::fs::read_dir(dir)
+		.unwrap()
+		.map(|entry| entry.unwrap().path())
+		.filter(|path| path.extension().is_some_and(|ext| ext == "ort" || ext == "onnx"))
+		.collect()
+}
+
+#[test]
+fn optimized_model_cache() -> ort::Result<()> {
+	let dir = std::env::temp_dir().join(format!("ort-model-cache-{}", std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	let input = || Tensor::<f32>::from_array(([1, 2, 2, 3], (0..12).map(|x| x as f32).collect::<Vec<_>>()));
+
+	let mut outputs = Vec::new();
+	for _ in 0..2 {
+		let session = Session::builder()?
+			.with_optimization_level(GraphOptimizationLevel::Level3)?
+			.with_optimized_cache(&dir)?
+			.commit_from_file("tests/data/upsample.onnx")?;
+		outputs.push(session.run(inputs![input()?])?[0].try_extract_tensor::<f32>()?.1.to_vec());
+		assert_eq!(cached_models(&dir).len(), 1);
+	}
+	assert_eq!(outputs[0], outputs[1]);
+
+	// a different optimization level creates a new cache entry
+	let _session = Session::builder()?
+		.with_optimization_level(GraphOptimizationLevel::Level1)?
+		.with_optimized_cache(&dir)?
+		.commit_from_file("tests/data/upsample.onnx")?;
+	assert_eq!(cached_models(&dir).len(), 2);
+
+	// so do different execution provider options
+	let _session = Session::builder()?
+		.with_optimization_level(GraphOptimizationLevel::Level1)?
+		.with_execution_providers([CPUExecutionProvider::default().with_arena_allocator(false).build()])?
+		.with_optimized_cache(&dir)?
+		.commit_from_file("tests/data/upsample.onnx")?;
+	assert_eq!(cached_models(&dir).len(), 3);
+
+	std::fs::remove_dir_all(dir).unwrap();
+	Ok(())
+}
+
+#[test]
+fn optimized_model_cache_with_external_data_from_memory() -> ort::Result<()> {
+	let dir = std::env::temp_dir().join(format!("ort-model-cache-external-{}", std::process::id()));
+	let cache_dir = dir.join("cache");
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	let model = common::add_bias_model("bias.bin")?.to_bytes();
+
+	let run = || -> ort::Result<Vec<f32>> {
+		let session = Session::builder()?
+			.with_config_entry("session.model_external_initializers_file_folder_path", dir.to_str().unwrap())?
+			.with_optimized_cache(&cache_dir)?
+			.commit_from_memory(&model)?;
+		let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+		Ok(outputs["y"].try_extract_tensor::<f32>()?.1.to_vec())
+	};
+
+	std::fs::write(dir.join("bias.bin"), common::bias()).unwrap();
+	assert_eq!(run()?, [1.5, -0.5]);
+	assert_eq!(cached_models(&cache_dir).len(), 1);
+
+	// changing the external data in the configured directory must not reuse the stale entry
+	let bias: Vec<u8> = [2.0_f32, 0.0].iter().flat_map(|x| x.to_le_bytes()).collect();
+	std::fs::write(dir.join("bias.bin"), bias).unwrap();
+	assert_eq!(run()?, [2.5, 0.5]);
+	assert_eq!(cached_models(&cache_dir).len(), 2);
+
+	std::fs::remove_dir_all(dir).unwrap();
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "signing")]
+fn optimized_model_cache_bypassed_for_signed_models() -> ort::Result<()> {
+	use ort::session::signing::{self, SigningKey};
+
+	let dir = std::env::temp_dir().join(format!("ort-model-cache-signed-{}", std::process::id()));
+	let cache_dir = dir.join("cache");
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	let model_path = dir.join("model.onnx");
+	std::fs::copy("tests/data/upsample.onnx", &model_path).unwrap();
+
+	let key = SigningKey::generate();
+	let digest = signing::sign_file(&model_path, &key)?;
+	for _ in 0..2 {
+		let session = Session::builder()?
+			.with_signature_verification([key.verifying_key()])?
+			.with_optimized_cache(&cache_dir)?
+			.commit_from_file(&model_path)?;
+		assert_eq!(session.verified_digest(), Some(&digest));
+		// a tampered cache entry could otherwise be loaded in place of the verified model
+		assert!(!cache_dir.exists() || cached_models(&cache_dir).is_empty());
+	}
+
+	std::fs::remove_dir_all(dir).unwrap();
+	Ok(())
+}
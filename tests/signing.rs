@@ -0,0 +1,76 @@
+#![cfg(feature = "signing")]
+
+use std::borrow::Cow;
+
+use ort::{
+	error::ErrorCode,
+	inputs,
+	session::{
+		Session,
+		signing::{self, Signature, SigningKey}
+	},
+	value::Tensor
+};
+
+mod common;
+
+#[test]
+fn signed_model_with_external_data() -> ort::Result<()> {
+	let bias = common::bias();
+	let model = common::add_bias_model("bias.bin")?;
+
+	let dir = std::env::temp_dir().join(format!("ort-signing-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	let model_path = dir.join("model.onnx");
+	std::fs::write(&model_path, model.to_bytes()).unwrap();
+	std::fs::write(dir.join("bias.bin"), &bias).unwrap();
+
+	let key = SigningKey::generate();
+	let trusted = [key.verifying_key()];
+
+	// unsigned
+	let error = Session::builder()?
+		.with_signature_verification(trusted)?
+		.commit_from_file(&model_path)
+		.unwrap_err();
+	assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+
+	let digest = signing::sign_file(&model_path, &key)?;
+	let session = Session::builder()?.with_signature_verification(trusted)?.commit_from_file(&model_path)?;
+	assert_eq!(session.verified_digest(), Some(&digest));
+	let outputs = session.run(inputs!["x" => Tensor::from_array(([2], vec![0.5_f32, 0.5]))?])?;
+	assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [1.5, -0.5]);
+
+	// untrusted key
+	let error = Session::builder()?
+		.with_signature_verification([SigningKey::generate().verifying_key()])?
+		.commit_from_file(&model_path)
+		.unwrap_err();
+	assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+
+	// tampered external data
+	std::fs::write(dir.join("bias.bin"), [0; 8]).unwrap();
+	let error = Session::builder()?
+		.with_signature_verification(trusted)?
+		.commit_from_file(&model_path)
+		.unwrap_err();
+	assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+
+	// from memory, with the external data also provided in memory
+	let signature = Signature::from_bytes(&std::fs::read(Signature::path_for(&model_path)).unwrap())?;
+	let session = Session::builder()?
+		.with_signature_verification(trusted)?
+		.with_model_signature(signature.clone())?
+		.with_external_initializer_file_in_memory("bias.bin", Cow::Owned(bias))?
+		.commit_from_memory(&model.to_bytes())?;
+	assert_eq!(session.verified_digest(), Some(&digest));
+
+	let error = Session::builder()?
+		.with_signature_verification(trusted)?
+		.commit_from_memory(&model.to_bytes())
+		.unwrap_err();
+	assert_eq!(error.code(), ErrorCode::SignatureVerificationFailed);
+
+	std::fs::remove_dir_all(dir).unwrap();
+	Ok(())
+}
@@ -0,0 +1,47 @@
+use ort::{
+	error::ErrorCode,
+	inputs,
+	model::{GraphBuilder, Node, TypeInfo},
+	session::Session,
+	tensor::TensorElementType,
+	value::{SparseTensor, Tensor, ValueType}
+};
+
+#[test]
+fn sparse_input() -> ort::Result<()> {
+	let mut model = GraphBuilder::new("sparse_matmul")
+		.with_opset("com.microsoft", 1)
+		.with_input("a", TensorElementType::Float32, [2_i64, 3])
+		.with_input("b", TensorElementType::Float32, [3_i64, 2])
+		.with_output("y", TensorElementType::Float32, [2_i64, 2])
+		.with_node(
+			Node::new("SparseToDenseMatMul")
+				.with_domain("com.microsoft")
+				.with_inputs(["a", "b"])
+				.with_outputs(["y"])
+		)
+		.build()?;
+	// `GraphBuilder` only declares dense inputs
+	let Some(TypeInfo::Tensor { elem_type, shape }) = model.graph.inputs[0].ty.take() else {
+		unreachable!()
+	};
+	model.graph.inputs[0].ty = Some(TypeInfo::SparseTensor { elem_type, shape });
+
+	let session = Session::builder()?.commit_from_model(&model)?;
+	assert!(matches!(session.inputs[0].input_type, ValueType::SparseTensor { ty: TensorElementType::Float32, .. }));
+
+	// [[0, 2, 0],
+	//  [1, 0, 3]]
+	let coo = SparseTensor::<f32>::from_coo([2_i64, 3], &[1, 3, 5], &[2.0, 1.0, 3.0])?;
+	let csr = SparseTensor::<f32>::from_csr([2_i64, 3], &[1, 0, 2], &[0, 1, 3], &[2.0, 1.0, 3.0])?;
+	let b = Tensor::from_array(([3, 2], vec![1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0]))?;
+	for a in [coo, csr] {
+		let outputs = session.run(inputs!["a" => a, "b" => b.view()])?;
+		assert_eq!(outputs["y"].try_extract_tensor::<f32>()?.1, [6.0, 8.0, 16.0, 20.0]);
+	}
+
+	let dense = Tensor::from_array(([2, 3], vec![0.0_f32, 2.0, 0.0, 1.0, 0.0, 3.0]))?;
+	let err = session.validate_inputs(inputs!["a" => dense, "b" => b.view()]).unwrap_err();
+	assert_eq!(err.code(), ErrorCode::InputTypeMismatch);
+	Ok(())
+}
@@ -19,7 +19,7 @@ fn squeezenet_mushroom() -> ort::Result<()> {
 
 	ort::init().with_name("integration_test").commit()?;
 
-	let mut session = Session::builder()?
+	let session = Session::builder()?
 		.with_optimization_level(GraphOptimizationLevel::Level1)?
 		.with_intra_threads(1)?
 		.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/squeezenet.onnx")
@@ -93,6 +93,54 @@ fn squeezenet_mushroom() -> ort::Result<()> {
 	Ok(())
 }
 
+/// Runs the same session from several threads at once to verify that `Session::run` can be used concurrently through a
+/// shared reference.
+#[test]
+fn squeezenet_concurrent() -> ort::Result<()> {
+	const NUM_THREADS: usize = 8;
+
+	ort::init().with_name("integration_test").commit()?;
+
+	let session = Session::builder()?
+		.with_optimization_level(GraphOptimizationLevel::Level1)?
+		.with_intra_threads(1)?
+		.commit_from_url("https://cdn.pyke.io/0/pyke:ort-rs/example-models@0.0.0/squeezenet.onnx")
+		.expect("Could not download model from file");
+
+	let inputs: Vec<ndarray::Array4<f32>> = (0..NUM_THREADS)
+		.map(|i| ndarray::Array4::from_shape_fn((1, 3, 224, 224), |(_, c, j, k)| ((i + c * 7 + j + k) % 255) as f32 / 255.0))
+		.collect();
+	let expected = inputs
+		.iter()
+		.map(|input| {
+			let outputs = session.run(inputs![TensorRef::from_array_view(input)?])?;
+			Ok(outputs[0].try_extract_array::<f32>()?.into_owned())
+		})
+		.collect::<ort::Result<Vec<_>>>()?;
+
+	std::thread::scope(|s| {
+		let handles: Vec<_> = inputs
+			.iter()
+			.zip(&expected)
+			.map(|(input, expected)| {
+				let session = &session;
+				s.spawn(move || -> ort::Result<()> {
+					for _ in 0..4 {
+						let outputs = session.run(inputs![TensorRef::from_array_view(input)?])?;
+						let output = outputs[0].try_extract_array::<f32>()?;
+						assert_eq!(output.shape(), [1, 1000]);
+						assert_eq!(output, expected);
+					}
+					Ok(())
+				})
+			})
+			.collect();
+		handles.into_iter().try_for_each(|h| h.join().expect("inference thread panicked"))
+	})?;
+
+	Ok(())
+}
+
 fn get_imagenet_labels() -> ort::Result<Vec<String>> {
 	// Download the ImageNet class labels, matching SqueezeNet's classes.
 	let labels_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("synset.txt");
@@ -53,7 +53,7 @@ fn upsample() -> ort::Result<()> {
 
 	let session_data =
 		std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("upsample.onnx")).expect("Could not open model from file");
-	let mut session = Session::builder()?
+	let session = Session::builder()?
 		.with_optimization_level(GraphOptimizationLevel::Level1)?
 		.with_intra_threads(1)?
 		.commit_from_memory(&session_data)
@@ -96,7 +96,7 @@ fn upsample_with_ort_model() -> ort::Result<()> {
 
 	let session_data =
 		std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("upsample.ort")).expect("Could not open model from file");
-	let mut session = Session::builder()?
+	let session = Session::builder()?
 		.with_optimization_level(GraphOptimizationLevel::Level1)?
 		.with_intra_threads(1)?
 		.commit_from_memory_directly(&session_data) // Zero-copy.
@@ -120,3 +120,46 @@ fn upsample_with_ort_model() -> ort::Result<()> {
 
 	Ok(())
 }
+
+/// Runs the same session from several threads at once to verify that `Session::run` can be used concurrently through a
+/// shared reference.
+#[test]
+fn upsample_concurrent() -> ort::Result<()> {
+	const IMAGE_TO_LOAD: &str = "mushroom.png";
+	const NUM_THREADS: usize = 8;
+
+	ort::init().with_name("integration_test").commit()?;
+
+	let session = Session::builder()?
+		.with_optimization_level(GraphOptimizationLevel::Level1)?
+		.with_intra_threads(1)?
+		.commit_from_file(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("upsample.onnx"))
+		.expect("Could not read model from file");
+
+	let image_buffer = load_input_image(IMAGE_TO_LOAD);
+	let array = convert_image_to_cow_array(&image_buffer);
+
+	let expected = {
+		let outputs = session.run(inputs![TensorRef::from_array_view(&array)?])?;
+		outputs[0].try_extract_array::<f32>()?.into_owned()
+	};
+
+	std::thread::scope(|s| {
+		let handles: Vec<_> = (0..NUM_THREADS)
+			.map(|_| {
+				s.spawn(|| -> ort::Result<()> {
+					for _ in 0..4 {
+						let outputs = session.run(inputs![TensorRef::from_array_view(&array)?])?;
+						let output: ArrayViewD<f32> = outputs[0].try_extract_array()?;
+						assert_eq!(output.shape(), [1, 448, 448, 3]);
+						assert_eq!(output, expected);
+					}
+					Ok(())
+				})
+			})
+			.collect();
+		handles.into_iter().try_for_each(|h| h.join().expect("inference thread panicked"))
+	})?;
+
+	Ok(())
+}